@@ -3,6 +3,7 @@ import QuartzCore
 import Metal
 import UIKit
 import UserNotifications
+import Network
 
 struct ContentView: View {
     var body: some View {
@@ -109,6 +110,11 @@ final class MetalHostView: UIView, UIKeyInput {
         onLayoutChange?()
     }
 
+    override func safeAreaInsetsDidChange() {
+        super.safeAreaInsetsDidChange()
+        onLayoutChange?()
+    }
+
     // ── Touch handling ──────────────────────────────────────────────
 
     override func touchesBegan(_ touches: Set<UITouch>, with event: UIEvent?) {
@@ -156,16 +162,22 @@ struct EguiView: UIViewRepresentable {
     }
 
     @MainActor
-    final class Coordinator: NSObject, UNUserNotificationCenterDelegate {
+    final class Coordinator: NSObject, UNUserNotificationCenterDelegate, UIDocumentPickerDelegate {
         private var renderer: RendererHandle?
         private var link: CADisplayLink?
         private var t0 = CACurrentMediaTime()
         private weak var hostView: MetalHostView?
         private var keyboardShown = false
+        private let pathMonitor = NWPathMonitor()
+        private var sawInitialPath = false
+        // While backgrounded: keep draining notifications for as long as iOS allows
+        private var backgroundTask: UIBackgroundTaskIdentifier = .invalid
+        private var backgroundDrainTimer: Timer?
 
         // Track last known pixel size to detect resize
         private var lastPixelWidth: UInt32 = 0
         private var lastPixelHeight: UInt32 = 0
+        private var lastSafeArea: UIEdgeInsets = .zero
 
         override init() {
             super.init()
@@ -195,6 +207,8 @@ struct EguiView: UIViewRepresentable {
             hostView = view
             lastPixelWidth = wPx
             lastPixelHeight = hPx
+            lastSafeArea = view.safeAreaInsets
+            renderer?.setSafeAreaInsets(view.safeAreaInsets)
 
             // Tell Rust where to save downloaded / pulled files
             do {
@@ -258,6 +272,94 @@ struct EguiView: UIViewRepresentable {
             let dl = CADisplayLink(target: self, selector: #selector(tick))
             dl.add(to: .main, forMode: .common)
             link = dl
+
+            startPathMonitor()
+            observeBackgrounding()
+        }
+
+        // ── Background notifications ──────────────────────────────────
+        // The Rust poll thread keeps running for a while after the app leaves the
+        // foreground, but the display link (and so `tick`) stops. Drain its
+        // notification queue on a timer until the background time runs out.
+
+        private func observeBackgrounding() {
+            let center = NotificationCenter.default
+            center.addObserver(
+                self, selector: #selector(didEnterBackground),
+                name: UIApplication.didEnterBackgroundNotification, object: nil)
+            center.addObserver(
+                self, selector: #selector(willEnterForeground),
+                name: UIApplication.willEnterForegroundNotification, object: nil)
+        }
+
+        @objc private func didEnterBackground() {
+            guard backgroundTask == .invalid else { return }
+            backgroundTask = UIApplication.shared.beginBackgroundTask(withName: "TailscaleDrive.notifications") { [weak self] in
+                // Runs on the main thread and must end the task before returning
+                MainActor.assumeIsolated {
+                    self?.endBackgroundDrain()
+                }
+            }
+            backgroundDrainTimer = Timer.scheduledTimer(withTimeInterval: 1.0, repeats: true) { [weak self] _ in
+                Task { @MainActor [weak self] in
+                    self?.drainNotificationsNow()
+                }
+            }
+        }
+
+        @objc private func willEnterForeground() {
+            endBackgroundDrain()
+        }
+
+        private func endBackgroundDrain() {
+            drainNotificationsNow()
+            backgroundDrainTimer?.invalidate()
+            backgroundDrainTimer = nil
+            if backgroundTask != .invalid {
+                UIApplication.shared.endBackgroundTask(backgroundTask)
+                backgroundTask = .invalid
+            }
+        }
+
+        private func drainNotificationsNow() {
+            guard let r = renderer else { return }
+            for n in r.drainAllNotifications() where !n.title.isEmpty || !n.body.isEmpty {
+                sendLocalNotification(title: n.title, body: n.body)
+            }
+        }
+
+        // ── Network changes (Wi-Fi ↔ cellular, VPN up/down) ────────
+
+        private func startPathMonitor() {
+            pathMonitor.pathUpdateHandler = { [weak self] path in
+                let type = Coordinator.networkType(of: path)
+                Task { @MainActor [weak self] in
+                    self?.renderer?.setNetworkType(type)
+                    if path.status == .satisfied {
+                        self?.networkPathSatisfied()
+                    }
+                }
+            }
+            pathMonitor.start(queue: DispatchQueue(label: "TailscaleDrive.pathMonitor"))
+        }
+
+        /// "wifi", "cellular", "none" or "other" for `renderer_set_network_type`.
+        /// `isExpensive` also covers the Tailscale tunnel running over cellular
+        /// or a personal hotspot.
+        nonisolated private static func networkType(of path: NWPath) -> String {
+            guard path.status == .satisfied else { return "none" }
+            if path.isExpensive || path.usesInterfaceType(.cellular) { return "cellular" }
+            if path.usesInterfaceType(.wifi) || path.usesInterfaceType(.wiredEthernet) { return "wifi" }
+            return "other"
+        }
+
+        private func networkPathSatisfied() {
+            // The monitor reports the current path on start; only react to changes
+            if !sawInitialPath {
+                sawInitialPath = true
+                return
+            }
+            renderer?.networkChanged()
         }
 
         func resizeIfNeeded(view: MetalHostView) {
@@ -276,6 +378,11 @@ struct EguiView: UIViewRepresentable {
                 r.setPixelsPerPoint(Float(scale))
                 r.resize(wPx, hPx)
             }
+            // Rotation and iPad multitasking change the insets too
+            if view.safeAreaInsets != lastSafeArea {
+                lastSafeArea = view.safeAreaInsets
+                r.setSafeAreaInsets(view.safeAreaInsets)
+            }
         }
 
         // ── Trackpad scroll gesture handler ─────────────────────────
@@ -311,6 +418,10 @@ struct EguiView: UIViewRepresentable {
 
             // ── Share sheet for saved files ──
             pollPendingShares()
+            pollPendingExports()
+
+            // ── "Send Clipboard Image" (Rust asks, Swift reads the pasteboard) ──
+            pollClipboardImageRequest()
 
             // ── Notification polling ──
             pollNotifications()
@@ -380,6 +491,76 @@ struct EguiView: UIViewRepresentable {
             rootVC.present(activityVC, animated: true)
         }
 
+        // ── "Save to Files" document picker ──────────────────────────
+
+        private var exportPickerPresented = false
+
+        private func pollPendingExports() {
+            guard let r = renderer, !exportPickerPresented, !shareSheetPresented else { return }
+
+            if r.hasPendingExport() {
+                let path = r.consumeExportPath()
+                if !path.isEmpty {
+                    presentExportPicker(filePath: path)
+                }
+            }
+        }
+
+        private func presentExportPicker(filePath: String) {
+            let fileURL = URL(fileURLWithPath: filePath)
+            guard FileManager.default.fileExists(atPath: filePath),
+                  let windowScene = UIApplication.shared.connectedScenes.first as? UIWindowScene,
+                  let rootVC = windowScene.windows.first?.rootViewController else { return }
+
+            exportPickerPresented = true
+            let picker = UIDocumentPickerViewController(forExporting: [fileURL], asCopy: true)
+            picker.delegate = self
+            rootVC.present(picker, animated: true)
+        }
+
+        // ── Clipboard image → desktop ───────────────────────────────
+
+        private func pollClipboardImageRequest() {
+            guard let r = renderer, r.wantsClipboardImage() else { return }
+
+            guard let cgImage = UIPasteboard.general.image?.cgImage,
+                  let pixels = Coordinator.rgbaPixels(of: cgImage) else {
+                r.ingestClipboardImage([], width: 0, height: 0)
+                return
+            }
+            r.ingestClipboardImage(pixels, width: UInt32(cgImage.width), height: UInt32(cgImage.height))
+        }
+
+        /// Redraw into a plain RGBA8 buffer so Rust doesn't have to care about
+        /// the pasteboard image's pixel format.
+        private static func rgbaPixels(of image: CGImage) -> [UInt8]? {
+            let width = image.width, height = image.height
+            guard width > 0, height > 0 else { return nil }
+            var pixels = [UInt8](repeating: 0, count: width * height * 4)
+            let drawn = pixels.withUnsafeMutableBytes { buf -> Bool in
+                guard let ctx = CGContext(
+                    data: buf.baseAddress,
+                    width: width,
+                    height: height,
+                    bitsPerComponent: 8,
+                    bytesPerRow: width * 4,
+                    space: CGColorSpaceCreateDeviceRGB(),
+                    bitmapInfo: CGImageAlphaInfo.premultipliedLast.rawValue
+                ) else { return false }
+                ctx.draw(image, in: CGRect(x: 0, y: 0, width: width, height: height))
+                return true
+            }
+            return drawn ? pixels : nil
+        }
+
+        func documentPicker(_ controller: UIDocumentPickerViewController, didPickDocumentsAt urls: [URL]) {
+            exportPickerPresented = false
+        }
+
+        func documentPickerWasCancelled(_ controller: UIDocumentPickerViewController) {
+            exportPickerPresented = false
+        }
+
         // ── Notification handling ──────────────────────────────────
 
         private func requestNotificationPermissions() {
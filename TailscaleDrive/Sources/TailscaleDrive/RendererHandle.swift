@@ -1,4 +1,5 @@
 import Foundation
+import UIKit
 import BridgeFFI
 
 final class RendererHandle {
@@ -18,6 +19,14 @@ final class RendererHandle {
     func touchMoved(_ xPt: Float, _ yPt: Float) { renderer_touch_moved(ptr, xPt, yPt) }
     func touchEnded(_ xPt: Float, _ yPt: Float) { renderer_touch_ended(ptr, xPt, yPt) }
 
+    /// Fling speed kept per millisecond (0.998 ≈ UIScrollView `.normal`, 0.99 ≈ `.fast`).
+    func setScrollDeceleration(_ rate: Float) { renderer_set_scroll_deceleration(ptr, rate) }
+
+    /// The view's `safeAreaInsets`; the UI keeps clear of the notch and home indicator.
+    func setSafeAreaInsets(_ insets: UIEdgeInsets) {
+        renderer_set_safe_area_insets(ptr, Float(insets.top), Float(insets.bottom), Float(insets.left), Float(insets.right))
+    }
+
     // Notification polling
     func hasPendingNotification() -> Bool {
         renderer_has_pending_notification(ptr)
@@ -32,6 +41,28 @@ final class RendererHandle {
         renderer_consume_notification_body(ptr).toString()
     }
 
+    /// Every queued notification, consumed. Processes client events first, so it
+    /// also works while the display link is paused in the background.
+    func drainAllNotifications() -> [(title: String, body: String)] {
+        struct Queued: Decodable {
+            let title: String
+            let body: String
+        }
+        let json = renderer_drain_all_notifications(ptr).toString()
+        guard let queued = try? JSONDecoder().decode([Queued].self, from: Data(json.utf8)) else { return [] }
+        return queued.map { ($0.title, $0.body) }
+    }
+
+    /// Notifications waiting to be delivered.
+    func notificationCount() -> Int {
+        Int(renderer_notification_count(ptr))
+    }
+
+    /// Drop every queued notification without delivering it.
+    func clearNotifications() {
+        renderer_clear_notifications(ptr)
+    }
+
     // iOS keyboard support
     func wantsKeyboard() -> Bool {
         renderer_wants_keyboard(ptr)
@@ -72,4 +103,81 @@ final class RendererHandle {
     func consumePendingSharePath() -> String {
         renderer_consume_pending_share_path(ptr).toString()
     }
+
+    // "Save to Files" exports, separate from the share sheet
+    func hasPendingExport() -> Bool {
+        renderer_has_pending_export(ptr)
+    }
+
+    func consumeExportPath() -> String {
+        renderer_consume_export_path(ptr).toString()
+    }
+
+    // Clipboard image → desktop
+    func wantsClipboardImage() -> Bool {
+        renderer_wants_clipboard_image(ptr)
+    }
+
+    /// `rgba` is width * height RGBA8 pixels; empty means no image on the pasteboard.
+    @discardableResult
+    func ingestClipboardImage(_ rgba: [UInt8], width: UInt32, height: UInt32) -> Bool {
+        // An empty array may have no base address; Rust ignores the pointer when the length is 0
+        let bytes = rgba.isEmpty ? [0] : rgba
+        return bytes.withUnsafeBytes { buf in
+            renderer_ingest_clipboard_image(ptr, buf.baseAddress!, UInt(rgba.count), width, height)
+        }
+    }
+
+    // Server URL (bookmarks / universal links / tsdrive:// join codes)
+    /// Returns false if the URL was rejected as malformed.
+    @discardableResult
+    func setServerURL(_ url: String) -> Bool {
+        renderer_set_server_url(ptr, url)
+    }
+
+    func currentServerURL() -> String {
+        renderer_current_server_url(ptr).toString()
+    }
+
+    /// Trust a PEM root certificate for the current server; empty restores the
+    /// built-in roots. Returns false if the PEM contains no certificate.
+    @discardableResult
+    func setServerRootCert(_ pem: String) -> Bool {
+        renderer_set_server_root_cert(ptr, pem)
+    }
+
+    /// Skip TLS verification for the current server (self-signed certs only).
+    func setAcceptInvalidCerts(_ accept: Bool) {
+        renderer_set_accept_invalid_certs(ptr, accept)
+    }
+
+    /// Ask before image previews / pulls larger than these byte counts; 0 never asks.
+    func setSizeConfirmLimits(preview: UInt64, pull: UInt64) {
+        renderer_set_size_confirm_limits(ptr, preview, pull)
+    }
+
+    // Connectivity
+    /// Call on network path changes; re-polls now instead of waiting for the next interval.
+    func networkChanged() {
+        renderer_network_changed(ptr)
+    }
+
+    func reconnect() {
+        renderer_reconnect(ptr)
+    }
+
+    /// "wifi", "cellular", "none" or "other", from `NWPathMonitor`.
+    func setNetworkType(_ type: String) {
+        renderer_set_network_type(ptr, type)
+    }
+
+    // Project Sync
+    /// Off, changes are still listed but only `syncNow()` transfers them.
+    func setAutoSync(_ on: Bool) {
+        renderer_set_auto_sync(ptr, on)
+    }
+
+    func syncNow() {
+        renderer_sync_now(ptr)
+    }
 }
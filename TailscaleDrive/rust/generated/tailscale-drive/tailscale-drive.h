@@ -9,9 +9,14 @@ void __swift_bridge__$renderer_render(void* ptr, double time_seconds);
 void __swift_bridge__$renderer_touch_began(void* ptr, float x_pt, float y_pt);
 void __swift_bridge__$renderer_touch_moved(void* ptr, float x_pt, float y_pt);
 void __swift_bridge__$renderer_touch_ended(void* ptr, float x_pt, float y_pt);
+void __swift_bridge__$renderer_set_scroll_deceleration(void* ptr, float rate);
+void __swift_bridge__$renderer_set_safe_area_insets(void* ptr, float top, float bottom, float left, float right);
 bool __swift_bridge__$renderer_has_pending_notification(void* ptr);
 void* __swift_bridge__$renderer_notification_title(void* ptr);
 void* __swift_bridge__$renderer_consume_notification_body(void* ptr);
+void* __swift_bridge__$renderer_drain_all_notifications(void* ptr);
+int32_t __swift_bridge__$renderer_notification_count(void* ptr);
+void __swift_bridge__$renderer_clear_notifications(void* ptr);
 bool __swift_bridge__$renderer_wants_keyboard(void* ptr);
 void __swift_bridge__$renderer_insert_text(void* ptr, void* text);
 void __swift_bridge__$renderer_delete_backward(void* ptr);
@@ -21,5 +26,19 @@ void __swift_bridge__$renderer_pointer_moved(void* ptr, float x_pt, float y_pt);
 void __swift_bridge__$renderer_set_save_directory(void* ptr, void* path);
 bool __swift_bridge__$renderer_has_pending_share(void* ptr);
 void* __swift_bridge__$renderer_consume_pending_share_path(void* ptr);
+bool __swift_bridge__$renderer_has_pending_export(void* ptr);
+void* __swift_bridge__$renderer_consume_export_path(void* ptr);
+bool __swift_bridge__$renderer_wants_clipboard_image(void* ptr);
+bool __swift_bridge__$renderer_ingest_clipboard_image(void* ptr, void* rgba_ptr, uintptr_t rgba_len, uint32_t width, uint32_t height);
+bool __swift_bridge__$renderer_set_server_url(void* ptr, void* url);
+void* __swift_bridge__$renderer_current_server_url(void* ptr);
+bool __swift_bridge__$renderer_set_server_root_cert(void* ptr, void* pem);
+void __swift_bridge__$renderer_set_accept_invalid_certs(void* ptr, bool accept);
+void __swift_bridge__$renderer_set_size_confirm_limits(void* ptr, uint64_t preview_bytes, uint64_t pull_bytes);
+void __swift_bridge__$renderer_set_auto_sync(void* ptr, bool on);
+void __swift_bridge__$renderer_sync_now(void* ptr);
+void __swift_bridge__$renderer_network_changed(void* ptr);
+void __swift_bridge__$renderer_set_network_type(void* ptr, void* network_type);
+void __swift_bridge__$renderer_reconnect(void* ptr);
 
 
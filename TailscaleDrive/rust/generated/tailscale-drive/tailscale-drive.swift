@@ -22,6 +22,12 @@ public func renderer_touch_moved(_ ptr: UnsafeMutableRawPointer, _ x_pt: Float,
 public func renderer_touch_ended(_ ptr: UnsafeMutableRawPointer, _ x_pt: Float, _ y_pt: Float) {
     __swift_bridge__$renderer_touch_ended(ptr, x_pt, y_pt)
 }
+public func renderer_set_scroll_deceleration(_ ptr: UnsafeMutableRawPointer, _ rate: Float) {
+    __swift_bridge__$renderer_set_scroll_deceleration(ptr, rate)
+}
+public func renderer_set_safe_area_insets(_ ptr: UnsafeMutableRawPointer, _ top: Float, _ bottom: Float, _ left: Float, _ right: Float) {
+    __swift_bridge__$renderer_set_safe_area_insets(ptr, top, bottom, left, right)
+}
 public func renderer_has_pending_notification(_ ptr: UnsafeMutableRawPointer) -> Bool {
     __swift_bridge__$renderer_has_pending_notification(ptr)
 }
@@ -31,6 +37,15 @@ public func renderer_notification_title(_ ptr: UnsafeMutableRawPointer) -> RustS
 public func renderer_consume_notification_body(_ ptr: UnsafeMutableRawPointer) -> RustString {
     RustString(ptr: __swift_bridge__$renderer_consume_notification_body(ptr))
 }
+public func renderer_drain_all_notifications(_ ptr: UnsafeMutableRawPointer) -> RustString {
+    RustString(ptr: __swift_bridge__$renderer_drain_all_notifications(ptr))
+}
+public func renderer_notification_count(_ ptr: UnsafeMutableRawPointer) -> Int32 {
+    __swift_bridge__$renderer_notification_count(ptr)
+}
+public func renderer_clear_notifications(_ ptr: UnsafeMutableRawPointer) {
+    __swift_bridge__$renderer_clear_notifications(ptr)
+}
 public func renderer_wants_keyboard(_ ptr: UnsafeMutableRawPointer) -> Bool {
     __swift_bridge__$renderer_wants_keyboard(ptr)
 }
@@ -58,5 +73,47 @@ public func renderer_has_pending_share(_ ptr: UnsafeMutableRawPointer) -> Bool {
 public func renderer_consume_pending_share_path(_ ptr: UnsafeMutableRawPointer) -> RustString {
     RustString(ptr: __swift_bridge__$renderer_consume_pending_share_path(ptr))
 }
+public func renderer_has_pending_export(_ ptr: UnsafeMutableRawPointer) -> Bool {
+    __swift_bridge__$renderer_has_pending_export(ptr)
+}
+public func renderer_consume_export_path(_ ptr: UnsafeMutableRawPointer) -> RustString {
+    RustString(ptr: __swift_bridge__$renderer_consume_export_path(ptr))
+}
+public func renderer_wants_clipboard_image(_ ptr: UnsafeMutableRawPointer) -> Bool {
+    __swift_bridge__$renderer_wants_clipboard_image(ptr)
+}
+public func renderer_ingest_clipboard_image(_ ptr: UnsafeMutableRawPointer, _ rgba_ptr: UnsafeRawPointer, _ rgba_len: UInt, _ width: UInt32, _ height: UInt32) -> Bool {
+    __swift_bridge__$renderer_ingest_clipboard_image(ptr, UnsafeMutableRawPointer(mutating: rgba_ptr), rgba_len, width, height)
+}
+public func renderer_set_server_url<GenericIntoRustString: IntoRustString>(_ ptr: UnsafeMutableRawPointer, _ url: GenericIntoRustString) -> Bool {
+    __swift_bridge__$renderer_set_server_url(ptr, { let rustString = url.intoRustString(); rustString.isOwned = false; return rustString.ptr }())
+}
+public func renderer_current_server_url(_ ptr: UnsafeMutableRawPointer) -> RustString {
+    RustString(ptr: __swift_bridge__$renderer_current_server_url(ptr))
+}
+public func renderer_set_server_root_cert<GenericIntoRustString: IntoRustString>(_ ptr: UnsafeMutableRawPointer, _ pem: GenericIntoRustString) -> Bool {
+    __swift_bridge__$renderer_set_server_root_cert(ptr, { let rustString = pem.intoRustString(); rustString.isOwned = false; return rustString.ptr }())
+}
+public func renderer_set_accept_invalid_certs(_ ptr: UnsafeMutableRawPointer, _ accept: Bool) {
+    __swift_bridge__$renderer_set_accept_invalid_certs(ptr, accept)
+}
+public func renderer_set_size_confirm_limits(_ ptr: UnsafeMutableRawPointer, _ preview_bytes: UInt64, _ pull_bytes: UInt64) {
+    __swift_bridge__$renderer_set_size_confirm_limits(ptr, preview_bytes, pull_bytes)
+}
+public func renderer_set_auto_sync(_ ptr: UnsafeMutableRawPointer, _ on: Bool) {
+    __swift_bridge__$renderer_set_auto_sync(ptr, on)
+}
+public func renderer_sync_now(_ ptr: UnsafeMutableRawPointer) {
+    __swift_bridge__$renderer_sync_now(ptr)
+}
+public func renderer_network_changed(_ ptr: UnsafeMutableRawPointer) {
+    __swift_bridge__$renderer_network_changed(ptr)
+}
+public func renderer_set_network_type<GenericIntoRustString: IntoRustString>(_ ptr: UnsafeMutableRawPointer, _ network_type: GenericIntoRustString) {
+    __swift_bridge__$renderer_set_network_type(ptr, { let rustString = network_type.intoRustString(); rustString.isOwned = false; return rustString.ptr }())
+}
+public func renderer_reconnect(_ ptr: UnsafeMutableRawPointer) {
+    __swift_bridge__$renderer_reconnect(ptr)
+}
 
 
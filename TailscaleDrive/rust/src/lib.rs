@@ -19,11 +19,17 @@ mod ffi {
         fn renderer_touch_began(ptr: *mut c_void, x_pt: f32, y_pt: f32);
         fn renderer_touch_moved(ptr: *mut c_void, x_pt: f32, y_pt: f32);
         fn renderer_touch_ended(ptr: *mut c_void, x_pt: f32, y_pt: f32);
+        fn renderer_set_scroll_deceleration(ptr: *mut c_void, rate: f32);
+        // UIKit safeAreaInsets, in points
+        fn renderer_set_safe_area_insets(ptr: *mut c_void, top: f32, bottom: f32, left: f32, right: f32);
 
         // Notification polling (called from Swift each tick)
         fn renderer_has_pending_notification(ptr: *mut c_void) -> bool;
         fn renderer_notification_title(ptr: *mut c_void) -> String;
         fn renderer_consume_notification_body(ptr: *mut c_void) -> String;
+        fn renderer_drain_all_notifications(ptr: *mut c_void) -> String;
+        fn renderer_notification_count(ptr: *mut c_void) -> i32;
+        fn renderer_clear_notifications(ptr: *mut c_void);
 
         // iOS keyboard support
         fn renderer_wants_keyboard(ptr: *mut c_void) -> bool;
@@ -43,6 +49,39 @@ mod ffi {
         fn renderer_set_save_directory(ptr: *mut c_void, path: String);
         fn renderer_has_pending_share(ptr: *mut c_void) -> bool;
         fn renderer_consume_pending_share_path(ptr: *mut c_void) -> String;
+        fn renderer_has_pending_export(ptr: *mut c_void) -> bool;
+        fn renderer_consume_export_path(ptr: *mut c_void) -> String;
+
+        // Clipboard image → desktop (Swift supplies RGBA8 pixels from UIPasteboard)
+        fn renderer_wants_clipboard_image(ptr: *mut c_void) -> bool;
+        fn renderer_ingest_clipboard_image(
+            ptr: *mut c_void,
+            rgba_ptr: *const c_void,
+            rgba_len: usize,
+            width: u32,
+            height: u32,
+        ) -> bool;
+
+        // Server URL (bookmarks / universal links)
+        fn renderer_set_server_url(ptr: *mut c_void, url: String) -> bool;
+        fn renderer_current_server_url(ptr: *mut c_void) -> String;
+
+        // TLS for servers behind their own HTTPS (current server only)
+        fn renderer_set_server_root_cert(ptr: *mut c_void, pem: String) -> bool;
+        fn renderer_set_accept_invalid_certs(ptr: *mut c_void, accept: bool);
+
+        // Confirmation thresholds for big previews / pulls (bytes, 0 = never ask)
+        fn renderer_set_size_confirm_limits(ptr: *mut c_void, preview_bytes: u64, pull_bytes: u64);
+
+        // Project Sync: automatic transfers on/off (saved), manual sync of everything
+        fn renderer_set_auto_sync(ptr: *mut c_void, on: bool);
+        fn renderer_sync_now(ptr: *mut c_void);
+
+        // Connectivity (NWPathMonitor / manual retry)
+        fn renderer_network_changed(ptr: *mut c_void);
+        // network_type: "wifi", "cellular", "none" or "other"
+        fn renderer_set_network_type(ptr: *mut c_void, network_type: String);
+        fn renderer_reconnect(ptr: *mut c_void);
     }
 }
 
@@ -53,7 +92,8 @@ pub fn renderer_new(layer_ptr: *mut c_void, width_px: u32, height_px: u32, pixel
 
 pub fn renderer_free(ptr: *mut c_void) {
     if !ptr.is_null() {
-        unsafe { drop(Box::from_raw(ptr as *mut Renderer)) };
+        let mut renderer = unsafe { Box::from_raw(ptr as *mut Renderer) };
+        renderer.shutdown();
     }
 }
 
@@ -99,6 +139,20 @@ pub fn renderer_touch_ended(ptr: *mut c_void, x_pt: f32, y_pt: f32) {
     unsafe { &mut *(ptr as *mut Renderer) }.touch_ended(x_pt, y_pt);
 }
 
+pub fn renderer_set_scroll_deceleration(ptr: *mut c_void, rate: f32) {
+    if ptr.is_null() {
+        return;
+    }
+    unsafe { &mut *(ptr as *mut Renderer) }.set_scroll_deceleration(rate);
+}
+
+pub fn renderer_set_safe_area_insets(ptr: *mut c_void, top: f32, bottom: f32, left: f32, right: f32) {
+    if ptr.is_null() {
+        return;
+    }
+    unsafe { &mut *(ptr as *mut Renderer) }.set_safe_area_insets(top, bottom, left, right);
+}
+
 // ── Notification bridge functions ─────────────────────────────────────
 
 pub fn renderer_has_pending_notification(ptr: *mut c_void) -> bool {
@@ -123,6 +177,32 @@ pub fn renderer_consume_notification_body(ptr: *mut c_void) -> String {
     unsafe { &mut *(ptr as *mut Renderer) }.consume_notification_body()
 }
 
+/// Every queued notification as a JSON array of `{"title", "body"}`, consumed.
+/// Also processes pending client events, so it works while rendering is paused.
+pub fn renderer_drain_all_notifications(ptr: *mut c_void) -> String {
+    if ptr.is_null() {
+        return "[]".to_string();
+    }
+    unsafe { &mut *(ptr as *mut Renderer) }.drain_all_notifications()
+}
+
+/// How many notifications are queued (after processing pending client events).
+pub fn renderer_notification_count(ptr: *mut c_void) -> i32 {
+    if ptr.is_null() {
+        return 0;
+    }
+    let count = unsafe { &mut *(ptr as *mut Renderer) }.notification_count();
+    i32::try_from(count).unwrap_or(i32::MAX)
+}
+
+/// Drop every queued notification unsent ("mark all as read").
+pub fn renderer_clear_notifications(ptr: *mut c_void) {
+    if ptr.is_null() {
+        return;
+    }
+    unsafe { &mut *(ptr as *mut Renderer) }.clear_notifications();
+}
+
 // ── iOS keyboard bridge functions ─────────────────────────────────────
 
 pub fn renderer_wants_keyboard(ptr: *mut c_void) -> bool {
@@ -193,3 +273,126 @@ pub fn renderer_consume_pending_share_path(ptr: *mut c_void) -> String {
     }
     unsafe { &mut *(ptr as *mut Renderer) }.consume_pending_share_path()
 }
+
+pub fn renderer_has_pending_export(ptr: *mut c_void) -> bool {
+    if ptr.is_null() {
+        return false;
+    }
+    unsafe { &*(ptr as *mut Renderer) }.has_pending_export()
+}
+
+pub fn renderer_consume_export_path(ptr: *mut c_void) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    unsafe { &mut *(ptr as *mut Renderer) }.consume_export_path()
+}
+
+// ── Clipboard image bridge functions ──────────────────────────────────
+
+/// True once after "Send Clipboard Image" was tapped.
+pub fn renderer_wants_clipboard_image(ptr: *mut c_void) -> bool {
+    if ptr.is_null() {
+        return false;
+    }
+    unsafe { &mut *(ptr as *mut Renderer) }.take_clipboard_image_request()
+}
+
+/// `rgba_ptr` holds `width * height` RGBA8 pixels (or is null / empty when the
+/// pasteboard had no image). Returns false if nothing was queued.
+pub fn renderer_ingest_clipboard_image(
+    ptr: *mut c_void,
+    rgba_ptr: *const c_void,
+    rgba_len: usize,
+    width: u32,
+    height: u32,
+) -> bool {
+    if ptr.is_null() {
+        return false;
+    }
+    let rgba: &[u8] = if rgba_ptr.is_null() || rgba_len == 0 {
+        &[]
+    } else {
+        unsafe { std::slice::from_raw_parts(rgba_ptr as *const u8, rgba_len) }
+    };
+    unsafe { &mut *(ptr as *mut Renderer) }.ingest_clipboard_image(rgba, width, height)
+}
+
+// ── Server URL bridge functions ───────────────────────────────────────
+
+/// Returns false if the URL was rejected as malformed.
+pub fn renderer_set_server_url(ptr: *mut c_void, url: String) -> bool {
+    if ptr.is_null() {
+        return false;
+    }
+    unsafe { &mut *(ptr as *mut Renderer) }.set_server_url(&url)
+}
+
+pub fn renderer_current_server_url(ptr: *mut c_void) -> String {
+    if ptr.is_null() {
+        return String::new();
+    }
+    unsafe { &*(ptr as *mut Renderer) }.current_server_url()
+}
+
+pub fn renderer_set_server_root_cert(ptr: *mut c_void, pem: String) -> bool {
+    if ptr.is_null() {
+        return false;
+    }
+    unsafe { &mut *(ptr as *mut Renderer) }.set_server_root_cert(&pem)
+}
+
+pub fn renderer_set_accept_invalid_certs(ptr: *mut c_void, accept: bool) {
+    if ptr.is_null() {
+        return;
+    }
+    unsafe { &mut *(ptr as *mut Renderer) }.set_accept_invalid_certs(accept);
+}
+
+pub fn renderer_set_size_confirm_limits(ptr: *mut c_void, preview_bytes: u64, pull_bytes: u64) {
+    if ptr.is_null() {
+        return;
+    }
+    unsafe { &mut *(ptr as *mut Renderer) }.set_size_confirm_limits(preview_bytes, pull_bytes);
+}
+
+// ── Connectivity bridge functions ─────────────────────────────────────
+
+pub fn renderer_network_changed(ptr: *mut c_void) {
+    if ptr.is_null() {
+        return;
+    }
+    unsafe { &mut *(ptr as *mut Renderer) }.network_changed()
+}
+
+/// With "Wi-Fi only" on, cellular pauses auto-sync and asks before large pulls.
+pub fn renderer_set_network_type(ptr: *mut c_void, network_type: String) {
+    if ptr.is_null() {
+        return;
+    }
+    unsafe { &mut *(ptr as *mut Renderer) }.set_network_type(&network_type)
+}
+
+pub fn renderer_reconnect(ptr: *mut c_void) {
+    if ptr.is_null() {
+        return;
+    }
+    unsafe { &mut *(ptr as *mut Renderer) }.reconnect_now()
+}
+
+// ── Project Sync bridge functions ─────────────────────────────────────
+
+/// Off, sync changes are still listed but only "Sync Now" transfers them.
+pub fn renderer_set_auto_sync(ptr: *mut c_void, on: bool) {
+    if ptr.is_null() {
+        return;
+    }
+    unsafe { &mut *(ptr as *mut Renderer) }.set_auto_sync(on);
+}
+
+pub fn renderer_sync_now(ptr: *mut c_void) {
+    if ptr.is_null() {
+        return;
+    }
+    unsafe { &mut *(ptr as *mut Renderer) }.sync_now();
+}
@@ -1,15 +1,98 @@
 use std::ffi::c_void;
 use std::time::{Duration, Instant};
 
-use egui::{Color32, RichText, pos2, vec2};
+use egui::{Color32, Pos2, RichText, Vec2, pos2, vec2};
 use egui_wgpu_backend::{RenderPass as EguiWgpuRenderer, ScreenDescriptor};
 
-use crate::tailscale_client::{format_size, format_timestamp, format_date_mmddyyyy, load_cached_peers, TailscaleClient};
+use crate::tailscale_client::{
+    format_size, format_timestamp, format_date_mmddyyyy, join_remote_path, load_cached_peers, load_saved_servers,
+    merge_peers, normalize_remote_path, parent_remote_path_within, pull_save_path, remote_file_name,
+    save_saved_servers, sync_paths_overlap, ActivityKind, NetworkType, PullCache, RemoteFile,
+    SyncDirection, SyncFeedEntry, SyncHistoryEntry, TailscaleClient, TlsOptions, NO_SAVE_LOCATION,
+};
 
 const DEFAULT_SERVER_URL: &str = "http://manjaro-work.taile483f.ts.net:8080";
 
 // ── Page enum ───────────────────────────────────────────────────────────
 
+/// Horizontal travel (points) below which a touch is never a page swipe.
+const SWIPE_MIN_DISTANCE: f32 = 60.0;
+/// Travel that counts as a swipe regardless of speed.
+const SWIPE_LONG_DISTANCE: f32 = 140.0;
+/// Points per millisecond for a short flick to count.
+const SWIPE_MIN_VELOCITY: f32 = 0.4;
+/// Duration of the page slide-in animation.
+const PAGE_SLIDE_SECS: f32 = 0.22;
+/// Lift-off speed (points per second) needed to start a fling.
+const MOMENTUM_MIN_VELOCITY: f32 = 150.0;
+/// A fling ends once it slows below this.
+const MOMENTUM_STOP_VELOCITY: f32 = 20.0;
+/// Keeps a jittery last sample from launching the list off-screen.
+const MOMENTUM_MAX_VELOCITY: f32 = 6000.0;
+/// Fraction of fling speed kept per millisecond, like `UIScrollView.DecelerationRate.normal`.
+const MOMENTUM_DEFAULT_DECELERATION: f32 = 0.998;
+/// A finger resting this long before lift-off doesn't fling.
+const MOMENTUM_HOLD_CANCEL: Duration = Duration::from_millis(60);
+/// egui spreads wheel steps of 8pt or more over later frames; inject smaller ones.
+const MOMENTUM_WHEEL_STEP: f32 = 7.0;
+
+/// Image previews of files larger than this ask first, unless changed in settings.
+const DEFAULT_PREVIEW_CONFIRM_BYTES: u64 = 200 * 1024 * 1024;
+/// Top inset assumed until the host reports real safe-area insets (clears a notch).
+const FALLBACK_TOP_INSET: f32 = 50.0;
+/// Default hold before a touch opens a context menu.
+const DEFAULT_LONG_PRESS_MS: u64 = 500;
+
+/// With "Wi-Fi only" on, pulls bigger than this ask first while on cellular.
+const CELLULAR_PULL_CONFIRM_BYTES: u64 = 20 * 1024 * 1024;
+
+/// Default desktop folder for "Send Clipboard Image".
+const DEFAULT_CLIPBOARD_DEST_DIR: &str = "~/Downloads";
+
+/// Notifications kept for the Activity window's list
+const NOTIFICATION_LOG_LIMIT: usize = 50;
+
+/// Sync results kept (and saved) for the Project Sync page's history
+const SYNC_FEED_LIMIT: usize = 100;
+
+/// How long `renderer_free` waits for a request in flight before letting the
+/// network thread finish on its own.
+const CLIENT_SHUTDOWN_WAIT: Duration = Duration::from_secs(1);
+
+/// A preview or pull held back by the size limit.
+#[derive(Clone)]
+struct LargeTransfer {
+    path: String,
+    path_id: Option<String>,
+    size: u64,
+    preview: bool,
+    /// Asked because of the "Wi-Fi only" cellular limit
+    cellular: bool,
+}
+
+/// What a tap in the tree view asked for, applied once the tree is drawn.
+#[derive(Default)]
+struct TreeActions {
+    /// Folder header tapped: expand or collapse
+    toggle: Option<String>,
+    /// Expanded folders with no listing yet
+    load: Vec<String>,
+    select: Option<String>,
+    pull: Option<(String, Option<String>)>,
+    preview: Option<(String, Option<String>)>,
+    pull_dir: Option<(String, Option<String>)>,
+    sync_from_remote: Option<(String, SyncDirection)>,
+}
+
+/// Scrolling that carries on after a flick, fed to egui as wheel events.
+struct Momentum {
+    /// Lift-off point; the wheel events scroll whatever is under it
+    pos: Pos2,
+    /// Points per second
+    velocity: Vec2,
+    last_tick: Instant,
+}
+
 #[derive(Clone, Copy, PartialEq)]
 pub enum Page {
     Monitor,
@@ -51,7 +134,19 @@ pub struct Renderer {
 
     // UI state
     server_url_input: String,
+    /// Shown under the URL field when a typed URL fails validation
+    server_url_error: Option<String>,
+    /// Bookmarked server URLs (persisted next to the peer cache)
+    saved_servers: Vec<String>,
     selected_file_idx: Option<usize>,
+    /// Pulled file waiting for the user to pick a destination folder
+    move_source: Option<String>,
+    /// Outcome of the last "Move here", shown above the local browser
+    move_status: Option<String>,
+    /// "Clear all" was tapped once; the next tap empties the desktop inbox
+    confirm_clear_inbox: bool,
+    /// "Remove all syncs" was tapped once; the next tap removes every project
+    confirm_clear_syncs: bool,
     theme_applied: bool,
     current_page: Page,
     browse_path_input: String,
@@ -61,6 +156,10 @@ pub struct Renderer {
 
     // Notification queue: (title, body)
     pending_notifications: Vec<(String, String)>,
+    /// Recent notifications, newest last: (title, body, unix time)
+    notification_log: std::collections::VecDeque<(String, String, u64)>,
+    /// Sync completions and errors, newest last (sync_feed.json)
+    sync_feed: std::collections::VecDeque<SyncFeedEntry>,
     last_known_received: Option<String>,
     last_known_sent_name: Option<String>,
 
@@ -72,8 +171,22 @@ pub struct Renderer {
     local_browse_path: String,
     local_files: Vec<LocalFileEntry>,
     selected_local_idx: Option<usize>,
+    /// Multi-select mode in the local browser: tapping files toggles them
+    local_multi_select: bool,
+    /// Paths picked in multi-select mode (files only)
+    local_selection: Vec<String>,
+    /// Monitor page "Send Text" box
+    note_input: String,
+    /// Extension filter for the local browser (persisted next to the peer cache)
+    local_filter: FileFilter,
+    /// Other persisted preferences (settings.json next to the peer cache)
+    settings: RendererSettings,
     /// The local file path selected for syncing
     sync_local_file: Option<String>,
+    /// Direction for the sync being set up on the Choose Destination step
+    sync_direction: SyncDirection,
+    /// (local path, name to use on the desktop) for Send / Sync; reset when the file changes
+    send_as: Option<(String, String)>,
     /// Whether we already fetched sync projects from server
     sync_projects_fetched: bool,
 
@@ -81,11 +194,63 @@ pub struct Renderer {
     long_press_start: Option<(f32, f32, Instant)>,
     long_press_fired: bool,
 
+    // Double-tap tracking: position/time of the previous press, and whether
+    // the current press completed a double tap
+    last_tap: Option<(f32, f32, Instant)>,
+    double_tap: bool,
+
+    // Horizontal page swipe: where/when the current touch started, and a
+    // finished swipe (start x, start y, dx) waiting to be applied next frame
+    swipe_start: Option<(f32, f32, Instant)>,
+    pending_swipe: Option<(f32, f32, f32)>,
+
+    // Fling scrolling: last touch sample, smoothed finger velocity (points/s),
+    // the running fling and the wheel delta it injected this frame
+    touch_track: Option<(Pos2, Instant)>,
+    touch_velocity: Vec2,
+    momentum: Option<Momentum>,
+    momentum_injected: Vec2,
+    /// See `MOMENTUM_DEFAULT_DECELERATION`; set with `set_scroll_deceleration`
+    scroll_deceleration: f32,
+    /// Safe-area insets in points (notch, home indicator, rounded corners)
+    safe_area: SafeAreaInsets,
+    /// The surface size or scale changed; the next frame restores the page scroll
+    relayout_pending: bool,
+    /// Page scroll as a fraction of its scrollable height, as of the last stable frame
+    page_scroll_fraction: f32,
+    /// Offset to apply to the page scroll on the next frame
+    restore_scroll: Option<f32>,
+    /// Scroll the selected row into view this frame (after a relayout)
+    scroll_to_selection: bool,
+    /// Page drawn last frame; a change starts the slide animation
+    last_drawn_page: Page,
+    /// Slide-in animation: direction (+1 from the right, -1 from the left) and start
+    page_slide: Option<(f32, Instant)>,
+
     // File preview state
     show_preview: bool,
     preview_filename: String,
     preview_text: String,
     preview_texture: Option<egui::TextureHandle>,
+    /// Metadata decoded from the head of an audio file
+    preview_audio: Option<AudioInfo>,
+    /// "Send Clipboard Image" was tapped; Swift reads the pasteboard and calls back
+    clipboard_image_requested: bool,
+    /// Remote path of the file last sent to the preview window
+    preview_path: Option<String>,
+    /// Pinned: tapping another previewable file shows it in the open window
+    preview_pinned: bool,
+    /// Tree view: expanded remote folders (full paths); kept across refreshes
+    tree_expanded: std::collections::HashSet<String>,
+    /// Tree view: the selected file's full path
+    tree_selected: Option<String>,
+    /// "Pull matching…" is open with this glob typed so far
+    glob_input: Option<String>,
+
+    /// Preview or pull of a file over the size limit, waiting for confirmation
+    large_transfer: Option<LargeTransfer>,
+    /// Latest `NWPathMonitor` report; kept across reconnects
+    network_type: NetworkType,
 
     // ── Overwrite confirmation modal state ──
     show_overwrite_modal: bool,
@@ -94,10 +259,40 @@ pub struct Renderer {
     /// Whether we're waiting for a file-info response from the server
     overwrite_checking: bool,
     /// Deferred: remote file path to initiate "sync to iPhone" from context menu
-    pending_sync_from_remote: Option<String>,
+    pending_sync_from_remote: Option<(String, SyncDirection)>,
+    /// Sync project card currently in edit mode
+    sync_edit: Option<SyncEdit>,
 
     // iOS keyboard state
     wants_keyboard: bool,
+
+    /// Whether the log window is open
+    show_logs: bool,
+    show_settings: bool,
+    /// Recent-activity timeline window
+    show_activity: bool,
+    /// "What's New" window: desktop files changed since the last visit
+    show_changes: bool,
+    /// This connection already asked the desktop what changed
+    changes_checked: bool,
+    /// Auth token being edited in Settings for the current server
+    token_input: String,
+    /// PEM root certificate being pasted in Settings
+    cert_input: String,
+    cert_error: Option<String>,
+}
+
+/// A TLS change made in Settings, applied after the window is drawn
+enum TlsEdit {
+    AcceptInvalid(bool),
+    RootCert(String),
+}
+
+/// In-progress edit of a sync project's paths
+struct SyncEdit {
+    id: String,
+    desktop_path: String,
+    ios_path: String,
 }
 
 /// Data for the overwrite confirmation modal
@@ -118,6 +313,7 @@ struct OverwritePending {
     /// Whether this sync was initiated from the remote file browser
     /// (pulling from desktop to iOS, rather than pushing from iOS to desktop)
     from_remote: bool,
+    direction: SyncDirection,
 }
 
 impl Renderer {
@@ -127,6 +323,11 @@ impl Renderer {
         height_px: u32,
         pixels_per_point: f32,
     ) -> Self {
+        // A second renderer (e.g. after a scene reconnect) finds the logger already set
+        let _ = egui_logger::builder()
+            .max_level(log::LevelFilter::Info)
+            .init();
+
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
             backends: wgpu::Backends::METAL,
             ..Default::default()
@@ -191,7 +392,13 @@ impl Renderer {
 
             client,
             server_url_input: DEFAULT_SERVER_URL.to_string(),
+            server_url_error: None,
+            saved_servers: Vec::new(),
             selected_file_idx: None,
+            move_source: None,
+            move_status: None,
+            confirm_clear_inbox: false,
+            confirm_clear_syncs: false,
             theme_applied: false,
             current_page: Page::Monitor,
             browse_path_input: String::new(),
@@ -200,6 +407,8 @@ impl Renderer {
             auto_browsed: false,
 
             pending_notifications: Vec::new(),
+            notification_log: std::collections::VecDeque::new(),
+            sync_feed: std::collections::VecDeque::new(),
             last_known_received: None,
             last_known_sent_name: None,
             selected_peer_id: None,
@@ -208,42 +417,415 @@ impl Renderer {
             local_browse_path: String::new(),
             local_files: Vec::new(),
             selected_local_idx: None,
+            local_multi_select: false,
+            local_selection: Vec::new(),
+            note_input: String::new(),
+            local_filter: FileFilter::All,
+            settings: RendererSettings::default(),
             sync_local_file: None,
+            sync_direction: SyncDirection::default(),
+            send_as: None,
             sync_projects_fetched: false,
 
             long_press_start: None,
             long_press_fired: false,
+            last_tap: None,
+            double_tap: false,
+            swipe_start: None,
+            pending_swipe: None,
+            touch_track: None,
+            touch_velocity: Vec2::ZERO,
+            momentum: None,
+            momentum_injected: Vec2::ZERO,
+            scroll_deceleration: MOMENTUM_DEFAULT_DECELERATION,
+            safe_area: SafeAreaInsets { top: FALLBACK_TOP_INSET, ..Default::default() },
+            relayout_pending: false,
+            page_scroll_fraction: 0.0,
+            restore_scroll: None,
+            scroll_to_selection: false,
+            last_drawn_page: Page::Monitor,
+            page_slide: None,
 
             show_preview: false,
             preview_filename: String::new(),
             preview_text: String::new(),
             preview_texture: None,
-
+            clipboard_image_requested: false,
+            preview_path: None,
+            preview_pinned: false,
+            tree_expanded: std::collections::HashSet::new(),
+            tree_selected: None,
+            glob_input: None,
+            preview_audio: None,
+
+            large_transfer: None,
+            network_type: NetworkType::Unknown,
             show_overwrite_modal: false,
             overwrite_pending: None,
             overwrite_checking: false,
             pending_sync_from_remote: None,
+            sync_edit: None,
 
             wants_keyboard: false,
+            show_logs: false,
+            show_settings: false,
+            show_activity: false,
+            show_changes: false,
+            changes_checked: false,
+            token_input: String::new(),
+            cert_input: String::new(),
+            cert_error: None,
         }
     }
 
+    /// Only the scale changes; scroll positions and selections are kept.
     pub fn set_pixels_per_point(&mut self, ppp: f32) {
-        self.pixels_per_point = ppp.max(0.5);
+        let ppp = ppp.max(0.5);
+        if ppp != self.pixels_per_point {
+            self.pixels_per_point = ppp;
+            self.relayout_pending = true;
+        }
     }
 
     /// Set the directory where downloaded/pulled files are saved (iOS Documents dir).
     pub fn set_save_directory(&mut self, path: &str) {
         self.client.save_directory = Some(path.to_string());
+        self.local_filter = load_browser_filter(path);
+        self.settings = load_settings(path);
+        self.apply_client_settings();
+        self.client.pull_cache = PullCache::load(path);
+        self.sync_feed = load_sync_feed(path);
+        self.saved_servers = load_saved_servers(path);
+        self.client.set_probe_urls(&self.saved_servers);
+        // The startup client was created before settings were loaded
+        if self.settings.server_tokens.contains_key(&self.client.server_url)
+            || self.settings.server_tls.contains_key(&self.client.server_url)
+        {
+            let url = self.client.server_url.clone();
+            self.reconnect(&url);
+        }
         // Load cached peers so the device list is available even when disconnected
         if self.client.peers.is_empty() {
-            let cached = load_cached_peers(path);
+            let cached = load_cached_peers(path, &self.client.server_url);
             if !cached.is_empty() {
-                self.client.peers = cached;
+                self.client.set_peers(cached);
+            }
+        }
+    }
+
+    /// Hand the settings the client acts on to the current client.
+    fn apply_client_settings(&mut self) {
+        self.client.keep_folder_structure = self.settings.keep_folder_structure;
+        self.client.pull_cache_enabled = !self.settings.always_download;
+        self.client.set_poll_interval(self.settings.poll_secs);
+        self.client.cellular_paused = self.cellular_restricted();
+        self.client.set_auto_sync(self.settings.auto_sync);
+        self.client.set_verify_uploads(self.settings.verify_sync_uploads);
+        self.client.set_compress_uploads(self.settings.compress_uploads);
+        self.client.set_rate_limit(self.settings.speed_limit.bytes_per_sec());
+    }
+
+    /// "Wi-Fi only" is on and the phone is on cellular.
+    fn cellular_restricted(&self) -> bool {
+        self.settings.wifi_only && self.network_type == NetworkType::Cellular
+    }
+
+    /// From `NWPathMonitor`: "wifi", "cellular", "none" or anything else.
+    pub fn set_network_type(&mut self, name: &str) {
+        let network_type = NetworkType::from_name(name);
+        if network_type != self.network_type {
+            log::info!("[network] now {:?}", network_type);
+            self.network_type = network_type;
+            self.client.set_cellular_paused(self.cellular_restricted());
+        }
+    }
+
+    /// Transfer pending changes for every sync project now.
+    pub fn sync_now(&mut self) {
+        self.client.sync_now(None);
+    }
+
+    /// Turn automatic sync transfers on or off (saved).
+    pub fn set_auto_sync(&mut self, on: bool) {
+        if self.settings.auto_sync != on {
+            self.settings.auto_sync = on;
+            self.save_settings();
+            self.client.set_auto_sync(on);
+        }
+    }
+
+    fn save_settings(&self) {
+        if let Some(ref dir) = self.client.save_directory {
+            save_settings(dir, &self.settings);
+        }
+    }
+
+    /// The next change check for this server lists only files modified after `now`
+    /// (desktop time).
+    fn mark_changes_seen(&mut self, now: u64) {
+        self.settings.changes_seen.insert(self.client.server_url.clone(), now);
+        self.save_settings();
+    }
+
+    /// Ask before previews / pulls of files larger than these sizes; 0 never asks.
+    pub fn set_size_confirm_limits(&mut self, preview_bytes: u64, pull_bytes: u64) {
+        self.settings.preview_confirm_bytes = preview_bytes;
+        self.settings.pull_confirm_bytes = pull_bytes;
+        if let Some(ref dir) = self.client.save_directory {
+            save_settings(dir, &self.settings);
+        }
+    }
+
+    /// Start a pull or preview, or hold it for confirmation when the file is
+    /// over the limit. The size comes from the current listing. Text and audio
+    /// previews only fetch the head of the file, so just image previews ask.
+    fn pull_or_confirm(&mut self, path: String, path_id: Option<String>, preview: bool) {
+        let limit = if preview {
+            if is_image_ext(&file_extension(&path)) {
+                self.settings.preview_confirm_bytes
+            } else {
+                0
+            }
+        } else {
+            self.settings.pull_confirm_bytes
+        };
+        // On cellular with "Wi-Fi only", large pulls ask even if the normal limit wouldn't
+        let cellular = !preview && self.cellular_restricted();
+        let limit = match (cellular, limit) {
+            (true, 0) => CELLULAR_PULL_CONFIRM_BYTES,
+            (true, limit) => limit.min(CELLULAR_PULL_CONFIRM_BYTES),
+            (false, limit) => limit,
+        };
+        let name = remote_file_name(&path);
+        let size = self
+            .client
+            .remote_files
+            .iter()
+            .find(|f| match (&path_id, &f.path_id) {
+                (Some(id), Some(fid)) => id == fid,
+                _ => f.name == name,
+            })
+            .map(|f| f.size.max(0) as u64);
+        match size {
+            Some(size) if limit > 0 && size > limit => {
+                self.large_transfer = Some(LargeTransfer { path, path_id, size, preview, cellular });
+            }
+            _ => self.start_transfer(&path, path_id.as_deref(), preview),
+        }
+    }
+
+    /// Desktop path of `name` in the folder being browsed.
+    fn remote_entry_path(&self, name: &str) -> String {
+        join_remote_path(&self.browse_path_input, name, self.client.remote_separator())
+    }
+
+    fn start_transfer(&mut self, path: &str, path_id: Option<&str>, preview: bool) {
+        if preview {
+            self.client.preview_file(path, path_id, preview_max_bytes(path));
+        } else {
+            self.client.pull_file(path, path_id);
+        }
+    }
+
+    /// Point the client at a new server URL (e.g. from a bookmark, universal link or
+    /// a scanned `tsdrive://connect?url=...&token=...` join code). Returns false
+    /// without reconnecting if the URL isn't `http(s)://host[:port]`.
+    pub fn set_server_url(&mut self, url: &str) -> bool {
+        let Some((url, token)) = parse_server_input(url) else {
+            return false;
+        };
+        self.server_url_input = url.clone();
+        let token_changed = self.remember_server_token(&url, token);
+        if url != self.client.server_url || token_changed {
+            self.reconnect(&url);
+        }
+        true
+    }
+
+    /// Store a join-code token for `url`. Returns true if it differs from the saved one.
+    fn remember_server_token(&mut self, url: &str, token: Option<String>) -> bool {
+        let Some(token) = token else {
+            return false;
+        };
+        if self.settings.server_tokens.get(url) == Some(&token) {
+            return false;
+        }
+        self.settings.server_tokens.insert(url.to_string(), token);
+        if let Some(ref dir) = self.client.save_directory {
+            save_settings(dir, &self.settings);
+        }
+        true
+    }
+
+    /// Called when the network path changes (Wi-Fi ↔ cellular, VPN up/down).
+    /// The server is re-polled at once on fresh connections, keeping queued uploads,
+    /// transfer progress and sync state (a new client would drop them).
+    pub fn network_changed(&mut self) {
+        self.client.network_changed();
+    }
+
+    /// Manually retry the current server with a fresh client.
+    pub fn reconnect_now(&mut self) {
+        let url = self.client.server_url.clone();
+        self.reconnect(&url);
+    }
+
+    /// Trust the PEM root certificate(s) for the current server instead of the
+    /// bundled roots; empty restores them. Returns false if the PEM has no certificate.
+    pub fn set_server_root_cert(&mut self, pem: &str) -> bool {
+        let pem = pem.trim();
+        let pem = if pem.is_empty() {
+            None
+        } else {
+            if let Err(e) = crate::tailscale_client::parse_root_certs(pem) {
+                self.cert_error = Some(e);
+                return false;
+            }
+            Some(pem.to_string())
+        };
+        self.cert_error = None;
+        self.update_server_tls(|tls| tls.root_cert_pem = pem);
+        true
+    }
+
+    /// Skip certificate verification for the current server. Only for
+    /// self-signed setups; anyone on the path could impersonate the server.
+    pub fn set_accept_invalid_certs(&mut self, accept: bool) {
+        self.update_server_tls(|tls| tls.accept_invalid_certs = accept);
+    }
+
+    /// Edit the current server's TLS options; saves and reconnects if they changed.
+    fn update_server_tls(&mut self, edit: impl FnOnce(&mut TlsOptions)) {
+        let url = self.client.server_url.clone();
+        let before = self.settings.server_tls.get(&url).cloned().unwrap_or_default();
+        let mut tls = before.clone();
+        edit(&mut tls);
+        if tls == before {
+            return;
+        }
+        if tls == TlsOptions::default() {
+            self.settings.server_tls.remove(&url);
+        } else {
+            self.settings.server_tls.insert(url.clone(), tls);
+        }
+        self.save_settings();
+        self.reconnect(&url);
+    }
+
+    pub fn current_server_url(&self) -> String {
+        self.client.server_url.clone()
+    }
+
+    /// Replace the client with one for `url`, keeping the save directory. Peers come
+    /// from that server's cache, falling back to the current list.
+    fn reconnect(&mut self, url: &str) {
+        self.server_url_error = None;
+        let mut cached_peers = std::mem::take(&mut self.client.peers);
+        let same_server = url == self.client.server_url;
+        let save_dir = self.client.save_directory.clone();
+        let pull_cache = std::mem::take(&mut self.client.pull_cache);
+        if let Some(ref dir) = save_dir {
+            let for_server = load_cached_peers(dir, url);
+            if same_server {
+                // The in-memory list is newer than the disk cache for this server
+                cached_peers = merge_peers(for_server, cached_peers);
+            } else if !for_server.is_empty() {
+                cached_peers = for_server;
+            }
+        }
+        let token = self.settings.server_tokens.get(url).cloned();
+        let tls = self.settings.server_tls.get(url).cloned().unwrap_or_default();
+        self.client = TailscaleClient::with_options(url, token, tls);
+        self.client.set_peers(cached_peers);
+        self.client.save_directory = save_dir;
+        self.apply_client_settings();
+        self.client.pull_cache = pull_cache;
+        self.client.set_probe_urls(&self.saved_servers);
+        self.browse_fetched = false;
+        self.auto_browsed = false;
+        self.changes_checked = false;
+        self.selected_remote_idx = None;
+    }
+
+    /// Peer we're connected to: the one picked in the combo, else the one whose
+    /// server URL we're on (e.g. after a bookmark or a typed URL).
+    fn connected_peer_id(&self) -> Option<String> {
+        self.selected_peer_id.clone().or_else(|| {
+            self.client
+                .peers
+                .iter()
+                .find(|p| {
+                    [false, true]
+                        .iter()
+                        .any(|&https| peer_server_url(&p.dns_name, https) == self.client.server_url)
+                })
+                .map(|p| p.id.clone())
+        })
+    }
+
+    /// Previewable files of the current listing in list order (by name), as
+    /// (index into `remote_files`, full path, path id) for the preview's ◀ / ▶.
+    fn previewable_remote_files(&self) -> Vec<(usize, String, Option<String>)> {
+        let mut files: Vec<(usize, String, Option<String>)> = self
+            .client
+            .remote_files
+            .iter()
+            .enumerate()
+            .filter(|(_, f)| !f.is_dir && is_previewable(&file_extension(&f.name)))
+            .map(|(i, f)| {
+                let path = self.remote_entry_path(&f.name);
+                (i, path, f.path_id.clone())
+            })
+            .collect();
+        files.sort_by_key(|(i, _, _)| self.client.remote_files[*i].name.to_lowercase());
+        files
+    }
+
+    /// True once after "Send Clipboard Image" was tapped.
+    pub fn take_clipboard_image_request(&mut self) -> bool {
+        std::mem::take(&mut self.clipboard_image_requested)
+    }
+
+    /// Save RGBA pixels from the iOS pasteboard as a PNG in the save directory
+    /// and upload it to the desktop folder from Settings. Returns false (with a
+    /// status message) if there was nothing to send.
+    pub fn ingest_clipboard_image(&mut self, rgba: &[u8], width: u32, height: u32) -> bool {
+        match self.stage_clipboard_image(rgba, width, height) {
+            Ok((local, remote)) => {
+                self.client.upload_clipboard_image(local, remote);
+                true
+            }
+            Err(e) => {
+                self.client.clipboard_status = Some(format!("🗙 {}", e));
+                false
             }
         }
     }
 
+    /// Write the PNG; returns its local path and desktop destination.
+    fn stage_clipboard_image(&self, rgba: &[u8], width: u32, height: u32) -> Result<(String, String), String> {
+        let dir = self.client.save_directory.as_deref().ok_or("No save directory set")?;
+        if rgba.is_empty() {
+            return Err("No image on the clipboard".to_string());
+        }
+        let image = image::RgbaImage::from_raw(width, height, rgba.to_vec())
+            .ok_or("Clipboard image data doesn't match its size")?;
+        let mut png = Vec::new();
+        image
+            .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+            .map_err(|e| format!("Couldn't encode PNG: {}", e))?;
+        let millis = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_millis())
+            .unwrap_or(0);
+        let name = format!("clipboard-{}.png", millis);
+        let local = format!("{}/{}", dir, name);
+        std::fs::write(&local, &png).map_err(|e| format!("Couldn't save '{}': {}", name, e))?;
+        let dest = self.settings.clipboard_dest_dir.trim().trim_end_matches('/');
+        let dest = if dest.is_empty() { DEFAULT_CLIPBOARD_DEST_DIR } else { dest };
+        Ok((local, format!("{}/{}", dest, name)))
+    }
+
     /// Returns true when there's a newly-saved file ready for the iOS share sheet.
     pub fn has_pending_share(&self) -> bool {
         !self.client.pending_share_paths.is_empty()
@@ -258,18 +840,46 @@ impl Renderer {
         }
     }
 
+    /// Returns true when a file is waiting to be saved through the Files app picker.
+    pub fn has_pending_export(&self) -> bool {
+        !self.client.pending_export_paths.is_empty()
+    }
+
+    /// Pops and returns the full path to the next file for "Save to Files".
+    pub fn consume_export_path(&mut self) -> String {
+        if self.client.pending_export_paths.is_empty() {
+            String::new()
+        } else {
+            self.client.pending_export_paths.remove(0)
+        }
+    }
+
+    /// Stop the network thread before the renderer goes away, so a recreated
+    /// renderer doesn't run alongside the old one's.
+    pub fn shutdown(&mut self) {
+        self.client.shutdown(CLIENT_SHUTDOWN_WAIT);
+    }
+
     pub fn resize(&mut self, width_px: u32, height_px: u32) {
         if width_px == 0 || height_px == 0 {
             return;
         }
+        if width_px == self.config.width && height_px == self.config.height {
+            return;
+        }
         self.config.width = width_px;
         self.config.height = height_px;
         self.surface.configure(&self.device, &self.config);
+        self.relayout_pending = true;
     }
 
     // Touch input is in points
     pub fn touch_began(&mut self, x_pt: f32, y_pt: f32) {
         let pos = pos2(x_pt, y_pt);
+        // A new touch catches a running fling
+        self.momentum = None;
+        self.touch_track = Some((pos, Instant::now()));
+        self.touch_velocity = Vec2::ZERO;
         self.pending_events.push(egui::Event::PointerMoved(pos));
         self.pending_events.push(egui::Event::PointerButton {
             pos,
@@ -280,11 +890,25 @@ impl Renderer {
         // Start tracking for long-press (context menu on iOS)
         self.long_press_start = Some((x_pt, y_pt, Instant::now()));
         self.long_press_fired = false;
+        self.swipe_start = Some((x_pt, y_pt, Instant::now()));
+
+        // Second press shortly after and near the first one is a double tap.
+        // A double tap consumes the pair so a triple tap doesn't count twice.
+        self.double_tap = self.last_tap.is_some_and(|(lx, ly, at)| {
+            let (dx, dy) = (x_pt - lx, y_pt - ly);
+            at.elapsed() <= Duration::from_millis(350) && (dx * dx + dy * dy).sqrt() <= 20.0
+        });
+        self.last_tap = if self.double_tap {
+            None
+        } else {
+            Some((x_pt, y_pt, Instant::now()))
+        };
     }
 
     pub fn touch_moved(&mut self, x_pt: f32, y_pt: f32) {
         self.pending_events
             .push(egui::Event::PointerMoved(pos2(x_pt, y_pt)));
+        self.track_touch_velocity(pos2(x_pt, y_pt));
         // Cancel long-press if finger moved too far from start
         if let Some((sx, sy, _)) = self.long_press_start {
             let dx = x_pt - sx;
@@ -297,12 +921,42 @@ impl Renderer {
 
     pub fn touch_ended(&mut self, x_pt: f32, y_pt: f32) {
         self.long_press_start = None;
+        // A mostly-horizontal flick that is either long or fast flips pages.
+        // Whether it lands on a scrollable overlay is decided in `render`.
+        if let Some((sx, sy, at)) = self.swipe_start.take() {
+            let (dx, dy) = (x_pt - sx, y_pt - sy);
+            let ms = at.elapsed().as_millis().max(1) as f32;
+            let horizontal = dx.abs() > SWIPE_MIN_DISTANCE && dx.abs() > 2.0 * dy.abs();
+            if horizontal && (dx.abs() >= SWIPE_LONG_DISTANCE || dx.abs() / ms >= SWIPE_MIN_VELOCITY) {
+                self.pending_swipe = Some((sx, sy, dx));
+            }
+        }
+        // Fling unless this was a page swipe or a long press, or the finger
+        // came to rest before lifting
+        let pos = pos2(x_pt, y_pt);
+        let moving = self
+            .touch_track
+            .is_some_and(|(_, at)| at.elapsed() < MOMENTUM_HOLD_CANCEL);
+        self.track_touch_velocity(pos);
+        self.touch_track = None;
+        let velocity = self.touch_velocity;
+        self.touch_velocity = Vec2::ZERO;
+        if moving
+            && self.pending_swipe.is_none()
+            && !self.long_press_fired
+            && velocity.length() >= MOMENTUM_MIN_VELOCITY
+        {
+            self.momentum = Some(Momentum {
+                pos,
+                velocity,
+                last_tick: Instant::now(),
+            });
+        }
         // If a long-press just fired, suppress the normal touch-end sequence
         // to avoid PointerGone closing the freshly-opened context menu.
         if self.long_press_fired {
             return;
         }
-        let pos = pos2(x_pt, y_pt);
         self.pending_events.push(egui::Event::PointerMoved(pos));
         self.pending_events.push(egui::Event::PointerButton {
             pos,
@@ -314,6 +968,89 @@ impl Renderer {
         self.pending_events.push(egui::Event::PointerGone);
     }
 
+    /// Fold the latest touch sample into the smoothed finger velocity.
+    fn track_touch_velocity(&mut self, pos: Pos2) {
+        let now = Instant::now();
+        let Some((last, at)) = self.touch_track else {
+            return;
+        };
+        let dt = now.duration_since(at).as_secs_f32();
+        // Coalesced touches a few microseconds apart would give absurd speeds
+        if dt < 0.002 {
+            return;
+        }
+        let mut sample = (pos - last) / dt;
+        if sample.length() > MOMENTUM_MAX_VELOCITY {
+            sample = sample.normalized() * MOMENTUM_MAX_VELOCITY;
+        }
+        // Weighted toward recent samples so the fling follows the end of the gesture
+        self.touch_velocity = self.touch_velocity * 0.2 + sample * 0.8;
+        self.touch_track = Some((pos, now));
+    }
+
+    /// UIKit `safeAreaInsets`, in points. The top bar starts below `top`, the
+    /// UI stays between `left` and `right`, and pages scroll clear of `bottom`.
+    pub fn set_safe_area_insets(&mut self, top: f32, bottom: f32, left: f32, right: f32) {
+        self.safe_area = SafeAreaInsets {
+            top: top.max(0.0),
+            bottom: bottom.max(0.0),
+            left: left.max(0.0),
+            right: right.max(0.0),
+        };
+    }
+
+    /// Fraction of fling speed kept per millisecond: 0.998 matches
+    /// UIScrollView's normal rate, 0.99 its fast one. Lower stops sooner.
+    pub fn set_scroll_deceleration(&mut self, rate: f32) {
+        self.scroll_deceleration = rate.clamp(0.9, 0.9995);
+    }
+
+    /// Advance the fling by one frame, queueing its wheel events.
+    /// Returns the delta injected.
+    fn tick_momentum(&mut self) -> Vec2 {
+        let Some(m) = self.momentum.as_mut() else {
+            return Vec2::ZERO;
+        };
+        let now = Instant::now();
+        // Cap the step so a stalled frame doesn't jump the list
+        let secs = now.duration_since(m.last_tick).as_secs_f32().min(0.05);
+        m.last_tick = now;
+        if m.velocity.length() < MOMENTUM_STOP_VELOCITY {
+            self.momentum = None;
+            self.pending_events.push(egui::Event::PointerGone);
+            return Vec2::ZERO;
+        }
+        let delta = m.velocity * secs;
+        m.velocity *= self.scroll_deceleration.powf(secs * 1000.0);
+
+        self.pending_events.push(egui::Event::PointerMoved(m.pos));
+        let steps = (delta.length() / MOMENTUM_WHEEL_STEP).ceil().max(1.0);
+        for _ in 0..steps as usize {
+            self.pending_events.push(egui::Event::MouseWheel {
+                unit: egui::MouseWheelUnit::Point,
+                delta: delta / steps,
+                modifiers: egui::Modifiers::default(),
+            });
+        }
+        delta
+    }
+
+    /// Clamp the fling at the ends of the list. A scroll area only takes
+    /// wheel delta while it can still move that way, so whatever is left
+    /// over after the frame means the fling ran out of content on that axis.
+    fn clamp_momentum(&mut self) {
+        let injected = std::mem::take(&mut self.momentum_injected);
+        let Some(m) = self.momentum.as_mut() else {
+            return;
+        };
+        let left = self.egui_ctx.input(|i| i.smooth_scroll_delta);
+        for d in 0..2 {
+            if injected[d] != 0.0 && left[d].abs() >= injected[d].abs() * 0.5 {
+                m.velocity[d] = 0.0;
+            }
+        }
+    }
+
     // ── Notification API (called from Swift via bridge) ─────────────────
 
     pub fn has_pending_notification(&self) -> bool {
@@ -327,6 +1064,133 @@ impl Renderer {
             .unwrap_or_default()
     }
 
+    /// Process client events and return every queued notification as a JSON
+    /// array of `{"title", "body"}`, emptying the queue. For short background
+    /// windows where `render` isn't being called.
+    pub fn drain_all_notifications(&mut self) -> String {
+        self.client.process_events();
+        self.collect_notifications();
+        let notifications: Vec<_> = self
+            .pending_notifications
+            .drain(..)
+            .map(|(title, body)| serde_json::json!({ "title": title, "body": body }))
+            .collect();
+        serde_json::Value::Array(notifications).to_string()
+    }
+
+    /// Queued notifications, after picking up new client events.
+    pub fn notification_count(&mut self) -> usize {
+        self.client.process_events();
+        self.collect_notifications();
+        self.pending_notifications.len()
+    }
+
+    /// Drop every queued notification without delivering it, and empty the
+    /// Activity window's list.
+    pub fn clear_notifications(&mut self) {
+        self.collect_notifications();
+        self.pending_notifications.clear();
+        self.notification_log.clear();
+    }
+
+    fn queue_notification(&mut self, title: String, body: String) {
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        if self.notification_log.len() == NOTIFICATION_LOG_LIMIT {
+            self.notification_log.pop_front();
+        }
+        self.notification_log.push_back((title.clone(), body.clone(), now));
+        self.pending_notifications.push((title, body));
+    }
+
+    /// The Activity window's list of recent notifications, newest first, with a
+    /// Clear button that also drops any not yet delivered.
+    fn draw_notification_log(&mut self, ui: &mut egui::Ui) {
+        let mut clear = false;
+        egui::CollapsingHeader::new(format!("🔔 Notifications ({})", self.notification_log.len()))
+            .id_salt("notification_log")
+            .show(ui, |ui| {
+                if ui.small_button("✔ Clear").clicked() {
+                    clear = true;
+                }
+                for (title, body, at) in self.notification_log.iter().rev() {
+                    ui.label(RichText::new(title).strong());
+                    ui.label(RichText::new(format!("{} · {}", body, format_timestamp(*at))).weak().small());
+                }
+            });
+        if clear {
+            self.clear_notifications();
+        }
+    }
+
+    /// Move the client's new sync results into the history and save it.
+    fn collect_sync_feed(&mut self) {
+        if self.client.pending_sync_feed.is_empty() {
+            return;
+        }
+        self.sync_feed.extend(self.client.pending_sync_feed.drain(..));
+        let excess = self.sync_feed.len().saturating_sub(SYNC_FEED_LIMIT);
+        self.sync_feed.drain(..excess);
+        self.save_sync_feed();
+    }
+
+    fn save_sync_feed(&self) {
+        if let Some(ref dir) = self.client.save_directory {
+            save_sync_feed(dir, &self.sync_feed);
+        }
+    }
+
+    /// The Project Sync page's history of sync results, newest first, each
+    /// dismissable.
+    fn draw_sync_feed(&mut self, ui: &mut egui::Ui) {
+        let mut dismiss: Option<usize> = None;
+        let mut clear = false;
+        egui::CollapsingHeader::new(format!("📋 Sync history ({})", self.sync_feed.len()))
+            .id_salt("sync_feed")
+            .show(ui, |ui| {
+                if ui.small_button("✔ Clear").clicked() {
+                    clear = true;
+                }
+                egui::ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                    for (i, entry) in self.sync_feed.iter().enumerate().rev() {
+                        ui.push_id(i, |ui| {
+                            ui.horizontal(|ui| {
+                                let (icon, color) = if entry.failed {
+                                    ("🗙", Color32::from_rgb(231, 76, 60))
+                                } else {
+                                    ("✔", Color32::from_rgb(46, 204, 113))
+                                };
+                                ui.colored_label(color, icon);
+                                if !entry.project.is_empty() {
+                                    ui.label(RichText::new(&entry.project).strong());
+                                }
+                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                    if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                                        dismiss = Some(i);
+                                    }
+                                });
+                            });
+                            ui.label(
+                                RichText::new(format!("{} · {}", entry.message, format_timestamp(entry.at)))
+                                    .weak()
+                                    .small(),
+                            );
+                        });
+                    }
+                });
+            });
+        if clear {
+            self.sync_feed.clear();
+        } else if let Some(i) = dismiss {
+            self.sync_feed.remove(i);
+        }
+        if clear || dismiss.is_some() {
+            self.save_sync_feed();
+        }
+    }
+
     /// Returns the body AND consumes (pops) the front notification.
     pub fn consume_notification_body(&mut self) -> String {
         if self.pending_notifications.is_empty() {
@@ -337,6 +1201,50 @@ impl Renderer {
         }
     }
 
+    /// Queue notifications for files received / sent since the last check and
+    /// for sync events from the client.
+    fn collect_notifications(&mut self) {
+        self.collect_sync_feed();
+
+        // Check for new received files
+        if self.client.last_received_file != self.last_known_received {
+            if let Some(ref name) = self.client.last_received_file {
+                if self.last_known_received.is_some() {
+                    // Not the initial load — a genuinely new file
+                    let body = format!("Tap to download: {}", name);
+                    self.queue_notification("File Ready".to_string(), body);
+                }
+            }
+            self.last_known_received = self.client.last_received_file.clone();
+        }
+
+        // Check for new sent files — only track when succeeded to avoid
+        // missing the notification (status updates while sending would set the
+        // name but succeeded=false, then when it flips to true the name matches
+        // and we'd skip the notification).
+        if let Some(ref sent) = self.client.last_sent {
+            if sent.succeeded {
+                let sent_name = Some(sent.name.clone());
+                if sent_name != self.last_known_sent_name {
+                    if self.last_known_sent_name.is_some() {
+                        let body = format!(
+                            "Desktop sent {} to {}",
+                            sent.name,
+                            self.client.peer_name(&sent.peer_id)
+                        );
+                        self.queue_notification("File Sent".to_string(), body);
+                    }
+                    self.last_known_sent_name = sent_name;
+                }
+            }
+        }
+
+        // Drain sync notifications
+        while let Some((title, body)) = self.client.pending_sync_notifications.pop() {
+            self.queue_notification(title, body);
+        }
+    }
+
     // ── iOS Keyboard API (called from Swift via bridge) ───────────────
 
     /// Returns true when an egui text edit has focus and wants keyboard input.
@@ -441,8 +1349,8 @@ impl Renderer {
                         pending.desktop_modified = info.modified;
                         pending.desktop_size = info.size;
                         if info.exists {
-                            // File exists — show the overwrite modal
-                            self.show_overwrite_modal = true;
+                            let p = pending.clone();
+                            self.resolve_overwrite(p);
                         } else {
                             // File doesn't exist — proceed directly
                             let p = pending.clone();
@@ -454,54 +1362,37 @@ impl Renderer {
             }
         }
 
-        // Auto-browse server CWD once connected
+        // Auto-browse once connected: this device's default folder, else the server CWD
         if self.client.connected && !self.auto_browsed {
-            if let Some(ref cwd) = self.client.server_cwd {
-                self.browse_path_input = cwd.clone();
-                self.client.browse(Some(cwd.clone()));
+            let start = self
+                .connected_peer_id()
+                .and_then(|id| self.settings.peer_default_paths.get(&id).cloned())
+                .or_else(|| self.client.server_cwd.clone());
+            if let Some(path) = start {
+                self.browse_path_input = path.clone();
+                self.client.browse(Some(path));
                 self.auto_browsed = true;
                 self.browse_fetched = true;
             }
         }
 
-        // ── Notification detection ──
-        // Check for new received files
-        if self.client.last_received_file != self.last_known_received {
-            if let Some(ref name) = self.client.last_received_file {
-                if self.last_known_received.is_some() {
-                    // Not the initial load — a genuinely new file
-                    self.pending_notifications.push((
-                        "File Ready".to_string(),
-                        format!("Tap to download: {}", name),
-                    ));
-                }
+        // Once per connection: what changed on the desktop since the last visit
+        if !self.client.connected {
+            self.changes_checked = false;
+        } else if !self.changes_checked && self.client.server_version.is_some() {
+            self.changes_checked = true;
+            if self.client.supports_feature("changes") {
+                // First visit: only later changes are news. Nothing is newer than
+                // u64::MAX, so the empty answer just seeds the desktop's own clock.
+                let since = self.settings.changes_seen.get(&self.client.server_url).copied();
+                self.client.fetch_changes(since.unwrap_or(u64::MAX));
             }
-            self.last_known_received = self.client.last_received_file.clone();
         }
-
-        // Check for new sent files — only track when succeeded to avoid
-        // missing the notification (status updates while sending would set the
-        // name but succeeded=false, then when it flips to true the name matches
-        // and we'd skip the notification).
-        if let Some(ref sent) = self.client.last_sent {
-            if sent.succeeded {
-                let sent_name = Some(sent.name.clone());
-                if sent_name != self.last_known_sent_name {
-                    if self.last_known_sent_name.is_some() {
-                        self.pending_notifications.push((
-                            "File Sent".to_string(),
-                            format!("Desktop sent: {}", sent.name),
-                        ));
-                    }
-                    self.last_known_sent_name = sent_name;
-                }
-            }
+        if let Some(changes) = self.client.changes.take_if(|c| c.files.is_empty()) {
+            self.mark_changes_seen(changes.now);
         }
 
-        // Drain sync notifications
-        while let Some((title, body)) = self.client.pending_sync_notifications.pop() {
-            self.pending_notifications.push((title, body));
-        }
+        self.collect_notifications();
 
         // Validate selected file index
         if let Some(idx) = self.selected_file_idx {
@@ -509,35 +1400,45 @@ impl Renderer {
                 self.selected_file_idx = None;
             }
         }
+        if self.selected_remote_idx.is_some_and(|idx| idx >= self.client.remote_files.len()) {
+            self.selected_remote_idx = None;
+        }
+        if self.selected_local_idx.is_some_and(|idx| idx >= self.local_files.len()) {
+            self.selected_local_idx = None;
+        }
 
         // Take preview content from client if available
-        if let Some((filename, data)) = self.client.preview_content.take() {
+        if let Some((filename, data, total_size)) = self.client.preview_content.take() {
             let ext = file_extension(&filename);
             self.preview_filename = filename.clone();
             self.preview_texture = None;
+            self.preview_audio = None;
             self.preview_text.clear();
 
-            if is_image_ext(&ext) {
-                if let Ok(img) = image::load_from_memory(&data) {
-                    let rgba = img.to_rgba8();
-                    let size = [rgba.width() as usize, rgba.height() as usize];
-                    let color_image =
-                        egui::ColorImage::from_rgba_unmultiplied(size, rgba.as_raw());
-                    let texture = self.egui_ctx.load_texture(
-                        &filename,
-                        color_image,
-                        egui::TextureOptions::LINEAR,
-                    );
-                    self.preview_texture = Some(texture);
-                } else {
-                    self.preview_text = "(Failed to decode image)".to_string();
+            if is_audio_ext(&ext) {
+                match read_audio_info(&data, total_size) {
+                    Ok(info) => self.preview_audio = Some(info),
+                    Err(e) => self.preview_text = format!("(No audio metadata in the first {}: {})", format_size(data.len() as u64), e),
+                }
+            } else if is_image_ext(&ext) {
+                match decode_preview_image(&data) {
+                    Ok(color_image) => {
+                        let texture = self.egui_ctx.load_texture(
+                            &filename,
+                            color_image,
+                            egui::TextureOptions::LINEAR,
+                        );
+                        self.preview_texture = Some(texture);
+                    }
+                    Err(message) => self.preview_text = message.to_string(),
                 }
             } else {
                 let text = String::from_utf8_lossy(&data);
-                if text.len() > 100_000 {
+                let limit = PREVIEW_TEXT_MAX_BYTES as usize;
+                if text.len() > limit {
                     self.preview_text = format!(
                         "{}…\n\n(truncated at 100 KB)",
-                        &text[..100_000]
+                        &text[..limit]
                     );
                 } else {
                     self.preview_text = text.into_owned();
@@ -558,10 +1459,10 @@ impl Renderer {
         let mut viewports = egui::ViewportIdMap::default();
         viewports.insert(egui::ViewportId::ROOT, viewport_info);
 
-        // Long-press detection: after 500ms hold without movement, inject a
+        // Long-press detection: after the configured hold without movement, inject a
         // secondary (right-click) event to trigger egui context menus on iOS.
         if let Some((x, y, start_time)) = self.long_press_start {
-            if !self.long_press_fired && start_time.elapsed() >= Duration::from_millis(500) {
+            if !self.long_press_fired && start_time.elapsed() >= Duration::from_millis(self.settings.long_press_ms) {
                 self.long_press_fired = true;
                 self.long_press_start = None;
                 let pos = pos2(x, y);
@@ -588,10 +1489,17 @@ impl Renderer {
             }
         }
 
+        self.momentum_injected = self.tick_momentum();
+
+        // Side insets shrink the screen; the top and bottom ones pad the panels
+        // so their backgrounds still reach the edges
+        let inset = self.safe_area;
+        let left = inset.left.min(width_pt / 4.0);
+        let right = (width_pt - inset.right).max(left + width_pt / 2.0);
         let raw_input = egui::RawInput {
-            screen_rect: Some(egui::Rect::from_min_size(
-                egui::Pos2::new(0.0, 0.0),
-                vec2(width_pt, height_pt),
+            screen_rect: Some(egui::Rect::from_min_max(
+                egui::Pos2::new(left, 0.0),
+                egui::Pos2::new(right, height_pt),
             )),
             time: Some(time_seconds),
             events: std::mem::take(&mut self.pending_events),
@@ -610,8 +1518,9 @@ impl Renderer {
             let mut do_download_last = false;
             let mut do_refresh = false;
             let mut do_browse: Option<Option<String>> = None;
-            let mut file_to_pull: Option<String> = None;
-            let mut file_to_preview: Option<String> = None;
+            // (display path, server path id)
+            let mut file_to_pull: Option<(String, Option<String>)> = None;
+            let mut file_to_preview: Option<(String, Option<String>)> = None;
             let mut do_upload: Option<(String, String)> = None; // (local, remote)
             let mut do_create_sync: Option<(String, String)> = None; // (local, remote)
             let mut do_delete_sync: Option<String> = None;
@@ -621,7 +1530,7 @@ impl Renderer {
             //  TOP BAR
             // ═══════════════════════════════════════════════════
             egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
-                ui.add_space(50.0);
+                ui.add_space(self.safe_area.top);
                 ui.horizontal(|ui| {
                     ui.heading(RichText::new("📡 Tailscale Drive").strong());
                     ui.separator();
@@ -639,6 +1548,32 @@ impl Renderer {
                             if ui.button("⟳ Refresh").clicked() {
                                 do_refresh = true;
                             }
+                            if ui.selectable_label(self.show_logs, "📜 Logs").clicked() {
+                                self.show_logs = !self.show_logs;
+                            }
+                            if let Some(n) = self.client.changes.as_ref().map(|c| c.files.len())
+                                && ui
+                                    .selectable_label(self.show_changes, format!("🆕 {}", n))
+                                    .on_hover_text("Changed on the desktop since your last visit")
+                                    .clicked()
+                            {
+                                self.show_changes = !self.show_changes;
+                            }
+                            if ui.selectable_label(self.show_activity, "🕘 Activity").clicked() {
+                                self.show_activity = !self.show_activity;
+                                if self.show_activity {
+                                    self.client.fetch_received();
+                                }
+                            }
+                            if ui.selectable_label(self.show_settings, "⚙ Settings").clicked() {
+                                self.show_settings = !self.show_settings;
+                                self.token_input = self
+                                    .settings
+                                    .server_tokens
+                                    .get(&self.client.server_url)
+                                    .cloned()
+                                    .unwrap_or_default();
+                            }
                         },
                     );
                 });
@@ -648,6 +1583,13 @@ impl Renderer {
                         .weak()
                         .small(),
                 );
+                if self.client.save_directory.is_none() {
+                    ui.colored_label(
+                        Color32::from_rgb(241, 196, 15),
+                        RichText::new("⚠ No save location configured: downloads and pulls are disabled")
+                            .small(),
+                    );
+                }
 
                 ui.add_space(4.0);
 
@@ -666,6 +1608,20 @@ impl Renderer {
                         self.current_page = Page::Monitor;
                     }
 
+                    if !self.client.supports_feature("sync") {
+                        // Server doesn't speak /sync/*; fall back to Monitor
+                        self.current_page = Page::Monitor;
+                        if self.client.connected {
+                            ui.separator();
+                            ui.label(
+                                RichText::new("Sync not supported by this server")
+                                    .small()
+                                    .color(Color32::GRAY),
+                            );
+                        }
+                        return;
+                    }
+
                     ui.separator();
 
                     if ui
@@ -675,65 +1631,124 @@ impl Renderer {
                         )
                         .clicked()
                     {
-                        self.current_page = Page::ProjectSync;
-                        if !self.browse_fetched {
-                            do_browse = Some(None);
-                            self.browse_fetched = true;
-                        }
-                        if !self.sync_projects_fetched {
-                            self.client.fetch_sync_projects();
-                            self.sync_projects_fetched = true;
-                        }
-                        // Initialize local browse path from save directory
-                        if self.local_browse_path.is_empty() {
-                            if let Some(ref dir) = self.client.save_directory {
-                                // Go up from Downloads to the Documents dir
-                                if let Some(parent) = std::path::Path::new(dir).parent() {
-                                    self.local_browse_path = parent.to_string_lossy().to_string();
-                                } else {
-                                    self.local_browse_path = dir.clone();
-                                }
-                            }
-                            self.refresh_local_files();
-                        }
+                        self.open_project_sync(&mut do_browse);
+                    }
+                    // Badge: projects with changes in flight or a failed last sync
+                    let attention = self.client.sync_attention_count();
+                    if attention > 0 {
+                        let color = if self.client.failing_sync_projects() > 0 {
+                            Color32::from_rgb(231, 76, 60)
+                        } else {
+                            Color32::from_rgb(52, 152, 219)
+                        };
+                        ui.label(
+                            RichText::new(format!(" {} ", attention))
+                                .small()
+                                .strong()
+                                .color(Color32::WHITE)
+                                .background_color(color),
+                        )
+                        .on_hover_text("Sync projects with pending changes or errors");
                     }
                 });
 
                 ui.add_space(2.0);
             });
 
+            // ── Page swipe ──
+            // Only swipes that start on the panels count; the preview window
+            // (which scrolls horizontally) and modals live on higher layers.
+            if let Some((sx, sy, dx)) = self.pending_swipe.take() {
+                let on_panels = ctx
+                    .layer_id_at(pos2(sx, sy))
+                    .is_none_or(|layer| layer.order == egui::Order::Background);
+                if on_panels && !self.show_overwrite_modal && !ctx.wants_keyboard_input() {
+                    match self.current_page {
+                        Page::Monitor if dx < 0.0 && self.client.supports_feature("sync") => {
+                            self.open_project_sync(&mut do_browse);
+                        }
+                        Page::ProjectSync if dx > 0.0 => self.current_page = Page::Monitor,
+                        _ => {}
+                    }
+                }
+            }
+            if self.current_page != self.last_drawn_page {
+                // Sync sits to the right of Monitor
+                let dir = if self.current_page == Page::ProjectSync { 1.0 } else { -1.0 };
+                self.page_slide = Some((dir, Instant::now()));
+                self.last_drawn_page = self.current_page;
+            }
+
             // ═══════════════════════════════════════════════════
             //  CENTRAL PANEL
             // ═══════════════════════════════════════════════════
             egui::CentralPanel::default().show(ctx, |ui| {
-                egui::ScrollArea::vertical()
-                    .auto_shrink([false, false])
-                    .show(ui, |ui| {
-                        match self.current_page {
-                            Page::Monitor => {
-                                self.draw_monitor_page(
-                                    ui,
-                                    &mut reconnect_url,
-                                    &mut file_to_download,
-                                    &mut do_download_last,
-                                    &mut do_browse,
-                                    &mut file_to_pull,
-                                    &mut file_to_preview,
-                                );
-                            }
-                            Page::ProjectSync => {
-                                self.draw_project_sync_page(
-                                    ui,
-                                    &mut do_browse,
-                                    &mut file_to_pull,
-                                    &mut do_upload,
-                                    &mut do_create_sync,
-                                    &mut do_delete_sync,
-                                    &mut do_fetch_sync_projects,
-                                );
+                // Ease-out slide of the whole page while the animation runs
+                let progress = self
+                    .page_slide
+                    .map(|(dir, at)| (dir, at.elapsed().as_secs_f32() / PAGE_SLIDE_SECS));
+                let offset = match progress {
+                    Some((dir, t)) if t < 1.0 => {
+                        ctx.request_repaint();
+                        dir * ui.available_width() * (1.0 - t).powi(3)
+                    }
+                    _ => {
+                        self.page_slide = None;
+                        0.0
+                    }
+                };
+                let page_rect = ui.max_rect().translate(vec2(offset, 0.0));
+                // After a rotation (or the preview's ◀ / ▶) the selected row is
+                // brought into view; without one the page keeps its relative scroll
+                let relayout = std::mem::take(&mut self.relayout_pending);
+                self.scroll_to_selection |= relayout
+                    && match self.current_page {
+                        Page::Monitor => self.selected_remote_idx.is_some(),
+                        Page::ProjectSync => self.selected_local_idx.is_some(),
+                    };
+                ui.scope_builder(egui::UiBuilder::new().max_rect(page_rect), |ui| {
+                    let mut area = egui::ScrollArea::vertical().auto_shrink([false, false]);
+                    if let Some(offset) = self.restore_scroll.take() {
+                        area = area.vertical_scroll_offset(offset);
+                    }
+                    let output = area.show(ui, |ui| {
+                            match self.current_page {
+                                Page::Monitor => {
+                                    self.draw_monitor_page(
+                                        ui,
+                                        &mut reconnect_url,
+                                        &mut file_to_download,
+                                        &mut do_download_last,
+                                        &mut do_browse,
+                                        &mut file_to_pull,
+                                        &mut file_to_preview,
+                                    );
+                                }
+                                Page::ProjectSync => {
+                                    self.draw_project_sync_page(
+                                        ui,
+                                        &mut do_browse,
+                                        &mut file_to_pull,
+                                        &mut do_upload,
+                                        &mut do_create_sync,
+                                        &mut do_delete_sync,
+                                        &mut do_fetch_sync_projects,
+                                    );
+                                }
                             }
-                        }
-                    });
+                            // Let the last rows scroll above the home indicator
+                            ui.add_space(self.safe_area.bottom);
+                        });
+                    let scrollable = (output.content_size.y - output.inner_rect.height()).max(0.0);
+                    if relayout && !self.scroll_to_selection {
+                        // This frame laid out at the new size; restore on the next one
+                        self.restore_scroll = Some(self.page_scroll_fraction * scrollable);
+                        ctx.request_repaint();
+                    } else if scrollable > 0.0 {
+                        self.page_scroll_fraction = output.state.offset.y / scrollable;
+                    }
+                    self.scroll_to_selection = false;
+                });
             });
 
             // ═══════════════════════════════════════════════════
@@ -741,12 +1756,38 @@ impl Renderer {
             // ═══════════════════════════════════════════════════
             if self.show_preview {
                 let mut open = true;
+                // Fixed id so a pinned window stays put as its title changes
                 egui::Window::new(format!("Preview: {}", self.preview_filename))
+                    .id(egui::Id::new("preview_window"))
                     .open(&mut open)
                     .resizable(true)
                     .collapsible(false)
                     .default_size([width_pt - 40.0, height_pt * 0.7])
                     .show(ctx, |ui| {
+                        let neighbours = self.previewable_remote_files();
+                        let current = neighbours
+                            .iter()
+                            .position(|(_, path, _)| Some(path) == self.preview_path.as_ref());
+                        ui.horizontal(|ui| {
+                            ui.toggle_value(&mut self.preview_pinned, "📌 Pin")
+                                .on_hover_text("Keep this window open; tapping another file previews it here");
+                            if let Some(pos) = current {
+                                let mut step = None;
+                                if ui.add_enabled(pos > 0, egui::Button::new("◀")).clicked() {
+                                    step = Some(pos - 1);
+                                }
+                                ui.label(RichText::new(format!("{}/{}", pos + 1, neighbours.len())).weak().small());
+                                if ui.add_enabled(pos + 1 < neighbours.len(), egui::Button::new("▶")).clicked() {
+                                    step = Some(pos + 1);
+                                }
+                                if let Some((idx, path, path_id)) = step.and_then(|i| neighbours.get(i)).cloned() {
+                                    self.selected_remote_idx = Some(idx);
+                                    self.scroll_to_selection = true;
+                                    file_to_preview = Some((path, path_id));
+                                }
+                            }
+                        });
+                        ui.separator();
                         if let Some(ref texture) = self.preview_texture {
                             // Image preview
                             egui::ScrollArea::both()
@@ -761,6 +1802,8 @@ impl Renderer {
                                         vec2(tex_size.x * scale, tex_size.y * scale);
                                     ui.image((texture.id(), display_size));
                                 });
+                        } else if let Some(ref info) = self.preview_audio {
+                            draw_audio_info(ui, info);
                         } else {
                             // Text / code preview
                             egui::ScrollArea::both()
@@ -778,12 +1821,246 @@ impl Renderer {
                     });
                 if !open {
                     self.show_preview = false;
+                    self.preview_pinned = false;
+                    self.preview_path = None;
                     self.preview_text.clear();
                     self.preview_texture = None;
+                    self.preview_audio = None;
                     self.preview_filename.clear();
                 }
             }
 
+            // ═══════════════════════════════════════════════════
+            //  SYNC PREVIEW (dry run, confirm before syncing)
+            // ═══════════════════════════════════════════════════
+            if let Some(plan) = self.client.sync_plan.clone() {
+                let mut close = false;
+                let mut open = true;
+                let name = plan.remote_path.rsplit('/').next().unwrap_or(&plan.remote_path).to_string();
+                egui::Window::new(format!("Sync preview: {}", name))
+                    .open(&mut open)
+                    .collapsible(false)
+                    .default_size([width_pt - 40.0, height_pt * 0.5])
+                    .show(ctx, |ui| {
+                        if plan.paused {
+                            ui.label(RichText::new("Paused — resume the project to sync it").weak());
+                        } else if plan.entries.is_empty() {
+                            ui.label(RichText::new("Everything is up to date — nothing would transfer").weak());
+                        } else {
+                            egui::ScrollArea::vertical().max_height(height_pt * 0.35).show(ui, |ui| {
+                                for entry in &plan.entries {
+                                    let (icon, what) = match entry.action.as_str() {
+                                        "add" => ("➕", "new → iPhone"),
+                                        "modify" => ("✏", "changed → iPhone"),
+                                        "delete" => ("🗑", "missing on desktop"),
+                                        "push" => ("⬆", "changed → desktop"),
+                                        _ => ("•", entry.action.as_str()),
+                                    };
+                                    let file = entry.path.rsplit('/').next().unwrap_or(&entry.path);
+                                    ui.horizontal(|ui| {
+                                        ui.label(format!("{} {}", icon, file));
+                                        ui.label(RichText::new(what).weak().small());
+                                        if entry.action != "delete" {
+                                            ui.label(RichText::new(format_size(entry.size)).weak().small());
+                                        }
+                                    });
+                                }
+                            });
+                            if plan.truncated {
+                                ui.label(RichText::new("Listing cut short — the folder is very large").weak().small());
+                            }
+                        }
+                        ui.separator();
+                        ui.label(format!("Total to transfer: {}", format_size(plan.total_bytes)));
+                        ui.horizontal(|ui| {
+                            if !plan.entries.is_empty() && ui.button("⟳ Sync now").clicked() {
+                                // The next poll performs exactly this plan
+                                self.client.refresh();
+                                close = true;
+                            }
+                            if ui.button("Cancel").clicked() {
+                                close = true;
+                            }
+                        });
+                    });
+                if close || !open {
+                    self.client.sync_plan = None;
+                }
+            }
+
+            // ═══════════════════════════════════════════════════
+            //  LOG WINDOW (floating overlay, hidden by default)
+            // ═══════════════════════════════════════════════════
+            egui::Window::new("Logs")
+                .open(&mut self.show_logs)
+                .resizable(true)
+                .default_size([width_pt - 40.0, height_pt * 0.5])
+                .show(ctx, |ui| egui_logger::logger_ui().show(ui));
+
+            // ═══════════════════════════════════════════════════
+            //  SETTINGS WINDOW
+            // ═══════════════════════════════════════════════════
+            if self.show_settings {
+                let mut open = true;
+                egui::Window::new("Settings")
+                    .open(&mut open)
+                    .collapsible(false)
+                    .default_size([width_pt - 40.0, height_pt * 0.6])
+                    .show(ctx, |ui| {
+                        egui::ScrollArea::vertical().show(ui, |ui| self.draw_settings(ui, &mut reconnect_url));
+                    });
+                self.show_settings &= open;
+            }
+
+            // ═══════════════════════════════════════════════════
+            //  ACTIVITY TIMELINE (both directions, newest first)
+            // ═══════════════════════════════════════════════════
+            if self.show_activity {
+                let mut open = true;
+                let mut reveal: Option<String> = None;
+                let entries = self.client.activity_timeline();
+                egui::Window::new("Recent Activity")
+                    .open(&mut open)
+                    .collapsible(false)
+                    .default_size([width_pt - 40.0, height_pt * 0.6])
+                    .show(ctx, |ui| {
+                        if ui.small_button("⟳ Reload").clicked() {
+                            self.client.fetch_received();
+                        }
+                        if !self.notification_log.is_empty() {
+                            ui.separator();
+                            self.draw_notification_log(ui);
+                        }
+                        ui.separator();
+                        if entries.is_empty() {
+                            ui.label(RichText::new("Nothing has moved yet").weak());
+                        }
+                        egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+                            for (i, entry) in entries.iter().enumerate() {
+                                let (icon, what) = match entry.kind {
+                                    ActivityKind::Inbox => ("📨", "in desktop inbox"),
+                                    ActivityKind::Received => ("📥", "received by desktop"),
+                                    ActivityKind::Pulled => ("📲", "pulled to iPhone"),
+                                    ActivityKind::Synced => ("🔄", "synced"),
+                                };
+                                ui.push_id(i, |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.label(format!("{} {}", icon, entry.name));
+                                        if let Some(size) = entry.size {
+                                            ui.label(RichText::new(format_size(size)).weak().small());
+                                        }
+                                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                            if entry.downloadable && ui.small_button("📥").on_hover_text("Download").clicked() {
+                                                file_to_download = Some(entry.name.clone());
+                                            }
+                                            if let Some(ref path) = entry.local_path
+                                                && std::path::Path::new(path).is_file()
+                                            {
+                                                if ui.small_button("↗").on_hover_text("Share").clicked() {
+                                                    self.client.pending_share_paths.push(path.clone());
+                                                }
+                                                if ui.small_button("💾").on_hover_text("Save to Files").clicked() {
+                                                    self.client.pending_export_paths.push(path.clone());
+                                                }
+                                                if ui.small_button("📂").on_hover_text("Show").clicked() {
+                                                    reveal = Some(path.clone());
+                                                }
+                                            }
+                                        });
+                                    });
+                                    let detail = if entry.detail.is_empty() {
+                                        format!("{} · {}", what, format_timestamp(entry.at))
+                                    } else {
+                                        format!("{} · {} · {}", what, entry.detail, format_timestamp(entry.at))
+                                    };
+                                    ui.label(RichText::new(detail).weak().small());
+                                });
+                                ui.separator();
+                            }
+                        });
+                    });
+                self.show_activity &= open;
+                if let Some(path) = reveal {
+                    self.show_activity = false;
+                    self.reveal_local_file(&path, &mut do_browse);
+                }
+            }
+
+            // ═══════════════════════════════════════════════════
+            //  WHAT'S NEW (desktop changes since the last visit)
+            // ═══════════════════════════════════════════════════
+            let mut change_to_pull: Option<(String, Option<String>)> = None;
+            let mut change_folder: Option<String> = None;
+            if self.show_changes
+                && let Some(ref changes) = self.client.changes
+            {
+                let mut open = true;
+                egui::Window::new("What's New")
+                    .open(&mut open)
+                    .collapsible(false)
+                    .default_size([width_pt - 40.0, height_pt * 0.6])
+                    .show(ctx, |ui| {
+                        ui.label(RichText::new(format!("Changed under {} since your last visit", changes.root)).weak().small());
+                        if changes.truncated {
+                            ui.label(
+                                RichText::new("The desktop stopped looking early; older changes may be missing")
+                                    .weak()
+                                    .small(),
+                            );
+                        }
+                        ui.separator();
+                        egui::ScrollArea::vertical().auto_shrink([false, false]).show(ui, |ui| {
+                            for (i, changed) in changes.files.iter().enumerate() {
+                                let relative = changed
+                                    .path
+                                    .strip_prefix(changes.root.as_str())
+                                    .unwrap_or(&changed.path)
+                                    .trim_start_matches(['/', '\\']);
+                                ui.push_id(i, |ui| {
+                                    ui.horizontal(|ui| {
+                                        ui.label(format!("📄 {}", relative));
+                                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                            if ui.small_button("📥").on_hover_text("Pull to iPhone").clicked() {
+                                                change_to_pull = Some((changed.path.clone(), changed.file.path_id.clone()));
+                                            }
+                                            if ui.small_button("📂").on_hover_text("Open folder").clicked() {
+                                                change_folder = Some(parent_remote_path_within(&changed.path, None));
+                                            }
+                                        });
+                                    });
+                                    ui.label(
+                                        RichText::new(format!(
+                                            "{} · {}",
+                                            format_size(changed.file.size as u64),
+                                            format_timestamp(changed.file.modified)
+                                        ))
+                                        .weak()
+                                        .small(),
+                                    );
+                                });
+                                ui.separator();
+                            }
+                        });
+                    });
+                // Closing the list marks it all seen
+                if !open {
+                    self.show_changes = false;
+                    let now = changes.now;
+                    self.client.changes = None;
+                    self.mark_changes_seen(now);
+                }
+            }
+            if let Some((path, path_id)) = change_to_pull {
+                self.pull_or_confirm(path, path_id, false);
+            }
+            if let Some(folder) = change_folder {
+                self.show_changes = false;
+                self.current_page = Page::Monitor;
+                self.browse_path_input = folder.clone();
+                self.client.browse(Some(folder));
+                self.selected_remote_idx = None;
+            }
+
             // ═══════════════════════════════════════════════════
             //  OVERWRITE CONFIRMATION MODAL
             // ═══════════════════════════════════════════════════
@@ -818,7 +2095,7 @@ impl Renderer {
                             ui.label("Overwrite the local iPhone file and create a sync?");
                         } else {
                             // Pushing from iOS to desktop — desktop file exists
-                            let filename = pending.desktop_path.rsplit('/').next().unwrap_or(&pending.desktop_path);
+                            let filename = remote_file_name(&pending.desktop_path);
                             ui.label(
                                 RichText::new(format!("\"{}\" already exists on {}", filename, device))
                                     .strong(),
@@ -869,6 +2146,41 @@ impl Renderer {
                 }
             }
 
+            // ═══════════════════════════════════════════════════
+            //  LARGE FILE CONFIRMATION MODAL
+            // ═══════════════════════════════════════════════════
+            let mut confirmed_large: Option<LargeTransfer> = None;
+            if let Some(pending) = self.large_transfer.clone() {
+                let name = remote_file_name(&pending.path).to_string();
+                let modal_response = egui::Modal::new(egui::Id::new("large_file_modal")).show(ctx, |ui| {
+                    ui.heading("⚠ Large File");
+                    ui.add_space(8.0);
+                    ui.label(RichText::new(&name).strong());
+                    ui.label(format!("This file is {} — continue?", format_size(pending.size)));
+                    if pending.preview {
+                        ui.label(RichText::new("The desktop decodes the whole image to make the preview.").weak().small());
+                    }
+                    if pending.cellular {
+                        ui.label(RichText::new("You're on cellular and Wi-Fi only is on.").weak().small());
+                    }
+                    ui.add_space(12.0);
+                    ui.horizontal(|ui| {
+                        let go = if pending.preview { "👁 Preview anyway" } else { "📥 Pull anyway" };
+                        if ui.button(RichText::new(go).strong().color(Color32::from_rgb(46, 204, 113))).clicked() {
+                            confirmed_large = Some(pending.clone());
+                            self.large_transfer = None;
+                        }
+                        ui.add_space(8.0);
+                        if ui.button(RichText::new("✗ Cancel").color(Color32::from_rgb(231, 76, 60))).clicked() {
+                            self.large_transfer = None;
+                        }
+                    });
+                });
+                if modal_response.should_close() {
+                    self.large_transfer = None;
+                }
+            }
+
             // Apply deferred actions
             if let Some(ref name) = file_to_download {
                 self.client.download_file(name);
@@ -881,12 +2193,23 @@ impl Renderer {
             }
             if let Some(path) = do_browse {
                 self.client.browse(path);
+                // A refresh also refreshes the folders open in the tree below it
+                if self.settings.browse_tree_mode {
+                    let root = format!("{}/", self.browse_path_input.trim_end_matches('/'));
+                    for path in self.tree_expanded.iter().filter(|p| p.starts_with(&root)) {
+                        self.client.browse_children(path);
+                    }
+                }
+            }
+            if let Some((path, path_id)) = file_to_pull {
+                self.pull_or_confirm(path, path_id, false);
             }
-            if let Some(ref name) = file_to_pull {
-                self.client.pull_file(name);
+            if let Some((path, path_id)) = file_to_preview {
+                self.preview_path = Some(path.clone());
+                self.pull_or_confirm(path, path_id, true);
             }
-            if let Some(ref path) = file_to_preview {
-                self.client.preview_file(path);
+            if let Some(t) = confirmed_large {
+                self.start_transfer(&t.path, t.path_id.as_deref(), t.preview);
             }
             if let Some((local, remote)) = do_upload {
                 self.client.upload_file(&local, &remote);
@@ -895,7 +2218,12 @@ impl Renderer {
                 // ── Check for duplicates locally first ──
                 let already_synced = self.client.sync_projects.iter().any(|p| p.local_path == desktop_path);
                 if already_synced {
-                    self.client.sync_status = Some(format!("⚠ Already synced: {}", desktop_path.rsplit('/').next().unwrap_or(&desktop_path)));
+                    self.client.sync_status = Some(format!("⚠ Already synced: {}", remote_file_name(&desktop_path)));
+                } else if sync_paths_overlap(&ios_path, &desktop_path) {
+                    self.client.sync_status = Some(format!(
+                        "🗙 Can't sync '{}' into itself (paths overlap)",
+                        remote_file_name(&desktop_path)
+                    ));
                 } else {
                     // Get iOS file modified timestamp
                     let ios_modified = std::fs::metadata(&ios_path)
@@ -914,6 +2242,7 @@ impl Renderer {
                         desktop_size: 0,
                         desktop_exists: false,
                         from_remote: false,
+                        direction: self.sync_direction,
                     });
                     self.overwrite_checking = true;
                     self.client.check_file_info(&desktop_path);
@@ -927,17 +2256,17 @@ impl Renderer {
             }
 
             // ── Handle "Sync to iPhone" from remote context menu ──
-            if let Some(remote_path) = self.pending_sync_from_remote.take() {
+            if let Some((remote_path, direction)) = self.pending_sync_from_remote.take() {
                 // Check for duplicate: does a sync for this desktop path already exist?
                 let already_synced = self.client.sync_projects.iter().any(|p| p.local_path == remote_path);
                 if already_synced {
                     self.client.sync_status = Some(format!(
                         "⚠ Already synced: {}",
-                        remote_path.rsplit('/').next().unwrap_or(&remote_path)
+                        remote_file_name(&remote_path)
                     ));
                 } else {
                     // Compute the iOS destination path
-                    let filename = remote_path.rsplit('/').next().unwrap_or("file");
+                    let filename = remote_file_name(&remote_path);
                     let ios_dest = format!(
                         "{}/{}",
                         self.client.save_directory.as_deref().unwrap_or("/tmp"),
@@ -954,9 +2283,9 @@ impl Renderer {
                     let ios_exists = std::path::Path::new(&ios_dest).exists();
 
                     if ios_exists {
-                        // File exists on iOS — show overwrite modal with iOS as destination
+                        // File exists on iOS — the overwrite modal has iOS as destination
                         // (swapped perspective: source is desktop, dest is iOS)
-                        self.overwrite_pending = Some(OverwritePending {
+                        self.resolve_overwrite(OverwritePending {
                             ios_path: ios_dest,
                             desktop_path: remote_path,
                             ios_modified,
@@ -964,8 +2293,8 @@ impl Renderer {
                             desktop_size: 0,
                             desktop_exists: false,
                             from_remote: true,
+                            direction,
                         });
-                        self.show_overwrite_modal = true;
                     } else {
                         // File doesn't exist on iOS — proceed directly
                         let pending = OverwritePending {
@@ -976,6 +2305,7 @@ impl Renderer {
                             desktop_size: 0,
                             desktop_exists: false,
                             from_remote: true,
+                            direction,
                         };
                         self.execute_sync_creation(pending);
                     }
@@ -988,18 +2318,23 @@ impl Renderer {
         // which only checks if the pointer hovers over mutable text (goes false
         // after PointerGone on touch-up, causing the keyboard to dismiss).
         self.wants_keyboard = self.egui_ctx.wants_keyboard_input();
+        self.clamp_momentum();
 
         // Handle reconnect after run() (needs &mut self.client)
         if let Some(url) = reconnect_url {
-            // Preserve cached peers across reconnect
-            let cached_peers = std::mem::take(&mut self.client.peers);
-            let save_dir = self.client.save_directory.clone();
-            self.client = TailscaleClient::new(&url);
-            self.client.peers = cached_peers;
-            self.client.save_directory = save_dir;
-            self.browse_fetched = false;
-            self.auto_browsed = false;
-            self.selected_remote_idx = None;
+            match parse_server_input(&url) {
+                Some((url, token)) => {
+                    self.server_url_input = url.clone();
+                    self.remember_server_token(&url, token);
+                    self.reconnect(&url);
+                }
+                None => {
+                    self.server_url_error = Some(format!(
+                        "Invalid server URL '{}' — expected http(s)://host[:port] or a tsdrive:// join code",
+                        url.trim()
+                    ));
+                }
+            }
         }
 
         // ── Tessellate & render ──
@@ -1028,7 +2363,7 @@ impl Renderer {
         let frame = match self.surface.get_current_texture() {
             Ok(f) => f,
             Err(e) => {
-                eprintln!("[egui-renderer] get_current_texture error: {:?}", e);
+                log::error!("[egui-renderer] get_current_texture error: {:?}", e);
                 self.surface.configure(&self.device, &self.config);
                 return;
             }
@@ -1070,6 +2405,200 @@ impl Renderer {
     //  PAGE 1: MONITOR
     // ═══════════════════════════════════════════════════════════════════
 
+    /// Preferences window body. Every change is saved and applied right away.
+    fn draw_settings(&mut self, ui: &mut egui::Ui, reconnect_url: &mut Option<String>) {
+        let mut changed = false;
+        let mut tls_edit: Option<TlsEdit> = None;
+        let heading = |ui: &mut egui::Ui, text: &str| {
+            ui.label(RichText::new(text).strong().small().color(Color32::GRAY));
+        };
+
+        heading(ui, "CONNECTION");
+        ui.horizontal(|ui| {
+            ui.label("Poll every");
+            changed |= ui
+                .add(egui::DragValue::new(&mut self.settings.poll_secs).suffix(" s").range(0..=300))
+                .on_hover_text("How often the desktop is checked for new files (0 = default)")
+                .changed();
+        });
+        ui.horizontal(|ui| {
+            ui.label("Auth token");
+            ui.add(egui::TextEdit::singleline(&mut self.token_input).password(true).desired_width(160.0));
+            let saved = self.settings.server_tokens.get(&self.client.server_url);
+            let token = self.token_input.trim();
+            if saved.map(String::as_str).unwrap_or_default() != token && ui.button("Save").clicked() {
+                let url = self.client.server_url.clone();
+                if token.is_empty() {
+                    self.settings.server_tokens.remove(&url);
+                } else {
+                    self.settings.server_tokens.insert(url.clone(), token.to_string());
+                }
+                changed = true;
+                *reconnect_url = Some(url);
+            }
+        });
+        ui.label(RichText::new("The token is sent to this server only").weak().small());
+        if self.client.server_url.starts_with("https://") {
+            let tls = self.settings.server_tls.get(&self.client.server_url).cloned().unwrap_or_default();
+            let mut accept_invalid = tls.accept_invalid_certs;
+            if ui
+                .checkbox(&mut accept_invalid, "Accept invalid certificates")
+                .on_hover_text("For a self-signed certificate with no root to import")
+                .changed()
+            {
+                tls_edit = Some(TlsEdit::AcceptInvalid(accept_invalid));
+            }
+            if accept_invalid {
+                ui.colored_label(
+                    Color32::from_rgb(231, 76, 60),
+                    RichText::new("⚠ Verification is off: anyone on the network path can impersonate this server")
+                        .small(),
+                );
+            }
+            ui.horizontal(|ui| {
+                let source = if tls.root_cert_pem.is_some() { "custom" } else { "built-in" };
+                ui.label(format!("Root certificate: {}", source));
+                if tls.root_cert_pem.is_some() && ui.small_button("Clear").clicked() {
+                    tls_edit = Some(TlsEdit::RootCert(String::new()));
+                }
+            });
+            ui.add(
+                egui::TextEdit::multiline(&mut self.cert_input)
+                    .hint_text("Paste a PEM root certificate…")
+                    .desired_rows(3)
+                    .font(egui::TextStyle::Monospace),
+            );
+            if !self.cert_input.trim().is_empty() && ui.button("Trust this root").clicked() {
+                tls_edit = Some(TlsEdit::RootCert(self.cert_input.clone()));
+            }
+            if let Some(ref e) = self.cert_error {
+                ui.colored_label(Color32::from_rgb(231, 76, 60), RichText::new(e).small());
+            }
+        }
+
+        ui.add_space(6.0);
+        heading(ui, "TRANSFERS");
+        changed |= ui
+            .checkbox(&mut self.settings.keep_folder_structure, "Keep folder structure")
+            .on_hover_text("Save pulls under their desktop folders instead of all in one folder")
+            .changed();
+        ui.horizontal(|ui| {
+            let mut reuse = !self.settings.always_download;
+            if ui
+                .checkbox(&mut reuse, "Reuse unchanged files")
+                .on_hover_text("Re-share the saved copy when the desktop file's date and size haven't changed")
+                .changed()
+            {
+                self.settings.always_download = !reuse;
+                changed = true;
+            }
+            let cached = self.client.pull_cache.len();
+            if ui
+                .add_enabled(!self.client.pull_cache.is_empty(), egui::Button::new(format!("Clear cache ({})", cached)).small())
+                .on_hover_text("Forget earlier pulls so the next ones download again; saved files are kept")
+                .clicked()
+            {
+                self.client.clear_pull_cache();
+            }
+        });
+        // Size limits, in MB; 0 turns the prompt off
+        ui.horizontal(|ui| {
+            let mb = 1024 * 1024;
+            ui.label(RichText::new("Ask first over").small());
+            let mut preview_mb = self.settings.preview_confirm_bytes / mb;
+            let mut pull_mb = self.settings.pull_confirm_bytes / mb;
+            let mut limits_changed = ui
+                .add(egui::DragValue::new(&mut preview_mb).suffix(" MB").range(0..=100_000))
+                .on_hover_text("Image previews larger than this ask first (0 = never)")
+                .changed();
+            ui.label(RichText::new("preview,").small());
+            limits_changed |= ui
+                .add(egui::DragValue::new(&mut pull_mb).suffix(" MB").range(0..=100_000))
+                .on_hover_text("Pulls larger than this ask first (0 = never)")
+                .changed();
+            ui.label(RichText::new("pull").small());
+            if limits_changed {
+                self.settings.preview_confirm_bytes = preview_mb * mb;
+                self.settings.pull_confirm_bytes = pull_mb * mb;
+                changed = true;
+            }
+        });
+
+        ui.horizontal(|ui| {
+            ui.label("Clipboard images go to");
+            changed |= ui
+                .add(egui::TextEdit::singleline(&mut self.settings.clipboard_dest_dir).desired_width(140.0))
+                .on_hover_text("Desktop folder; ~/ is the desktop's home")
+                .lost_focus();
+        });
+
+        ui.add_space(6.0);
+        heading(ui, "SYNC");
+        ui.horizontal(|ui| {
+            ui.label("If the file already exists");
+            egui::ComboBox::from_id_salt("overwrite_policy")
+                .selected_text(self.settings.overwrite_policy.label())
+                .show_ui(ui, |ui| {
+                    for policy in [OverwritePolicy::Ask, OverwritePolicy::Overwrite, OverwritePolicy::Skip] {
+                        changed |= ui
+                            .selectable_value(&mut self.settings.overwrite_policy, policy, policy.label())
+                            .changed();
+                    }
+                });
+        });
+        changed |= ui
+            .checkbox(&mut self.settings.auto_sync, "Auto-sync")
+            .on_hover_text("Off: changes are still listed, but only Sync Now transfers them")
+            .changed();
+        changed |= ui
+            .checkbox(&mut self.settings.wifi_only, "Wi-Fi only")
+            .on_hover_text(format!(
+                "On cellular, pause auto-sync and ask before pulls over {}",
+                format_size(CELLULAR_PULL_CONFIRM_BYTES)
+            ))
+            .changed();
+        changed |= ui
+            .checkbox(&mut self.settings.verify_sync_uploads, "Verify pushed files")
+            .on_hover_text("Compare checksums with the desktop before marking a push synced; off is faster on a LAN")
+            .changed();
+        changed |= ui
+            .checkbox(&mut self.settings.compress_uploads, "Compress uploads")
+            .on_hover_text(
+                "Gzip text and other uncompressed files over 16 KB on the way to the desktop; \
+                 photos, videos and archives always go as they are",
+            )
+            .changed();
+        ui.horizontal(|ui| {
+            ui.label("Speed limit");
+            for limit in [SpeedLimit::Unlimited, SpeedLimit::FiveMBps, SpeedLimit::OneMBps] {
+                changed |= ui
+                    .selectable_value(&mut self.settings.speed_limit, limit, limit.label())
+                    .on_hover_text("Applies to pulls, downloads and uploads, including ones already running")
+                    .changed();
+            }
+        });
+
+        ui.add_space(6.0);
+        heading(ui, "TOUCH");
+        ui.horizontal(|ui| {
+            ui.label("Long press");
+            changed |= ui
+                .add(egui::DragValue::new(&mut self.settings.long_press_ms).suffix(" ms").range(200..=1500))
+                .on_hover_text("Hold this long to open a context menu")
+                .changed();
+        });
+
+        if changed {
+            self.apply_client_settings();
+            self.save_settings();
+        }
+        match tls_edit {
+            Some(TlsEdit::AcceptInvalid(accept)) => self.set_accept_invalid_certs(accept),
+            Some(TlsEdit::RootCert(pem)) if self.set_server_root_cert(&pem) => self.cert_input.clear(),
+            _ => {}
+        }
+    }
+
     fn draw_monitor_page(
         &mut self,
         ui: &mut egui::Ui,
@@ -1077,9 +2606,18 @@ impl Renderer {
         file_to_download: &mut Option<String>,
         do_download_last: &mut bool,
         do_browse: &mut Option<Option<String>>,
-        file_to_pull: &mut Option<String>,
-        file_to_preview: &mut Option<String>,
+        file_to_pull: &mut Option<(String, Option<String>)>,
+        file_to_preview: &mut Option<(String, Option<String>)>,
     ) {
+        let mut dir_to_pull: Option<(String, Option<String>)> = None;
+
+        // Nothing to show yet: lead with why, and keep only the server config below
+        let unreachable = !self.client.connected && self.client.peers.is_empty();
+        if unreachable {
+            self.draw_unreachable_state(ui, reconnect_url);
+            ui.add_space(8.0);
+        }
+
         // ─── Server Config ───
         ui.group(|ui| {
             ui.label(
@@ -1092,6 +2630,9 @@ impl Renderer {
             ui.horizontal(|ui| {
                 ui.label("URL:");
                 let re = ui.text_edit_singleline(&mut self.server_url_input);
+                if re.changed() {
+                    self.server_url_error = None;
+                }
                 if self.server_url_input != self.client.server_url {
                     if ui.button("Connect").clicked()
                         || (re.lost_focus()
@@ -1102,6 +2643,91 @@ impl Renderer {
                 }
             });
 
+            if let Some(ref err) = self.server_url_error {
+                ui.colored_label(Color32::from_rgb(231, 76, 60), RichText::new(err).small());
+            }
+
+            ui.horizontal(|ui| {
+                if self.client.self_test_running() {
+                    ui.spinner();
+                    ui.label(RichText::new("Testing connection…").small());
+                } else if ui.small_button("🩺 Test connection").clicked() {
+                    self.client.run_self_test();
+                }
+                if self.client.self_test.as_ref().is_some_and(|steps| !steps.is_empty())
+                    && ui.small_button("✕").on_hover_text("Hide results").clicked()
+                {
+                    self.client.self_test = None;
+                }
+            });
+            if let Some(ref steps) = self.client.self_test {
+                for step in steps {
+                    let (icon, color, detail) = match &step.result {
+                        Ok(found) => ("✔", Color32::from_rgb(46, 204, 113), found),
+                        Err(e) => ("🗙", Color32::from_rgb(231, 76, 60), e),
+                    };
+                    ui.colored_label(color, RichText::new(format!("{} {}: {}", icon, step.name, detail)).small());
+                    if let Some(hint) = step.hint {
+                        ui.label(RichText::new(hint).weak().small());
+                    }
+                }
+            }
+
+            // ─── Saved servers with reachability dots ───
+            let mut servers_changed = false;
+            let mut remove_idx: Option<usize> = None;
+            for (idx, url) in self.saved_servers.iter().enumerate() {
+                let current = *url == self.client.server_url;
+                let reachable = if current {
+                    Some(self.client.connected)
+                } else {
+                    self.client.server_reachability.get(url).copied()
+                };
+                let dot_color = match reachable {
+                    Some(true) => Color32::from_rgb(46, 204, 113),
+                    Some(false) => Color32::from_rgb(231, 76, 60),
+                    None => Color32::GRAY,
+                };
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new("●").color(dot_color));
+                    let host = url.split("://").nth(1).unwrap_or(url);
+                    if ui.selectable_label(current, RichText::new(host).small()).clicked() && !current {
+                        self.server_url_input = url.clone();
+                        *reconnect_url = Some(url.clone());
+                    }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.small_button("🗑").clicked() {
+                            remove_idx = Some(idx);
+                        }
+                    });
+                });
+            }
+            if let Some(idx) = remove_idx {
+                self.saved_servers.remove(idx);
+                servers_changed = true;
+            }
+            if !self.saved_servers.contains(&self.client.server_url)
+                && ui.small_button("➕ Save this server").clicked()
+            {
+                self.saved_servers.push(self.client.server_url.clone());
+                servers_changed = true;
+            }
+            if servers_changed {
+                self.client.set_probe_urls(&self.saved_servers);
+                if let Some(ref dir) = self.client.save_directory {
+                    save_saved_servers(dir, &self.saved_servers);
+                }
+            }
+
+            if let Some(ref version) = self.client.server_version {
+                let text = if version.version.is_empty() {
+                    "Server version: unknown (older than /version)".to_string()
+                } else {
+                    format!("Server version: {} (API {})", version.version, version.api_version)
+                };
+                ui.label(RichText::new(text).weak().small());
+            }
+
             // ─── Peer ComboBox (always visible, uses cached list when disconnected) ───
             ui.add_space(4.0);
 
@@ -1124,9 +2750,7 @@ impl Renderer {
                 });
 
                 // Find the currently selected peer by ID
-                let selected_peer = self.selected_peer_id.as_ref().and_then(|id| {
-                    self.client.peers.iter().find(|p| &p.id == id)
-                });
+                let selected_peer = self.selected_peer_id.as_ref().and_then(|id| self.client.peer(id));
 
                 let current_label = selected_peer
                     .map(|p| {
@@ -1171,8 +2795,9 @@ impl Renderer {
                                 let resp = ui.selectable_label(is_selected, RichText::new(&label).color(status_color));
                                 if resp.clicked() {
                                     self.selected_peer_id = Some(peer.id.clone());
-                                    let dns = peer.dns_name.trim_end_matches('.');
-                                    let new_url = format!("http://{}:8080", dns);
+                                    // Keep the scheme of the current connection when switching peers
+                                    let https = self.client.server_url.starts_with("https://");
+                                    let new_url = peer_server_url(&peer.dns_name, https);
                                     self.server_url_input = new_url.clone();
                                     *reconnect_url = Some(new_url);
                                 }
@@ -1191,16 +2816,65 @@ impl Renderer {
                         .weak()
                         .small(),
                     );
+
+                    // Plain server on :8080, or fronted by `tailscale serve --https`
+                    let http_url = peer_server_url(&peer.dns_name, false);
+                    let https_url = peer_server_url(&peer.dns_name, true);
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new("Connect via:").weak().small());
+                        for (label, url) in [("http :8080", http_url), ("https", https_url)] {
+                            let current = self.client.server_url == url;
+                            if ui.selectable_label(current, RichText::new(label).small()).clicked()
+                                && !current
+                            {
+                                self.server_url_input = url.clone();
+                                *reconnect_url = Some(url);
+                            }
+                        }
+                    });
                 }
             }
         });
 
-        ui.add_space(8.0);
+        if unreachable {
+            return;
+        }
 
-        // ─── Desktop Transfer Activity ───
-        ui.group(|ui| {
-            ui.label(
-                RichText::new("DESKTOP ACTIVITY")
+        // ─── Server Info (which machine this actually is) ───
+        if let Some(ref info) = self.client.server_info {
+            ui.add_space(8.0);
+            ui.group(|ui| {
+                ui.label(
+                    RichText::new("SERVER INFO")
+                        .strong()
+                        .small()
+                        .color(Color32::GRAY),
+                );
+                ui.add_space(4.0);
+                egui::Grid::new("server_info").num_columns(2).show(ui, |ui| {
+                    let rows = [
+                        ("Host", info.hostname.as_str()),
+                        ("System", &format!("{} · {}", info.os, info.arch)),
+                        ("Tailnet", info.tailnet.as_str()),
+                    ];
+                    for (label, value) in rows {
+                        if value.is_empty() {
+                            continue;
+                        }
+                        ui.label(RichText::new(label).small().color(Color32::GRAY));
+                        ui.label(RichText::new(value).small());
+                        ui.end_row();
+                    }
+                });
+            });
+        }
+
+        ui.add_space(8.0);
+
+        // ─── Desktop Transfer Activity ───
+        ui.group(|ui| {
+            ui.label(
+                RichText::new("DESKTOP ACTIVITY")
                     .strong()
                     .small()
                     .color(Color32::GRAY),
@@ -1217,12 +2891,17 @@ impl Renderer {
                 } else {
                     "❌"
                 };
-                ui.label(format!(
-                    "{} {} ({})",
-                    icon,
-                    sent.name,
-                    format_size(sent.size)
-                ));
+                if sent.peer_id.is_empty() {
+                    ui.label(format!("{} {} ({})", icon, sent.name, format_size(sent.size)));
+                } else {
+                    ui.label(format!(
+                        "{} {} → {} ({})",
+                        icon,
+                        sent.name,
+                        self.client.peer_name(&sent.peer_id),
+                        format_size(sent.size)
+                    ));
+                }
             } else {
                 ui.label(RichText::new("None yet").weak());
             }
@@ -1233,7 +2912,7 @@ impl Renderer {
             ui.label(RichText::new("Last Received on Desktop:").small().color(Color32::GRAY));
             if let Some(ref name) = self.client.last_received_file {
                 ui.horizontal(|ui| {
-                    ui.label(format!("📄 {}", name));
+                    ui.label(format!("{} {}", file_icon(name, false).0, name));
                     if ui.button("💾 Save to iPhone").clicked() {
                         *do_download_last = true;
                     }
@@ -1250,13 +2929,92 @@ impl Renderer {
                 } else {
                     Color32::from_rgb(231, 76, 60)
                 };
-                ui.colored_label(color, status.as_str());
+                let mut reveal: Option<String> = None;
+                ui.horizontal(|ui| {
+                    ui.colored_label(color, status.as_str());
+                    if let Some(ref path) = self.client.last_downloaded_path
+                        && self.client.supports_feature("sync")
+                        && ui.small_button("📂 Show").clicked()
+                    {
+                        reveal = Some(path.clone());
+                    }
+                });
+                if let Some(path) = reveal {
+                    self.reveal_local_file(&path, do_browse);
+                }
             }
         });
 
         ui.add_space(8.0);
 
+        // ─── Send Text (desktop notes inbox) ───
+        if self.client.supports_feature("notes") {
+            ui.group(|ui| {
+                ui.label(
+                    RichText::new("SEND TEXT")
+                        .strong()
+                        .small()
+                        .color(Color32::GRAY),
+                );
+                ui.add_space(4.0);
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.note_input)
+                        .hint_text("Link, snippet, anything…")
+                        .desired_rows(3)
+                        .desired_width(f32::INFINITY),
+                );
+                ui.horizontal(|ui| {
+                    let can_send = !self.note_input.trim().is_empty();
+                    if ui
+                        .add_enabled(can_send, egui::Button::new("📝 Send to Desktop"))
+                        .clicked()
+                    {
+                        self.client.send_note(&self.note_input);
+                        self.note_input.clear();
+                    }
+                    if let Some(ref status) = self.client.note_status {
+                        let color = if status.starts_with('✔') {
+                            Color32::from_rgb(46, 204, 113)
+                        } else if status.starts_with('🗙') {
+                            Color32::from_rgb(231, 76, 60)
+                        } else {
+                            Color32::GRAY
+                        };
+                        ui.colored_label(color, status.as_str());
+                    }
+                });
+            });
+
+            ui.add_space(8.0);
+        }
+
+        // ─── Clipboard image → desktop (uploads over /sync/upload) ───
+        if self.client.supports_feature("sync") {
+            ui.horizontal(|ui| {
+                if ui
+                    .button("📋 Send Clipboard Image")
+                    .on_hover_text(format!("Upload the copied image to {}", self.settings.clipboard_dest_dir))
+                    .clicked()
+                {
+                    self.clipboard_image_requested = true;
+                }
+                if let Some(ref status) = self.client.clipboard_status {
+                    let color = if status.starts_with('✔') {
+                        Color32::from_rgb(46, 204, 113)
+                    } else if status.starts_with('🗙') {
+                        Color32::from_rgb(231, 76, 60)
+                    } else {
+                        Color32::GRAY
+                    };
+                    ui.colored_label(color, status.as_str());
+                }
+            });
+            ui.add_space(8.0);
+        }
+
         // ─── Waiting Files (desktop Taildrop inbox) ───
+        let mut file_to_delete: Option<String> = None;
+        let mut do_clear_inbox = false;
         ui.group(|ui| {
             ui.horizontal(|ui| {
                 ui.label(
@@ -1270,6 +3028,23 @@ impl Renderer {
                         .weak()
                         .small(),
                 );
+                if !self.client.waiting_files.is_empty() {
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if self.confirm_clear_inbox {
+                            if ui.small_button("Cancel").clicked() {
+                                self.confirm_clear_inbox = false;
+                            }
+                            if ui
+                                .small_button(RichText::new("Confirm clear").color(Color32::from_rgb(231, 76, 60)))
+                                .clicked()
+                            {
+                                do_clear_inbox = true;
+                            }
+                        } else if ui.small_button("🗑 Clear all").clicked() {
+                            self.confirm_clear_inbox = true;
+                        }
+                    });
+                }
             });
             ui.add_space(4.0);
 
@@ -1284,7 +3059,7 @@ impl Renderer {
                             if ui
                                 .selectable_label(
                                     is_selected,
-                                    RichText::new(format!("📄 {}", file.name)).strong(),
+                                    RichText::new(format!("{} {}", file_icon(&file.name, false).0, file.name)).strong(),
                                 )
                                 .clicked()
                             {
@@ -1295,16 +3070,42 @@ impl Renderer {
                             );
                         });
 
+                        let mut origin = Vec::new();
+                        if !file.from_peer.is_empty() {
+                            origin.push(format!("from {}", file.from_peer));
+                        }
+                        if file.received_at > 0 {
+                            origin.push(format_timestamp(file.received_at));
+                        }
+                        if !origin.is_empty() {
+                            ui.label(RichText::new(origin.join(" · ")).weak().small());
+                        }
+
                         if is_selected {
-                            if ui.button("💾 Save to iPhone").clicked() {
-                                *file_to_download = Some(file.name.clone());
-                            }
+                            ui.horizontal(|ui| {
+                                if ui.button("💾 Save to iPhone").clicked() {
+                                    *file_to_download = Some(file.name.clone());
+                                }
+                                if ui.button("🗑 Remove from inbox").clicked() {
+                                    file_to_delete = Some(file.name.clone());
+                                }
+                            });
                         }
                     });
                 }
             }
         });
 
+        if let Some(name) = file_to_delete {
+            self.client.delete_waiting(&name);
+            self.selected_file_idx = None;
+        }
+        if do_clear_inbox {
+            self.client.clear_waiting();
+            self.selected_file_idx = None;
+            self.confirm_clear_inbox = false;
+        }
+
         ui.add_space(8.0);
 
         // ─── Remote File Browser ───
@@ -1319,15 +3120,12 @@ impl Renderer {
 
             // Navigation bar
             ui.horizontal(|ui| {
-                if ui.button("⬆ Up").clicked() {
-                    if let Some(pos) = self.browse_path_input.rfind('/') {
-                        self.browse_path_input.truncate(pos);
-                        if self.browse_path_input.is_empty() {
-                            self.browse_path_input = "/".to_string();
-                        }
-                    } else {
-                        self.browse_path_input = "/".to_string();
-                    }
+                let parent = parent_remote_path_within(&self.browse_path_input, self.client.browse_root());
+                if ui
+                    .add_enabled(parent != normalize_remote_path(&self.browse_path_input), egui::Button::new("⬆ Up"))
+                    .clicked()
+                {
+                    self.browse_path_input = parent;
                     *do_browse = Some(Some(self.browse_path_input.clone()));
                     self.selected_remote_idx = None;
                 }
@@ -1348,14 +3146,97 @@ impl Renderer {
                     };
                     *do_browse = Some(path);
                 }
+
+                if ui
+                    .toggle_value(&mut self.settings.browse_tree_mode, "🌲 Tree")
+                    .on_hover_text("Expand folders in place instead of opening them")
+                    .changed()
+                {
+                    self.selected_remote_idx = None;
+                    self.tree_selected = None;
+                    self.save_settings();
+                }
             });
 
-            // Path display
-            ui.label(
-                RichText::new(&self.browse_path_input)
-                    .weak()
-                    .small(),
-            );
+            // Path display, with the per-device default folder toggle
+            let peer_id = self.connected_peer_id();
+            let mut default_changed = false;
+            ui.horizontal(|ui| {
+                ui.label(
+                    RichText::new(&self.browse_path_input)
+                        .weak()
+                        .small(),
+                );
+                let Some(id) = peer_id else {
+                    return;
+                };
+                let is_default = self.settings.peer_default_paths.get(&id) == Some(&self.browse_path_input);
+                if is_default {
+                    if ui
+                        .small_button("📌 Default")
+                        .on_hover_text("Opened when connecting to this device; tap to clear")
+                        .clicked()
+                    {
+                        self.settings.peer_default_paths.remove(&id);
+                        default_changed = true;
+                    }
+                } else if !self.browse_path_input.is_empty()
+                    && ui
+                        .small_button("📌")
+                        .on_hover_text("Set as default folder for this device")
+                        .clicked()
+                {
+                    self.settings
+                        .peer_default_paths
+                        .insert(id, self.browse_path_input.clone());
+                    default_changed = true;
+                }
+            });
+            if default_changed && let Some(ref dir) = self.client.save_directory {
+                save_settings(dir, &self.settings);
+            }
+
+            // Pull every file in this folder matching a glob, as one archive
+            if self.client.supports_feature("pullglob") && !self.browse_path_input.is_empty() {
+                let mut pull_pattern: Option<String> = None;
+                match self.glob_input.as_mut() {
+                    None => {
+                        if ui
+                            .small_button("✳ Pull matching…")
+                            .on_hover_text("Pull the files in this folder matching a pattern like *.log")
+                            .clicked()
+                        {
+                            self.glob_input = Some(String::new());
+                        }
+                    }
+                    Some(pattern) => {
+                        let mut close = false;
+                        ui.horizontal(|ui| {
+                            let response = ui.add(
+                                egui::TextEdit::singleline(pattern)
+                                    .hint_text("*.log, **/*.txt")
+                                    .desired_width(140.0),
+                            );
+                            let entered = response.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                            let can_pull = !pattern.trim().is_empty() && self.client.pull_dir_progress.is_none();
+                            if (ui.add_enabled(can_pull, egui::Button::new("📥 Pull")).clicked() || entered) && can_pull {
+                                pull_pattern = Some(pattern.trim().to_string());
+                            }
+                            close = ui.small_button("✗").clicked();
+                        });
+                        if close {
+                            self.glob_input = None;
+                        }
+                    }
+                }
+                if let Some(pattern) = pull_pattern {
+                    let dir = self.browse_path_input.clone();
+                    if self.client.pull_matching(&dir, None, &pattern) {
+                        self.glob_input = None;
+                    }
+                }
+            }
+
 
             // Status toast
             if let Some(ref status) = self.client.browse_status {
@@ -1364,7 +3245,74 @@ impl Renderer {
                 } else {
                     Color32::GRAY
                 };
-                ui.colored_label(color, status.as_str());
+                let mut start_move = false;
+                let mut reveal: Option<String> = None;
+                let mut share: Option<String> = None;
+                let mut export: Option<String> = None;
+                ui.horizontal(|ui| {
+                    ui.colored_label(color, status.as_str());
+                    // Offer to file the just-pulled file somewhere other than Downloads
+                    if let Some(ref path) = self.client.last_pulled_path {
+                        if ui.small_button("↗ Share").clicked() {
+                            share = Some(path.clone());
+                        }
+                        if ui.small_button("💾 Save to Files").clicked() {
+                            export = Some(path.clone());
+                        }
+                        if !self.client.supports_feature("sync") {
+                            return;
+                        }
+                        if ui.small_button("📂 Show").clicked() {
+                            reveal = Some(path.clone());
+                        }
+                        if self.move_source.is_none() && ui.small_button("📁 Move to…").clicked() {
+                            start_move = true;
+                        }
+                    }
+                });
+                if let Some(path) = reveal {
+                    self.reveal_local_file(&path, do_browse);
+                }
+                self.client.pending_share_paths.extend(share);
+                self.client.pending_export_paths.extend(export);
+                if start_move {
+                    self.move_source = self.client.last_pulled_path.clone();
+                    self.move_status = None;
+                    self.selected_local_idx = None;
+                    self.sync_step = SyncStep::BrowseLocal;
+                    self.open_project_sync(do_browse);
+                }
+            }
+
+            // Folder pull progress
+            if let Some(ref progress) = self.client.pull_dir_progress {
+                let fraction = if progress.bytes_total > 0 {
+                    progress.bytes_done as f32 / progress.bytes_total as f32
+                } else if progress.entries_total > 0 {
+                    progress.entries_done as f32 / progress.entries_total as f32
+                } else {
+                    0.0
+                };
+                let mut cancel = false;
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::ProgressBar::new(fraction.min(1.0))
+                            .desired_width(ui.available_width() - 70.0)
+                            .text(format!(
+                                "{}/{} items · {}",
+                                progress.entries_done,
+                                progress.entries_total,
+                                format_size(progress.bytes_done)
+                            )),
+                    );
+                    cancel = ui.small_button("✗ Cancel").clicked();
+                });
+                if !progress.current.is_empty() {
+                    ui.label(RichText::new(&progress.current).weak().small());
+                }
+                if cancel {
+                    self.client.cancel_pull_dir();
+                }
             }
 
             ui.separator();
@@ -1374,6 +3322,35 @@ impl Renderer {
                 ui.label(RichText::new("Not connected — waiting for server…").weak());
             } else if self.client.remote_files.is_empty() {
                 ui.label(RichText::new("Empty directory").weak());
+            } else if self.settings.browse_tree_mode {
+                let mut actions = TreeActions::default();
+                let root = self.browse_path_input.clone();
+                self.draw_remote_tree(ui, &root, &self.client.remote_files, &mut actions);
+                if let Some(path) = actions.toggle
+                    && !self.tree_expanded.remove(&path)
+                {
+                    // Re-expanding refreshes; the old listing shows until it's back
+                    self.client.browse_children(&path);
+                    self.tree_expanded.insert(path);
+                }
+                for path in actions.load {
+                    self.client.browse_children(&path);
+                }
+                if actions.select.is_some() {
+                    self.tree_selected = actions.select;
+                }
+                if actions.pull.is_some() {
+                    *file_to_pull = actions.pull;
+                }
+                if actions.preview.is_some() {
+                    *file_to_preview = actions.preview;
+                }
+                if actions.pull_dir.is_some() {
+                    dir_to_pull = actions.pull_dir;
+                }
+                if actions.sync_from_remote.is_some() {
+                    self.pending_sync_from_remote = actions.sync_from_remote;
+                }
             } else {
                 // Action buttons for selected remote file
                 if let Some(sel_idx) = self.selected_remote_idx {
@@ -1382,7 +3359,7 @@ impl Renderer {
                             ui.add_space(4.0);
                             ui.horizontal(|ui| {
                                 ui.label(
-                                    RichText::new(format!("📄 {}", selected.name)).strong(),
+                                    RichText::new(format!("{} {}", file_icon(&selected.name, false).0, selected.name)).strong(),
                                 );
                                 ui.label(
                                     RichText::new(format_size(selected.size as u64))
@@ -1397,16 +3374,43 @@ impl Renderer {
                                     .weak()
                                     .small(),
                                 );
+                                if self.client.supports_feature("stat")
+                                    && ui.small_button("⟳").on_hover_text("Re-check this file on the desktop").clicked()
+                                {
+                                    let full_path = self.remote_entry_path(&selected.name);
+                                    self.client.stat_file(&full_path, selected.path_id.as_deref());
+                                }
                             });
                             if ui.button("📥 Pull File to iPhone").clicked() {
-                                let full_path = if self.browse_path_input.is_empty()
-                                    || self.browse_path_input == "/"
-                                {
-                                    format!("/{}", selected.name)
-                                } else {
-                                    format!("{}/{}", self.browse_path_input, selected.name)
-                                };
-                                *file_to_pull = Some(full_path);
+                                let full_path = self.remote_entry_path(&selected.name);
+                                *file_to_pull = Some((full_path, selected.path_id.clone()));
+                            }
+                        } else {
+                            let dir_name = selected.name.clone();
+                            let dir_path_id = selected.path_id.clone();
+                            let full_path = self.remote_entry_path(&dir_name);
+                            let summary = if self.client.supports_feature("dirinfo") {
+                                self.client
+                                    .dir_info(&full_path)
+                                    .map(|info| info.summary())
+                                    .unwrap_or_else(|| "Calculating…".to_string())
+                            } else {
+                                "Size unavailable".to_string()
+                            };
+                            ui.add_space(4.0);
+                            ui.horizontal(|ui| {
+                                ui.label(RichText::new(format!("📂 {}", dir_name)).strong());
+                                ui.label(RichText::new(summary).weak().small());
+                            });
+                            if self.client.supports_feature("pulldir")
+                                && ui
+                                    .add_enabled(
+                                        self.client.pull_dir_progress.is_none(),
+                                        egui::Button::new("📥 Pull Folder to iPhone"),
+                                    )
+                                    .clicked()
+                            {
+                                dir_to_pull = Some((full_path, dir_path_id));
                             }
                         }
                     }
@@ -1447,20 +3451,15 @@ impl Renderer {
                 for &idx in &sorted {
                     let entry = &self.client.remote_files[idx];
                     let is_selected = self.selected_remote_idx == Some(idx);
-                    let icon = if entry.is_dir { "📂" } else { "📄" };
+                    let (icon, color) = file_icon(&entry.name, entry.is_dir);
 
                     // Pre-clone data needed by the context_menu closure
                     let entry_name = entry.name.clone();
                     let entry_size = entry.size;
                     let entry_modified = entry.modified;
                     let entry_is_dir = entry.is_dir;
-                    let full_path = if self.browse_path_input.is_empty()
-                        || self.browse_path_input == "/"
-                    {
-                        format!("/{}", entry.name)
-                    } else {
-                        format!("{}/{}", self.browse_path_input, entry.name)
-                    };
+                    let entry_path_id = entry.path_id.clone();
+                    let full_path = self.remote_entry_path(&entry.name);
 
                     let label_text = if entry.is_dir {
                         format!("{} {}/", icon, entry.name)
@@ -1473,8 +3472,13 @@ impl Renderer {
                         )
                     };
 
-                    let response =
-                        ui.selectable_label(is_selected, RichText::new(&label_text));
+                    let response = ui.selectable_label(
+                        is_selected,
+                        tinted(RichText::new(&label_text), color),
+                    );
+                    if is_selected && self.scroll_to_selection {
+                        response.scroll_to_me(Some(egui::Align::Center));
+                    }
 
                     // Context menu: uses pre-cloned data so it works
                     // correctly with long-press (secondary click) even
@@ -1484,7 +3488,7 @@ impl Renderer {
                             ui.add_space(4.0);
                             ui.horizontal(|ui| {
                                 ui.label(
-                                    RichText::new(format!("📄 {}", entry_name)).strong(),
+                                    RichText::new(format!("{} {}", file_icon(&entry_name, false).0, entry_name)).strong(),
                                 );
                                 ui.label(
                                     RichText::new(format_size(entry_size as u64))
@@ -1502,39 +3506,186 @@ impl Renderer {
                             });
                             ui.separator();
                             if ui.button("📥 Pull File to iPhone").clicked() {
-                                *file_to_pull = Some(full_path.clone());
+                                *file_to_pull = Some((full_path.clone(), entry_path_id.clone()));
                                 ui.close();
                             }
-                            if ui.button("🔄 Sync to iPhone").clicked() {
-                                self.pending_sync_from_remote = Some(full_path.clone());
+                            if self.client.supports_feature("sync")
+                                && ui.button("🔄 Sync to iPhone").clicked()
+                            {
+                                self.pending_sync_from_remote = Some((full_path.clone(), SyncDirection::Bidirectional));
+                                ui.close();
+                            }
+                            if self.client.supports_feature("syncdirection")
+                                && ui.button("⬇ Mirror to iPhone").clicked()
+                            {
+                                self.pending_sync_from_remote = Some((full_path.clone(), SyncDirection::DesktopToPhone));
                                 ui.close();
                             }
                             let ext = file_extension(&entry_name);
-                            if is_previewable(&ext) {
+                            if is_previewable(&ext) && self.client.supports_feature("preview") {
                                 if ui.button("👁 Preview").clicked() {
-                                    *file_to_preview = Some(full_path.clone());
+                                    *file_to_preview = Some((full_path.clone(), entry_path_id.clone()));
                                     ui.close();
                                 }
                             }
                         });
+                    } else if self.client.supports_feature("pulldir") {
+                        response.context_menu(|ui| {
+                            ui.label(RichText::new(format!("📂 {}", entry_name)).strong());
+                            ui.separator();
+                            if ui
+                                .add_enabled(
+                                    self.client.pull_dir_progress.is_none(),
+                                    egui::Button::new("📥 Pull Folder to iPhone"),
+                                )
+                                .clicked()
+                            {
+                                dir_to_pull = Some((full_path.clone(), entry_path_id.clone()));
+                                ui.close();
+                            }
+                        });
                     }
 
                     if response.clicked() {
                         if entry_is_dir && !self.long_press_fired {
                             nav_to = Some(full_path);
+                            // The list is about to change; don't pair this tap with the next one
+                            self.last_tap = None;
                         } else {
                             self.selected_remote_idx = Some(idx);
+                            // Double tap opens the preview directly; a pinned preview follows every tap
+                            if (self.double_tap || (self.preview_pinned && self.show_preview))
+                                && !entry_is_dir
+                                && is_previewable(&file_extension(&entry_name))
+                                && self.client.supports_feature("preview")
+                            {
+                                *file_to_preview = Some((full_path, entry_path_id));
+                            }
                         }
                     }
                 }
 
                 if let Some(new_path) = nav_to {
-                    self.browse_path_input = new_path.clone();
+                    self.browse_path_input = normalize_remote_path(&new_path);
                     *do_browse = Some(Some(new_path));
                     self.selected_remote_idx = None;
                 }
             }
         });
+
+        if let Some((path, path_id)) = dir_to_pull {
+            self.client.pull_dir(&path, path_id.as_deref());
+        }
+    }
+
+    /// One level of the tree view: folders are collapsing headers whose
+    /// children load on first expand; files select, preview and pull like the list.
+    fn draw_remote_tree(&self, ui: &mut egui::Ui, dir: &str, files: &[RemoteFile], out: &mut TreeActions) {
+        let mut sorted: Vec<&RemoteFile> = files.iter().collect();
+        sorted.sort_by_key(|f| (!f.is_dir, f.name.to_lowercase()));
+
+        for entry in sorted {
+            let full_path = remote_child_path(dir, &entry.name, self.client.remote_separator());
+            let (icon, color) = file_icon(&entry.name, entry.is_dir);
+            if entry.is_dir {
+                let expanded = self.tree_expanded.contains(&full_path);
+                let header = egui::CollapsingHeader::new(tinted(RichText::new(format!("{} {}", icon, entry.name)), color))
+                    .id_salt(("remote_tree", &full_path))
+                    .open(Some(expanded))
+                    .show(ui, |ui| match self.client.tree_children.get(&full_path) {
+                        Some(children) if children.is_empty() => {
+                            ui.label(RichText::new("Empty folder").weak().small());
+                        }
+                        Some(children) => self.draw_remote_tree(ui, &full_path, children, out),
+                        None => match self.client.tree_errors.get(&full_path) {
+                            Some(e) => {
+                                ui.colored_label(Color32::from_rgb(231, 76, 60), format!("🗙 {}", e));
+                            }
+                            None => {
+                                // Expanded before a reconnect (or never loaded): ask again
+                                out.load.push(full_path.clone());
+                                ui.horizontal(|ui| {
+                                    ui.spinner();
+                                    ui.label(RichText::new("Loading…").weak().small());
+                                });
+                            }
+                        },
+                    });
+                if header.header_response.clicked() && !self.long_press_fired {
+                    out.toggle = Some(full_path.clone());
+                }
+                if self.client.supports_feature("pulldir") {
+                    header.header_response.context_menu(|ui| {
+                        ui.label(RichText::new(format!("📂 {}", entry.name)).strong());
+                        ui.separator();
+                        if ui
+                            .add_enabled(
+                                self.client.pull_dir_progress.is_none(),
+                                egui::Button::new("📥 Pull Folder to iPhone"),
+                            )
+                            .clicked()
+                        {
+                            out.pull_dir = Some((full_path.clone(), entry.path_id.clone()));
+                            ui.close();
+                        }
+                    });
+                }
+                continue;
+            }
+
+            let is_selected = self.tree_selected.as_deref() == Some(full_path.as_str());
+            let previewable = is_previewable(&file_extension(&entry.name)) && self.client.supports_feature("preview");
+            let response = ui
+                .horizontal(|ui| {
+                    let response = ui.selectable_label(
+                        is_selected,
+                        tinted(
+                            RichText::new(format!("{} {} ({})", icon, entry.name, format_size(entry.size as u64))),
+                            color,
+                        ),
+                    );
+                    if is_selected && ui.small_button("📥").on_hover_text("Pull File to iPhone").clicked() {
+                        out.pull = Some((full_path.clone(), entry.path_id.clone()));
+                    }
+                    response
+                })
+                .inner;
+            response.context_menu(|ui| {
+                ui.label(RichText::new(format!("{} {}", icon, entry.name)).strong());
+                ui.label(
+                    RichText::new(format!(
+                        "{} · Modified: {}",
+                        format_size(entry.size as u64),
+                        format_timestamp(entry.modified)
+                    ))
+                    .weak()
+                    .small(),
+                );
+                ui.separator();
+                if ui.button("📥 Pull File to iPhone").clicked() {
+                    out.pull = Some((full_path.clone(), entry.path_id.clone()));
+                    ui.close();
+                }
+                if self.client.supports_feature("sync") && ui.button("🔄 Sync to iPhone").clicked() {
+                    out.sync_from_remote = Some((full_path.clone(), SyncDirection::Bidirectional));
+                    ui.close();
+                }
+                if self.client.supports_feature("syncdirection") && ui.button("⬇ Mirror to iPhone").clicked() {
+                    out.sync_from_remote = Some((full_path.clone(), SyncDirection::DesktopToPhone));
+                    ui.close();
+                }
+                if previewable && ui.button("👁 Preview").clicked() {
+                    out.preview = Some((full_path.clone(), entry.path_id.clone()));
+                    ui.close();
+                }
+            });
+            if response.clicked() {
+                out.select = Some(full_path.clone());
+                if previewable && (self.double_tap || (self.preview_pinned && self.show_preview)) {
+                    out.preview = Some((full_path, entry.path_id.clone()));
+                }
+            }
+        }
     }
 
     // ═══════════════════════════════════════════════════════════════════
@@ -1544,6 +3695,7 @@ impl Renderer {
     fn refresh_local_files(&mut self) {
         self.local_files.clear();
         self.selected_local_idx = None;
+        self.client.clear_local_dir_infos();
 
         let path = if self.local_browse_path.is_empty() {
             return;
@@ -1584,35 +3736,143 @@ impl Renderer {
 
             self.local_files = items;
         }
+        let files = &self.local_files;
+        self.local_selection
+            .retain(|path| files.iter().any(|e| !e.is_dir && e.path == *path));
     }
 
     /// Execute the sync creation (upload file + create sync project)
+    /// A new sync would overwrite an existing file: ask, or apply the saved policy.
+    fn resolve_overwrite(&mut self, pending: OverwritePending) {
+        match self.settings.overwrite_policy {
+            OverwritePolicy::Ask => {
+                self.overwrite_pending = Some(pending);
+                self.show_overwrite_modal = true;
+            }
+            OverwritePolicy::Overwrite => {
+                self.overwrite_pending = None;
+                self.execute_sync_creation(pending);
+            }
+            OverwritePolicy::Skip => {
+                self.overwrite_pending = None;
+                let path = if pending.from_remote { &pending.ios_path } else { &pending.desktop_path };
+                self.client.sync_status = Some(format!(
+                    "⚠ Skipped '{}' — it already exists",
+                    remote_file_name(path)
+                ));
+            }
+        }
+    }
+
     fn execute_sync_creation(&mut self, pending: OverwritePending) {
         if pending.from_remote {
             // Sync from remote: pull the file to iOS, then create sync project
-            self.client.pull_file(&pending.desktop_path);
+            // The pull lands wherever PullComplete saves it; point the project there too
+            if !self.client.pull_file(&pending.desktop_path, None) {
+                self.client.sync_status = Some(NO_SAVE_LOCATION.to_string());
+                return;
+            }
             self.client.create_sync_project(
-                &format!(
-                    "{}/{}",
+                &pull_save_path(
                     self.client.save_directory.as_deref().unwrap_or("/tmp"),
-                    pending.desktop_path.rsplit('/').next().unwrap_or("file")
+                    &pending.desktop_path,
+                    remote_file_name(&pending.desktop_path),
+                    self.client.keep_folder_structure,
                 ),
                 &pending.desktop_path,
+                pending.direction,
             );
         } else {
             // Sync from iOS to desktop: upload file, then create sync project
             self.client.upload_file(&pending.ios_path, &pending.desktop_path);
-            self.client.create_sync_project(&pending.ios_path, &pending.desktop_path);
+            self.client.create_sync_project(&pending.ios_path, &pending.desktop_path, pending.direction);
         }
         self.sync_step = SyncStep::BrowseLocal;
         self.sync_local_file = None;
+        self.sync_direction = SyncDirection::default();
+    }
+
+    /// First-run / offline empty state: the reason, a retry, and what to check.
+    fn draw_unreachable_state(&self, ui: &mut egui::Ui, reconnect_url: &mut Option<String>) {
+        // Before the first poll answers, the client still says "Connecting…"
+        let connecting = self.client.status_message.starts_with("Connecting");
+        ui.add_space(24.0);
+        ui.vertical_centered(|ui| {
+            if connecting {
+                ui.spinner();
+                ui.label(RichText::new("Connecting to desktop…").heading());
+            } else {
+                ui.label(RichText::new("📡").size(40.0));
+                ui.label(RichText::new("Can't reach the desktop").heading());
+                ui.label(RichText::new(&self.client.status_message).color(Color32::from_rgb(231, 76, 60)));
+            }
+            ui.label(RichText::new(&self.client.server_url).weak().small());
+            ui.add_space(8.0);
+            if ui.button("⟳ Retry").clicked() {
+                *reconnect_url = Some(self.client.server_url.clone());
+            }
+            ui.add_space(8.0);
+            ui.label(
+                RichText::new(
+                    "Make sure tailscaled is running and the Tailscale Drive desktop app is open \
+                     on the other machine, and that this phone is on the same tailnet. \
+                     🩺 Test connection below shows which step fails.",
+                )
+                .weak()
+                .small(),
+            );
+        });
+        ui.add_space(24.0);
+    }
+
+    /// Open the local browser on the folder containing `path` with the file selected.
+    fn reveal_local_file(&mut self, path: &str, do_browse: &mut Option<Option<String>>) {
+        let file = std::path::Path::new(path);
+        let Some(dir) = file.parent() else {
+            return;
+        };
+        self.sync_step = SyncStep::BrowseLocal;
+        self.open_project_sync(do_browse);
+        self.local_browse_path = dir.to_string_lossy().to_string();
+        self.refresh_local_files();
+        let name = file.file_name().map(|n| n.to_string_lossy()).unwrap_or_default();
+        self.selected_local_idx = self.local_files.iter().position(|e| !e.is_dir && e.name == name);
+        // Don't leave the revealed file hidden behind an extension filter
+        if self.selected_local_idx.is_some() && !self.local_filter.matches(&name) {
+            self.local_filter = FileFilter::All;
+        }
+    }
+
+    /// Switch to the Project Sync tab, fetching its data on first use.
+    fn open_project_sync(&mut self, do_browse: &mut Option<Option<String>>) {
+        self.current_page = Page::ProjectSync;
+        if !self.browse_fetched {
+            *do_browse = Some(None);
+            self.browse_fetched = true;
+        }
+        if !self.sync_projects_fetched {
+            self.client.fetch_sync_projects();
+            self.sync_projects_fetched = true;
+        }
+        // Initialize local browse path from save directory
+        if self.local_browse_path.is_empty() {
+            if let Some(ref dir) = self.client.save_directory {
+                // Go up from Downloads to the Documents dir
+                if let Some(parent) = std::path::Path::new(dir).parent() {
+                    self.local_browse_path = parent.to_string_lossy().to_string();
+                } else {
+                    self.local_browse_path = dir.clone();
+                }
+            }
+            self.refresh_local_files();
+        }
     }
 
     fn draw_project_sync_page(
         &mut self,
         ui: &mut egui::Ui,
         do_browse: &mut Option<Option<String>>,
-        file_to_pull: &mut Option<String>,
+        file_to_pull: &mut Option<(String, Option<String>)>,
         do_upload: &mut Option<(String, String)>,
         do_create_sync: &mut Option<(String, String)>,
         do_delete_sync: &mut Option<String>,
@@ -1632,7 +3892,7 @@ impl Renderer {
         &mut self,
         ui: &mut egui::Ui,
         do_browse: &mut Option<Option<String>>,
-        _file_to_pull: &mut Option<String>,
+        _file_to_pull: &mut Option<(String, Option<String>)>,
         do_upload: &mut Option<(String, String)>,
         do_delete_sync: &mut Option<String>,
         do_fetch_sync_projects: &mut bool,
@@ -1640,6 +3900,10 @@ impl Renderer {
         // ═══════════════════════════════════════════
         //  ACTIVE SYNCS LIST
         // ═══════════════════════════════════════════
+        // Applied after the list: Some(None) syncs every project
+        let mut sync_now: Option<Option<String>> = None;
+        let mut set_auto_sync: Option<bool> = None;
+        let mut clear_syncs = false;
         ui.group(|ui| {
             ui.horizontal(|ui| {
                 ui.label(
@@ -1656,6 +3920,24 @@ impl Renderer {
                 if ui.small_button("⟳").clicked() {
                     *do_fetch_sync_projects = true;
                 }
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    if !self.client.sync_projects.is_empty() && ui.small_button("⇅ Sync Now").clicked() {
+                        sync_now = Some(None);
+                    }
+                    let mut auto_sync = self.settings.auto_sync;
+                    if ui.checkbox(&mut auto_sync, "Auto").changed() {
+                        set_auto_sync = Some(auto_sync);
+                    }
+                    if !auto_sync {
+                        ui.label(RichText::new("Auto-sync off").small().color(Color32::from_rgb(230, 126, 34)));
+                    } else if self.client.cellular_paused {
+                        ui.label(RichText::new("Paused (cellular)").small().color(Color32::from_rgb(230, 126, 34)));
+                    }
+                    if self.settings.speed_limit != SpeedLimit::Unlimited {
+                        ui.label(RichText::new(format!("⏱ {}", self.settings.speed_limit.label())).small().weak())
+                            .on_hover_text("Speed limit; change it in ⚙ Settings → Sync");
+                    }
+                });
             });
             ui.add_space(4.0);
 
@@ -1663,6 +3945,10 @@ impl Renderer {
                 ui.label(RichText::new("No active syncs — select a file below to start").weak());
             } else {
                 let mut delete_id: Option<String> = None;
+                let mut edit_start: Option<SyncEdit> = None;
+                let mut preview_id: Option<String> = None;
+                // Some(true) = save, Some(false) = cancel
+                let mut edit_done: Option<bool> = None;
                 let mut add_to_current_file: Option<String> = None;
                 let connected_device = self.client.connected_device_name.clone().unwrap_or_else(|| "Desktop".to_string());
 
@@ -1671,10 +3957,7 @@ impl Renderer {
                         let status_icon = if project.paused { "⏸" } else { "🔄" };
 
                         // ── File name + sync icon ──
-                        let local_name = project.local_path
-                            .rsplit('/')
-                            .next()
-                            .unwrap_or(&project.local_path);
+                        let local_name = remote_file_name(&project.local_path);
                         let remote_name = project.remote_path
                             .rsplit('/')
                             .next()
@@ -1693,21 +3976,85 @@ impl Renderer {
                         // ── Device + path info ──
                         ui.horizontal(|ui| {
                             ui.label(RichText::new(format!("  🖥 {}", device_label)).small().color(Color32::from_rgb(100, 149, 237)));
-                            ui.label(RichText::new("↔").small().weak());
+                            let arrow = match project.direction {
+                                SyncDirection::Bidirectional => "↔",
+                                SyncDirection::DesktopToPhone => "→",
+                                SyncDirection::PhoneToDesktop => "←",
+                            };
+                            ui.label(RichText::new(arrow).small().weak())
+                                .on_hover_text(project.direction.label());
                             ui.label(RichText::new(format!("📱 {}", remote_name)).small().color(Color32::from_rgb(46, 204, 113)));
                         });
 
-                        // ── Paths (collapsed) ──
-                        ui.label(
-                            RichText::new(format!("  Desktop: {}", project.local_path))
-                                .weak()
-                                .small(),
-                        );
-                        ui.label(
-                            RichText::new(format!("  iPhone: {}", project.remote_path))
-                                .weak()
-                                .small(),
-                        );
+                        // ── Paths (collapsed, or editable while editing) ──
+                        if let Some(edit) = self.sync_edit.as_mut().filter(|e| e.id == project.id) {
+                            ui.horizontal(|ui| {
+                                ui.label(RichText::new("  Desktop:").weak().small());
+                                ui.text_edit_singleline(&mut edit.desktop_path);
+                            });
+                            ui.horizontal(|ui| {
+                                ui.label(RichText::new("  iPhone:").weak().small());
+                                ui.text_edit_singleline(&mut edit.ios_path);
+                            });
+                            ui.horizontal(|ui| {
+                                if ui.small_button("✔ Save").clicked() {
+                                    edit_done = Some(true);
+                                }
+                                if ui.small_button("Cancel").clicked() {
+                                    edit_done = Some(false);
+                                }
+                            });
+                        } else {
+                            ui.label(
+                                RichText::new(format!("  Desktop: {}", project.local_path))
+                                    .weak()
+                                    .small(),
+                            );
+                            ui.label(
+                                RichText::new(format!("  iPhone: {}", project.remote_path))
+                                    .weak()
+                                    .small(),
+                            );
+                        }
+
+                        // ── This project's last status / error ──
+                        let syncing_now = self.client.is_syncing_now(&project.id);
+                        if syncing_now {
+                            ui.horizontal(|ui| {
+                                ui.add_space(4.0);
+                                ui.spinner();
+                                ui.label(RichText::new("Syncing…").small().color(Color32::GRAY));
+                            });
+                        } else if let Some(status) = self.client.project_sync_status.get(&project.id) {
+                            let color = if status.starts_with('✔') {
+                                Color32::from_rgb(46, 204, 113)
+                            } else if status.starts_with('🗙') {
+                                Color32::from_rgb(231, 76, 60)
+                            } else {
+                                Color32::GRAY
+                            };
+                            ui.label(RichText::new(format!("  {}", status)).small().color(color));
+                        }
+                        if let Some(history) = self.client.sync_history.get(&project.id)
+                            && let Some(last) = history.last()
+                        {
+                            let describe = |entry: &SyncHistoryEntry| match entry.action.as_str() {
+                                "pushed" => format!("received '{}'", remote_file_name(&entry.path)),
+                                _ => "marked synced".to_string(),
+                            };
+                            let all = history
+                                .iter()
+                                .rev()
+                                .map(|e| format!("{} · {}", describe(e), format_timestamp(e.at)))
+                                .collect::<Vec<_>>()
+                                .join("\n");
+                            ui.label(
+                                RichText::new(format!("  Desktop {} · {}", describe(last), format_timestamp(last.at)))
+                                    .weak()
+                                    .small(),
+                            )
+                            .on_hover_text(all);
+                        }
 
                         // ── Last synced + actions ──
                         ui.horizontal(|ui| {
@@ -1726,6 +4073,26 @@ impl Renderer {
                                     if ui.small_button("🗑").clicked() {
                                         delete_id = Some(project.id.clone());
                                     }
+                                    if !project.paused
+                                        && ui
+                                            .add_enabled(!syncing_now, egui::Button::new("⇅").small())
+                                            .on_hover_text("Sync Now")
+                                            .clicked()
+                                    {
+                                        sync_now = Some(Some(project.id.clone()));
+                                    }
+                                    if self.client.supports_feature("syncplan")
+                                        && ui.small_button("👁").clicked()
+                                    {
+                                        preview_id = Some(project.id.clone());
+                                    }
+                                    if ui.small_button("✏").clicked() {
+                                        edit_start = Some(SyncEdit {
+                                            id: project.id.clone(),
+                                            desktop_path: project.local_path.clone(),
+                                            ios_path: project.remote_path.clone(),
+                                        });
+                                    }
 
                                     // "Add to current device" — if connected to a different
                                     // device than the one the sync is on
@@ -1746,25 +4113,98 @@ impl Renderer {
                 if let Some(id) = delete_id {
                     *do_delete_sync = Some(id);
                 }
+                if edit_start.is_some() {
+                    self.sync_edit = edit_start;
+                }
+                if let Some(id) = preview_id {
+                    self.client.sync_plan = None;
+                    self.client.fetch_sync_plan(&id);
+                }
+                match edit_done {
+                    Some(true) => {
+                        if let Some(edit) = self.sync_edit.take() {
+                            let ios_path = edit.ios_path.trim();
+                            // The desktop validates its own path; check ours here
+                            if !std::path::Path::new(ios_path).exists() {
+                                self.client.set_project_sync_status(
+                                    &edit.id,
+                                    format!("🗙 iPhone path '{}' does not exist", ios_path),
+                                );
+                                self.sync_edit = Some(edit);
+                            } else if sync_paths_overlap(ios_path, edit.desktop_path.trim()) {
+                                self.client.set_project_sync_status(
+                                    &edit.id,
+                                    "🗙 Desktop and iPhone paths overlap".to_string(),
+                                );
+                                self.sync_edit = Some(edit);
+                            } else {
+                                self.client.update_sync_project(&edit.id, edit.desktop_path.trim(), ios_path);
+                            }
+                        }
+                    }
+                    Some(false) => self.sync_edit = None,
+                    None => {}
+                }
                 // Trigger "add to current device" flow
                 if let Some(ios_file) = add_to_current_file {
                     self.sync_local_file = Some(ios_file);
                     self.sync_step = SyncStep::PickRemoteDest;
                     *do_browse = Some(None);
                 }
+
+                ui.add_space(4.0);
+                ui.horizontal(|ui| {
+                    if self.confirm_clear_syncs {
+                        if ui
+                            .small_button(RichText::new("Confirm remove all").color(Color32::from_rgb(231, 76, 60)))
+                            .clicked()
+                        {
+                            clear_syncs = true;
+                        }
+                        if ui.small_button("Cancel").clicked() {
+                            self.confirm_clear_syncs = false;
+                        }
+                    } else if ui.small_button("🗑 Remove all syncs").clicked() {
+                        self.confirm_clear_syncs = true;
+                    }
+                });
             }
 
-            // Sync status toast
+            // Sync status toast: latest event, plus how many projects are failing
             if let Some(ref status) = self.client.sync_status {
                 ui.add_space(4.0);
                 let color = if status.starts_with('✔') {
                     Color32::from_rgb(46, 204, 113)
+                } else if status.starts_with('🗙') {
+                    Color32::from_rgb(231, 76, 60)
                 } else {
                     Color32::GRAY
                 };
                 ui.colored_label(color, status.as_str());
             }
+            let failing = self.client.failing_sync_projects();
+            if failing > 0 {
+                ui.colored_label(
+                    Color32::from_rgb(231, 76, 60),
+                    format!("⚠ {} of {} syncs failing — see the cards above", failing, self.client.sync_projects.len()),
+                );
+            }
+            if !self.sync_feed.is_empty() {
+                ui.add_space(4.0);
+                self.draw_sync_feed(ui);
+            }
         });
+        if let Some(on) = set_auto_sync {
+            self.set_auto_sync(on);
+        }
+        if let Some(id) = sync_now {
+            self.client.sync_now(id.as_deref());
+        }
+        if clear_syncs {
+            self.client.clear_sync_projects();
+            self.sync_edit = None;
+            self.confirm_clear_syncs = false;
+        }
 
         ui.add_space(8.0);
 
@@ -1778,6 +4218,47 @@ impl Renderer {
                 .color(Color32::GRAY),
         );
 
+        // Pending "Move to…" from a pull: pick a folder, then move here
+        if let Some(src) = self.move_source.clone() {
+            let name = std::path::Path::new(&src)
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            ui.group(|ui| {
+                ui.label(
+                    RichText::new(format!("Moving '{}' — open a folder and tap Move here", name))
+                        .small(),
+                );
+                ui.horizontal(|ui| {
+                    if ui.button("📥 Move here").clicked() {
+                        match move_local_file(&src, &self.local_browse_path) {
+                            Ok(dest) => {
+                                self.move_status = Some(format!("✔ Moved '{}' here", name));
+                                self.client.last_pulled_path = Some(dest);
+                                self.move_source = None;
+                            }
+                            // Keep the banner up so another folder can be tried
+                            Err(e) => self.move_status = Some(format!("🗙 {}", e)),
+                        }
+                        self.refresh_local_files();
+                    }
+                    if ui.button("Cancel").clicked() {
+                        self.move_source = None;
+                        self.move_status = None;
+                    }
+                });
+            });
+        }
+        if let Some(ref status) = self.move_status {
+            let color = if status.starts_with('✔') {
+                Color32::from_rgb(46, 204, 113)
+            } else {
+                Color32::from_rgb(231, 76, 60)
+            };
+            ui.colored_label(color, status.as_str());
+            ui.add_space(4.0);
+        }
+
         // Show the current full path so the user knows where they are
         ui.label(
             RichText::new(&self.local_browse_path)
@@ -1819,6 +4300,51 @@ impl Renderer {
             if ui.button("⟳").clicked() {
                 self.refresh_local_files();
             }
+
+            if ui.selectable_label(self.local_multi_select, "☑ Select").clicked() {
+                self.local_multi_select = !self.local_multi_select;
+                self.local_selection.clear();
+                self.selected_local_idx = None;
+            }
+        });
+
+        // Extension filter chips (folders always stay visible)
+        ui.horizontal_wrapped(|ui| {
+            let before = self.local_filter.clone();
+            let presets = [
+                ("All", FileFilter::All),
+                ("🖼 Images", FileFilter::Images),
+                ("💻 Code", FileFilter::Code),
+                ("📃 Docs", FileFilter::Documents),
+            ];
+            for (label, filter) in presets {
+                let selected = self.local_filter == filter;
+                if ui.selectable_label(selected, RichText::new(label).small()).clicked() {
+                    self.local_filter = filter;
+                }
+            }
+            let mut custom = match &self.local_filter {
+                FileFilter::Custom(list) => list.clone(),
+                _ => String::new(),
+            };
+            let re = ui.add(
+                egui::TextEdit::singleline(&mut custom)
+                    .hint_text("ext, ext")
+                    .desired_width(90.0),
+            );
+            if re.changed() {
+                self.local_filter = if custom.trim().is_empty() {
+                    FileFilter::All
+                } else {
+                    FileFilter::Custom(custom)
+                };
+            }
+            if self.local_filter != before {
+                self.selected_local_idx = None;
+                if let Some(ref dir) = self.client.save_directory {
+                    save_browser_filter(dir, &self.local_filter);
+                }
+            }
         });
 
         ui.separator();
@@ -1831,8 +4357,15 @@ impl Renderer {
             let files_snapshot = self.local_files.clone();
 
             for (idx, entry) in files_snapshot.iter().enumerate() {
-                let is_selected = self.selected_local_idx == Some(idx);
-                let icon = if entry.is_dir { "📂" } else { "📄" };
+                if !entry.is_dir && !self.local_filter.matches(&entry.name) {
+                    continue;
+                }
+                let is_selected = if self.local_multi_select {
+                    self.local_selection.contains(&entry.path)
+                } else {
+                    self.selected_local_idx == Some(idx)
+                };
+                let (icon, color) = file_icon(&entry.name, entry.is_dir);
 
                 let label_text = if entry.is_dir {
                     format!("{} {}/", icon, entry.name)
@@ -1845,11 +4378,26 @@ impl Renderer {
                     )
                 };
 
-                let response = ui.selectable_label(is_selected, RichText::new(&label_text));
+                let response =
+                    ui.selectable_label(is_selected, tinted(RichText::new(&label_text), color));
+                if is_selected && self.scroll_to_selection {
+                    response.scroll_to_me(Some(egui::Align::Center));
+                }
 
                 if response.clicked() {
-                    if entry.is_dir {
+                    // Long-press selects a folder (to show its size) instead of opening it
+                    if entry.is_dir && !self.long_press_fired {
                         nav_to = Some(entry.path.clone());
+                    } else if self.local_multi_select {
+                        // Folders can't be batch-sent
+                        if !entry.is_dir {
+                            match self.local_selection.iter().position(|p| *p == entry.path) {
+                                Some(pos) => {
+                                    self.local_selection.remove(pos);
+                                }
+                                None => self.local_selection.push(entry.path.clone()),
+                            }
+                        }
                     } else {
                         self.selected_local_idx = Some(idx);
                     }
@@ -1864,14 +4412,81 @@ impl Renderer {
 
         ui.add_space(8.0);
 
+        // ─── Batch send (multi-select mode) ───
+        if self.local_multi_select {
+            let count = self.local_selection.len();
+            let total: u64 = self
+                .local_files
+                .iter()
+                .filter(|e| self.local_selection.contains(&e.path))
+                .map(|e| e.size)
+                .sum();
+            let mut send_batch = false;
+            ui.group(|ui| {
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(format!("{} selected", count)).strong());
+                    ui.label(RichText::new(format_size(total)).weak().small());
+                    if count > 0 && ui.small_button("Clear").clicked() {
+                        self.local_selection.clear();
+                    }
+                });
+                let can_send = count > 0 && self.client.server_cwd.is_some();
+                let label = format!("📤 Send {} to Desktop", count);
+                let resp = ui.add_enabled(can_send, egui::Button::new(label));
+                let resp = if self.client.connected {
+                    resp
+                } else {
+                    resp.on_hover_text("Desktop unreachable — files are sent when it's back")
+                };
+                send_batch = resp.clicked();
+            });
+            if send_batch && let Some(cwd) = self.client.server_cwd.clone() {
+                let files = std::mem::take(&mut self.local_selection)
+                    .into_iter()
+                    .map(|local| {
+                        let name = local.rsplit('/').next().unwrap_or(&local).to_string();
+                        (local, format!("{}/{}", cwd, name))
+                    })
+                    .collect();
+                self.client.upload_files(files);
+            }
+        }
+
+        if let Some((sent, failed, total)) = self.client.upload_progress {
+            let queued = self.client.queued_upload_count();
+            let mut text = format!("📤 Sent {} of {}", sent, total);
+            if failed > 0 {
+                text.push_str(&format!(" · {} failed", failed));
+            }
+            if queued > 0 {
+                text.push_str(&format!(" · {} waiting for desktop", queued));
+            }
+            ui.add(
+                egui::ProgressBar::new((sent + failed) as f32 / total.max(1) as f32)
+                    .text(RichText::new(text).small()),
+            );
+        }
+
         // ─── Action buttons for selected local file ───
         if let Some(sel_idx) = self.selected_local_idx {
             if let Some(selected) = self.local_files.get(sel_idx) {
-                if !selected.is_dir {
+                if selected.is_dir {
+                    let summary = self
+                        .client
+                        .local_dir_info(&selected.path)
+                        .map(|info| info.summary())
+                        .unwrap_or_else(|| "Calculating…".to_string());
+                    ui.group(|ui| {
+                        ui.horizontal(|ui| {
+                            ui.label(RichText::new(format!("📂 {}", selected.name)).strong());
+                            ui.label(RichText::new(summary).weak().small());
+                        });
+                    });
+                } else {
                     ui.group(|ui| {
                         ui.horizontal(|ui| {
                             ui.label(
-                                RichText::new(format!("📄 {}", selected.name)).strong(),
+                                RichText::new(format!("{} {}", file_icon(&selected.name, false).0, selected.name)).strong(),
                             );
                             ui.label(
                                 RichText::new(format_size(selected.size))
@@ -1880,15 +4495,19 @@ impl Renderer {
                             );
                         });
 
+                        let send_name = send_as_field(ui, &mut self.send_as, &selected.path);
+
                         ui.horizontal(|ui| {
-                            if ui.button("📤 Send to Desktop").clicked() {
+                            let send_btn = ui.add_enabled(send_name.is_some(), egui::Button::new("📤 Send to Desktop"));
+                            if send_btn.clicked() {
                                 // One-shot send: upload to the server CWD
-                                if let Some(ref cwd) = self.client.server_cwd {
-                                    let remote = format!("{}/{}", cwd, selected.name);
+                                if let (Some(cwd), Some(name)) = (&self.client.server_cwd, &send_name) {
+                                    let remote = format!("{}/{}", cwd, name);
                                     *do_upload = Some((selected.path.clone(), remote));
                                 }
                             }
-                            if ui.button("🔄 Sync with Desktop").clicked() {
+                            let sync_btn = ui.add_enabled(send_name.is_some(), egui::Button::new("🔄 Sync with Desktop"));
+                            if sync_btn.clicked() {
                                 // Start the sync flow: pick remote destination
                                 self.sync_local_file = Some(selected.path.clone());
                                 self.sync_step = SyncStep::PickRemoteDest;
@@ -1920,6 +4539,7 @@ impl Renderer {
             ui.heading("Choose Destination");
         });
 
+        let mut send_name = None;
         if let Some(ref local_file) = self.sync_local_file.clone() {
             let filename = local_file
                 .rsplit('/')
@@ -1929,21 +4549,30 @@ impl Renderer {
                 RichText::new(format!("Syncing: {}", filename))
                     .color(Color32::from_rgb(46, 204, 113)),
             );
+            send_name = send_as_field(ui, &mut self.send_as, local_file);
+        }
+        if self.client.supports_feature("syncdirection") {
+            ui.horizontal_wrapped(|ui| {
+                for direction in [
+                    SyncDirection::Bidirectional,
+                    SyncDirection::DesktopToPhone,
+                    SyncDirection::PhoneToDesktop,
+                ] {
+                    ui.selectable_value(&mut self.sync_direction, direction, direction.label());
+                }
+            });
         }
 
         ui.add_space(4.0);
 
         // ─── Remote Navigation bar ───
         ui.horizontal(|ui| {
-            if ui.button("⬆ Up").clicked() {
-                if let Some(pos) = self.browse_path_input.rfind('/') {
-                    self.browse_path_input.truncate(pos);
-                    if self.browse_path_input.is_empty() {
-                        self.browse_path_input = "/".to_string();
-                    }
-                } else {
-                    self.browse_path_input = "/".to_string();
-                }
+            let parent = parent_remote_path_within(&self.browse_path_input, self.client.browse_root());
+            if ui
+                .add_enabled(parent != normalize_remote_path(&self.browse_path_input), egui::Button::new("⬆ Up"))
+                .clicked()
+            {
+                self.browse_path_input = parent;
                 *do_browse = Some(Some(self.browse_path_input.clone()));
                 self.selected_remote_idx = None;
             }
@@ -2031,7 +4660,7 @@ impl Renderer {
 
             for &idx in &sorted {
                 let entry = &self.client.remote_files[idx];
-                let icon = if entry.is_dir { "📂" } else { "📄" };
+                let (icon, color) = file_icon(&entry.name, entry.is_dir);
 
                 let label_text = if entry.is_dir {
                     format!("{} {}/", icon, entry.name)
@@ -2044,24 +4673,18 @@ impl Renderer {
                     )
                 };
 
-                let response = ui.selectable_label(false, RichText::new(&label_text));
+                let response = ui.selectable_label(false, tinted(RichText::new(&label_text), color));
 
                 if response.clicked() {
                     if entry.is_dir {
-                        let new_path = if self.browse_path_input.is_empty()
-                            || self.browse_path_input == "/"
-                        {
-                            format!("/{}", entry.name)
-                        } else {
-                            format!("{}/{}", self.browse_path_input, entry.name)
-                        };
+                        let new_path = self.remote_entry_path(&entry.name);
                         nav_to = Some(new_path);
                     }
                 }
             }
 
             if let Some(new_path) = nav_to {
-                self.browse_path_input = new_path.clone();
+                self.browse_path_input = normalize_remote_path(&new_path);
                 *do_browse = Some(Some(new_path));
             }
         }
@@ -2074,19 +4697,9 @@ impl Renderer {
                 RichText::new(format!("Destination: {}/", self.browse_path_input))
                     .strong(),
             );
-            if ui.button("🔄 Sync to This Folder").clicked() {
-                if let Some(ref local_file) = self.sync_local_file.clone() {
-                    let filename = local_file
-                        .rsplit('/')
-                        .next()
-                        .unwrap_or(local_file);
-                    let remote_path = if self.browse_path_input.is_empty()
-                        || self.browse_path_input == "/"
-                    {
-                        format!("/{}", filename)
-                    } else {
-                        format!("{}/{}", self.browse_path_input, filename)
-                    };
+            if ui.add_enabled(send_name.is_some(), egui::Button::new("🔄 Sync to This Folder")).clicked() {
+                if let (Some(local_file), Some(filename)) = (self.sync_local_file.clone(), send_name) {
+                    let remote_path = self.remote_entry_path(&filename);
                     *do_create_sync = Some((local_file.clone(), remote_path));
                 }
             }
@@ -2218,7 +4831,7 @@ fn apply_theme(ctx: &egui::Context) {
             ctx.set_style(std::sync::Arc::new(theme));
         }
         Err(e) => {
-            eprintln!("[theme] serde_json deserialize failed: {e}");
+            log::error!("[theme] serde_json deserialize failed: {e}");
             let mut style = (*ctx.style()).clone();
             style.override_font_id = Some(egui::FontId::new(15., egui::FontFamily::Proportional));
             style.visuals.dark_mode = true;
@@ -2266,6 +4879,81 @@ fn apply_theme(ctx: &egui::Context) {
 
 // ── File type helpers ────────────────────────────────────────────────────
 
+/// Server URL for a peer: the app's own port over http, or the default port
+/// when the desktop is served over TLS by `tailscale serve --https`.
+fn peer_server_url(dns_name: &str, https: bool) -> String {
+    let host = dns_name.trim_end_matches('.');
+    if https {
+        format!("https://{}", host)
+    } else {
+        format!("http://{}:8080", host)
+    }
+}
+
+/// Trim and validate a server URL: requires an http/https scheme and a host,
+/// and drops any trailing slash.
+fn normalize_server_url(url: &str) -> Option<String> {
+    let url = url.trim().trim_end_matches('/');
+    let rest = url
+        .strip_prefix("http://")
+        .or_else(|| url.strip_prefix("https://"))?;
+    let host = rest.split(['/', ':']).next().unwrap_or("");
+    if host.is_empty() || rest.contains(char::is_whitespace) {
+        return None;
+    }
+    if let Some((_, port)) = rest.split('/').next().unwrap_or("").split_once(':') {
+        port.parse::<u16>().ok()?;
+    }
+    Some(url.to_string())
+}
+
+/// Accept either a plain server URL or a join code from the desktop's QR
+/// (`tsdrive://connect?url=<percent-encoded>&token=<optional>`).
+/// Returns the normalized URL and the token, if any.
+fn parse_server_input(input: &str) -> Option<(String, Option<String>)> {
+    let input = input.trim();
+    let Some(query) = input.strip_prefix("tsdrive://connect?") else {
+        return normalize_server_url(input).map(|url| (url, None));
+    };
+    let mut url = None;
+    let mut token = None;
+    for pair in query.split('&') {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        let value = percent_decode(value)?;
+        match key {
+            "url" => url = normalize_server_url(&value),
+            "token" if !value.is_empty() => token = Some(value),
+            _ => {}
+        }
+    }
+    Some((url?, token))
+}
+
+/// Decode `%XX` escapes and `+` in a query value. None on malformed input.
+fn percent_decode(value: &str) -> Option<String> {
+    let bytes = value.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = value.get(i + 1..i + 3)?;
+                out.push(u8::from_str_radix(hex, 16).ok()?);
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            b => {
+                out.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(out).ok()
+}
+
 fn file_extension(name: &str) -> String {
     if let Some(pos) = name.rfind('.') {
         name[pos + 1..].to_lowercase()
@@ -2297,10 +4985,499 @@ fn is_text_ext(ext: &str) -> bool {
     )
 }
 
+/// HEIC/HEIF/AVIF arrive as PNG thumbnails from desktops that can decode them;
+/// otherwise the preview says the format is unsupported.
 fn is_image_ext(ext: &str) -> bool {
-    matches!(ext, "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp")
+    matches!(ext, "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp" | "heic" | "heif" | "avif")
+}
+
+/// Decode preview bytes (usually the server's PNG thumbnail) into a texture image.
+/// The error is the message shown in place of the image.
+fn decode_preview_image(data: &[u8]) -> Result<egui::ColorImage, &'static str> {
+    let img = image::load_from_memory(data).map_err(|e| match e {
+        image::ImageError::Unsupported(_) => "(Unsupported image format)",
+        _ => "(Failed to decode image)",
+    })?;
+    let rgba = img.to_rgba8();
+    let size = [rgba.width() as usize, rgba.height() as usize];
+    Ok(egui::ColorImage::from_rgba_unmultiplied(size, rgba.as_raw()))
+}
+
+/// Formats whose duration and format details live in the file header.
+/// Ogg/Opus are left out: their duration comes from the last page.
+fn is_audio_ext(ext: &str) -> bool {
+    matches!(ext, "mp3" | "wav" | "flac" | "aac" | "m4a" | "aiff" | "aif")
 }
 
 fn is_previewable(ext: &str) -> bool {
-    is_text_ext(ext) || is_image_ext(ext)
+    is_text_ext(ext) || is_image_ext(ext) || is_audio_ext(ext)
+}
+
+/// Text previews are cut at this many bytes.
+const PREVIEW_TEXT_MAX_BYTES: u64 = 100_000;
+/// Head of an audio file fetched for its metadata; leaves room for ID3 cover art.
+const PREVIEW_AUDIO_MAX_BYTES: u64 = 512 * 1024;
+
+fn preview_max_bytes(path: &str) -> u64 {
+    if is_audio_ext(&file_extension(path)) {
+        PREVIEW_AUDIO_MAX_BYTES
+    } else {
+        PREVIEW_TEXT_MAX_BYTES
+    }
+}
+
+/// What the preview window shows for an audio file.
+struct AudioInfo {
+    format: String,
+    duration_secs: u64,
+    /// Duration was worked out from bitrate and file size, not read from the header
+    duration_estimated: bool,
+    bitrate_kbps: Option<u32>,
+    sample_rate: Option<u32>,
+    channels: Option<u8>,
+    bit_depth: Option<u8>,
+    title: Option<String>,
+    artist: Option<String>,
+    album: Option<String>,
+}
+
+/// Decode audio properties and tags from the head of a file. `total_size` is
+/// the full file size, used to correct figures lofty derives from stream length.
+fn read_audio_info(head: &[u8], total_size: Option<u64>) -> Result<AudioInfo, String> {
+    use lofty::file::FileType;
+    use lofty::prelude::*;
+
+    let tagged = lofty::probe::Probe::new(std::io::Cursor::new(head))
+        .guess_file_type()
+        .map_err(|e| e.to_string())?
+        .read()
+        .map_err(|e| e.to_string())?;
+    let props = tagged.properties();
+
+    let mut duration = props.duration().as_secs_f64();
+    let mut duration_estimated = false;
+    let mut bitrate_kbps = props.audio_bitrate().or(props.overall_bitrate());
+    if let Some(total) = total_size.filter(|&total| total > head.len() as u64) {
+        match tagged.file_type() {
+            // Frame-based streams without a Xing/VBRI header: lofty can only time
+            // the bytes it was given, so scale up from the bitrate
+            FileType::Mpeg | FileType::Aac => {
+                if let Some(kbps) = bitrate_kbps.filter(|&k| k > 0) {
+                    let head_secs = head.len() as f64 * 8.0 / (kbps as f64 * 1000.0);
+                    if duration <= head_secs * 1.5 {
+                        duration = total as f64 * 8.0 / (kbps as f64 * 1000.0);
+                        duration_estimated = true;
+                    }
+                }
+            }
+            // Duration came from the header; the bitrate was computed from the truncated stream
+            _ => {
+                if duration > 0.0 {
+                    bitrate_kbps = Some((total as f64 * 8.0 / duration / 1000.0).round() as u32);
+                }
+            }
+        }
+    }
+
+    let tag = tagged.primary_tag().or_else(|| tagged.first_tag());
+    Ok(AudioInfo {
+        format: format!("{:?}", tagged.file_type()),
+        duration_secs: duration.round() as u64,
+        duration_estimated,
+        bitrate_kbps,
+        sample_rate: props.sample_rate(),
+        channels: props.channels(),
+        bit_depth: props.bit_depth(),
+        title: tag.and_then(|t| t.title()).map(|s| s.into_owned()),
+        artist: tag.and_then(|t| t.artist()).map(|s| s.into_owned()),
+        album: tag.and_then(|t| t.album()).map(|s| s.into_owned()),
+    })
+}
+
+fn draw_audio_info(ui: &mut egui::Ui, info: &AudioInfo) {
+    ui.label(RichText::new("🎵 Audio").strong());
+    ui.add_space(4.0);
+    egui::Grid::new("audio_preview_grid")
+        .num_columns(2)
+        .spacing([16.0, 4.0])
+        .show(ui, |ui| {
+            let mut row = |label: &str, value: String| {
+                ui.label(RichText::new(label).weak());
+                ui.label(value);
+                ui.end_row();
+            };
+            if let Some(ref title) = info.title {
+                row("Title", title.clone());
+            }
+            if let Some(ref artist) = info.artist {
+                row("Artist", artist.clone());
+            }
+            if let Some(ref album) = info.album {
+                row("Album", album.clone());
+            }
+            let secs = info.duration_secs;
+            row(
+                "Duration",
+                format!(
+                    "{}{}:{:02}",
+                    if info.duration_estimated { "≈ " } else { "" },
+                    secs / 60,
+                    secs % 60
+                ),
+            );
+            if let Some(kbps) = info.bitrate_kbps {
+                row("Bitrate", format!("{} kbps", kbps));
+            }
+            if let Some(rate) = info.sample_rate {
+                row("Sample rate", format!("{:.1} kHz", rate as f64 / 1000.0));
+            }
+            if let Some(channels) = info.channels {
+                let label = match channels {
+                    1 => "Mono".to_string(),
+                    2 => "Stereo".to_string(),
+                    n => format!("{} channels", n),
+                };
+                row("Channels", label);
+            }
+            if let Some(bits) = info.bit_depth {
+                row("Bit depth", format!("{}-bit", bits));
+            }
+            row("Format", info.format.clone());
+        });
+    ui.add_space(8.0);
+    ui.label(RichText::new("Playback isn't available yet — pull the file to listen.").weak().small());
+}
+
+/// Normalized path of `name` inside the remote folder `dir` ("" is the root),
+/// matching the keys of `TailscaleClient::tree_children`.
+fn remote_child_path(dir: &str, name: &str, sep: char) -> String {
+    normalize_remote_path(&join_remote_path(dir, name, sep))
+}
+
+/// Icon and accent color for a browser entry, picked from the file extension.
+/// Unknown types keep the plain 📄 and the default text color.
+fn file_icon(name: &str, is_dir: bool) -> (&'static str, Option<Color32>) {
+    if is_dir {
+        return ("📂", None);
+    }
+    match file_extension(name).as_str() {
+        "jpg" | "jpeg" | "png" | "gif" | "webp" | "bmp" | "heic" | "heif" | "tif" | "tiff"
+        | "svg" | "ico" => ("🖼", Some(Color32::from_rgb(155, 89, 182))),
+        "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "7z" | "rar" | "dmg" | "iso" => {
+            ("📦", Some(Color32::from_rgb(230, 126, 34)))
+        }
+        "mp3" | "wav" | "flac" | "aac" | "m4a" | "ogg" | "opus" | "aiff" => {
+            ("🎵", Some(Color32::from_rgb(26, 188, 156)))
+        }
+        "mp4" | "mov" | "m4v" | "mkv" | "avi" | "webm" => {
+            ("🎬", Some(Color32::from_rgb(231, 76, 60)))
+        }
+        "pdf" | "doc" | "docx" | "odt" | "rtf" | "txt" | "md" | "markdown" | "xls" | "xlsx"
+        | "csv" | "ppt" | "pptx" | "pages" | "numbers" | "key" => {
+            ("📃", Some(Color32::from_rgb(241, 196, 15)))
+        }
+        ext if is_text_ext(ext) => ("💻", Some(Color32::from_rgb(52, 152, 219))),
+        _ => ("📄", None),
+    }
+}
+
+const DOCUMENT_EXTS: &[&str] = &[
+    "pdf", "doc", "docx", "odt", "rtf", "txt", "md", "markdown", "xls", "xlsx", "csv", "ppt",
+    "pptx", "pages", "numbers", "key",
+];
+
+/// Which files the local browser shows. Directories are always shown.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize, serde::Deserialize)]
+enum FileFilter {
+    #[default]
+    All,
+    Images,
+    Code,
+    Documents,
+    /// Comma/space separated extensions, e.g. "rs, toml"
+    Custom(String),
+}
+
+impl FileFilter {
+    fn matches(&self, name: &str) -> bool {
+        let ext = file_extension(name);
+        match self {
+            FileFilter::All => true,
+            FileFilter::Images => is_image_ext(&ext) || ext == "svg",
+            FileFilter::Code => is_text_ext(&ext) && !DOCUMENT_EXTS.contains(&ext.as_str()),
+            FileFilter::Documents => DOCUMENT_EXTS.contains(&ext.as_str()),
+            FileFilter::Custom(list) => {
+                let mut wanted = list
+                    .split([',', ' '])
+                    .map(|e| e.trim().trim_start_matches('.'))
+                    .filter(|e| !e.is_empty())
+                    .peekable();
+                wanted.peek().is_none() || wanted.any(|e| e.eq_ignore_ascii_case(&ext))
+            }
+        }
+    }
+}
+
+fn browser_filter_path(save_dir: &str) -> String {
+    // Stored alongside cached_peers.json in Documents
+    match std::path::Path::new(save_dir).parent() {
+        Some(parent) => format!("{}/browser_filter.json", parent.to_string_lossy()),
+        None => format!("{}/browser_filter.json", save_dir),
+    }
+}
+
+fn load_browser_filter(save_dir: &str) -> FileFilter {
+    std::fs::read_to_string(browser_filter_path(save_dir))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save_browser_filter(save_dir: &str, filter: &FileFilter) {
+    if let Ok(data) = serde_json::to_string(filter) {
+        let _ = std::fs::write(browser_filter_path(save_dir), data);
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct SafeAreaInsets {
+    top: f32,
+    bottom: f32,
+    left: f32,
+    right: f32,
+}
+
+/// Preferences persisted across launches. New fields need a default so older
+/// settings files keep loading.
+#[derive(Debug, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+struct RendererSettings {
+    keep_folder_structure: bool,
+    /// Ask before previewing images larger than this; 0 never asks
+    preview_confirm_bytes: u64,
+    /// Ask before pulling files larger than this; 0 never asks
+    pull_confirm_bytes: u64,
+    /// Always download pulls, even when the saved copy is still current
+    always_download: bool,
+    /// Remote folder to open when connecting to a peer, by peer id
+    peer_default_paths: std::collections::HashMap<String, String>,
+    /// Auth tokens from join QR codes, by server URL
+    server_tokens: std::collections::HashMap<String, String>,
+    /// Custom root certificates / relaxed verification, by server URL
+    server_tls: std::collections::HashMap<String, TlsOptions>,
+    /// Seconds between polls of the desktop; 0 uses `TAILSCALE_DRIVE_POLL_SECS` or 3s
+    poll_secs: u64,
+    /// What to do when a new sync would overwrite an existing file
+    overwrite_policy: OverwritePolicy,
+    /// Pull/push sync changes on every poll; off, only "Sync Now" transfers
+    auto_sync: bool,
+    /// On cellular: pause auto-sync and ask before large pulls
+    wifi_only: bool,
+    /// Checksum pushed sync files against the desktop before acking them
+    verify_sync_uploads: bool,
+    /// Gzip uploads worth compressing (servers with "syncgzip")
+    compress_uploads: bool,
+    /// Cap on transfer speed to and from the desktop
+    speed_limit: SpeedLimit,
+    /// Remote browser expands folders inline instead of navigating into them
+    browse_tree_mode: bool,
+    /// Hold this long to open a context menu
+    long_press_ms: u64,
+    /// Desktop folder clipboard images are uploaded to (`~/` is the desktop's home)
+    clipboard_dest_dir: String,
+    /// Desktop time of the last "What's New" visit, by server URL
+    changes_seen: std::collections::HashMap<String, u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+enum OverwritePolicy {
+    #[default]
+    Ask,
+    Overwrite,
+    Skip,
+}
+
+impl OverwritePolicy {
+    fn label(self) -> &'static str {
+        match self {
+            OverwritePolicy::Ask => "Ask every time",
+            OverwritePolicy::Overwrite => "Overwrite",
+            OverwritePolicy::Skip => "Skip",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, serde::Deserialize)]
+enum SpeedLimit {
+    #[default]
+    Unlimited,
+    FiveMBps,
+    OneMBps,
+}
+
+impl SpeedLimit {
+    fn label(self) -> &'static str {
+        match self {
+            SpeedLimit::Unlimited => "Unlimited",
+            SpeedLimit::FiveMBps => "5 MB/s",
+            SpeedLimit::OneMBps => "1 MB/s",
+        }
+    }
+
+    /// 0 means no cap.
+    fn bytes_per_sec(self) -> u64 {
+        match self {
+            SpeedLimit::Unlimited => 0,
+            SpeedLimit::FiveMBps => 5 * 1024 * 1024,
+            SpeedLimit::OneMBps => 1024 * 1024,
+        }
+    }
+}
+
+impl Default for RendererSettings {
+    fn default() -> Self {
+        Self {
+            keep_folder_structure: false,
+            preview_confirm_bytes: DEFAULT_PREVIEW_CONFIRM_BYTES,
+            pull_confirm_bytes: 0,
+            always_download: false,
+            peer_default_paths: Default::default(),
+            server_tokens: Default::default(),
+            server_tls: Default::default(),
+            poll_secs: 0,
+            overwrite_policy: OverwritePolicy::Ask,
+            auto_sync: true,
+            wifi_only: false,
+            verify_sync_uploads: true,
+            compress_uploads: true,
+            speed_limit: SpeedLimit::Unlimited,
+            browse_tree_mode: false,
+            long_press_ms: DEFAULT_LONG_PRESS_MS,
+            clipboard_dest_dir: DEFAULT_CLIPBOARD_DEST_DIR.to_string(),
+            changes_seen: Default::default(),
+        }
+    }
+}
+
+fn settings_path(save_dir: &str) -> String {
+    // Stored alongside cached_peers.json in Documents
+    match std::path::Path::new(save_dir).parent() {
+        Some(parent) => format!("{}/settings.json", parent.to_string_lossy()),
+        None => format!("{}/settings.json", save_dir),
+    }
+}
+
+fn load_settings(save_dir: &str) -> RendererSettings {
+    std::fs::read_to_string(settings_path(save_dir))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save_settings(save_dir: &str, settings: &RendererSettings) {
+    if let Ok(data) = serde_json::to_string(settings) {
+        let _ = std::fs::write(settings_path(save_dir), data);
+    }
+}
+
+fn sync_feed_path(save_dir: &str) -> String {
+    // Stored alongside settings.json in Documents
+    match std::path::Path::new(save_dir).parent() {
+        Some(parent) => format!("{}/sync_feed.json", parent.to_string_lossy()),
+        None => format!("{}/sync_feed.json", save_dir),
+    }
+}
+
+fn load_sync_feed(save_dir: &str) -> std::collections::VecDeque<SyncFeedEntry> {
+    std::fs::read_to_string(sync_feed_path(save_dir))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+fn save_sync_feed(save_dir: &str, feed: &std::collections::VecDeque<SyncFeedEntry>) {
+    if let Ok(data) = serde_json::to_string(feed) {
+        let _ = std::fs::write(sync_feed_path(save_dir), data);
+    }
+}
+
+/// Move a local file into `dest_dir`, keeping its name. Refuses to overwrite.
+/// Returns the new full path.
+fn move_local_file(src: &str, dest_dir: &str) -> Result<String, String> {
+    let src_path = std::path::Path::new(src);
+    let name = src_path
+        .file_name()
+        .ok_or_else(|| format!("Not a file: {}", src))?;
+    let dest = std::path::Path::new(dest_dir).join(name);
+    if dest == src_path {
+        return Ok(src.to_string());
+    }
+    if dest.exists() {
+        return Err(format!("'{}' already exists here", name.to_string_lossy()));
+    }
+    std::fs::rename(src_path, &dest).map_err(|e| format!("Move failed: {}", e))?;
+    Ok(dest.to_string_lossy().to_string())
+}
+
+/// Check a user-entered destination file name. Returns the trimmed name.
+fn validate_file_name(name: &str) -> Result<&str, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Name can't be empty".to_string());
+    }
+    if name == "." || name == ".." {
+        return Err(format!("'{}' isn't a valid file name", name));
+    }
+    if name.contains(['/', '\\', '\0']) {
+        return Err("Name can't contain / or \\".to_string());
+    }
+    Ok(name)
+}
+
+/// "Send as" row for picking the desktop-side name of `local_path`. `slot` is
+/// reset to the file's own name whenever a different file is shown. Returns the
+/// validated name, or `None` (with the reason shown) if it can't be used.
+fn send_as_field(ui: &mut egui::Ui, slot: &mut Option<(String, String)>, local_path: &str) -> Option<String> {
+    if slot.as_ref().is_none_or(|(path, _)| path != local_path) {
+        let name = local_path.rsplit('/').next().unwrap_or(local_path).to_string();
+        *slot = Some((local_path.to_string(), name));
+    }
+    let (_, name) = slot.as_mut()?;
+    ui.horizontal(|ui| {
+        ui.label("Send as:");
+        ui.add(egui::TextEdit::singleline(name).desired_width(ui.available_width()));
+    });
+    match validate_file_name(name) {
+        Ok(valid) => Some(valid.to_string()),
+        Err(e) => {
+            ui.colored_label(Color32::from_rgb(231, 76, 60), format!("🗙 {}", e));
+            None
+        }
+    }
+}
+
+fn tinted(text: RichText, color: Option<Color32>) -> RichText {
+    match color {
+        Some(color) => text.color(color),
+        None => text,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heic_preview_without_thumbnail_reports_unsupported_format() {
+        assert!(is_image_ext("heic") && is_image_ext("avif"));
+        // Head of an iPhone photo, as sent by a desktop that can't decode HEIC
+        let mut heic = b"\0\0\0\x18ftypheic\0\0\0\0mif1heic".to_vec();
+        heic.resize(1024, 0xAB);
+        assert_eq!(decode_preview_image(&heic).unwrap_err(), "(Unsupported image format)");
+        // A truncated PNG is recognised but can't be decoded
+        assert_eq!(
+            decode_preview_image(b"\x89PNG\r\n\x1a\n\0\0").unwrap_err(),
+            "(Failed to decode image)"
+        );
+    }
 }
@@ -1,7 +1,8 @@
-use std::sync::mpsc;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, mpsc};
 use std::time::{Duration, Instant};
 
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
 // ── Data models (match desktop server JSON) ─────────────────────────────
 
@@ -19,16 +20,88 @@ pub struct SentFileInfo {
 pub struct WaitingFile {
     pub name: String,
     pub size: u64,
+    /// Sender hostname (or peer ID); empty if the desktop doesn't know
+    #[serde(default)]
+    pub from_peer: String,
+    /// Unix time the desktop first saw the file; 0 if unknown
+    #[serde(default)]
+    pub received_at: u64,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct RemoteFile {
     pub name: String,
+    /// Opaque server-side id of the full path; survives names that aren't valid UTF-8
+    #[serde(default)]
+    pub path_id: Option<String>,
     pub is_dir: bool,
     pub size: i64,
     pub modified: u64,
 }
 
+/// A file `GET /changes` reports: its `/browse` entry plus the full path.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChangedFile {
+    pub path: String,
+    #[serde(flatten)]
+    pub file: RemoteFile,
+}
+
+/// Response of `GET /changes`: desktop files modified since a timestamp.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ChangeList {
+    pub root: String,
+    /// Desktop time of the check; the `since` for the next one
+    pub now: u64,
+    /// Newest first
+    pub files: Vec<ChangedFile>,
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+/// A file the desktop has received, from `GET /received`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReceivedFile {
+    pub name: String,
+    pub size: u64,
+    #[serde(default)]
+    pub from_peer: String,
+    #[serde(default)]
+    pub received_at: u64,
+    /// Whether `/download/{name}` can still serve it
+    #[serde(default)]
+    pub available: bool,
+}
+
+/// Where an activity timeline entry came from.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ActivityKind {
+    /// Waiting in the desktop's Taildrop inbox
+    Inbox,
+    /// Received by the desktop earlier (`/received`)
+    Received,
+    /// Pulled to this device
+    Pulled,
+    /// Last synced by a sync project
+    Synced,
+}
+
+/// One row of the recent-activity timeline.
+#[derive(Debug, Clone)]
+pub struct ActivityEntry {
+    pub kind: ActivityKind,
+    pub name: String,
+    /// Sender for desktop files, the desktop path for pulls and syncs
+    pub detail: String,
+    /// Unix time; 0 if unknown
+    pub at: u64,
+    pub size: Option<u64>,
+    /// Saved copy on this device, if any
+    pub local_path: Option<String>,
+    /// The desktop can serve it via `/download/{name}`
+    pub downloadable: bool,
+}
+
 #[derive(Debug, Clone, serde::Serialize, Deserialize)]
 pub struct PeerInfo {
     pub id: String,
@@ -50,6 +123,71 @@ pub struct SyncProject {
     pub device_name: String,
     #[serde(default)]
     pub device_dns: String,
+    #[serde(default)]
+    pub direction: SyncDirection,
+}
+
+/// A sync result kept in the Project Sync page's history.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncFeedEntry {
+    /// Unix time the client saw it
+    pub at: u64,
+    /// Desktop file or folder name of the project; empty if not tied to one
+    pub project: String,
+    pub failed: bool,
+    pub message: String,
+}
+
+/// Which side's edits a sync project carries over; one-way projects are mirrors.
+#[derive(Debug, Clone, Copy, PartialEq, Default, serde::Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncDirection {
+    #[default]
+    Bidirectional,
+    DesktopToPhone,
+    PhoneToDesktop,
+}
+
+impl SyncDirection {
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Bidirectional => "⇄ Both ways",
+            Self::DesktopToPhone => "⬇ Desktop → iPhone",
+            Self::PhoneToDesktop => "⬆ iPhone → Desktop",
+        }
+    }
+
+    /// iPhone edits are pushed to the desktop.
+    pub fn pushes_from_phone(self) -> bool {
+        self != Self::DesktopToPhone
+    }
+}
+
+/// What `/status` reports about the desktop machine itself.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServerInfo {
+    pub hostname: String,
+    pub os: String,
+    pub arch: String,
+    /// Tailnet DNS name, e.g. `tail1234.ts.net`
+    pub tailnet: String,
+    /// `\\` on Windows desktops; `/` when the server doesn't say
+    pub path_separator: char,
+    /// The only folder this client may browse, when the desktop limits it to one
+    pub browse_root: Option<String>,
+}
+
+/// Body of a successful upload; only the written size is checked.
+#[derive(Debug, Clone, Deserialize)]
+struct UploadResult {
+    bytes: u64,
+}
+
+/// `GET /sync/hash` response.
+#[derive(Debug, Clone, Deserialize)]
+struct FileHash {
+    size: u64,
+    crc32: u32,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -59,6 +197,31 @@ pub struct FileInfoResponse {
     pub size: u64,
 }
 
+/// Recursive directory summary (`GET /dirinfo`, or computed locally)
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct DirInfo {
+    pub total_size: u64,
+    pub file_count: u64,
+    #[serde(default)]
+    pub dir_count: u64,
+    #[serde(default)]
+    pub truncated: bool,
+}
+
+impl DirInfo {
+    /// e.g. "142 files · 3.4 GB" (with a "+" when the walk was cut short)
+    pub fn summary(&self) -> String {
+        let more = if self.truncated { "+" } else { "" };
+        format!(
+            "{}{} files · {}{}",
+            self.file_count,
+            more,
+            format_size(self.total_size),
+            more
+        )
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 pub struct SyncChange {
     pub id: String,
@@ -67,6 +230,85 @@ pub struct SyncChange {
     pub new_modified: u64,
 }
 
+/// One project from `GET /sync/status`: the project, its `/sync/check` state
+/// and what the desktop saw happen to it recently.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SyncProjectStatus {
+    #[serde(flatten)]
+    pub project: SyncProject,
+    pub pending: bool,
+    #[serde(default)]
+    pub desktop_modified: u64,
+    #[serde(default)]
+    pub history: Vec<SyncHistoryEntry>,
+}
+
+impl SyncProjectStatus {
+    /// The change `/sync/check` would have listed for this project.
+    fn change(&self) -> Option<SyncChange> {
+        self.pending.then(|| SyncChange {
+            id: self.project.id.clone(),
+            remote_path: self.project.remote_path.clone(),
+            local_path: self.project.local_path.clone(),
+            new_modified: self.desktop_modified,
+        })
+    }
+}
+
+/// A push or ack the desktop recorded for a sync project.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SyncHistoryEntry {
+    pub at: u64,
+    /// "pushed" or "synced"
+    pub action: String,
+    pub path: String,
+}
+
+/// One change a sync would make, from `GET /sync/projects/{id}/plan` (plus the
+/// iOS side's own pending push, added by the client).
+#[derive(Debug, Clone, Deserialize)]
+pub struct SyncPlanEntry {
+    pub path: String,
+    /// "add", "modify" or "delete" from the desktop; "push" for an iOS change
+    pub action: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SyncPlan {
+    pub remote_path: String,
+    pub last_synced: u64,
+    pub entries: Vec<SyncPlanEntry>,
+    pub total_bytes: u64,
+    #[serde(default)]
+    pub truncated: bool,
+    #[serde(default)]
+    pub direction: SyncDirection,
+    /// Paused projects plan nothing, on either side
+    #[serde(default)]
+    pub paused: bool,
+}
+
+/// Response of `GET /version`. Servers that predate the endpoint are treated as
+/// advertising no optional features.
+#[derive(Debug, Clone, Deserialize, Default)]
+pub struct ServerVersion {
+    pub version: String,
+    pub api_version: u32,
+    #[serde(default)]
+    pub features: Vec<String>,
+}
+
+/// One step of the connection self-test.
+#[derive(Debug, Clone)]
+pub struct SelfTestStep {
+    pub name: &'static str,
+    /// What the step found, or why it failed
+    pub result: Result<String, String>,
+    /// What to try when the step failed
+    pub hint: Option<&'static str>,
+}
+
 // ── Events / Commands ───────────────────────────────────────────────────
 
 pub enum ClientEvent {
@@ -77,38 +319,237 @@ pub enum ClientEvent {
         server_cwd: Option<String>,
     },
     FilesUpdate(Vec<WaitingFile>),
-    BrowseUpdate(Vec<RemoteFile>),
-    DownloadComplete { filename: String, data: Vec<u8> },
-    PullComplete { filename: String, data: Vec<u8> },
-    PreviewComplete { filename: String, data: Vec<u8> },
+    /// Listing for the path the `Browse` command asked for (None = server default)
+    BrowseUpdate { path: Option<String>, files: Vec<RemoteFile> },
+    /// Listing of a folder expanded in the tree view; the current listing is untouched
+    TreeChildren { path: String, files: Result<Vec<RemoteFile>, String> },
+    /// `via` is the desktop's view of how it reaches us (`direct` / `relay <region>`), if reported
+    DownloadComplete { filename: String, data: Vec<u8>, via: Option<String> },
+    PullComplete {
+        remote_path: String,
+        filename: String,
+        data: Vec<u8>,
+        via: Option<String>,
+        /// Server-side (mtime, size) at pull time, for the pull cache
+        remote_info: Option<(u64, u64)>,
+    },
+    /// The server copy still matches the cached pull; nothing was downloaded
+    PullUnchanged { remote_path: String, path_id: Option<String> },
+    PreviewComplete { filename: String, data: Vec<u8>, total_size: Option<u64> },
+    /// `/stat` answer for one remote path; Ok(None) when it no longer exists
+    StatResult { path: String, result: Result<Option<RemoteFile>, String> },
+    ChangesResult(Result<ChangeList, String>),
+    PullDirProgress(PullDirProgress),
+    /// Folder pull finished (or was cancelled); `dest` is the unpacked folder.
+    /// `truncated` when the desktop left out folders nested past its depth limit.
+    PullDirComplete { dest: String, entries: usize, cancelled: bool, truncated: bool },
+    PullDirFailed(String),
     PeersUpdate(Vec<PeerInfo>),
     SyncProjectsUpdate(Vec<SyncProject>),
+    /// `DELETE /sync/projects` finished: how many projects it removed
+    SyncProjectsCleared(Result<usize, String>),
+    /// Steps of a connection self-test, up to the first that failed
+    SelfTest(Vec<SelfTestStep>),
     SyncChangesAvailable(Vec<SyncChange>),
+    /// Projects and their history from `/sync/status` (servers with "syncstatus")
+    SyncStatusUpdate(Vec<SyncProjectStatus>),
     UploadComplete { remote_path: String },
+    UploadFailed { remote_path: String, error: String },
+    /// `POST /note` result: Ok(chars sent) or the error
+    NoteSent(Result<usize, String>),
     SyncPullComplete { project_id: String, filename: String },
+    /// A "Sync Now" pass finished: Ok(files it tried to move) or why it couldn't check
+    SyncNowDone { project_id: Option<String>, result: Result<usize, String> },
     FileInfoResult { path: String, info: FileInfoResponse },
     DeviceInfo { hostname: String, dns: String },
+    ServerInfo(ServerInfo),
+    VersionInfo(ServerVersion),
+    /// `/sync/*` keeps returning 404 on this connection; stop offering sync
+    SyncUnsupported,
+    DirInfoResult { path: String, info: Option<DirInfo> },
+    LocalDirInfoResult { path: String, info: DirInfo },
+    /// Probe results for saved servers: (url, reachable)
+    Reachability(Vec<(String, bool)>),
+    /// A sync-project request failed. Errors tied to a project are shown on its
+    /// card; the rest only in the sync status line
+    SyncError { project_id: Option<String>, message: String },
+    SyncPlanResult(SyncPlan),
+    ReceivedUpdate(Vec<ReceivedFile>),
     Error(String),
 }
 
 pub enum ClientCommand {
     DownloadFile(String),
     DownloadLast,
+    /// Remove one file from the desktop's Taildrop inbox
+    DeleteWaiting(String),
+    /// Remove every file from the desktop's Taildrop inbox
+    ClearWaiting,
     Browse(Option<String>),
-    PullFile(String),
-    PreviewFile(String),
+    /// List a folder for the tree view without navigating to it
+    BrowseChildren(String),
+    /// `cached` is the (mtime, size) of a reusable earlier pull, if any
+    /// `conditional`: the server answers `If-None-Match` on `/pull`, so `cached`
+    /// goes along as a validator instead of a separate file-info round trip
+    PullFile { path: String, path_id: Option<String>, cached: Option<(u64, u64)>, conditional: bool },
+    PreviewFile { path: String, path_id: Option<String>, max_bytes: u64 },
+    /// Fetch one remote path's details without listing its folder
+    StatFile { path: String, path_id: Option<String> },
+    /// Files changed on the desktop since this Unix time
+    FetchChanges(u64),
+    /// Stream a remote folder from `/pull-dir` and unpack it under `dest_dir`;
+    /// with a `pattern`, only its matching files from `/pull-glob`
+    PullDir {
+        path: String,
+        path_id: Option<String>,
+        pattern: Option<String>,
+        dest_dir: String,
+        cancel: Arc<AtomicBool>,
+    },
     Refresh,
+    /// None goes back to the default from `poll_interval()`
+    SetPollInterval(Option<Duration>),
+    /// Drop pooled connections and poll/probe right away
+    NetworkChanged,
+    SendNote(String),
     UploadFile { local_path: String, remote_dest_path: String },
-    CreateSyncProject { local_path: String, remote_path: String },
+    CreateSyncProject { local_path: String, remote_path: String, direction: SyncDirection },
     FetchSyncProjects,
     DeleteSyncProject(String),
+    /// Remove every sync project on the desktop
+    ClearSyncProjects,
+    /// Check `/healthz`, `/version` and `/browse` in turn
+    SelfTest,
+    /// Change a project's paths (desktop-side naming: local = desktop, remote = iOS)
+    UpdateSyncProject { id: String, desktop_path: String, ios_path: String },
+    /// Dry run: what the next sync of a project would transfer
+    FetchSyncPlan(String),
     AckSync { id: String, timestamp: u64 },
     CheckSyncChanges,
+    /// Off: the poll still checks for sync changes but doesn't transfer them
+    SetAutoSync(bool),
+    /// Run the sync transfers on the next poll; None = every project
+    SyncNow(Option<String>),
+    /// Check pushed files against the desktop's `/sync/hash` before acking
+    SetVerifyUploads(bool),
+    /// Gzip large uploads that aren't already compressed
+    SetCompressUploads(bool),
     CheckFileInfo { path: String },
+    FetchDirInfo(String),
+    /// Walk a folder on the phone off the UI thread
+    WalkLocalDir(String),
+    FetchReceived,
+    /// Replace the set of saved server URLs probed for reachability
+    SetProbeUrls(Vec<String>),
+}
+
+/// What the phone is connected over, as reported by `NWPathMonitor`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum NetworkType {
+    /// Not reported yet, or something other than Wi-Fi / cellular
+    #[default]
+    Unknown,
+    Wifi,
+    Cellular,
+    Offline,
+}
+
+impl NetworkType {
+    /// `"wifi"`, `"cellular"` or `"none"`; anything else is `Unknown`.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "wifi" => NetworkType::Wifi,
+            "cellular" => NetworkType::Cellular,
+            "none" => NetworkType::Offline,
+            _ => NetworkType::Unknown,
+        }
+    }
+}
+
+/// Progress of a `/pull-dir` transfer, updated per archive entry.
+#[derive(Debug, Clone, Default)]
+pub struct PullDirProgress {
+    pub entries_done: usize,
+    /// From the server's `x-dir-entries` header; 0 if not reported
+    pub entries_total: usize,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    /// Archive path of the entry being unpacked
+    pub current: String,
 }
 
 // ── Public client used by the Renderer ──────────────────────────────────
 
+/// TLS options for a server behind its own HTTPS rather than `tailscale serve`'s
+/// managed certificate. The default verifies against the bundled web roots.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TlsOptions {
+    /// PEM root certificate(s) to trust instead of the bundled roots
+    pub root_cert_pem: Option<String>,
+    /// Skip certificate verification entirely (self-signed certs without a CA)
+    pub accept_invalid_certs: bool,
+}
+
+/// Certificates in a PEM bundle; private keys are ignored. Errors if there are none.
+pub fn parse_root_certs(pem: &str) -> Result<Vec<ureq::tls::Certificate<'static>>, String> {
+    let mut certs = Vec::new();
+    for item in ureq::tls::parse_pem(pem.as_bytes()) {
+        match item {
+            Ok(ureq::tls::PemItem::Certificate(cert)) => certs.push(cert),
+            Ok(_) => {}
+            Err(e) => return Err(format!("Invalid PEM: {}", e)),
+        }
+    }
+    if certs.is_empty() {
+        return Err("No certificate found in PEM".to_string());
+    }
+    Ok(certs)
+}
+
+/// Default interval between status/inbox polls of the desktop.
+const DEFAULT_POLL_INTERVAL: Duration = Duration::from_secs(3);
+/// Env var (settable from the Xcode scheme) overriding the poll interval, in seconds.
+const POLL_INTERVAL_ENV: &str = "TAILSCALE_DRIVE_POLL_SECS";
+
+/// Poll interval from `TAILSCALE_DRIVE_POLL_SECS`, or the 3s default.
+fn poll_interval() -> Duration {
+    std::env::var(POLL_INTERVAL_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_POLL_INTERVAL)
+}
+
+/// Each poll waits `poll_interval` ± this fraction, so several devices polling
+/// the same desktop drift apart instead of hitting it in lockstep.
+const POLL_JITTER: f64 = 0.2;
+
+/// `interval` scaled by a uniform random factor in 1 ± `POLL_JITTER`; the
+/// average stays at `interval`.
+fn jittered(interval: Duration) -> Duration {
+    use std::hash::{BuildHasher, Hasher};
+    // A fresh RandomState is randomly keyed, which is plenty for this
+    let random = std::collections::hash_map::RandomState::new().build_hasher().finish();
+    let unit = random as f64 / u64::MAX as f64;
+    interval.mul_f64(1.0 + POLL_JITTER * (2.0 * unit - 1.0))
+}
+
+/// How long a remote directory summary (or a failed lookup) is kept before it is re-requested.
+const DIR_INFO_TTL: Duration = Duration::from_secs(30);
+
+/// A cached `/dirinfo` lookup
+enum DirInfoState {
+    /// Request in flight, with the previous answer if there was one
+    Pending(Option<DirInfo>),
+    Ready(DirInfo),
+    Failed,
+}
+
+/// Status shown when a download or pull is asked for before `save_directory` is known.
+pub const NO_SAVE_LOCATION: &str = "🗙 No save location configured";
+
 pub struct TailscaleClient {
     pub server_url: String,
     pub connected: bool,
@@ -119,40 +560,166 @@ pub struct TailscaleClient {
     pub remote_files: Vec<RemoteFile>,
     pub download_status: Option<String>,
     pub browse_status: Option<String>,
+    /// Path of the latest `browse()`; listings for any other path are stale
+    browse_requested: Option<String>,
+    /// Tree view: listings of expanded folders, by normalized path
+    pub tree_children: std::collections::HashMap<String, Vec<RemoteFile>>,
+    /// Tree view: folders whose listing failed, with the error
+    pub tree_errors: std::collections::HashMap<String, String>,
+    /// Tree view: listings requested but not answered yet
+    tree_pending: std::collections::HashSet<String>,
     pub server_cwd: Option<String>,
     pub save_directory: Option<String>,
+    /// Recreate the remote folders under `save_directory` for pulls instead of saving flat
+    pub keep_folder_structure: bool,
+    /// Earlier pulls that can be re-shared when the server copy is unchanged
+    pub pull_cache: PullCache,
+    /// Result of the last "Send Text" to the desktop
+    pub note_status: Option<String>,
+    /// Result of the last "Send Clipboard Image"
+    pub clipboard_status: Option<String>,
+    /// Desktop paths of clipboard images still uploading; they notify when done
+    clipboard_uploads: Vec<String>,
+    /// Folder pull in progress, if any
+    pub pull_dir_progress: Option<PullDirProgress>,
+    /// Set to stop the running folder pull after the current entry
+    pull_dir_cancel: Option<Arc<AtomicBool>>,
+    /// Transfer speed limit in bytes per second (0 = unlimited), read by the poll loop
+    rate_limit: Arc<AtomicU64>,
+    /// Multi-file send from the local browser: (sent, failed, total)
+    pub upload_progress: Option<(usize, usize, usize)>,
+    /// Remote paths of the current batch still in flight
+    upload_batch: Vec<String>,
+    /// Batch uploads held back while the server is unreachable; sent on reconnect
+    queued_uploads: Vec<(String, String)>,
+    /// Re-share unchanged files from the pull cache instead of downloading them again
+    pub pull_cache_enabled: bool,
     /// Full paths to files that were just saved and are ready for the iOS share sheet.
     pub pending_share_paths: Vec<String>,
-    /// Tailscale peers from the connected desktop
+    /// Files to hand to the Files app's "Save to" picker instead of the share sheet
+    pub pending_export_paths: Vec<String>,
+    /// Where the most recent pull was saved, for the "Move to…" action
+    pub last_pulled_path: Option<String>,
+    /// Where the most recent inbox download was saved
+    pub last_downloaded_path: Option<String>,
+    /// Tailscale peers from the connected desktop; replace through `set_peers`
     pub peers: Vec<PeerInfo>,
+    /// Index into `peers` by node ID
+    peer_index: std::collections::HashMap<String, usize>,
     /// Tracked sync projects
     pub sync_projects: Vec<SyncProject>,
-    /// Sync status message for UI
+    /// The desktop's recent pushes and acks per project, newest last (`/sync/status`)
+    pub sync_history: std::collections::HashMap<String, Vec<SyncHistoryEntry>>,
+    /// Sync status message for UI (latest event across all projects)
     pub sync_status: Option<String>,
+    /// Whether the poll loop pulls/pushes sync changes on its own
+    pub auto_sync: bool,
+    /// Auto-sync is held back because the phone is on cellular ("Wi-Fi only")
+    pub cellular_paused: bool,
+    /// Last status or error per sync project id, shown on each project's card
+    pub project_sync_status: std::collections::HashMap<String, String>,
+    /// Changes seen but not yet synced, by project id (from `SyncChangesAvailable`)
+    sync_pending: std::collections::HashMap<String, usize>,
+    /// Projects with a single-project Sync Now still running
+    sync_now_running: std::collections::HashSet<String>,
+    /// Projects removed with "Remove all syncs"; late events for them are dropped
+    sync_removed: std::collections::HashSet<String>,
+    /// Latest dry-run result, shown until the user confirms or cancels
+    pub sync_plan: Option<SyncPlan>,
     /// Pending notifications for sync events: (title, body)
     pub pending_sync_notifications: Vec<(String, String)>,
-    /// Preview content received from server (filename, raw bytes)
-    pub preview_content: Option<(String, Vec<u8>)>,
+    /// Sync results the renderer hasn't moved into its history yet
+    pub pending_sync_feed: Vec<SyncFeedEntry>,
+    /// Preview content received from server (filename, raw bytes, full file size if reported)
+    pub preview_content: Option<(String, Vec<u8>, Option<u64>)>,
     /// Hostname of the connected desktop device
     pub connected_device_name: Option<String>,
     /// DNS name of the connected desktop device
     pub connected_device_dns: Option<String>,
+    /// OS, architecture and tailnet of the connected desktop (servers that report them)
+    pub server_info: Option<ServerInfo>,
     /// Latest file info result from server (for overwrite modal)
     pub file_info_result: Option<(String, FileInfoResponse)>,
+    /// Version and feature list of the connected server (None until first fetched)
+    pub server_version: Option<ServerVersion>,
+    /// Desktop files changed since the last "What's new" was dismissed
+    pub changes: Option<ChangeList>,
+    /// Set when the server turned out not to serve `/sync/*` despite the version
+    /// check (cleared on the next version fetch)
+    sync_unsupported: bool,
+    /// Last probe result for each saved server URL
+    pub server_reachability: std::collections::HashMap<String, bool>,
+    /// Last connection self-test; an empty list while one is running
+    pub self_test: Option<Vec<SelfTestStep>>,
+    /// Files the desktop received, newest first (from `/received`)
+    pub received_history: Vec<ReceivedFile>,
+    /// Remote directory summaries by path, with when each was requested or answered
+    dir_infos: std::collections::HashMap<String, (Instant, DirInfoState)>,
+    /// Local folder summaries by path (None while the walk is running)
+    local_dir_infos: std::collections::HashMap<String, Option<DirInfo>>,
 
     event_rx: mpsc::Receiver<ClientEvent>,
     command_tx: mpsc::Sender<ClientCommand>,
+    /// Tells the poll thread to stop at its next check (set on drop)
+    shutdown: Arc<AtomicBool>,
+    poll_thread: Option<std::thread::JoinHandle<()>>,
+}
+
+/// Poll threads that haven't returned yet, across every client.
+static POLL_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts a poll thread as running until it returns, however it returns.
+struct PollThreadGuard;
+
+impl PollThreadGuard {
+    fn new() -> Self {
+        POLL_THREADS.fetch_add(1, Ordering::SeqCst);
+        Self
+    }
+}
+
+impl Drop for PollThreadGuard {
+    fn drop(&mut self) {
+        POLL_THREADS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl Drop for TailscaleClient {
+    fn drop(&mut self) {
+        // The dropped channels stop the thread too, but only once it next looks at them
+        self.shutdown.store(true, Ordering::Relaxed);
+    }
 }
 
 impl TailscaleClient {
     pub fn new(server_url: &str) -> Self {
+        Self::with_auth_token(server_url, None)
+    }
+
+    /// Like `new`, but every request to the server carries
+    /// `Authorization: Bearer <token>` (token from a join QR code).
+    pub fn with_auth_token(server_url: &str, auth_token: Option<String>) -> Self {
+        Self::with_options(server_url, auth_token, TlsOptions::default())
+    }
+
+    /// Like `with_auth_token`, with a custom root certificate or relaxed
+    /// verification for an `https://` server.
+    pub fn with_options(server_url: &str, auth_token: Option<String>, tls: TlsOptions) -> Self {
         let (event_tx, event_rx) = mpsc::channel();
         let (command_tx, command_rx) = mpsc::channel();
 
         let url = server_url.trim_end_matches('/').to_string();
-        std::thread::spawn(move || {
-            poll_loop(&url, event_tx, command_rx);
+        let rate_limit = Arc::new(AtomicU64::new(0));
+        let loop_rate_limit = rate_limit.clone();
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let loop_shutdown = shutdown.clone();
+        // Counted before spawning so a client that's dropped at once is still seen
+        let guard = PollThreadGuard::new();
+        let poll_thread = std::thread::spawn(move || {
+            let _guard = guard;
+            poll_loop(&url, auth_token, tls, loop_rate_limit, &loop_shutdown, event_tx, command_rx);
         });
+        log::debug!("Started poll thread for {} ({} running)", server_url, POLL_THREADS.load(Ordering::SeqCst));
 
         Self {
             server_url: server_url.to_string(),
@@ -164,20 +731,80 @@ impl TailscaleClient {
             remote_files: Vec::new(),
             download_status: None,
             browse_status: None,
+            browse_requested: None,
+            tree_children: std::collections::HashMap::new(),
+            tree_errors: std::collections::HashMap::new(),
+            tree_pending: std::collections::HashSet::new(),
+            last_pulled_path: None,
+            last_downloaded_path: None,
             server_cwd: None,
             save_directory: None,
+            keep_folder_structure: false,
+            pull_cache: PullCache::default(),
+            note_status: None,
+            clipboard_status: None,
+            clipboard_uploads: Vec::new(),
+            pull_dir_progress: None,
+            pull_dir_cancel: None,
+            rate_limit,
+            upload_progress: None,
+            upload_batch: Vec::new(),
+            queued_uploads: Vec::new(),
+            pull_cache_enabled: true,
             pending_share_paths: Vec::new(),
+            pending_export_paths: Vec::new(),
             peers: Vec::new(),
+            peer_index: std::collections::HashMap::new(),
             sync_projects: Vec::new(),
+            sync_history: std::collections::HashMap::new(),
             sync_status: None,
+            auto_sync: true,
+            cellular_paused: false,
+            project_sync_status: std::collections::HashMap::new(),
+            sync_pending: std::collections::HashMap::new(),
+            sync_now_running: std::collections::HashSet::new(),
+            sync_removed: std::collections::HashSet::new(),
+            sync_plan: None,
             pending_sync_notifications: Vec::new(),
+            pending_sync_feed: Vec::new(),
             preview_content: None,
             connected_device_name: None,
             connected_device_dns: None,
+            server_info: None,
             file_info_result: None,
+            server_version: None,
+            changes: None,
+            sync_unsupported: false,
+            server_reachability: std::collections::HashMap::new(),
+            self_test: None,
+            received_history: Vec::new(),
+            dir_infos: std::collections::HashMap::new(),
+            local_dir_infos: std::collections::HashMap::new(),
             event_rx,
             command_tx,
+            shutdown,
+            poll_thread: Some(poll_thread),
+        }
+    }
+
+    /// Stop the poll thread and wait up to `wait` for it to return (a request in
+    /// flight finishes first). Returns whether it did; dropping the client only
+    /// signals it.
+    pub fn shutdown(&mut self, wait: Duration) -> bool {
+        self.shutdown.store(true, Ordering::Relaxed);
+        let Some(thread) = self.poll_thread.take() else {
+            return true;
+        };
+        let deadline = Instant::now() + wait;
+        while !thread.is_finished() {
+            if Instant::now() >= deadline {
+                log::warn!("Poll thread for {} is still busy; leaving it to finish", self.server_url);
+                return false;
+            }
+            std::thread::sleep(Duration::from_millis(10));
         }
+        let _ = thread.join();
+        true
     }
 
     /// Drain the event channel from the background thread.
@@ -209,29 +836,54 @@ impl TailscaleClient {
                             peer.online = false;
                         }
                     }
+                    if connected && !self.queued_uploads.is_empty() {
+                        for (local, remote) in std::mem::take(&mut self.queued_uploads) {
+                            self.upload_file(&local, &remote);
+                        }
+                    }
                 }
                 ClientEvent::FilesUpdate(files) => {
                     self.waiting_files = files;
                 }
-                ClientEvent::BrowseUpdate(files) => {
+                ClientEvent::BrowseUpdate { path, .. } if path != self.browse_requested => {
+                    // Superseded by a later browse (e.g. tapping Up right after opening a folder)
+                    log::debug!("[browse] dropping stale listing for {:?}", path);
+                }
+                ClientEvent::BrowseUpdate { files, .. } => {
                     self.browse_status =
                         Some(format!("Found {} items", files.len()));
                     self.remote_files = files;
                 }
-                ClientEvent::DownloadComplete { filename, data } => {
+                ClientEvent::TreeChildren { path, files } => {
+                    self.tree_pending.remove(&path);
+                    match files {
+                        Ok(files) => {
+                            self.tree_errors.remove(&path);
+                            self.tree_children.insert(path, files);
+                        }
+                        Err(e) => {
+                            self.tree_errors.insert(path, e);
+                        }
+                    }
+                }
+                ClientEvent::DownloadComplete { filename, data, via } => {
                     let size = data.len();
                     if let Some(ref dir) = self.save_directory {
-                        let path = format!("{}/{}", dir, filename);
+                        let path = format!("{}/{}", dir, sanitize_filename(&filename));
+                        log_sanitized(&filename);
                         match std::fs::write(&path, &data) {
                             Ok(_) => {
                                 self.download_status = Some(format!(
-                                    "✔ Saved '{}' ({})",
+                                    "✔ Saved '{}' ({}){}",
                                     filename,
-                                    format_size(size as u64)
+                                    format_size(size as u64),
+                                    via_suffix(via.as_deref())
                                 ));
+                                self.last_downloaded_path = Some(path.clone());
                                 self.pending_share_paths.push(path);
                             }
                             Err(e) => {
+                                self.last_downloaded_path = None;
                                 self.download_status = Some(format!(
                                     "🗙 Failed to save '{}': {}",
                                     filename, e
@@ -240,33 +892,48 @@ impl TailscaleClient {
                         }
                     } else {
                         self.download_status = Some(format!(
-                            "✔ Downloaded '{}' ({}) — no save directory set",
+                            "✔ Downloaded '{}' ({}){} — no save directory set",
                             filename,
-                            format_size(size as u64)
+                            format_size(size as u64),
+                            via_suffix(via.as_deref())
                         ));
                     }
                 }
                 ClientEvent::PeersUpdate(peers) => {
-                    self.peers = peers;
+                    // The desktop's list is complete, so it replaces what we had
+                    self.set_peers(merge_peers(Vec::new(), peers));
                     // Cache to disk for offline access
                     if let Some(ref dir) = self.save_directory {
-                        save_cached_peers(dir, &self.peers);
+                        save_cached_peers(dir, &self.server_url, &self.peers);
                     }
                 }
-                ClientEvent::PullComplete { filename, data } => {
+                ClientEvent::PullComplete { remote_path, filename, data, via, remote_info } => {
                     let size = data.len();
                     if let Some(ref dir) = self.save_directory {
-                        let path = format!("{}/{}", dir, filename);
-                        match std::fs::write(&path, &data) {
+                        let path = pull_save_path(dir, &remote_path, &filename, self.keep_folder_structure);
+                        log_sanitized(&filename);
+                        let written = match std::path::Path::new(&path).parent() {
+                            Some(parent) => std::fs::create_dir_all(parent),
+                            None => Ok(()),
+                        }
+                        .and_then(|()| std::fs::write(&path, &data));
+                        match written {
                             Ok(_) => {
                                 self.browse_status = Some(format!(
-                                    "✔ Saved '{}' ({})",
+                                    "✔ Saved '{}' ({}){}",
                                     filename,
-                                    format_size(size as u64)
+                                    format_size(size as u64),
+                                    via_suffix(via.as_deref())
                                 ));
-                                self.pending_share_paths.push(path);
+                                if let Some((modified, size)) = remote_info {
+                                    self.pull_cache.record(&self.server_url, &remote_path, &path, modified, size);
+                                    self.save_pull_cache();
+                                }
+                                // The browse page offers Share / Save to Files for it
+                                self.last_pulled_path = Some(path);
                             }
                             Err(e) => {
+                                self.last_pulled_path = None;
                                 self.browse_status = Some(format!(
                                     "🗙 Failed to save '{}': {}",
                                     filename, e
@@ -275,21 +942,60 @@ impl TailscaleClient {
                         }
                     } else {
                         self.browse_status = Some(format!(
-                            "✔ Pulled '{}' ({}) — no save directory set",
+                            "✔ Pulled '{}' ({}){} — no save directory set",
                             filename,
-                            format_size(size as u64)
+                            format_size(size as u64),
+                            via_suffix(via.as_deref())
                         ));
                     }
                 }
                 ClientEvent::SyncProjectsUpdate(projects) => {
-                    self.sync_projects = projects;
+                    self.set_sync_projects(projects);
+                }
+                ClientEvent::SyncStatusUpdate(statuses) => {
+                    let mut projects = Vec::with_capacity(statuses.len());
+                    self.sync_history.clear();
+                    for status in statuses {
+                        self.sync_history.insert(status.project.id.clone(), status.history);
+                        projects.push(status.project);
+                    }
+                    self.set_sync_projects(projects);
+                }
+                ClientEvent::SyncProjectsCleared(Ok(removed)) => {
+                    self.sync_status = Some(format!("✔ Removed {} sync(s)", removed));
                 }
-                ClientEvent::SyncChangesAvailable(changes) => {
-                    // Auto-pull will handle these in the poll loop
+                ClientEvent::SyncProjectsCleared(Err(e)) => {
+                    log::warn!("[sync] remove all failed: {}", e);
+                    // They're still on the desktop; the refetch that follows shows them again
+                    self.sync_removed.clear();
+                    self.sync_status = Some(format!("🗙 Couldn't remove syncs: {}", e));
+                }
+                ClientEvent::SyncPullComplete { project_id, .. }
+                | ClientEvent::SyncError { project_id: Some(project_id), .. }
+                    if self.sync_removed.contains(&project_id) =>
+                {
+                    // A transfer that was already running when its project was removed
+                    log::debug!("[sync] ignoring result for removed project {}", project_id);
+                }
+                ClientEvent::SyncChangesAvailable(mut changes) => {
+                    changes.retain(|c| !self.sync_removed.contains(&c.id));
+                    // Auto-pull will handle these in the poll loop. Each check
+                    // reports everything outstanding, so it replaces the counts.
+                    self.sync_pending.clear();
+                    for change in &changes {
+                        *self.sync_pending.entry(change.id.clone()).or_default() += 1;
+                    }
                     if !changes.is_empty() {
                         self.sync_status = Some(format!(
-                            "{} file(s) updated on desktop",
-                            changes.len()
+                            "{} file(s) updated on desktop{}",
+                            changes.len(),
+                            if !self.auto_sync {
+                                " — auto-sync is off"
+                            } else if self.cellular_paused {
+                                " — paused on cellular"
+                            } else {
+                                ""
+                            }
                         ));
                     }
                 }
@@ -300,16 +1006,143 @@ impl TailscaleClient {
                         .unwrap_or(&remote_path)
                         .to_string();
                     self.sync_status = Some(format!("✔ Uploaded '{}'", filename));
+                    self.note_batch_upload(&remote_path, true);
+                    if self.take_clipboard_upload(&remote_path) {
+                        self.clipboard_status = Some(format!("✔ Sent '{}'", filename));
+                        self.pending_sync_notifications
+                            .push(("Image Sent".to_string(), format!("Saved to {}", remote_path)));
+                    }
+                }
+                ClientEvent::NoteSent(Ok(chars)) => {
+                    self.note_status = Some(format!("✔ Sent {} characters", chars));
+                }
+                ClientEvent::NoteSent(Err(e)) => {
+                    log::warn!("[client] note failed: {}", e);
+                    self.note_status = Some(format!("🗙 {}", e));
+                }
+                ClientEvent::UploadFailed { remote_path, error } => {
+                    log::warn!("[client] upload to {} failed: {}", remote_path, error);
+                    self.download_status = Some(format!("🗙 {}", error));
+                    self.note_batch_upload(&remote_path, false);
+                    if self.take_clipboard_upload(&remote_path) {
+                        self.clipboard_status = Some(format!("🗙 {}", error));
+                        self.pending_sync_notifications.push(("Image Not Sent".to_string(), error));
+                    }
                 }
-                ClientEvent::SyncPullComplete { project_id: _, filename } => {
-                    self.sync_status = Some(format!("✔ Synced '{}'", filename));
+                ClientEvent::SyncPullComplete { project_id, filename } => {
+                    self.settle_sync_change(&project_id);
+                    self.set_project_sync_status(&project_id, format!("✔ Synced '{}'", filename));
+                    self.push_sync_feed(Some(&project_id), false, format!("Synced '{}'", filename));
                     self.pending_sync_notifications.push((
                         "File Synced".to_string(),
                         format!("Updated: {}", filename),
                     ));
                 }
-                ClientEvent::PreviewComplete { filename, data } => {
-                    self.preview_content = Some((filename, data));
+                ClientEvent::SyncNowDone { project_id, result } => {
+                    // Transfers report themselves; only say something when there were none
+                    let status = match result {
+                        Ok(0) => Some("✔ Already up to date".to_string()),
+                        Ok(_) => None,
+                        Err(e) => {
+                            self.push_sync_feed(project_id.as_deref(), true, format!("Sync failed: {}", e));
+                            Some(format!("🗙 Sync failed: {}", e))
+                        }
+                    };
+                    match project_id {
+                        Some(id) => {
+                            self.sync_now_running.remove(&id);
+                            if let Some(status) = status {
+                                self.set_project_sync_status(&id, status);
+                            }
+                        }
+                        None => {
+                            // Different projects queued before one poll ran as a full sync
+                            self.sync_now_running.clear();
+                            if status.is_some() {
+                                self.sync_status = status;
+                            }
+                        }
+                    }
+                }
+                ClientEvent::PullUnchanged { remote_path, path_id } => {
+                    let local = self.cached_pull_target(&remote_path).and_then(|target| {
+                        self.pull_cache.touch(&self.server_url, &remote_path, &target)
+                    });
+                    match local {
+                        Some(path) => {
+                            self.save_pull_cache();
+                            let name = path.rsplit('/').next().unwrap_or(&path).to_string();
+                            self.browse_status = Some(format!("✔ '{}' unchanged — using saved copy", name));
+                            self.last_pulled_path = Some(path);
+                        }
+                        // Saved copy went away in the meantime; download after all
+                        None => {
+                            let _ = self.command_tx.send(ClientCommand::PullFile {
+                                path: remote_path,
+                                path_id,
+                                cached: None,
+                                conditional: self.supports_feature("conditional"),
+                            });
+                        }
+                    }
+                }
+                ClientEvent::PreviewComplete { filename, data, total_size } => {
+                    self.preview_content = Some((filename, data, total_size));
+                }
+                ClientEvent::StatResult { path, result } => {
+                    let name = remote_file_name(&path).to_string();
+                    self.browse_status = Some(match result {
+                        Ok(Some(file)) => {
+                            let listed = self.remote_files.iter_mut().find(|f| match (&f.path_id, &file.path_id) {
+                                (Some(listed), Some(id)) => listed == id,
+                                _ => f.name == file.name,
+                            });
+                            if let Some(listed) = listed {
+                                *listed = file.clone();
+                            }
+                            format!("✔ '{}': {}, modified {}", name, format_size(file.size as u64), format_timestamp(file.modified))
+                        }
+                        Ok(None) => format!("🗙 '{}' no longer exists on the desktop", name),
+                        Err(e) => format!("🗙 {}", e),
+                    });
+                }
+                ClientEvent::ChangesResult(Ok(changes)) => {
+                    // Listings that show a changed file get its new size and date
+                    for changed in &changes.files {
+                        let Some(ref id) = changed.file.path_id else {
+                            continue;
+                        };
+                        let listings = std::iter::once(&mut self.remote_files).chain(self.tree_children.values_mut());
+                        for listed in listings.flatten().filter(|f| f.path_id.as_ref() == Some(id)) {
+                            *listed = changed.file.clone();
+                        }
+                    }
+                    self.changes = Some(changes);
+                }
+                ClientEvent::ChangesResult(Err(e)) => {
+                    log::warn!("[client] change check failed: {}", e);
+                }
+                ClientEvent::PullDirProgress(progress) => {
+                    self.pull_dir_progress = Some(progress);
+                }
+                ClientEvent::PullDirComplete { dest, entries, cancelled, truncated } => {
+                    self.pull_dir_progress = None;
+                    self.pull_dir_cancel = None;
+                    let name = dest.rsplit('/').next().unwrap_or(&dest).to_string();
+                    self.browse_status = Some(if cancelled {
+                        format!("Pull of '{}' cancelled after {} items", name, entries)
+                    } else if truncated {
+                        format!("✔ Saved folder '{}' ({} items; folders nested too deep were left out)", name, entries)
+                    } else {
+                        format!("✔ Saved folder '{}' ({} items)", name, entries)
+                    });
+                    self.last_pulled_path = Some(dest);
+                }
+                ClientEvent::PullDirFailed(e) => {
+                    log::warn!("[client] folder pull failed: {}", e);
+                    self.pull_dir_progress = None;
+                    self.pull_dir_cancel = None;
+                    self.browse_status = Some(format!("🗙 {}", e));
                 }
                 ClientEvent::FileInfoResult { path, info } => {
                     self.file_info_result = Some((path, info));
@@ -318,128 +1151,758 @@ impl TailscaleClient {
                     self.connected_device_name = Some(hostname);
                     self.connected_device_dns = Some(dns);
                 }
+                ClientEvent::ServerInfo(info) => {
+                    self.server_info = Some(info);
+                }
+                ClientEvent::VersionInfo(version) => {
+                    self.server_version = Some(version);
+                    self.sync_unsupported = false;
+                }
+                ClientEvent::SyncUnsupported => {
+                    self.sync_unsupported = true;
+                }
+                ClientEvent::Reachability(results) => {
+                    self.server_reachability = results.into_iter().collect();
+                }
+                ClientEvent::SelfTest(steps) => {
+                    for step in &steps {
+                        match &step.result {
+                            Ok(found) => log::info!("[self-test] {}: {}", step.name, found),
+                            Err(e) => log::warn!("[self-test] {}: {}", step.name, e),
+                        }
+                    }
+                    self.self_test = Some(steps);
+                }
+                ClientEvent::DirInfoResult { path, info } => {
+                    // Failures are kept too, so they're only retried once the TTL runs out
+                    let state = info.map_or(DirInfoState::Failed, DirInfoState::Ready);
+                    self.dir_infos.insert(path, (Instant::now(), state));
+                }
+                ClientEvent::LocalDirInfoResult { path, info } => {
+                    // Drop results for folders cleared while the walk ran
+                    if let Some(slot) = self.local_dir_infos.get_mut(&path) {
+                        *slot = Some(info);
+                    }
+                }
+                ClientEvent::SyncPlanResult(plan) => {
+                    self.sync_plan = Some(plan);
+                }
+                ClientEvent::ReceivedUpdate(files) => {
+                    self.received_history = files;
+                }
+                ClientEvent::SyncError { project_id: Some(id), message } => {
+                    self.settle_sync_change(&id);
+                    let status = format!("🗙 {}", message);
+                    // Auto-sync retries every poll; only log (and retoast) when the error changes
+                    if self.project_sync_status.get(&id) != Some(&status) {
+                        log::warn!("[sync] {}: {}", id, message);
+                        self.set_project_sync_status(&id, status);
+                        self.push_sync_feed(Some(&id), true, message);
+                    }
+                }
+                ClientEvent::SyncError { project_id: None, message } => {
+                    log::warn!("[sync] {}", message);
+                    self.sync_status = Some(format!("🗙 {}", message));
+                    self.push_sync_feed(None, true, message);
+                }
                 ClientEvent::Error(msg) => {
+                    log::warn!("[client] {}", msg);
                     self.download_status = Some(format!("🗙 {}", msg));
                 }
             }
         }
     }
 
-    pub fn download_file(&self, name: &str) {
+    /// Separator for joining desktop paths, as reported by `/status`.
+    pub fn remote_separator(&self) -> char {
+        self.server_info.as_ref().map_or('/', |info| info.path_separator)
+    }
+
+    /// Folder the desktop limits browsing to, if it does (from `/status`).
+    pub fn browse_root(&self) -> Option<&str> {
+        self.server_info.as_ref().and_then(|info| info.browse_root.as_deref())
+    }
+
+    /// Whether the server advertises `feature`. Assumed true until the version
+    /// has been fetched so the UI doesn't flicker on connect.
+    pub fn supports_feature(&self, feature: &str) -> bool {
+        if self.sync_unsupported && feature.starts_with("sync") {
+            return false;
+        }
+        self.server_version
+            .as_ref()
+            .is_none_or(|v| v.features.iter().any(|f| f == feature))
+    }
+
+    /// Hostname for a tailscaled peer ID from the cached peer list, or the
+    /// raw ID when the peer isn't known (yet).
+    pub fn peer_name<'a>(&'a self, peer_id: &'a str) -> &'a str {
+        self.peer(peer_id)
+            .filter(|p| !p.hostname.is_empty())
+            .map(|p| p.hostname.as_str())
+            .unwrap_or(peer_id)
+    }
+
+    pub fn peer(&self, id: &str) -> Option<&PeerInfo> {
+        self.peer_index.get(id).and_then(|&i| self.peers.get(i))
+    }
+
+    /// Replace the peer list and rebuild the by-ID index the UI looks peers up in.
+    pub fn set_peers(&mut self, peers: Vec<PeerInfo>) {
+        self.peer_index = peers.iter().enumerate().map(|(i, p)| (p.id.clone(), i)).collect();
+        self.peers = peers;
+    }
+
+    pub fn download_file(&mut self, name: &str) {
+        if self.save_directory.is_none() {
+            self.download_status = Some(NO_SAVE_LOCATION.to_string());
+            return;
+        }
         let _ = self.command_tx.send(ClientCommand::DownloadFile(name.to_string()));
     }
 
-    pub fn download_last(&self) {
+    pub fn download_last(&mut self) {
+        if self.save_directory.is_none() {
+            self.download_status = Some(NO_SAVE_LOCATION.to_string());
+            return;
+        }
         let _ = self.command_tx.send(ClientCommand::DownloadLast);
     }
 
-    pub fn browse(&self, path: Option<String>) {
-        let _ = self.command_tx.send(ClientCommand::Browse(path));
+    pub fn delete_waiting(&self, name: &str) {
+        let _ = self.command_tx.send(ClientCommand::DeleteWaiting(name.to_string()));
     }
 
-    pub fn pull_file(&self, name: &str) {
-        let _ = self.command_tx.send(ClientCommand::PullFile(name.to_string()));
+    pub fn clear_waiting(&self) {
+        let _ = self.command_tx.send(ClientCommand::ClearWaiting);
     }
 
-    pub fn preview_file(&self, path: &str) {
-        let _ = self.command_tx.send(ClientCommand::PreviewFile(path.to_string()));
+    pub fn browse(&mut self, path: Option<String>) {
+        let path = path.map(|p| normalize_remote_path(&p));
+        self.browse_requested = path.clone();
+        let _ = self.command_tx.send(ClientCommand::Browse(path));
     }
 
-    pub fn refresh(&self) {
-        let _ = self.command_tx.send(ClientCommand::Refresh);
+    /// Fetch a folder's listing for the tree view, unless it's already on the way.
+    /// Cached listings stay shown until the new one arrives.
+    pub fn browse_children(&mut self, path: &str) {
+        let path = normalize_remote_path(path);
+        if self.tree_pending.insert(path.clone()) {
+            let _ = self.command_tx.send(ClientCommand::BrowseChildren(path));
+        }
     }
 
-    pub fn upload_file(&self, local_path: &str, remote_dest_path: &str) {
-        let _ = self.command_tx.send(ClientCommand::UploadFile {
-            local_path: local_path.to_string(),
-            remote_dest_path: remote_dest_path.to_string(),
+    /// Pull a desktop file. `path_id` (from `RemoteFile`) is preferred over the display path.
+    /// Returns false, without pulling, if there is no save directory.
+    pub fn pull_file(&mut self, path: &str, path_id: Option<&str>) -> bool {
+        if self.save_directory.is_none() {
+            self.browse_status = Some(NO_SAVE_LOCATION.to_string());
+            return false;
+        }
+        let cached = self
+            .cached_pull_target(path)
+            .and_then(|target| self.pull_cache.lookup(&self.server_url, path, &target));
+        let _ = self.command_tx.send(ClientCommand::PullFile {
+            path: path.to_string(),
+            path_id: path_id.map(String::from),
+            cached,
+            conditional: self.supports_feature("conditional"),
         });
+        true
     }
 
-    pub fn create_sync_project(&self, local_path: &str, remote_path: &str) {
-        let _ = self.command_tx.send(ClientCommand::CreateSyncProject {
-            local_path: local_path.to_string(),
-            remote_path: remote_path.to_string(),
+    /// Pull a whole desktop folder in one `/pull-dir` transfer. It is unpacked
+    /// where `pull_file` would save a file of the same path. Returns false if a
+    /// folder pull is already running or there is no save directory.
+    pub fn pull_dir(&mut self, path: &str, path_id: Option<&str>) -> bool {
+        self.start_pull_dir(path, path_id, None)
+    }
+
+    /// Pull the files in a remote folder matching a glob (`*.log`, `**/*.txt`),
+    /// unpacked like a folder pull. Needs the server's "pullglob" feature.
+    pub fn pull_matching(&mut self, dir: &str, path_id: Option<&str>, pattern: &str) -> bool {
+        self.start_pull_dir(dir, path_id, Some(pattern.to_string()))
+    }
+
+    fn start_pull_dir(&mut self, path: &str, path_id: Option<&str>, pattern: Option<String>) -> bool {
+        let Some(dir) = self.save_directory.as_deref() else {
+            self.browse_status = Some(NO_SAVE_LOCATION.to_string());
+            return false;
+        };
+        if self.pull_dir_cancel.is_some() {
+            return false;
+        }
+        let name = remote_file_name(path);
+        let target = pull_save_path(dir, path, name, self.keep_folder_structure);
+        // The archive's entries start with the folder's name, so unpack next to it
+        let dest_dir = match target.rsplit_once('/') {
+            Some((parent, _)) => parent.to_string(),
+            None => dir.to_string(),
+        };
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.pull_dir_cancel = Some(cancel.clone());
+        self.pull_dir_progress = Some(PullDirProgress::default());
+        let _ = self.command_tx.send(ClientCommand::PullDir {
+            path: path.to_string(),
+            path_id: path_id.map(String::from),
+            pattern,
+            dest_dir,
+            cancel,
         });
+        true
     }
 
-    pub fn fetch_sync_projects(&self) {
-        let _ = self.command_tx.send(ClientCommand::FetchSyncProjects);
+    /// Poll the desktop every `secs` seconds; 0 restores the default.
+    pub fn set_poll_interval(&self, secs: u64) {
+        let interval = (secs > 0).then(|| Duration::from_secs(secs));
+        let _ = self.command_tx.send(ClientCommand::SetPollInterval(interval));
     }
 
-    pub fn delete_sync_project(&self, id: &str) {
-        let _ = self.command_tx.send(ClientCommand::DeleteSyncProject(id.to_string()));
+    /// Stop the running folder pull; whatever was unpacked so far stays.
+    pub fn cancel_pull_dir(&self) {
+        if let Some(cancel) = &self.pull_dir_cancel {
+            cancel.store(true, Ordering::Relaxed);
+        }
     }
 
-    pub fn check_sync_changes(&self) {
-        let _ = self.command_tx.send(ClientCommand::CheckSyncChanges);
+    /// Where a pull of `remote_path` would be saved, if the cache may be used for it.
+    /// Only copies at that exact spot are reused, so sync projects created from a
+    /// pull still find their file.
+    fn cached_pull_target(&self, remote_path: &str) -> Option<String> {
+        if !self.pull_cache_enabled {
+            return None;
+        }
+        let dir = self.save_directory.as_deref()?;
+        let name = remote_file_name(remote_path);
+        Some(pull_save_path(dir, remote_path, name, self.keep_folder_structure))
     }
 
-    pub fn check_file_info(&self, path: &str) {
-        let _ = self.command_tx.send(ClientCommand::CheckFileInfo { path: path.to_string() });
+    fn save_pull_cache(&self) {
+        if let Some(ref dir) = self.save_directory {
+            self.pull_cache.save(dir);
+        }
     }
-}
 
-// ── Background polling thread ───────────────────────────────────────────
+    /// Forget every cached pull. The saved files themselves stay put.
+    pub fn clear_pull_cache(&mut self) {
+        self.pull_cache = PullCache::default();
+        self.save_pull_cache();
+    }
 
-fn poll_loop(
-    base_url: &str,
-    event_tx: mpsc::Sender<ClientEvent>,
-    command_rx: mpsc::Receiver<ClientCommand>,
-) {
-    let config = ureq::Agent::config_builder()
-        .timeout_global(Some(Duration::from_secs(8)))
-        .build();
+    /// Record a project's latest status for its card and the global sync line.
+    pub fn set_project_sync_status(&mut self, project_id: &str, status: String) {
+        self.project_sync_status.insert(project_id.to_string(), status.clone());
+        self.sync_status = Some(status);
+    }
 
-    let agent = config.into();
+    fn set_sync_projects(&mut self, mut projects: Vec<SyncProject>) {
+        // A list fetched before "Remove all syncs" went through
+        projects.retain(|p| !self.sync_removed.contains(&p.id));
+        self.project_sync_status
+            .retain(|id, _| projects.iter().any(|p| &p.id == id));
+        self.sync_pending.retain(|id, _| projects.iter().any(|p| &p.id == id));
+        self.sync_history.retain(|id, _| projects.iter().any(|p| &p.id == id));
+        self.sync_projects = projects;
+    }
 
-    let poll_interval = Duration::from_secs(3);
-    let mut last_poll = Instant::now() - poll_interval; // poll immediately on start
+    /// Record a sync result for the renderer's history, named after its project.
+    fn push_sync_feed(&mut self, project_id: Option<&str>, failed: bool, message: String) {
+        let project = project_id
+            .and_then(|id| self.sync_projects.iter().find(|p| p.id == id))
+            .map(|p| p.local_path.rsplit(['/', '\\']).next().unwrap_or(&p.local_path).to_string())
+            .unwrap_or_default();
+        self.pending_sync_feed.push(SyncFeedEntry { at: unix_now(), project, failed, message });
+    }
 
-    loop {
-        // ── Process commands (non-blocking) ──
-        loop {
-            match command_rx.try_recv() {
-                Ok(cmd) => match cmd {
-                    ClientCommand::DownloadFile(name) => {
-                        match http_download_file(&agent, base_url, &name) {
-                            Ok(data) => {
-                                let filename = name;
-                                if event_tx
-                                    .send(ClientEvent::DownloadComplete { filename, data })
-                                    .is_err()
-                                {
-                                    return;
-                                }
-                            }
-                            Err(e) => {
-                                if event_tx.send(ClientEvent::Error(e)).is_err() {
-                                    return;
-                                }
-                            }
-                        }
+    /// One pending change for a project was pulled (or failed).
+    fn settle_sync_change(&mut self, project_id: &str) {
+        if let Some(n) = self.sync_pending.get_mut(project_id) {
+            *n -= 1;
+            if *n == 0 {
+                self.sync_pending.remove(project_id);
+            }
+        }
+    }
+
+    /// Sync projects that need attention: changes still being pulled, or a
+    /// last status that's an error. 0 once everything is synced.
+    pub fn sync_attention_count(&self) -> usize {
+        self.sync_projects
+            .iter()
+            .filter(|p| {
+                self.sync_pending.contains_key(&p.id)
+                    || self.project_sync_status.get(&p.id).is_some_and(|s| s.starts_with('🗙'))
+            })
+            .count()
+    }
+
+    /// Number of sync projects whose last status is an error.
+    pub fn failing_sync_projects(&self) -> usize {
+        self.project_sync_status
+            .values()
+            .filter(|s| s.starts_with('🗙'))
+            .count()
+    }
+
+    /// Fetch at most `max_bytes` from the head of a remote file (images come back as thumbnails)
+    pub fn preview_file(&self, path: &str, path_id: Option<&str>, max_bytes: u64) {
+        let _ = self.command_tx.send(ClientCommand::PreviewFile {
+            path: path.to_string(),
+            path_id: path_id.map(String::from),
+            max_bytes,
+        });
+    }
+
+    /// Re-check one remote file or folder (servers with the "stat" feature);
+    /// its entry in `remote_files` is updated if listed.
+    pub fn stat_file(&self, path: &str, path_id: Option<&str>) {
+        let _ = self.command_tx.send(ClientCommand::StatFile {
+            path: path.to_string(),
+            path_id: path_id.map(String::from),
+        });
+    }
+
+    /// Ask for desktop files modified after `since` (servers with the "changes" feature).
+    pub fn fetch_changes(&self, since: u64) {
+        let _ = self.command_tx.send(ClientCommand::FetchChanges(since));
+    }
+
+    pub fn refresh(&self) {
+        let _ = self.command_tx.send(ClientCommand::Refresh);
+    }
+
+    /// Re-fetch the desktop's received-files history, if the server keeps one.
+    pub fn fetch_received(&self) {
+        if self.supports_feature("received") {
+            let _ = self.command_tx.send(ClientCommand::FetchReceived);
+        }
+    }
+
+    /// Everything that moved recently, newest first: the desktop inbox and
+    /// received history, pulls to this device and sync projects' last runs.
+    pub fn activity_timeline(&self) -> Vec<ActivityEntry> {
+        let mut entries: Vec<ActivityEntry> = self
+            .waiting_files
+            .iter()
+            .map(|f| ActivityEntry {
+                kind: ActivityKind::Inbox,
+                name: f.name.clone(),
+                detail: self.peer_name(&f.from_peer).to_string(),
+                at: f.received_at,
+                size: Some(f.size),
+                local_path: None,
+                downloadable: true,
+            })
+            .collect();
+        // Files still in the inbox already have their row
+        for f in &self.received_history {
+            if self.waiting_files.iter().any(|w| w.name == f.name) {
+                continue;
+            }
+            entries.push(ActivityEntry {
+                kind: ActivityKind::Received,
+                name: f.name.clone(),
+                detail: f.from_peer.clone(),
+                at: f.received_at,
+                size: Some(f.size),
+                local_path: None,
+                downloadable: f.available,
+            });
+        }
+        for pull in self.pull_cache.for_server(&self.server_url) {
+            entries.push(ActivityEntry {
+                kind: ActivityKind::Pulled,
+                name: pull.remote_path.rsplit('/').next().unwrap_or(&pull.remote_path).to_string(),
+                detail: pull.remote_path.clone(),
+                at: pull.last_used,
+                size: Some(pull.size),
+                local_path: Some(pull.local_path.clone()),
+                downloadable: false,
+            });
+        }
+        // Desktop-side naming: local_path is on the desktop, remote_path on this device
+        for project in self.sync_projects.iter().filter(|p| p.last_synced > 0) {
+            entries.push(ActivityEntry {
+                kind: ActivityKind::Synced,
+                name: project.remote_path.rsplit('/').next().unwrap_or(&project.remote_path).to_string(),
+                detail: project.local_path.clone(),
+                at: project.last_synced,
+                size: None,
+                local_path: Some(project.remote_path.clone()),
+                downloadable: false,
+            });
+        }
+        entries.sort_by_key(|e| std::cmp::Reverse(e.at));
+        entries
+    }
+
+    /// Send a text snippet to the desktop's notes inbox.
+    pub fn send_note(&mut self, text: &str) {
+        self.note_status = Some("Sending…".to_string());
+        let _ = self.command_tx.send(ClientCommand::SendNote(text.to_string()));
+    }
+
+    /// The device's network path changed; re-poll on fresh connections now
+    /// instead of waiting for the next interval.
+    pub fn network_changed(&self) {
+        let _ = self.command_tx.send(ClientCommand::NetworkChanged);
+    }
+
+    pub fn upload_file(&self, local_path: &str, remote_dest_path: &str) {
+        let _ = self.command_tx.send(ClientCommand::UploadFile {
+            local_path: local_path.to_string(),
+            remote_dest_path: remote_dest_path.to_string(),
+        });
+    }
+
+    /// Send several (local, remote) files as one batch with shared progress. While
+    /// the server is unreachable they wait and go out on reconnect.
+    pub fn upload_files(&mut self, files: Vec<(String, String)>) {
+        let (sent, failed, total) = match self.upload_progress {
+            Some(progress) if !self.upload_batch.is_empty() => progress,
+            _ => (0, 0, 0),
+        };
+        self.upload_progress = Some((sent, failed, total + files.len()));
+        self.upload_batch.extend(files.iter().map(|(_, remote)| remote.clone()));
+        if self.connected {
+            for (local, remote) in &files {
+                self.upload_file(local, remote);
+            }
+        } else {
+            self.queued_uploads.extend(files);
+        }
+    }
+
+    /// Upload a staged clipboard image; a notification follows once it lands.
+    pub fn upload_clipboard_image(&mut self, local_path: String, remote_dest_path: String) {
+        self.clipboard_uploads.push(remote_dest_path.clone());
+        self.clipboard_status = Some(if self.connected {
+            "Sending…".to_string()
+        } else {
+            "Queued until the desktop is back".to_string()
+        });
+        self.upload_files(vec![(local_path, remote_dest_path)]);
+    }
+
+    fn take_clipboard_upload(&mut self, remote_path: &str) -> bool {
+        match self.clipboard_uploads.iter().position(|p| p == remote_path) {
+            Some(idx) => {
+                self.clipboard_uploads.remove(idx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Uploads of the current batch waiting for the server to come back.
+    pub fn queued_upload_count(&self) -> usize {
+        self.queued_uploads.len()
+    }
+
+    fn note_batch_upload(&mut self, remote_path: &str, ok: bool) {
+        let Some(idx) = self.upload_batch.iter().position(|p| p == remote_path) else {
+            return;
+        };
+        self.upload_batch.remove(idx);
+        if let Some((sent, failed, _)) = self.upload_progress.as_mut() {
+            if ok {
+                *sent += 1;
+            } else {
+                *failed += 1;
+            }
+        }
+    }
+
+    pub fn create_sync_project(&self, local_path: &str, remote_path: &str, direction: SyncDirection) {
+        let _ = self.command_tx.send(ClientCommand::CreateSyncProject {
+            local_path: local_path.to_string(),
+            remote_path: remote_path.to_string(),
+            direction,
+        });
+    }
+
+    /// Change where an existing sync project points. `last_synced` is kept by
+    /// the server unless a path actually changed.
+    pub fn update_sync_project(&self, id: &str, desktop_path: &str, ios_path: &str) {
+        let _ = self.command_tx.send(ClientCommand::UpdateSyncProject {
+            id: id.to_string(),
+            desktop_path: desktop_path.to_string(),
+            ios_path: ios_path.to_string(),
+        });
+    }
+
+    pub fn fetch_sync_plan(&self, id: &str) {
+        let _ = self.command_tx.send(ClientCommand::FetchSyncPlan(id.to_string()));
+    }
+
+    pub fn fetch_sync_projects(&self) {
+        let _ = self.command_tx.send(ClientCommand::FetchSyncProjects);
+    }
+
+    pub fn delete_sync_project(&self, id: &str) {
+        let _ = self.command_tx.send(ClientCommand::DeleteSyncProject(id.to_string()));
+    }
+
+    /// Remove every sync project on the desktop. The local list is emptied right
+    /// away, and results from transfers still running for them are discarded.
+    pub fn clear_sync_projects(&mut self) {
+        self.sync_removed.extend(self.sync_projects.drain(..).map(|p| p.id));
+        self.project_sync_status.clear();
+        self.sync_pending.clear();
+        self.sync_now_running.clear();
+        self.sync_status = Some("Removing all syncs…".to_string());
+        let _ = self.command_tx.send(ClientCommand::ClearSyncProjects);
+    }
+
+    /// Run the connection self-test; the result lands in `self_test`.
+    pub fn run_self_test(&mut self) {
+        self.self_test = Some(Vec::new());
+        let _ = self.command_tx.send(ClientCommand::SelfTest);
+    }
+
+    pub fn self_test_running(&self) -> bool {
+        self.self_test.as_ref().is_some_and(|steps| steps.is_empty())
+    }
+
+    pub fn check_sync_changes(&self) {
+        let _ = self.command_tx.send(ClientCommand::CheckSyncChanges);
+    }
+
+    /// Cap pulls, downloads and uploads at this many bytes per second (0 = no
+    /// cap). Applies to transfers already running too.
+    pub fn set_rate_limit(&self, bytes_per_sec: u64) {
+        self.rate_limit.store(bytes_per_sec, Ordering::Relaxed);
+    }
+
+    /// Compare checksums after each sync push (servers with the "hash" feature).
+    pub fn set_verify_uploads(&self, on: bool) {
+        let _ = self.command_tx.send(ClientCommand::SetVerifyUploads(on));
+    }
+
+    /// Gzip uploads worth compressing (servers with the "syncgzip" feature).
+    pub fn set_compress_uploads(&self, on: bool) {
+        let _ = self.command_tx.send(ClientCommand::SetCompressUploads(on));
+    }
+
+    pub fn set_auto_sync(&mut self, on: bool) {
+        self.auto_sync = on;
+        let _ = self.command_tx.send(ClientCommand::SetAutoSync(on && !self.cellular_paused));
+    }
+
+    /// Hold auto-sync transfers while on cellular; "Sync Now" still works.
+    pub fn set_cellular_paused(&mut self, paused: bool) {
+        self.cellular_paused = paused;
+        let _ = self.command_tx.send(ClientCommand::SetAutoSync(self.auto_sync && !paused));
+    }
+
+    /// Pull/push changes now, for one project or all of them (None).
+    pub fn sync_now(&mut self, project_id: Option<&str>) {
+        match project_id {
+            Some(id) => {
+                self.sync_now_running.insert(id.to_string());
+                self.set_project_sync_status(id, "Syncing…".to_string());
+            }
+            None => self.sync_status = Some("Syncing…".to_string()),
+        }
+        let _ = self.command_tx.send(ClientCommand::SyncNow(project_id.map(str::to_string)));
+    }
+
+    /// A Sync Now for this project hasn't finished yet.
+    pub fn is_syncing_now(&self, project_id: &str) -> bool {
+        self.sync_now_running.contains(project_id)
+    }
+
+    pub fn check_file_info(&self, path: &str) {
+        let _ = self.command_tx.send(ClientCommand::CheckFileInfo { path: path.to_string() });
+    }
+
+    /// Probe these server URLs in the background (replaces the previous set).
+    pub fn set_probe_urls(&self, urls: &[String]) {
+        let _ = self.command_tx.send(ClientCommand::SetProbeUrls(urls.to_vec()));
+    }
+
+    /// Cached summary for a remote directory, requesting one if it is missing or stale.
+    /// Safe to call every frame; at most one request per directory is in flight.
+    pub fn dir_info(&mut self, path: &str) -> Option<&DirInfo> {
+        let stale = match self.dir_infos.get(path) {
+            Some((_, DirInfoState::Pending(_))) => false,
+            Some((at, _)) => at.elapsed() >= DIR_INFO_TTL,
+            None => true,
+        };
+        if stale {
+            let _ = self.command_tx.send(ClientCommand::FetchDirInfo(path.to_string()));
+            let previous = match self.dir_infos.remove(path) {
+                Some((_, DirInfoState::Ready(info))) => Some(info),
+                _ => None,
+            };
+            // Keep showing the old value while the refresh is in flight
+            self.dir_infos.insert(path.to_string(), (Instant::now(), DirInfoState::Pending(previous)));
+        }
+        match self.dir_infos.get(path) {
+            Some((_, DirInfoState::Ready(info) | DirInfoState::Pending(Some(info)))) => Some(info),
+            _ => None,
+        }
+    }
+
+    /// Summary for a folder on the phone, walked once on a background thread.
+    /// Safe to call every frame; returns None until the walk finishes.
+    pub fn local_dir_info(&mut self, path: &str) -> Option<&DirInfo> {
+        if !self.local_dir_infos.contains_key(path) {
+            let _ = self.command_tx.send(ClientCommand::WalkLocalDir(path.to_string()));
+            self.local_dir_infos.insert(path.to_string(), None);
+        }
+        self.local_dir_infos.get(path).and_then(Option::as_ref)
+    }
+
+    /// Forget local folder summaries, e.g. after the local browser is refreshed.
+    pub fn clear_local_dir_infos(&mut self) {
+        self.local_dir_infos.clear();
+    }
+}
+
+// ── Background polling thread ───────────────────────────────────────────
+
+/// Deadline for a whole API request. Transfers only give up when the server
+/// goes quiet for this long.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(8);
+
+/// Agent for requests to the connected server, with the join-code token if any.
+fn server_agent(auth_token: Option<&str>, tls: &TlsOptions) -> ureq::Agent {
+    let config = ureq::Agent::config_builder().timeout_global(Some(REQUEST_TIMEOUT));
+    build_server_agent(config, auth_token, tls)
+}
+
+/// Agent for pulls, downloads and uploads. Their bodies have no deadline: at a
+/// speed limit a large file takes as long as it takes.
+fn transfer_agent(auth_token: Option<&str>, tls: &TlsOptions) -> ureq::Agent {
+    build_server_agent(transfer_config(REQUEST_TIMEOUT), auth_token, tls)
+}
+
+/// ureq measures each phase's timeout from the end of the one before, and keeps
+/// checking it in the next phase. So `send_body` is what bounds the wait for the
+/// response headers, and `recv_body` (never `recv_response`, which would cap the
+/// whole body) only bounds each read.
+fn transfer_config(timeout: Duration) -> ureq::config::ConfigBuilder<ureq::typestate::AgentScope> {
+    ureq::Agent::config_builder()
+        .timeout_connect(Some(timeout))
+        .timeout_send_body(Some(timeout))
+        .timeout_recv_body(Some(timeout))
+}
+
+fn build_server_agent(
+    mut config: ureq::config::ConfigBuilder<ureq::typestate::AgentScope>,
+    auth_token: Option<&str>,
+    tls: &TlsOptions,
+) -> ureq::Agent {
+    let mut tls_config = ureq::tls::TlsConfig::builder().disable_verification(tls.accept_invalid_certs);
+    if let Some(ref pem) = tls.root_cert_pem {
+        match parse_root_certs(pem) {
+            Ok(certs) => tls_config = tls_config.root_certs(ureq::tls::RootCerts::new_with_certs(&certs)),
+            Err(e) => log::warn!("Ignoring custom root certificate: {}", e),
+        }
+    }
+    config = config.tls_config(tls_config.build());
+    if let Some(token) = auth_token {
+        let value = format!("Bearer {}", token);
+        config = config.middleware(
+            move |mut req: ureq::http::Request<ureq::SendBody>, next: ureq::middleware::MiddlewareNext| {
+                if let Ok(v) = ureq::http::HeaderValue::from_str(&value) {
+                    req.headers_mut().insert(ureq::http::header::AUTHORIZATION, v);
+                }
+                next.handle(req)
+            },
+        );
+    }
+    config.build().into()
+}
+
+fn poll_loop(
+    base_url: &str,
+    auth_token: Option<String>,
+    tls: TlsOptions,
+    rate_limit: Arc<AtomicU64>,
+    shutdown: &AtomicBool,
+    event_tx: mpsc::Sender<ClientEvent>,
+    command_rx: mpsc::Receiver<ClientCommand>,
+) {
+    if tls.accept_invalid_certs {
+        log::warn!("TLS certificate verification is disabled for {}", base_url);
+    }
+    let mut agent = server_agent(auth_token.as_deref(), &tls);
+    // Shared with the transfer thread, which picks it up per transfer
+    let transfers = Arc::new(Mutex::new(transfer_agent(auth_token.as_deref(), &tls)));
+    let (transfer_tx, transfer_rx) = mpsc::channel();
+    {
+        let base_url = base_url.to_string();
+        let transfers = transfers.clone();
+        let rate_limit = rate_limit.clone();
+        let event_tx = event_tx.clone();
+        std::thread::spawn(move || transfer_loop(&base_url, &transfers, &rate_limit, &event_tx, transfer_rx));
+    }
+    // Last listing and its ETag per browsed path, sent back as `If-None-Match`
+    let mut browse_cache = BrowseCache::new();
+
+    let default_poll_interval = poll_interval();
+    let mut poll_interval = default_poll_interval;
+    let mut last_poll = Instant::now();
+    let mut poll_wait = Duration::ZERO; // poll immediately on start
+    let mut version_fetched = false;
+    // Auto-sync runs only while the server advertises "sync"; consecutive 404s
+    // from `/sync/*` switch it off too, until the next reconnect.
+    let mut sync_enabled = true;
+    let mut sync_not_found = 0;
+    let mut auto_sync = true;
+    let mut verify_uploads = true;
+    let mut hash_supported = false;
+    let mut compress_uploads = true;
+    let mut gzip_supported = false;
+    // One `/sync/status` per poll instead of `/sync/check` plus `/sync/projects`
+    let mut status_supported = false;
+    // A "Sync Now" waiting for the next poll: Some(None) = every project
+    let mut sync_now: Option<Option<String>> = None;
+    let mut logged_unreachable = false;
+
+    // Saved-server reachability probes use a short timeout so an offline
+    // desktop doesn't stall the poll loop for long.
+    let probe_agent: ureq::Agent = ureq::Agent::config_builder()
+        .timeout_global(Some(Duration::from_secs(2)))
+        .build()
+        .into();
+    let probe_interval = Duration::from_secs(15);
+    let mut probe_urls: Vec<String> = Vec::new();
+    let mut last_probe = Instant::now();
+
+    loop {
+        if shutdown.load(Ordering::Relaxed) {
+            return;
+        }
+
+        // ── Process commands (non-blocking) ──
+        loop {
+            match command_rx.try_recv() {
+                Ok(cmd) => match cmd {
+                    ClientCommand::DownloadFile(name) => {
+                        let _ = transfer_tx.send(Transfer::Download(name));
                     }
                     ClientCommand::DownloadLast => {
-                        match http_download_last(&agent, base_url) {
-                            Ok((name, data)) => {
-                                if event_tx
-                                    .send(ClientEvent::DownloadComplete { filename: name, data })
-                                    .is_err()
-                                {
-                                    return;
-                                }
-                            }
-                            Err(e) => {
-                                if event_tx.send(ClientEvent::Error(e)).is_err() {
-                                    return;
-                                }
-                            }
+                        let _ = transfer_tx.send(Transfer::DownloadLast);
+                    }
+                    ClientCommand::DeleteWaiting(name) => {
+                        let result = http_delete_waiting(&agent, base_url, Some(&name));
+                        if !send_inbox_after_delete(&agent, base_url, &event_tx, result) {
+                            return;
+                        }
+                    }
+                    ClientCommand::ClearWaiting => {
+                        let result = http_delete_waiting(&agent, base_url, None);
+                        if !send_inbox_after_delete(&agent, base_url, &event_tx, result) {
+                            return;
                         }
                     }
                     ClientCommand::Browse(path) => {
-                        match http_fetch_browse(&agent, base_url, path.as_deref()) {
+                        match browse_cached(&agent, base_url, &mut browse_cache, path.clone()) {
                             Ok(files) => {
-                                if event_tx.send(ClientEvent::BrowseUpdate(files)).is_err() {
+                                if event_tx.send(ClientEvent::BrowseUpdate { path, files }).is_err() {
                                     return;
                                 }
                             }
@@ -450,11 +1913,32 @@ fn poll_loop(
                             }
                         }
                     }
-                    ClientCommand::PullFile(path) => {
-                        match http_pull_remote_file(&agent, base_url, &path) {
-                            Ok((filename, data)) => {
+                    ClientCommand::BrowseChildren(path) => {
+                        let files = browse_cached(&agent, base_url, &mut browse_cache, Some(path.clone()));
+                        if event_tx.send(ClientEvent::TreeChildren { path, files }).is_err() {
+                            return;
+                        }
+                    }
+                    ClientCommand::PullFile { path, path_id, cached, conditional } => {
+                        let _ = transfer_tx.send(Transfer::Pull { path, path_id, cached, conditional });
+                    }
+                    ClientCommand::StatFile { path, path_id } => {
+                        let result = http_stat(&agent, base_url, &path, path_id.as_deref());
+                        if event_tx.send(ClientEvent::StatResult { path, result }).is_err() {
+                            return;
+                        }
+                    }
+                    ClientCommand::FetchChanges(since) => {
+                        let result = http_fetch_changes(&agent, base_url, since);
+                        if event_tx.send(ClientEvent::ChangesResult(result)).is_err() {
+                            return;
+                        }
+                    }
+                    ClientCommand::PreviewFile { path, path_id, max_bytes } => {
+                        match http_preview_remote_file(&agent, base_url, &path, path_id.as_deref(), max_bytes) {
+                            Ok((filename, data, total_size)) => {
                                 if event_tx
-                                    .send(ClientEvent::PullComplete { filename, data })
+                                    .send(ClientEvent::PreviewComplete { filename, data, total_size })
                                     .is_err()
                                 {
                                     return;
@@ -467,45 +1951,78 @@ fn poll_loop(
                             }
                         }
                     }
-                    ClientCommand::PreviewFile(path) => {
-                        match http_pull_remote_file(&agent, base_url, &path) {
-                            Ok((filename, data)) => {
-                                if event_tx
-                                    .send(ClientEvent::PreviewComplete { filename, data })
-                                    .is_err()
-                                {
-                                    return;
-                                }
-                            }
-                            Err(e) => {
-                                if event_tx.send(ClientEvent::Error(e)).is_err() {
-                                    return;
-                                }
+                    ClientCommand::PullDir { path, path_id, pattern, dest_dir, cancel } => {
+                        // Runs on its own thread so polling (and cancelling) carry on meanwhile
+                        let agent = transfers.lock().unwrap().clone();
+                        let base_url = base_url.to_string();
+                        let event_tx = event_tx.clone();
+                        let rate_limit = rate_limit.clone();
+                        std::thread::spawn(move || {
+                            let request =
+                                pull_dir_request(&agent, &base_url, &path, path_id.as_deref(), pattern.as_deref());
+                            let event = match http_pull_dir(request, &path, &dest_dir, &cancel, &rate_limit, &event_tx) {
+                                Ok((dest, entries, truncated)) => ClientEvent::PullDirComplete {
+                                    dest,
+                                    entries,
+                                    cancelled: cancel.load(Ordering::Relaxed),
+                                    truncated,
+                                },
+                                Err(e) => ClientEvent::PullDirFailed(e),
+                            };
+                            let _ = event_tx.send(event);
+                        });
+                    }
+                    ClientCommand::SetProbeUrls(urls) => {
+                        probe_urls = urls;
+                        last_probe = Instant::now() - probe_interval; // probe right away
+                    }
+                    ClientCommand::FetchReceived => match http_fetch_received(&agent, base_url) {
+                        Ok(files) => {
+                            if event_tx.send(ClientEvent::ReceivedUpdate(files)).is_err() {
+                                return;
                             }
                         }
+                        Err(e) => log::warn!("[client] /received failed: {}", e),
+                    },
+                    ClientCommand::FetchDirInfo(path) => {
+                        let info = http_fetch_dir_info(&agent, base_url, &path).ok();
+                        if event_tx.send(ClientEvent::DirInfoResult { path, info }).is_err() {
+                            return;
+                        }
+                    }
+                    ClientCommand::WalkLocalDir(path) => {
+                        let event_tx = event_tx.clone();
+                        std::thread::spawn(move || {
+                            let info = local_dir_info(&path);
+                            let _ = event_tx.send(ClientEvent::LocalDirInfoResult { path, info });
+                        });
+                    }
+                    ClientCommand::SetPollInterval(interval) => {
+                        poll_interval = interval.unwrap_or(default_poll_interval);
                     }
                     ClientCommand::Refresh => {
-                        last_poll = Instant::now() - poll_interval;
+                        poll_wait = Duration::ZERO;
                     }
-                    ClientCommand::UploadFile { local_path, remote_dest_path } => {
-                        match http_upload_file(&agent, base_url, &local_path, &remote_dest_path) {
-                            Ok(()) => {
-                                if event_tx
-                                    .send(ClientEvent::UploadComplete { remote_path: remote_dest_path })
-                                    .is_err()
-                                {
-                                    return;
-                                }
-                            }
-                            Err(e) => {
-                                if event_tx.send(ClientEvent::Error(e)).is_err() {
-                                    return;
-                                }
-                            }
+                    ClientCommand::SendNote(text) => {
+                        let chars = text.chars().count();
+                        let result = http_send_note(&agent, base_url, &text).map(|()| chars);
+                        if event_tx.send(ClientEvent::NoteSent(result)).is_err() {
+                            return;
                         }
                     }
-                    ClientCommand::CreateSyncProject { local_path, remote_path } => {
-                        match http_create_sync_project(&agent, base_url, &local_path, &remote_path) {
+                    ClientCommand::NetworkChanged => {
+                        // Pooled connections may be bound to the old interface
+                        agent = server_agent(auth_token.as_deref(), &tls);
+                        *transfers.lock().unwrap() = transfer_agent(auth_token.as_deref(), &tls);
+                        poll_wait = Duration::ZERO;
+                        last_probe = Instant::now() - probe_interval;
+                    }
+                    ClientCommand::UploadFile { local_path, remote_dest_path } => {
+                        let gzip = compress_uploads && gzip_supported;
+                        let _ = transfer_tx.send(Transfer::Upload { local_path, remote_dest_path, gzip });
+                    }
+                    ClientCommand::CreateSyncProject { local_path, remote_path, direction } => {
+                        match http_create_sync_project(&agent, base_url, &local_path, &remote_path, direction) {
                             Ok(project) => {
                                 // Also save locally
                                 save_local_sync_project(&project);
@@ -554,9 +2071,71 @@ fn poll_loop(
                             }
                         }
                     }
+                    ClientCommand::SelfTest => {
+                        let steps = run_self_test(&agent, base_url);
+                        if event_tx.send(ClientEvent::SelfTest(steps)).is_err() {
+                            return;
+                        }
+                    }
+                    ClientCommand::ClearSyncProjects => {
+                        let result = http_clear_sync_projects(&agent, base_url);
+                        if event_tx.send(ClientEvent::SyncProjectsCleared(result)).is_err() {
+                            return;
+                        }
+                        if let Ok(projects) = http_fetch_sync_projects(&agent, base_url)
+                            && event_tx.send(ClientEvent::SyncProjectsUpdate(projects)).is_err()
+                        {
+                            return;
+                        }
+                    }
+                    ClientCommand::UpdateSyncProject { id, desktop_path, ios_path } => {
+                        let event = match http_update_sync_project(&agent, base_url, &id, &desktop_path, &ios_path)
+                            .and_then(|()| http_fetch_sync_projects(&agent, base_url))
+                        {
+                            Ok(projects) => ClientEvent::SyncProjectsUpdate(projects),
+                            Err(message) => ClientEvent::SyncError { project_id: Some(id), message },
+                        };
+                        if event_tx.send(event).is_err() {
+                            return;
+                        }
+                    }
+                    ClientCommand::FetchSyncPlan(id) => {
+                        let event = match http_fetch_sync_plan(&agent, base_url, &id) {
+                            Ok(mut plan) => {
+                                // The desktop can't see our side; add the push the poll would make
+                                if let Some(push) = local_sync_push(&plan) {
+                                    plan.total_bytes += push.size;
+                                    plan.entries.push(push);
+                                }
+                                ClientEvent::SyncPlanResult(plan)
+                            }
+                            Err(message) => ClientEvent::SyncError { project_id: Some(id), message },
+                        };
+                        if event_tx.send(event).is_err() {
+                            return;
+                        }
+                    }
                     ClientCommand::AckSync { id, timestamp } => {
                         let _ = http_sync_ack(&agent, base_url, &id, timestamp);
                     }
+                    ClientCommand::SetAutoSync(on) => {
+                        auto_sync = on;
+                    }
+                    ClientCommand::SetVerifyUploads(on) => {
+                        verify_uploads = on;
+                    }
+                    ClientCommand::SetCompressUploads(on) => {
+                        compress_uploads = on;
+                    }
+                    ClientCommand::SyncNow(id) => {
+                        // Two different projects queued before the poll: just sync everything
+                        sync_now = Some(match (sync_now.take(), id) {
+                            (None, id) => id,
+                            (Some(Some(a)), Some(b)) if a == b => Some(a),
+                            _ => None,
+                        });
+                        poll_wait = Duration::ZERO;
+                    }
                     ClientCommand::CheckSyncChanges => {
                         match http_sync_check(&agent, base_url) {
                             Ok(changes) => {
@@ -594,8 +2173,9 @@ fn poll_loop(
         }
 
         // ── Periodic polling ──
-        if last_poll.elapsed() >= poll_interval {
+        if !shutdown.load(Ordering::Relaxed) && last_poll.elapsed() >= poll_wait {
             last_poll = Instant::now();
+            poll_wait = jittered(poll_interval);
 
             match http_fetch_status(&agent, base_url) {
                 Ok(status) => {
@@ -614,8 +2194,32 @@ fn poll_loop(
                     if let (Some(hostname), Some(dns)) = (status.device_hostname, status.device_dns) {
                         let _ = event_tx.send(ClientEvent::DeviceInfo { hostname, dns });
                     }
+                    if let Some(info) = status.server_info {
+                        let _ = event_tx.send(ClientEvent::ServerInfo(info));
+                    }
+                    // Fetch version once per connection; a failure means an older server
+                    if !version_fetched {
+                        log::info!("[client] connected to {}", base_url);
+                        logged_unreachable = false;
+                        version_fetched = true;
+                        let version = http_fetch_version(&agent, base_url).unwrap_or_default();
+                        sync_enabled = version.features.iter().any(|f| f == "sync");
+                        hash_supported = version.features.iter().any(|f| f == "hash");
+                        gzip_supported = version.features.iter().any(|f| f == "syncgzip");
+                        status_supported = version.features.iter().any(|f| f == "syncstatus");
+                        sync_not_found = 0;
+                        if event_tx.send(ClientEvent::VersionInfo(version)).is_err() {
+                            return;
+                        }
+                    }
                 }
-                Err(_) => {
+                Err(e) => {
+                    // Only log the transition; the poll repeats every few seconds
+                    if !logged_unreachable {
+                        log::warn!("[client] {} unreachable: {}", base_url, e);
+                        logged_unreachable = true;
+                    }
+                    version_fetched = false;
                     if event_tx
                         .send(ClientEvent::StatusUpdate {
                             connected: false,
@@ -642,34 +2246,97 @@ fn poll_loop(
                 }
             }
 
+            // Off, changes are still checked (and shown) but only "Sync Now" moves them
+            let requested = |id: &str| sync_now.as_ref().is_some_and(|only| only.as_deref().is_none_or(|o| o == id));
+            let transfer = |id: &str| auto_sync || requested(id);
+
             // ── Auto-sync: check for remote changes and pull them ──
-            if let Ok(changes) = http_sync_check(&agent, base_url) {
-                for change in &changes {
-                    // Pull the changed file from desktop
-                    if let Ok((filename, data)) = http_pull_remote_file(&agent, base_url, &change.local_path) {
-                        // The change.remote_path is the iOS local path
-                        // Save to that path
-                        if std::fs::write(&change.remote_path, &data).is_ok() {
-                            // Acknowledge the sync
-                            let _ = http_sync_ack(&agent, base_url, &change.id, change.new_modified);
-                            if event_tx
-                                .send(ClientEvent::SyncPullComplete {
+            let mut status_projects = None;
+            let changes = if !sync_enabled {
+                Ok(Vec::new())
+            } else if status_supported {
+                match http_sync_status(&agent, base_url) {
+                    Ok(statuses) => {
+                        let changes = statuses.iter().filter_map(SyncProjectStatus::change).collect();
+                        status_projects = Some(Ok(statuses.iter().map(|s| s.project.clone()).collect()));
+                        if event_tx.send(ClientEvent::SyncStatusUpdate(statuses)).is_err() {
+                            return;
+                        }
+                        Ok(changes)
+                    }
+                    Err(e) => Err(e),
+                }
+            } else {
+                http_sync_check(&agent, base_url)
+            };
+            if let Err(ref e) = changes
+                && is_not_found(e)
+            {
+                sync_not_found += 1;
+                if sync_not_found >= SYNC_NOT_FOUND_LIMIT {
+                    log::info!("[sync] {} has no /sync endpoints; auto-sync disabled", base_url);
+                    sync_enabled = false;
+                    if event_tx.send(ClientEvent::SyncUnsupported).is_err() {
+                        return;
+                    }
+                }
+            } else {
+                sync_not_found = 0;
+            }
+            let mut sync_now_error = changes.as_ref().err().cloned();
+            let mut attempted = 0;
+            if let Ok(changes) = changes {
+                // Lets the Project Sync tab show them as in progress
+                if !changes.is_empty() && event_tx.send(ClientEvent::SyncChangesAvailable(changes.clone())).is_err() {
+                    return;
+                }
+                for change in changes.iter().filter(|c| transfer(&c.id)) {
+                    // Pull the changed file from desktop; the change.remote_path is the iOS local path
+                    let transfer_agent = transfers.lock().unwrap().clone();
+                    let event = match http_pull_remote_file(&transfer_agent, base_url, &change.local_path, None, &rate_limit) {
+                        Ok((filename, data, _)) => match std::fs::write(&change.remote_path, &data) {
+                            Ok(()) => {
+                                // Acknowledge the sync
+                                let _ = http_sync_ack(&agent, base_url, &change.id, change.new_modified);
+                                ClientEvent::SyncPullComplete {
                                     project_id: change.id.clone(),
                                     filename,
-                                })
-                                .is_err()
-                            {
-                                return;
+                                }
                             }
-                        }
+                            Err(e) => ClientEvent::SyncError {
+                                project_id: Some(change.id.clone()),
+                                message: format!("Couldn't save '{}': {}", filename, e),
+                            },
+                        },
+                        Err(e) => ClientEvent::SyncError {
+                            project_id: Some(change.id.clone()),
+                            message: format!("Pull failed: {}", e),
+                        },
+                    };
+                    // Only what Sync Now asked for decides its "up to date" answer
+                    if requested(&change.id) {
+                        attempted += 1;
+                    }
+                    if event_tx.send(event).is_err() {
+                        return;
                     }
                 }
             }
 
             // ── Auto-sync: check for local changes and push them ──
-            if let Ok(projects) = http_fetch_sync_projects(&agent, base_url) {
+            let projects = if sync_enabled && (auto_sync || sync_now.is_some()) {
+                // Listed before this poll's pulls, which doesn't change what gets pushed
+                status_projects.unwrap_or_else(|| http_fetch_sync_projects(&agent, base_url))
+            } else {
+                Ok(Vec::new())
+            };
+            if let Err(ref e) = projects {
+                sync_now_error.get_or_insert_with(|| e.clone());
+            }
+            if let Ok(projects) = projects {
                 for project in &projects {
-                    if project.paused {
+                    // Desktop → iPhone mirrors never upload
+                    if project.paused || !project.direction.pushes_from_phone() || !transfer(&project.id) {
                         continue;
                     }
                     // project.remote_path is the iOS local path (from desktop's perspective)
@@ -683,36 +2350,228 @@ fn poll_loop(
                             .unwrap_or(0);
                         if modified > project.last_synced {
                             // File changed locally on iOS, push to desktop
-                            if http_upload_file(&agent, base_url, ios_path, &project.local_path).is_ok() {
-                                // Update last_synced
-                                let _ = http_sync_ack(&agent, base_url, &project.id, modified);
-                                let filename = ios_path
-                                    .rsplit('/')
-                                    .next()
-                                    .unwrap_or(ios_path)
-                                    .to_string();
-                                if event_tx
-                                    .send(ClientEvent::SyncPullComplete {
+                            let verify = verify_uploads && hash_supported;
+                            let gzip = compress_uploads && gzip_supported;
+                            let transfer_agent = transfers.lock().unwrap().clone();
+                            let event = match push_sync_file(&transfer_agent, base_url, ios_path, &project.local_path, verify, gzip, &rate_limit) {
+                                Ok(()) => {
+                                    // Update last_synced
+                                    let _ = http_sync_ack(&agent, base_url, &project.id, modified);
+                                    let filename = ios_path
+                                        .rsplit('/')
+                                        .next()
+                                        .unwrap_or(ios_path)
+                                        .to_string();
+                                    ClientEvent::SyncPullComplete {
                                         project_id: project.id.clone(),
                                         filename,
-                                    })
-                                    .is_err()
-                                {
-                                    return;
+                                    }
                                 }
+                                Err(e) => ClientEvent::SyncError {
+                                    project_id: Some(project.id.clone()),
+                                    message: format!("Push failed: {}", e),
+                                },
+                            };
+                            if requested(&project.id) {
+                                attempted += 1;
+                            }
+                            if event_tx.send(event).is_err() {
+                                return;
                             }
                         }
                     }
                 }
             }
+
+            if let Some(project_id) = sync_now.take() {
+                let result = match sync_now_error {
+                    Some(e) => Err(e),
+                    None => Ok(attempted),
+                };
+                if event_tx.send(ClientEvent::SyncNowDone { project_id, result }).is_err() {
+                    return;
+                }
+            }
+        }
+
+        // ── Saved server reachability ──
+        if !probe_urls.is_empty() && last_probe.elapsed() >= probe_interval {
+            last_probe = Instant::now();
+            let results = probe_urls
+                .iter()
+                .map(|url| (url.clone(), http_probe(&probe_agent, url)))
+                .collect();
+            if event_tx.send(ClientEvent::Reachability(results)).is_err() {
+                return;
+            }
         }
 
         std::thread::sleep(Duration::from_millis(100));
     }
 }
 
+/// Transfers the poll loop hands to its transfer thread. They run one at a time
+/// there, so a long (or speed-limited) one doesn't hold up polling and commands.
+enum Transfer {
+    Download(String),
+    DownloadLast,
+    Pull { path: String, path_id: Option<String>, cached: Option<(u64, u64)>, conditional: bool },
+    Upload { local_path: String, remote_dest_path: String, gzip: bool },
+}
+
+fn transfer_loop(
+    base_url: &str,
+    agent: &Mutex<ureq::Agent>,
+    rate_limit: &AtomicU64,
+    event_tx: &mpsc::Sender<ClientEvent>,
+    transfer_rx: mpsc::Receiver<Transfer>,
+) {
+    // Ends once the poll loop (and its sender) is gone
+    for transfer in transfer_rx {
+        let agent = agent.lock().unwrap().clone();
+        let event = match transfer {
+            Transfer::Download(filename) => match http_download_file(&agent, base_url, &filename, rate_limit) {
+                Ok((data, via)) => ClientEvent::DownloadComplete { filename, data, via },
+                Err(e) => ClientEvent::Error(e),
+            },
+            Transfer::DownloadLast => match http_download_last(&agent, base_url, rate_limit) {
+                Ok((filename, data, via)) => ClientEvent::DownloadComplete { filename, data, via },
+                Err(e) => ClientEvent::Error(e),
+            },
+            Transfer::Pull { path, path_id, cached, conditional } => {
+                pull_file(&agent, base_url, path, path_id, cached, conditional, rate_limit)
+            }
+            Transfer::Upload { local_path, remote_dest_path, gzip } => {
+                match http_upload_file(&agent, base_url, &local_path, &remote_dest_path, gzip, rate_limit) {
+                    Ok(()) => ClientEvent::UploadComplete { remote_path: remote_dest_path },
+                    Err(error) => ClientEvent::UploadFailed { remote_path: remote_dest_path, error },
+                }
+            }
+        };
+        if event_tx.send(event).is_err() {
+            return;
+        }
+    }
+}
+
+/// Pull one file, or report that the saved copy (`cached`, its mtime and size)
+/// is still current.
+fn pull_file(
+    agent: &ureq::Agent,
+    base_url: &str,
+    path: String,
+    path_id: Option<String>,
+    cached: Option<(u64, u64)>,
+    conditional: bool,
+    rate_limit: &AtomicU64,
+) -> ClientEvent {
+    let (remote_info, pulled) = if conditional {
+        let etag = cached.map(|(modified, size)| file_etag(modified, size));
+        match http_pull_conditional(agent, base_url, &path, path_id.as_deref(), etag.as_deref(), rate_limit) {
+            Ok(Some(file)) => (file.validator, Ok(file)),
+            Ok(None) => (cached, Err(None)),
+            Err(e) => (None, Err(Some(e))),
+        }
+    } else {
+        // Only worth asking /sync/file-info when there's a saved copy to compare
+        // with; servers without it just always download
+        let remote_info = cached.and_then(|_| {
+            http_check_file_info(agent, base_url, &path)
+                .ok()
+                .filter(|info| info.exists)
+                .map(|info| (info.modified, info.size))
+        });
+        if cached.is_some() && cached == remote_info {
+            (remote_info, Err(None))
+        } else {
+            let pulled = http_pull_conditional(agent, base_url, &path, path_id.as_deref(), None, rate_limit)
+                .map_err(Some)
+                .and_then(|file| file.ok_or(None));
+            let validator = pulled.as_ref().ok().and_then(|file| file.validator);
+            (validator.or(remote_info), pulled)
+        }
+    };
+    match pulled {
+        // Err(None): the saved copy is still current
+        Err(None) => ClientEvent::PullUnchanged { remote_path: path, path_id },
+        Ok(file) => ClientEvent::PullComplete {
+            remote_path: path,
+            filename: file.name,
+            data: file.data,
+            via: file.via,
+            remote_info,
+        },
+        Err(Some(e)) => ClientEvent::Error(e),
+    }
+}
+
 // ── HTTP helpers ────────────────────────────────────────────────────────
 
+/// Consecutive `/sync/check` 404s before auto-sync gives up on a connection.
+const SYNC_NOT_FOUND_LIMIT: u32 = 3;
+
+/// Browse listings (with their ETags) kept for `If-None-Match`; cleared when full.
+const BROWSE_CACHE_LIMIT: usize = 64;
+
+/// Last listing and its ETag per browsed path.
+type BrowseCache = std::collections::HashMap<Option<String>, (String, Vec<RemoteFile>)>;
+
+/// `/browse` with `If-None-Match` from `cache`; a 304 reuses the cached listing.
+fn browse_cached(
+    agent: &ureq::Agent,
+    base_url: &str,
+    cache: &mut BrowseCache,
+    path: Option<String>,
+) -> Result<Vec<RemoteFile>, String> {
+    let etag = cache.get(&path).map(|(etag, _)| etag.as_str());
+    match http_fetch_browse(agent, base_url, path.as_deref(), etag)? {
+        // 304: the listing we already have is current
+        None => Ok(cache.get(&path).map(|(_, files)| files.clone()).unwrap_or_default()),
+        Some((files, etag)) => {
+            if let Some(etag) = etag {
+                if cache.len() >= BROWSE_CACHE_LIMIT {
+                    cache.clear();
+                }
+                cache.insert(path, (etag, files.clone()));
+            }
+            Ok(files)
+        }
+    }
+}
+
+/// Whether an error string from one of the `http_*` helpers is a 404.
+fn is_not_found(err: &str) -> bool {
+    err == ureq::Error::StatusCode(404).to_string()
+}
+
+/// Parse a JSON array body entry by entry. Only a body that isn't an array at
+/// all is an error; individual entries that don't match `T` are logged and skipped
+/// so one odd file or a newer server schema doesn't blank the whole list.
+fn parse_entry_list<T: serde::de::DeserializeOwned>(body: &str, what: &str) -> Result<Vec<T>, String> {
+    let value: serde_json::Value = serde_json::from_str(body).map_err(|e| e.to_string())?;
+    if !value.is_array() {
+        return Err(format!("expected a JSON array of {} entries", what));
+    }
+    Ok(parse_entries(value, what))
+}
+
+/// Lenient counterpart of `serde_json::from_value::<Vec<T>>`; non-arrays yield an empty list.
+fn parse_entries<T: serde::de::DeserializeOwned>(value: serde_json::Value, what: &str) -> Vec<T> {
+    let serde_json::Value::Array(items) = value else {
+        return Vec::new();
+    };
+    items
+        .into_iter()
+        .filter_map(|item| match serde_json::from_value(item) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                log::warn!("[client] skipping malformed {} entry: {}", what, e);
+                None
+            }
+        })
+        .collect()
+}
+
 /// Status response including device identity
 struct StatusResponse {
     last_sent: Option<SentFileInfo>,
@@ -720,6 +2579,8 @@ struct StatusResponse {
     server_cwd: Option<String>,
     device_hostname: Option<String>,
     device_dns: Option<String>,
+    /// None for servers that don't report os/arch
+    server_info: Option<ServerInfo>,
 }
 
 fn http_fetch_status(
@@ -773,15 +2634,132 @@ fn http_fetch_status(
         .filter(|s| !s.is_empty())
         .map(String::from);
 
+    let field = |key: &str| {
+        json.get(key)
+            .and_then(|v| v.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let server_info = Some(ServerInfo {
+        hostname: field("hostname"),
+        os: field("os"),
+        arch: field("arch"),
+        tailnet: field("tailnet"),
+        path_separator: field("path_separator").chars().next().unwrap_or('/'),
+        browse_root: Some(field("browse_root")).filter(|root| !root.is_empty()),
+    })
+    .filter(|info| !info.os.is_empty());
+
     Ok(StatusResponse {
         last_sent,
         last_received,
         server_cwd,
         device_hostname,
         device_dns,
+        server_info,
     })
 }
 
+/// Lightweight reachability check against `/healthz`. Any HTTP response counts,
+/// so servers that predate the endpoint (404) still show as reachable.
+fn http_probe(agent: &ureq::Agent, base_url: &str) -> bool {
+    let url = format!("{}/healthz", base_url.trim_end_matches('/'));
+    match agent.get(&url).call() {
+        Ok(_) | Err(ureq::Error::StatusCode(_)) => true,
+        Err(_) => false,
+    }
+}
+
+/// Check each layer between the phone and the desktop app, stopping at the
+/// first that fails: the server answering at all, it being Tailscale Drive,
+/// and it serving files.
+fn run_self_test(agent: &ureq::Agent, base_url: &str) -> Vec<SelfTestStep> {
+    let mut steps = Vec::new();
+
+    let url = format!("{}/healthz", base_url);
+    let (result, hint) = match agent.get(&url).call() {
+        Ok(_) => (Ok("responded".to_string()), None),
+        // Servers from before /healthz still answer
+        Err(ureq::Error::StatusCode(404)) => (Ok("responded".to_string()), None),
+        Err(ureq::Error::StatusCode(code @ (401 | 403))) => (
+            Err(format!("refused the request (HTTP {})", code)),
+            Some("Check the auth token for this server in ⚙ Settings."),
+        ),
+        Err(ureq::Error::StatusCode(code)) => (
+            Err(format!("HTTP {}", code)),
+            Some("Something answered, but not as expected. Is the URL's port the desktop app's (8080)?"),
+        ),
+        Err(e @ ureq::Error::HostNotFound) => (
+            Err(e.to_string()),
+            Some("Check the URL. MagicDNS names only resolve while Tailscale is connected on this phone."),
+        ),
+        Err(e @ ureq::Error::Timeout(_)) => (
+            Err(e.to_string()),
+            Some("Is Tailscale connected on both devices? The desktop may be asleep or offline."),
+        ),
+        Err(e) => (
+            Err(e.to_string()),
+            Some("Is the desktop app running? If it is, `tailscale serve` or a firewall may not be exposing port 8080."),
+        ),
+    };
+    let failed = result.is_err();
+    steps.push(SelfTestStep { name: "Reach the server", result, hint });
+    if failed {
+        return steps;
+    }
+
+    let (result, hint) = match http_fetch_version(agent, base_url) {
+        Ok(v) => (Ok(format!("Tailscale Drive {} ({} features)", v.version, v.features.len())), None),
+        Err(e) => (
+            Err(e),
+            Some("This may not be Tailscale Drive, or the desktop app is too old. Update it and try again."),
+        ),
+    };
+    let failed = result.is_err();
+    steps.push(SelfTestStep { name: "Identify the server", result, hint });
+    if failed {
+        return steps;
+    }
+
+    let (result, hint) = match http_fetch_browse(agent, base_url, None, None) {
+        Ok(listing) => {
+            let items = listing.map_or(0, |(files, _)| files.len());
+            (Ok(format!("{} items in the home folder", items)), None)
+        }
+        Err(e) => (
+            Err(e),
+            Some("The server is up but couldn't list its home folder. See the desktop app's log."),
+        ),
+    };
+    steps.push(SelfTestStep { name: "List files", result, hint });
+    steps
+}
+
+fn http_fetch_version(agent: &ureq::Agent, base_url: &str) -> Result<ServerVersion, String> {
+    let url = format!("{}/version", base_url);
+    let body = agent
+        .get(&url)
+        .call()
+        .map_err(|e| e.to_string())?
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| e.to_string())?;
+    serde_json::from_str(&body).map_err(|e| e.to_string())
+}
+
+fn http_fetch_dir_info(agent: &ureq::Agent, base_url: &str, path: &str) -> Result<DirInfo, String> {
+    let url = format!("{}/dirinfo", base_url);
+    let body = agent
+        .get(&url)
+        .query("path", path)
+        .call()
+        .map_err(|e| e.to_string())?
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| e.to_string())?;
+    serde_json::from_str(&body).map_err(|e| e.to_string())
+}
+
 fn http_check_file_info(
     agent: &ureq::Agent,
     base_url: &str,
@@ -813,70 +2791,199 @@ fn http_fetch_files(agent: &ureq::Agent, base_url: &str) -> Result<Vec<WaitingFi
 
     let files: Vec<WaitingFile> = json
         .get("files")
-        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .map(|v| parse_entries(v.clone(), "waiting file"))
         .unwrap_or_default();
 
     Ok(files)
 }
 
+/// A directory listing and its `ETag`, if the server sent one.
+type BrowseListing = (Vec<RemoteFile>, Option<String>);
+
+/// List a remote directory. With `if_none_match`, None means the server
+/// answered 304 and the listing with that ETag is still current.
 fn http_fetch_browse(
     agent: &ureq::Agent,
     base_url: &str,
     path: Option<&str>,
-) -> Result<Vec<RemoteFile>, String> {
+    if_none_match: Option<&str>,
+) -> Result<Option<BrowseListing>, String> {
     let url = format!("{}/browse", base_url);
     let mut req = agent.get(&url);
     // Use .query() for proper URL-encoding (fixes 404 with spaces / special chars)
     if let Some(p) = path {
         req = req.query("path", p);
     }
-    let body = req
+    if let Some(etag) = if_none_match {
+        req = req.header("if-none-match", etag);
+    }
+    let mut resp = req
         .call()
-        .map_err(|e| format!("browse request failed: {}", e))?
+        .map_err(|e| format!("browse request failed: {}", e))?;
+    if resp.status() == 304 {
+        return Ok(None);
+    }
+    let etag = header_str(&resp, "etag");
+    let body = resp
         .body_mut()
         .read_to_string()
         .map_err(|e| e.to_string())?;
 
-    let files: Vec<RemoteFile> = serde_json::from_str(&body).map_err(|e| e.to_string())?;
-    Ok(files)
+    let files: Vec<RemoteFile> = parse_entry_list(&body, "remote file")?;
+    Ok(Some((files, etag)))
+}
+
+/// Escape a file name for use as one URL path segment (`/files/{name}`).
+fn encode_path_segment(name: &str) -> String {
+    let mut out = String::with_capacity(name.len());
+    for byte in name.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => out.push(byte as char),
+            _ => out.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    out
+}
+
+/// Delete one inbox file, or the whole inbox when `name` is `None`.
+fn http_delete_waiting(
+    agent: &ureq::Agent,
+    base_url: &str,
+    name: Option<&str>,
+) -> Result<(), String> {
+    let url = match name {
+        Some(name) => format!("{}/files/{}", base_url, encode_path_segment(name)),
+        None => format!("{}/files", base_url),
+    };
+    agent
+        .delete(&url)
+        .call()
+        .map_err(|e| format!("delete from inbox failed: {}", e))?;
+    Ok(())
+}
+
+/// Report a delete failure (if any) and push the refreshed inbox listing.
+/// Returns false once the renderer side of the channel is gone.
+fn send_inbox_after_delete(
+    agent: &ureq::Agent,
+    base_url: &str,
+    event_tx: &mpsc::Sender<ClientEvent>,
+    result: Result<(), String>,
+) -> bool {
+    if let Err(e) = result {
+        if event_tx.send(ClientEvent::Error(e)).is_err() {
+            return false;
+        }
+    }
+    match http_fetch_files(agent, base_url) {
+        Ok(files) => event_tx.send(ClientEvent::FilesUpdate(files)).is_ok(),
+        Err(_) => true,
+    }
+}
+
+/// `Throttled` never reads more than this at once, so pacing stays smooth.
+const THROTTLE_CHUNK_BYTES: usize = 16 * 1024;
+
+/// Reads `inner` no faster than `limit` bytes per second (0 = unlimited). The
+/// limit is re-read for every chunk, so changing it affects running transfers.
+struct Throttled<'a, R> {
+    inner: R,
+    limit: &'a AtomicU64,
+    /// Limit the current pacing window started with, when, and bytes read since
+    window_limit: u64,
+    window_start: Instant,
+    window_bytes: u64,
+}
+
+impl<'a, R> Throttled<'a, R> {
+    fn new(inner: R, limit: &'a AtomicU64) -> Self {
+        Self {
+            inner,
+            limit,
+            window_limit: limit.load(Ordering::Relaxed),
+            window_start: Instant::now(),
+            window_bytes: 0,
+        }
+    }
+}
+
+impl<R: std::io::Read> std::io::Read for Throttled<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let limit = self.limit.load(Ordering::Relaxed);
+        if limit != self.window_limit {
+            self.window_limit = limit;
+            self.window_start = Instant::now();
+            self.window_bytes = 0;
+        }
+        if limit == 0 {
+            return self.inner.read(buf);
+        }
+        let len = buf.len().min(THROTTLE_CHUNK_BYTES);
+        let n = self.inner.read(&mut buf[..len])?;
+        self.window_bytes += n as u64;
+        let due = Duration::from_secs_f64(self.window_bytes as f64 / limit as f64);
+        if let Some(wait) = due.checked_sub(self.window_start.elapsed()) {
+            std::thread::sleep(wait);
+        }
+        Ok(n)
+    }
+}
+
+/// A whole response body, read at no more than `rate_limit`.
+fn read_body_throttled(body: &mut ureq::Body, rate_limit: &AtomicU64) -> Result<Vec<u8>, String> {
+    use std::io::Read;
+    let mut data = Vec::new();
+    Throttled::new(body.as_reader(), rate_limit)
+        .read_to_end(&mut data)
+        .map_err(|e| e.to_string())?;
+    Ok(data)
+}
+
+/// `X-Tailscale-Path` from a transfer response: how the desktop reaches this device.
+fn transfer_path<B>(resp: &ureq::http::Response<B>) -> Option<String> {
+    resp.headers()
+        .get("x-tailscale-path")
+        .and_then(|v| v.to_str().ok())
+        .map(String::from)
+}
+
+/// " · direct" / " · relay nyc" for completion toasts, empty when unknown.
+fn via_suffix(via: Option<&str>) -> String {
+    via.map(|v| format!(" · {}", v)).unwrap_or_default()
 }
 
 fn http_download_file(
     agent: &ureq::Agent,
     base_url: &str,
     name: &str,
-) -> Result<Vec<u8>, String> {
-    let url = format!("{}/download/{}", base_url, name);
+    rate_limit: &AtomicU64,
+) -> Result<(Vec<u8>, Option<String>), String> {
+    let url = format!("{}/download/{}", base_url, encode_path_segment(name));
     let mut resp = agent.get(&url).call().map_err(|e| e.to_string())?;
-    let data = resp.body_mut()
-        .read_to_vec()
-        .map_err(|e| e.to_string())?;
+    let via = transfer_path(&resp);
+    let data = read_body_throttled(resp.body_mut(), rate_limit)?;
     
-    Ok(data)
+    Ok((data, via))
 }
 
-fn http_download_last(agent: &ureq::Agent, base_url: &str) -> Result<(String, Vec<u8>), String> {
+fn http_download_last(
+    agent: &ureq::Agent,
+    base_url: &str,
+    rate_limit: &AtomicU64,
+) -> Result<(String, Vec<u8>, Option<String>), String> {
     let url = format!("{}/download", base_url);
     let mut resp = agent.get(&url).call().map_err(|e| e.to_string())?;
 
     // Try to get filename from Content-Disposition header
-    let name = resp
-        .headers()
-        .get("content-disposition")
-        .and_then(|cd| cd.to_str().ok())
-        .and_then(|cd| {
-            cd.split("filename=\"")
-                .nth(1)
-                .and_then(|s| s.strip_suffix('"'))
-        })
-        .unwrap_or("downloaded_file")
-        .to_string();
+    let name = header_str(&resp, "content-disposition")
+        .and_then(|cd| disposition_filename(&cd))
+        .unwrap_or_else(|| "downloaded_file".to_string());
 
-     let data =resp.body_mut()
-        .read_to_vec()
-        .map_err(|e| e.to_string())?;
+    let via = transfer_path(&resp);
 
-    Ok((name, data))
+    let data = read_body_throttled(resp.body_mut(), rate_limit)?;
+
+    Ok((name, data, via))
 }
 
 fn http_fetch_peers(agent: &ureq::Agent, base_url: &str) -> Result<Vec<PeerInfo>, String> {
@@ -889,34 +2996,119 @@ fn http_fetch_peers(agent: &ureq::Agent, base_url: &str) -> Result<Vec<PeerInfo>
         .read_to_string()
         .map_err(|e| e.to_string())?;
 
-    let peers: Vec<PeerInfo> = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+    let peers: Vec<PeerInfo> = parse_entry_list(&body, "peer")?;
     Ok(peers)
 }
 
-/// GET /pull?path=<filepath> — download an arbitrary file from the server's filesystem
+/// GET /received — the desktop's history of received files, newest first
+fn http_fetch_received(agent: &ureq::Agent, base_url: &str) -> Result<Vec<ReceivedFile>, String> {
+    let url = format!("{}/received", base_url);
+    let body = agent
+        .get(&url)
+        .call()
+        .map_err(|e| e.to_string())?
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| e.to_string())?;
+
+    let value: serde_json::Value = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+    Ok(parse_entries(value["files"].clone(), "received file"))
+}
+
+/// GET /pull?path=<filepath>|id=<path_id> — download an arbitrary file from the server's filesystem
 fn http_pull_remote_file(
     agent: &ureq::Agent,
     base_url: &str,
     path: &str,
-) -> Result<(String, Vec<u8>), String> {
+    path_id: Option<&str>,
+    rate_limit: &AtomicU64,
+) -> Result<(String, Vec<u8>, Option<String>), String> {
+    let file = http_pull_conditional(agent, base_url, path, path_id, None, rate_limit)?
+        .ok_or_else(|| "pull request failed: unexpected 304".to_string())?;
+    Ok((file.name, file.data, file.via))
+}
+
+/// A `/pull` body and what its headers said about it.
+struct PulledFile {
+    name: String,
+    data: Vec<u8>,
+    via: Option<String>,
+    /// (modified, size) from the `ETag`, for the pull cache
+    validator: Option<(u64, u64)>,
+}
+
+/// `/pull` ETag for a file with this mtime (Unix seconds) and size.
+fn file_etag(modified: u64, size: u64) -> String {
+    format!("\"{}-{}\"", modified, size)
+}
+
+fn parse_file_etag(etag: &str) -> Option<(u64, u64)> {
+    let (modified, size) = etag.trim_start_matches("W/").trim_matches('"').split_once('-')?;
+    Some((modified.parse().ok()?, size.parse().ok()?))
+}
+
+fn header_str(resp: &ureq::http::Response<ureq::Body>, name: &str) -> Option<String> {
+    resp.headers().get(name).and_then(|v| v.to_str().ok()).map(String::from)
+}
+
+/// The filename in a `Content-Disposition` value: the exact `filename*` when
+/// the server sent one, else the quoted `filename` as older servers wrote it.
+fn disposition_filename(value: &str) -> Option<String> {
+    if let Some(encoded) = value.split("filename*=UTF-8''").nth(1) {
+        let encoded = encoded.split(';').next().unwrap_or_default().trim();
+        let mut bytes = Vec::with_capacity(encoded.len());
+        let mut rest = encoded.as_bytes();
+        while let Some((&byte, tail)) = rest.split_first() {
+            if byte == b'%'
+                && let Some(hex) = tail.get(..2).and_then(|h| std::str::from_utf8(h).ok())
+                && let Ok(decoded) = u8::from_str_radix(hex, 16)
+            {
+                bytes.push(decoded);
+                rest = &tail[2..];
+            } else {
+                bytes.push(byte);
+                rest = tail;
+            }
+        }
+        if let Ok(name) = String::from_utf8(bytes) {
+            return Some(name);
+        }
+    }
+    value
+        .split("filename=\"")
+        .nth(1)
+        .and_then(|s| s.strip_suffix('"'))
+        .map(String::from)
+}
+
+/// Pull a file, or None when `if_none_match` is still the file's ETag (304).
+fn http_pull_conditional(
+    agent: &ureq::Agent,
+    base_url: &str,
+    path: &str,
+    path_id: Option<&str>,
+    if_none_match: Option<&str>,
+    rate_limit: &AtomicU64,
+) -> Result<Option<PulledFile>, String> {
     let url = format!("{}/pull", base_url);
-    let mut resp = agent
-        .get(&url)
-        .query("path", path)
+    let mut req = match path_id {
+        Some(id) => agent.get(&url).query("id", id),
+        None => agent.get(&url).query("path", path),
+    };
+    if let Some(etag) = if_none_match {
+        req = req.header("if-none-match", etag);
+    }
+    let mut resp = req
         .call()
         .map_err(|e| format!("pull request failed: {}", e))?;
+    if resp.status() == 304 {
+        return Ok(None);
+    }
+    let validator = header_str(&resp, "etag").and_then(|etag| parse_file_etag(&etag));
 
     // Try to get filename from Content-Disposition header, fall back to path basename
-    let name = resp
-        .headers()
-        .get("content-disposition")
-        .and_then(|cd| cd.to_str().ok())
-        .and_then(|cd| {
-            cd.split("filename=\"")
-                .nth(1)
-                .and_then(|s| s.strip_suffix('"'))
-        })
-        .map(String::from)
+    let name = header_str(&resp, "content-disposition")
+        .and_then(|cd| disposition_filename(&cd))
         .unwrap_or_else(|| {
             path.rsplit('/')
                 .next()
@@ -924,31 +3116,283 @@ fn http_pull_remote_file(
                 .to_string()
         });
 
+    let via = transfer_path(&resp);
+
+    let data = read_body_throttled(resp.body_mut(), rate_limit)?;
+
+    Ok(Some(PulledFile { name, data, via, validator }))
+}
+
+/// GET /pull-dir?path=<dir>|id=<path_id> — stream a folder as tar and unpack it
+/// under `dest_dir` entry by entry, stopping early once `cancel` is set.
+/// Returns the unpacked folder and the number of entries written.
+/// `/pull-dir` for the folder, or `/pull-glob` for its files matching `pattern`.
+fn pull_dir_request(
+    agent: &ureq::Agent,
+    base_url: &str,
+    path: &str,
+    path_id: Option<&str>,
+    pattern: Option<&str>,
+) -> ureq::RequestBuilder<ureq::typestate::WithoutBody> {
+    match (pattern, path_id) {
+        (Some(pattern), id) => {
+            let req = agent.get(format!("{}/pull-glob", base_url)).query("pattern", pattern);
+            match id {
+                Some(id) => req.query("id", id),
+                None => req.query("dir", path),
+            }
+        }
+        (None, Some(id)) => agent.get(format!("{}/pull-dir", base_url)).query("id", id),
+        (None, None) => agent.get(format!("{}/pull-dir", base_url)).query("path", path),
+    }
+}
+
+/// Unpack the folder archive `req` answers with; `path` names it if the archive is empty.
+/// Also returns the entry count and the server's `x-dir-truncated` flag.
+fn http_pull_dir(
+    req: ureq::RequestBuilder<ureq::typestate::WithoutBody>,
+    path: &str,
+    dest_dir: &str,
+    cancel: &AtomicBool,
+    rate_limit: &AtomicU64,
+    event_tx: &mpsc::Sender<ClientEvent>,
+) -> Result<(String, usize, bool), String> {
+    let mut resp = req
+        .config()
+        .http_status_as_error(false)
+        .build()
+        .call()
+        .map_err(|e| format!("folder pull failed: {}", e))?;
+    if !resp.status().is_success() {
+        let message = resp.body_mut().read_to_string().unwrap_or_default();
+        return Err(if message.is_empty() {
+            format!("folder pull failed: HTTP {}", resp.status())
+        } else {
+            message
+        });
+    }
+    let header = |name: &str| {
+        resp.headers()
+            .get(name)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.parse::<u64>().ok())
+            .unwrap_or(0)
+    };
+    let mut progress = PullDirProgress {
+        entries_total: header("x-dir-entries") as usize,
+        bytes_total: header("x-dir-bytes"),
+        ..Default::default()
+    };
+    let truncated = header("x-dir-truncated") != 0;
+
+    std::fs::create_dir_all(dest_dir).map_err(|e| format!("{}: {}", dest_dir, e))?;
+    let mut archive = tar::Archive::new(Throttled::new(resp.body_mut().as_reader(), rate_limit));
+    let mut root: Option<String> = None;
+    for entry in archive.entries().map_err(|e| e.to_string())? {
+        if cancel.load(Ordering::Relaxed) {
+            break;
+        }
+        let mut entry = entry.map_err(|e| format!("folder pull interrupted: {}", e))?;
+        let name = entry
+            .path()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if root.is_none() {
+            root = Some(name.trim_end_matches('/').to_string());
+        }
+        // `unpack_in` refuses entries that would land outside `dest_dir`
+        entry
+            .unpack_in(dest_dir)
+            .map_err(|e| format!("Failed to save '{}': {}", name, e))?;
+        progress.entries_done += 1;
+        progress.bytes_done += entry.size();
+        progress.current = name;
+        let _ = event_tx.send(ClientEvent::PullDirProgress(progress.clone()));
+    }
+
+    let folder = root.unwrap_or_else(|| path.rsplit('/').next().unwrap_or("folder").to_string());
+    Ok((format!("{}/{}", dest_dir, folder), progress.entries_done, truncated))
+}
+
+/// GET /preview?path=<filepath>|id=<path_id>&max_bytes=<n> — head of a file or a downsized image.
+/// Also returns the full file size when the server reports it (`X-File-Size`).
+fn http_preview_remote_file(
+    agent: &ureq::Agent,
+    base_url: &str,
+    path: &str,
+    path_id: Option<&str>,
+    max_bytes: u64,
+) -> Result<(String, Vec<u8>, Option<u64>), String> {
+    let url = format!("{}/preview", base_url);
+    let req = match path_id {
+        Some(id) => agent.get(&url).query("id", id),
+        None => agent.get(&url).query("path", path),
+    };
+    let mut resp = req
+        .query("max_bytes", max_bytes.to_string())
+        .call()
+        .map_err(|e| format!("preview request failed: {}", e))?;
+
+    let name = path.rsplit('/').next().unwrap_or("file").to_string();
+    let total_size = resp
+        .headers()
+        .get("x-file-size")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse().ok());
+
     let data = resp.body_mut()
         .read_to_vec()
         .map_err(|e| e.to_string())?;
 
-    Ok((name, data))
+    Ok((name, data, total_size))
+}
+
+/// GET /changes?since=<ts> — files under the client's browse start modified since then.
+fn http_fetch_changes(agent: &ureq::Agent, base_url: &str, since: u64) -> Result<ChangeList, String> {
+    let url = format!("{}/changes", base_url);
+    let body = agent
+        .get(&url)
+        .query("since", since.to_string())
+        .call()
+        .map_err(|e| format!("change check failed: {}", e))?
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| e.to_string())?;
+    serde_json::from_str(&body).map_err(|e| e.to_string())
+}
+
+/// GET /stat?path=<path>|id=<path_id> — one entry's details; None on 404.
+fn http_stat(
+    agent: &ureq::Agent,
+    base_url: &str,
+    path: &str,
+    path_id: Option<&str>,
+) -> Result<Option<RemoteFile>, String> {
+    let url = format!("{}/stat", base_url);
+    let req = match path_id {
+        Some(id) => agent.get(&url).query("id", id),
+        None => agent.get(&url).query("path", path),
+    };
+    let mut resp = req
+        .config()
+        .http_status_as_error(false)
+        .build()
+        .call()
+        .map_err(|e| format!("stat failed: {}", e))?;
+    if resp.status() == 404 {
+        return Ok(None);
+    }
+    let body = resp.body_mut().read_to_string().unwrap_or_default();
+    if !resp.status().is_success() {
+        return Err(if body.is_empty() {
+            format!("stat failed: HTTP {}", resp.status())
+        } else {
+            body
+        });
+    }
+    serde_json::from_str(&body).map(Some).map_err(|e| e.to_string())
 }
 
 // ── Sync HTTP helpers ───────────────────────────────────────────────
 
+/// POST /note — the body is the note text
+fn http_send_note(agent: &ureq::Agent, base_url: &str, text: &str) -> Result<(), String> {
+    let url = format!("{}/note", base_url);
+    // The server explains empty or oversized notes in the body
+    let mut resp = agent
+        .post(&url)
+        .config()
+        .http_status_as_error(false)
+        .build()
+        .header("Content-Type", "text/plain; charset=utf-8")
+        .send(text)
+        .map_err(|e| format!("send note failed: {}", e))?;
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        let msg = resp.body_mut().read_to_string().unwrap_or_default();
+        Err(if msg.is_empty() {
+            format!("send note failed: {}", resp.status())
+        } else {
+            msg
+        })
+    }
+}
+
+/// Uploads smaller than this go as they are; gzip saves too little on them.
+const UPLOAD_GZIP_MIN_BYTES: usize = 16 * 1024;
+
+/// Extensions of formats that are compressed already, so gzip would only cost time.
+const COMPRESSED_EXTS: &[&str] = &[
+    "gz", "tgz", "zip", "7z", "rar", "xz", "bz2", "zst", "jpg", "jpeg", "png", "gif", "webp", "heic", "heif",
+    "avif", "mp3", "m4a", "aac", "ogg", "opus", "flac", "mp4", "mov", "m4v", "mkv", "webm", "pdf", "docx",
+    "xlsx", "pptx", "pages", "numbers", "key", "epub", "ipa", "apk",
+];
+
+/// Whether `http_upload_file` would gzip this upload when compression is on.
+fn worth_compressing(local_path: &str, len: usize) -> bool {
+    let ext = std::path::Path::new(local_path)
+        .extension()
+        .map(|e| e.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    len >= UPLOAD_GZIP_MIN_BYTES && !COMPRESSED_EXTS.contains(&ext.as_str())
+}
+
+/// PUT a file to `/sync/upload`. With `gzip`, a body worth compressing is sent
+/// with `Content-Encoding: gzip`; the desktop still reports the unpacked size.
 fn http_upload_file(
     agent: &ureq::Agent,
     base_url: &str,
     local_path: &str,
     remote_dest_path: &str,
+    gzip: bool,
+    rate_limit: &AtomicU64,
 ) -> Result<(), String> {
+    use std::io::Write;
+
     let data = std::fs::read(local_path)
         .map_err(|e| format!("Failed to read '{}': {}", local_path, e))?;
+    let compressed = if gzip && worth_compressing(local_path, data.len()) {
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder
+            .write_all(&data)
+            .and_then(|()| encoder.finish())
+            .map(Some)
+            .map_err(|e| format!("Couldn't compress '{}': {}", local_path, e))?
+    } else {
+        None
+    };
+    let body = compressed.as_deref().unwrap_or(&data);
 
     let url = format!("{}/sync/upload", base_url);
-    agent
+    let mut reader = Throttled::new(body, rate_limit);
+    let mut request = agent
         .put(&url)
         .query("path", remote_dest_path)
-        .send(&data)
+        // Keeps the body length-delimited; a reader body is chunked otherwise
+        .header("Content-Length", body.len());
+    if compressed.is_some() {
+        request = request.header("Content-Encoding", "gzip");
+    }
+    let mut resp = request
+        .send(ureq::SendBody::from_reader(&mut reader))
         .map_err(|e| format!("upload failed: {}", e))?;
 
+    // Older servers answer with an empty 200; only a reported size can be checked
+    let body = resp.body_mut().read_to_string().unwrap_or_default();
+    if body.trim().is_empty() {
+        return Ok(());
+    }
+    let result: UploadResult = serde_json::from_str(&body)
+        .map_err(|e| format!("upload: unexpected response: {}", e))?;
+    if result.bytes != data.len() as u64 {
+        return Err(format!(
+            "upload of '{}' truncated: sent {}, desktop wrote {}",
+            remote_dest_path,
+            format_size(data.len() as u64),
+            format_size(result.bytes)
+        ));
+    }
     Ok(())
 }
 
@@ -957,13 +3401,15 @@ fn http_create_sync_project(
     base_url: &str,
     local_path: &str,
     remote_path: &str,
+    direction: SyncDirection,
 ) -> Result<SyncProject, String> {
     let url = format!("{}/sync/projects", base_url);
     // Note: from the desktop's perspective, local_path is the desktop path (remote_path here)
     // and remote_path is the iOS path (local_path here)
     let body = serde_json::json!({
         "local_path": remote_path,
-        "remote_path": local_path,
+        "remote_path": contract_home(local_path),
+        "direction": direction,
     });
 
     let mut resp = agent
@@ -975,7 +3421,8 @@ fn http_create_sync_project(
     let text = resp.body_mut()
         .read_to_string()
         .map_err(|e| e.to_string())?;
-    let project: SyncProject = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+    let mut project: SyncProject = serde_json::from_str(&text).map_err(|e| e.to_string())?;
+    project.remote_path = expand_home(&project.remote_path);
     Ok(project)
 }
 
@@ -992,7 +3439,10 @@ fn http_fetch_sync_projects(
         .read_to_string()
         .map_err(|e| e.to_string())?;
 
-    let projects: Vec<SyncProject> = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+    let mut projects: Vec<SyncProject> = parse_entry_list(&body, "sync project")?;
+    for project in &mut projects {
+        project.remote_path = expand_home(&project.remote_path);
+    }
     Ok(projects)
 }
 
@@ -1009,9 +3459,115 @@ fn http_delete_sync_project(
     Ok(())
 }
 
-fn http_sync_check(
-    agent: &ureq::Agent,
-    base_url: &str,
+/// `DELETE /sync/projects`; returns how many projects the desktop removed.
+fn http_clear_sync_projects(agent: &ureq::Agent, base_url: &str) -> Result<usize, String> {
+    let url = format!("{}/sync/projects", base_url);
+    let body = agent
+        .delete(&url)
+        .call()
+        .map_err(|e| format!("remove all syncs failed: {}", e))?
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| e.to_string())?;
+    let value: serde_json::Value = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+    Ok(value["deleted"].as_u64().unwrap_or(0) as usize)
+}
+
+fn http_update_sync_project(
+    agent: &ureq::Agent,
+    base_url: &str,
+    id: &str,
+    desktop_path: &str,
+    ios_path: &str,
+) -> Result<(), String> {
+    let url = format!("{}/sync/projects/{}", base_url, id);
+    let body = serde_json::json!({
+        "local_path": desktop_path,
+        "remote_path": contract_home(ios_path),
+    });
+
+    // Read the body on 4xx so the server's validation message reaches the UI
+    let mut resp = agent
+        .put(&url)
+        .config()
+        .http_status_as_error(false)
+        .build()
+        .header("Content-Type", "application/json")
+        .send(&body.to_string())
+        .map_err(|e| format!("update sync project failed: {}", e))?;
+    if resp.status().is_success() {
+        Ok(())
+    } else {
+        let msg = resp.body_mut().read_to_string().unwrap_or_default();
+        Err(if msg.is_empty() {
+            format!("update sync project failed: {}", resp.status())
+        } else {
+            msg
+        })
+    }
+}
+
+fn http_fetch_sync_plan(
+    agent: &ureq::Agent,
+    base_url: &str,
+    id: &str,
+) -> Result<SyncPlan, String> {
+    let url = format!("{}/sync/projects/{}/plan", base_url, id);
+    let body = agent
+        .get(&url)
+        .call()
+        .map_err(|e| format!("sync preview failed: {}", e))?
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| e.to_string())?;
+    let mut plan: SyncPlan = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+    plan.remote_path = expand_home(&plan.remote_path);
+    Ok(plan)
+}
+
+/// The iOS file of a plan, if it changed since the last sync (the poll loop
+/// would upload it).
+fn local_sync_push(plan: &SyncPlan) -> Option<SyncPlanEntry> {
+    if plan.paused || !plan.direction.pushes_from_phone() {
+        return None;
+    }
+    let metadata = std::fs::metadata(&plan.remote_path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    (metadata.is_file() && modified > plan.last_synced).then(|| SyncPlanEntry {
+        path: plan.remote_path.clone(),
+        action: "push".to_string(),
+        size: metadata.len(),
+    })
+}
+
+fn http_sync_status(
+    agent: &ureq::Agent,
+    base_url: &str,
+) -> Result<Vec<SyncProjectStatus>, String> {
+    let url = format!("{}/sync/status", base_url);
+    let body = agent
+        .get(&url)
+        .call()
+        .map_err(|e| e.to_string())?
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| e.to_string())?;
+
+    let mut statuses: Vec<SyncProjectStatus> = parse_entry_list(&body, "sync status")?;
+    for status in &mut statuses {
+        status.project.remote_path = expand_home(&status.project.remote_path);
+    }
+    Ok(statuses)
+}
+
+fn http_sync_check(
+    agent: &ureq::Agent,
+    base_url: &str,
 ) -> Result<Vec<SyncChange>, String> {
     let url = format!("{}/sync/check", base_url);
     let body = agent
@@ -1022,10 +3578,77 @@ fn http_sync_check(
         .read_to_string()
         .map_err(|e| e.to_string())?;
 
-    let changes: Vec<SyncChange> = serde_json::from_str(&body).map_err(|e| e.to_string())?;
+    let mut changes: Vec<SyncChange> = parse_entry_list(&body, "sync change")?;
+    for change in &mut changes {
+        change.remote_path = expand_home(&change.remote_path);
+    }
     Ok(changes)
 }
 
+/// CRC-32 and size of a file on the desktop.
+fn http_sync_hash(agent: &ureq::Agent, base_url: &str, path: &str) -> Result<FileHash, String> {
+    let url = format!("{}/sync/hash", base_url);
+    let body = agent
+        .get(&url)
+        .query("path", path)
+        .call()
+        .map_err(|e| e.to_string())?
+        .body_mut()
+        .read_to_string()
+        .map_err(|e| e.to_string())?;
+    serde_json::from_str(&body).map_err(|e| e.to_string())
+}
+
+/// CRC-32 and size of a local file, read in chunks.
+fn local_file_crc32(path: &str) -> Result<FileHash, String> {
+    use std::io::Read;
+    let mut file = std::fs::File::open(path).map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = file.read(&mut buf).map_err(|e| format!("Failed to read '{}': {}", path, e))?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
+    }
+    Ok(FileHash { size, crc32: hasher.finalize() })
+}
+
+/// Upload a sync project's iOS file. With `verify`, the desktop's copy is
+/// checksummed and a mismatch is uploaded once more before giving up, so a
+/// corrupted write is never acked as synced.
+fn push_sync_file(
+    agent: &ureq::Agent,
+    base_url: &str,
+    local_path: &str,
+    remote_dest_path: &str,
+    verify: bool,
+    gzip: bool,
+    rate_limit: &AtomicU64,
+) -> Result<(), String> {
+    const ATTEMPTS: usize = 2;
+    for attempt in 1..=ATTEMPTS {
+        http_upload_file(agent, base_url, local_path, remote_dest_path, gzip, rate_limit)?;
+        if !verify {
+            return Ok(());
+        }
+        let local = local_file_crc32(local_path)?;
+        let remote = http_sync_hash(agent, base_url, remote_dest_path)
+            .map_err(|e| format!("couldn't verify upload: {}", e))?;
+        if local.crc32 == remote.crc32 && local.size == remote.size {
+            return Ok(());
+        }
+        log::warn!(
+            "[sync] {} didn't verify (attempt {}/{}): local {:08x}/{} bytes, desktop {:08x}/{} bytes",
+            remote_dest_path, attempt, ATTEMPTS, local.crc32, local.size, remote.crc32, remote.size
+        );
+    }
+    Err("checksum mismatch after upload; will retry".to_string())
+}
+
 fn http_sync_ack(
     agent: &ureq::Agent,
     base_url: &str,
@@ -1053,18 +3676,207 @@ fn cached_peers_path(save_dir: &str) -> String {
     }
 }
 
-pub fn load_cached_peers(save_dir: &str) -> Vec<PeerInfo> {
-    let path = cached_peers_path(save_dir);
-    match std::fs::read_to_string(&path) {
-        Ok(data) => serde_json::from_str(&data).unwrap_or_default(),
-        Err(_) => Vec::new(),
+/// On-disk shape of cached_peers.json: peers keyed by server URL. Older builds
+/// wrote a bare array, which is used for every server until it is rewritten.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum PeerCacheFile {
+    PerServer(std::collections::HashMap<String, Vec<PeerInfo>>),
+    Legacy(Vec<PeerInfo>),
+}
+
+fn read_peer_cache(save_dir: &str) -> Option<PeerCacheFile> {
+    let data = std::fs::read_to_string(cached_peers_path(save_dir)).ok()?;
+    serde_json::from_str(&data).ok()
+}
+
+pub fn load_cached_peers(save_dir: &str, server_url: &str) -> Vec<PeerInfo> {
+    match read_peer_cache(save_dir) {
+        Some(PeerCacheFile::PerServer(mut map)) => map.remove(server_url).unwrap_or_default(),
+        Some(PeerCacheFile::Legacy(peers)) => peers,
+        None => Vec::new(),
     }
 }
 
-fn save_cached_peers(save_dir: &str, peers: &[PeerInfo]) {
-    let path = cached_peers_path(save_dir);
-    if let Ok(data) = serde_json::to_string_pretty(peers) {
-        let _ = std::fs::write(&path, data);
+/// One entry per peer id: every `live` peer (an online copy wins over an
+/// offline duplicate), then `cached` peers the live list doesn't have, marked
+/// offline since nothing confirms they're up.
+pub fn merge_peers(cached: Vec<PeerInfo>, live: Vec<PeerInfo>) -> Vec<PeerInfo> {
+    let mut merged: Vec<PeerInfo> = Vec::with_capacity(live.len() + cached.len());
+    for peer in live {
+        match merged.iter_mut().find(|p| p.id == peer.id) {
+            Some(existing) if peer.online && !existing.online => *existing = peer,
+            Some(_) => {}
+            None => merged.push(peer),
+        }
+    }
+    for mut peer in cached {
+        if !merged.iter().any(|p| p.id == peer.id) {
+            peer.online = false;
+            merged.push(peer);
+        }
+    }
+    merged
+}
+
+fn save_cached_peers(save_dir: &str, server_url: &str, peers: &[PeerInfo]) {
+    let mut map = match read_peer_cache(save_dir) {
+        Some(PeerCacheFile::PerServer(map)) => map,
+        _ => std::collections::HashMap::new(),
+    };
+    map.insert(server_url.to_string(), peers.to_vec());
+    if let Ok(data) = serde_json::to_string_pretty(&map) {
+        let _ = std::fs::write(cached_peers_path(save_dir), data);
+    }
+}
+
+// ── Pull cache (iOS side) ───────────────────────────────────────────
+
+/// Most cached pulls remembered; the least recently used are forgotten first.
+const PULL_CACHE_MAX_ENTRIES: usize = 500;
+
+/// A pulled file as it was saved, with the server copy's mtime and size then.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedPull {
+    server_url: String,
+    remote_path: String,
+    local_path: String,
+    remote_modified: u64,
+    size: u64,
+    /// Local mtime right after saving; a later edit on the phone invalidates the entry
+    local_modified: u64,
+    last_used: u64,
+}
+
+/// Index of earlier pulls (`pull_cache.json`). The cached copies are the saved
+/// files themselves, so a hit costs no extra storage. Only the UI thread touches it.
+#[derive(Debug, Default)]
+pub struct PullCache {
+    entries: Vec<CachedPull>,
+}
+
+fn pull_cache_path(save_dir: &str) -> String {
+    // Stored alongside cached_peers.json in Documents
+    match std::path::Path::new(save_dir).parent() {
+        Some(parent) => format!("{}/pull_cache.json", parent.to_string_lossy()),
+        None => format!("{}/pull_cache.json", save_dir),
+    }
+}
+
+fn file_mtime_secs(path: &str) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs()
+}
+
+impl PullCache {
+    pub fn load(save_dir: &str) -> Self {
+        let entries = std::fs::read_to_string(pull_cache_path(save_dir))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        Self { entries }
+    }
+
+    /// Write via a temp file so a crash mid-write can't leave a torn index.
+    fn save(&self, save_dir: &str) {
+        let path = pull_cache_path(save_dir);
+        let tmp = format!("{}.tmp", path);
+        if let Ok(data) = serde_json::to_string(&self.entries)
+            && std::fs::write(&tmp, data).is_ok()
+        {
+            let _ = std::fs::rename(&tmp, &path);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn for_server<'a>(&'a self, server_url: &'a str) -> impl Iterator<Item = &'a CachedPull> + 'a {
+        self.entries.iter().filter(move |e| e.server_url == server_url)
+    }
+
+    fn position(&self, server_url: &str, remote_path: &str) -> Option<usize> {
+        self.entries
+            .iter()
+            .position(|e| e.server_url == server_url && e.remote_path == remote_path)
+    }
+
+    /// Server (mtime, size) of a cached pull whose saved copy at `local_path` is
+    /// still untouched.
+    fn lookup(&self, server_url: &str, remote_path: &str, local_path: &str) -> Option<(u64, u64)> {
+        let entry = &self.entries[self.position(server_url, remote_path)?];
+        let intact = entry.local_path == local_path
+            && std::fs::metadata(local_path).is_ok_and(|m| m.len() == entry.size)
+            && file_mtime_secs(local_path) == Some(entry.local_modified);
+        intact.then_some((entry.remote_modified, entry.size))
+    }
+
+    /// Mark a cache hit as used. Returns the saved copy's path if it is still valid.
+    fn touch(&mut self, server_url: &str, remote_path: &str, local_path: &str) -> Option<String> {
+        self.lookup(server_url, remote_path, local_path)?;
+        let idx = self.position(server_url, remote_path)?;
+        self.entries[idx].last_used = unix_now();
+        Some(self.entries[idx].local_path.clone())
+    }
+
+    fn record(&mut self, server_url: &str, remote_path: &str, local_path: &str, remote_modified: u64, size: u64) {
+        if let Some(idx) = self.position(server_url, remote_path) {
+            self.entries.remove(idx);
+        }
+        let Some(local_modified) = file_mtime_secs(local_path) else {
+            return;
+        };
+        self.entries.push(CachedPull {
+            server_url: server_url.to_string(),
+            remote_path: remote_path.to_string(),
+            local_path: local_path.to_string(),
+            remote_modified,
+            size,
+            local_modified,
+            last_used: unix_now(),
+        });
+        if self.entries.len() > PULL_CACHE_MAX_ENTRIES {
+            self.entries.sort_by_key(|e| std::cmp::Reverse(e.last_used));
+            self.entries.truncate(PULL_CACHE_MAX_ENTRIES);
+        }
+    }
+}
+
+// ── Saved servers (iOS side) ────────────────────────────────────────
+
+fn saved_servers_path(save_dir: &str) -> String {
+    // Stored alongside cached_peers.json in Documents
+    match std::path::Path::new(save_dir).parent() {
+        Some(parent) => format!("{}/saved_servers.json", parent.to_string_lossy()),
+        None => format!("{}/saved_servers.json", save_dir),
+    }
+}
+
+pub fn load_saved_servers(save_dir: &str) -> Vec<String> {
+    std::fs::read_to_string(saved_servers_path(save_dir))
+        .ok()
+        .and_then(|data| serde_json::from_str(&data).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_saved_servers(save_dir: &str, urls: &[String]) {
+    if let Ok(data) = serde_json::to_string_pretty(urls) {
+        let _ = std::fs::write(saved_servers_path(save_dir), data);
     }
 }
 
@@ -1102,6 +3914,239 @@ fn remove_local_sync_project(id: &str) {
     let _ = id; // will be handled via the renderer's save flow
 }
 
+/// True when a desktop path and an iOS path are the same location or one
+/// contains the other (compared lexically). Mirrors the server's check so the
+/// user gets the error before a round-trip.
+pub fn sync_paths_overlap(a: &str, b: &str) -> bool {
+    let (a, b) = (normalize_remote_path(a), normalize_remote_path(b));
+    let a = std::path::Path::new(&a);
+    let b = std::path::Path::new(&b);
+    a.starts_with(b) || b.starts_with(a)
+}
+
+/// The app container; iOS points `HOME` at it.
+fn ios_home() -> Option<std::path::PathBuf> {
+    std::env::var_os("HOME").map(std::path::PathBuf::from)
+}
+
+/// iOS paths reach the desktop as `~/...` because the container path changes
+/// on reinstall or restore; this turns them back into real paths.
+/// `$HOME/...` is accepted too.
+pub fn expand_home(path: &str) -> String {
+    let rest = match path {
+        "~" | "$HOME" => "",
+        _ => match path.strip_prefix("~/").or_else(|| path.strip_prefix("$HOME/")) {
+            Some(rest) => rest,
+            None => return path.to_string(),
+        },
+    };
+    match ios_home() {
+        Some(home) => home.join(rest).to_string_lossy().trim_end_matches('/').to_string(),
+        None => path.to_string(),
+    }
+}
+
+/// Inverse of `expand_home`: paths inside the container become `~/...`.
+pub fn contract_home(path: &str) -> String {
+    let Some(home) = ios_home().filter(|h| h.parent().is_some()) else {
+        return path.to_string();
+    };
+    match std::path::Path::new(path).strip_prefix(&home) {
+        Ok(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Ok(rest) => format!("~/{}", rest.to_string_lossy()),
+        Err(_) => path.to_string(),
+    }
+}
+
+/// Normalize a desktop path before browsing: collapse `//`, drop `.` and any
+/// trailing `/`, and apply `..` (never above the root). Root stays `/`.
+pub fn normalize_remote_path(path: &str) -> String {
+    if is_windows_path(path) {
+        return normalize_windows_path(path);
+    }
+    let absolute = path.starts_with('/');
+    let mut parts: Vec<&str> = Vec::new();
+    for part in path.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => {
+                if parts.last().is_some_and(|p| *p != "..") {
+                    parts.pop();
+                } else if !absolute {
+                    parts.push("..");
+                }
+            }
+            other => parts.push(other),
+        }
+    }
+    let joined = parts.join("/");
+    match (absolute, joined.is_empty()) {
+        (true, _) => format!("/{}", joined),
+        (false, true) => ".".to_string(),
+        (false, false) => joined,
+    }
+}
+
+/// Parent of a desktop path, after normalizing. The root (or a drive's root)
+/// is its own parent.
+pub fn parent_remote_path(path: &str) -> String {
+    let windows = is_windows_path(path);
+    let path = normalize_remote_path(path);
+    let sep = if windows { '\\' } else { '/' };
+    match path.rfind(sep) {
+        Some(0) => sep.to_string(),
+        Some(pos) if windows && path[..pos].ends_with(':') => format!("{}\\", &path[..pos]),
+        Some(pos) => path[..pos].to_string(),
+        None => ".".to_string(),
+    }
+}
+
+/// `parent_remote_path`, but never above `root` (the folder the desktop limits
+/// this client to). Paths outside `root` go back to it.
+pub fn parent_remote_path_within(path: &str, root: Option<&str>) -> String {
+    let parent = parent_remote_path(path);
+    let Some(root) = root else {
+        return parent;
+    };
+    let root = normalize_remote_path(root);
+    let sep = if is_windows_path(&root) { '\\' } else { '/' };
+    let inside = parent == root
+        || parent
+            .strip_prefix(root.trim_end_matches(sep))
+            .is_some_and(|rest| rest.starts_with(sep));
+    if inside { parent } else { root }
+}
+
+/// `C:\...`, `C:/...` or a backslash-only path, as a Windows desktop reports them.
+fn is_windows_path(path: &str) -> bool {
+    let bytes = path.as_bytes();
+    (bytes.len() >= 2 && bytes[0].is_ascii_alphabetic() && bytes[1] == b':')
+        || (path.contains('\\') && !path.contains('/'))
+}
+
+/// `normalize_remote_path` for Windows paths: either separator is accepted,
+/// `\` is written, and the drive letter is kept (`C:` becomes `C:\`).
+fn normalize_windows_path(path: &str) -> String {
+    let (drive, rest) = match path.as_bytes() {
+        [letter, b':', ..] if letter.is_ascii_alphabetic() => path.split_at(2),
+        _ => ("", path),
+    };
+    let absolute = rest.starts_with(['\\', '/']) || (!drive.is_empty() && rest.is_empty());
+    let mut parts: Vec<&str> = Vec::new();
+    for part in rest.split(['\\', '/']) {
+        match part {
+            "" | "." => {}
+            ".." => {
+                if parts.last().is_some_and(|p| *p != "..") {
+                    parts.pop();
+                } else if !absolute {
+                    parts.push("..");
+                }
+            }
+            other => parts.push(other),
+        }
+    }
+    let joined = parts.join("\\");
+    match (absolute, joined.is_empty()) {
+        (true, _) => format!("{}\\{}", drive, joined),
+        (false, true) => ".".to_string(),
+        (false, false) => format!("{}{}", drive, joined),
+    }
+}
+
+/// `name` inside the desktop folder `dir`, joined with the desktop's separator.
+pub fn join_remote_path(dir: &str, name: &str, sep: char) -> String {
+    format!("{}{}{}", dir.trim_end_matches(sep), sep, name)
+}
+
+/// Last component of a desktop path, splitting on either separator.
+pub fn remote_file_name(path: &str) -> &str {
+    path.trim_end_matches(['/', '\\']).rsplit(['/', '\\']).next().unwrap_or(path)
+}
+
+/// Where a pulled file is saved: flat in `save_dir`, or (with `keep_structure`)
+/// under the remote file's folders, e.g. `/home/me/a/b.txt` → `<save_dir>/home/me/a/b.txt`.
+/// `filename` is the server-reported name and replaces the last path component.
+pub fn pull_save_path(save_dir: &str, remote_path: &str, filename: &str, keep_structure: bool) -> String {
+    let filename = sanitize_filename(filename);
+    if !keep_structure {
+        return format!("{}/{}", save_dir, filename);
+    }
+    // Normalizing an absolute path drops every `..`, so nothing escapes `save_dir`
+    let parent = if is_windows_path(remote_path) {
+        parent_remote_path(remote_path)
+    } else {
+        parent_remote_path(&format!("/{}", remote_path.trim_start_matches('/')))
+    };
+    let folders: Vec<String> = parent
+        .split(['/', '\\'])
+        .filter(|s| !s.is_empty())
+        .map(sanitize_filename)
+        .collect();
+    if folders.is_empty() {
+        format!("{}/{}", save_dir, filename)
+    } else {
+        format!("{}/{}/{}", save_dir, folders.join("/"), filename)
+    }
+}
+
+/// A desktop file name made safe to write in the app sandbox: path separators,
+/// `:` (shown as `/` by Files) and control characters become `_`, and `.`/`..`
+/// or an empty name become `_`. Status lines keep showing the original name.
+pub fn sanitize_filename(name: &str) -> String {
+    let clean: String = name
+        .chars()
+        .map(|c| if matches!(c, '/' | '\\' | ':') || c.is_control() { '_' } else { c })
+        .collect();
+    match clean.as_str() {
+        "" | "." | ".." => "_".to_string(),
+        _ => clean,
+    }
+}
+
+fn log_sanitized(filename: &str) {
+    let clean = sanitize_filename(filename);
+    if clean != filename {
+        log::info!("[client] saving {:?} as {:?}", filename, clean);
+    }
+}
+
+// ── Local directory summary (iOS side) ─────────────────────────────
+
+/// Walk a local directory for `DirInfo`. Bounded tighter than the server's walk
+/// so a huge folder still gets a (truncated) answer quickly.
+pub fn local_dir_info(root: &str) -> DirInfo {
+    const TIME_LIMIT: Duration = Duration::from_millis(500);
+    const ENTRY_LIMIT: u64 = 50_000;
+
+    let started = Instant::now();
+    let mut info = DirInfo::default();
+    let mut stack = vec![std::path::PathBuf::from(root)];
+
+    while let Some(dir) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if info.file_count + info.dir_count >= ENTRY_LIMIT || started.elapsed() >= TIME_LIMIT {
+                info.truncated = true;
+                return info;
+            }
+            let Ok(metadata) = entry.path().symlink_metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                info.dir_count += 1;
+                stack.push(entry.path());
+            } else if metadata.is_file() {
+                info.file_count += 1;
+                info.total_size += metadata.len();
+            }
+        }
+    }
+    info
+}
+
 // ── Utility ─────────────────────────────────────────────────────────────
 
 pub fn format_size(bytes: u64) -> String {
@@ -1165,3 +4210,359 @@ pub fn format_timestamp(ts: u64) -> String {
         format!("{} days ago", diff / 86400)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn path_segments_escape_reserved_characters() {
+        assert_eq!(encode_path_segment("notes.txt"), "notes.txt");
+        assert_eq!(encode_path_segment("a b#1?.txt"), "a%20b%231%3F.txt");
+        assert_eq!(encode_path_segment("../x/y"), "..%2Fx%2Fy");
+        assert_eq!(encode_path_segment("café"), "caf%C3%A9");
+    }
+
+    #[test]
+    fn disposition_filename_prefers_the_exact_name() {
+        let current = "attachment; filename=\"say _hi_; _.txt\"; filename*=UTF-8''say%20%22hi%22%3B%20%C3%A9.txt";
+        assert_eq!(disposition_filename(current).as_deref(), Some("say \"hi\"; é.txt"));
+        assert_eq!(disposition_filename("inline; filename=\"notes.txt\"").as_deref(), Some("notes.txt"));
+        assert_eq!(disposition_filename("attachment"), None);
+    }
+
+    #[test]
+    fn normalize_remote_path_cleans_typed_paths() {
+        assert_eq!(normalize_remote_path("/home//user"), "/home/user");
+        assert_eq!(normalize_remote_path("/home/user/"), "/home/user");
+        assert_eq!(normalize_remote_path("/home/./user/docs/.."), "/home/user");
+        assert_eq!(normalize_remote_path("//"), "/");
+        assert_eq!(normalize_remote_path("/.."), "/");
+        assert_eq!(normalize_remote_path("/"), "/");
+        assert_eq!(normalize_remote_path("docs/../../x"), "../x");
+    }
+
+    #[test]
+    fn sanitize_filename_replaces_separators_and_control_chars() {
+        assert_eq!(sanitize_filename("report.pdf"), "report.pdf");
+        assert_eq!(sanitize_filename("a/b\\c:d"), "a_b_c_d");
+        assert_eq!(sanitize_filename("bell\u{7}\nname"), "bell__name");
+        assert_eq!(sanitize_filename(".."), "_");
+        assert_eq!(sanitize_filename(""), "_");
+        assert_eq!(
+            pull_save_path("/save", "/home/u/we\u{1}ird/x:y", "x:y", true),
+            "/save/home/u/we_ird/x_y"
+        );
+    }
+
+    fn peer(id: &str, hostname: &str, online: bool) -> PeerInfo {
+        PeerInfo {
+            id: id.to_string(),
+            hostname: hostname.to_string(),
+            dns_name: String::new(),
+            ip_addresses: Vec::new(),
+            online,
+            os: "linux".to_string(),
+        }
+    }
+
+    #[test]
+    fn merge_peers_prefers_live_entries_and_dedupes_by_id() {
+        let cached = vec![peer("a", "old-name", false), peer("b", "laptop", true), peer("c", "tv", false)];
+        let live = vec![
+            peer("a", "desk", false),
+            // Same node reported twice (e.g. once per interface); the online copy wins
+            peer("a", "desk", true),
+            peer("c", "tv", true),
+        ];
+        let merged = merge_peers(cached, live);
+
+        let ids: Vec<&str> = merged.iter().map(|p| p.id.as_str()).collect();
+        assert_eq!(ids, ["a", "c", "b"]);
+        assert_eq!(merged[0].hostname, "desk");
+        assert!(merged[0].online);
+        assert!(merged[1].online, "cached offline copy must not shadow the live one");
+        // Only known from the cache, so not claimed online
+        assert!(!merged[2].online);
+    }
+
+    #[test]
+    fn parent_remote_path_handles_trailing_slashes_and_root() {
+        assert_eq!(parent_remote_path("/home/user/"), "/home");
+        assert_eq!(parent_remote_path("/home//user"), "/home");
+        assert_eq!(parent_remote_path("/home"), "/");
+        assert_eq!(parent_remote_path("/"), "/");
+    }
+
+    #[test]
+    fn parent_remote_path_within_stops_at_the_browse_root() {
+        // No root: the filesystem root is as far as it goes
+        assert_eq!(parent_remote_path_within("/", None), "/");
+        assert_eq!(parent_remote_path_within("/home", None), "/");
+        assert_eq!(parent_remote_path_within("/home/me/a/b/c", None), "/home/me/a/b");
+
+        let root = Some("/home/me/");
+        assert_eq!(parent_remote_path_within("/home/me/a/b/c", root), "/home/me/a/b");
+        assert_eq!(parent_remote_path_within("/home/me/docs", root), "/home/me");
+        assert_eq!(parent_remote_path_within("/home/me", root), "/home/me");
+        // Outside the root, or only sharing its name as a prefix
+        assert_eq!(parent_remote_path_within("/home", root), "/home/me");
+        assert_eq!(parent_remote_path_within("/", root), "/home/me");
+        assert_eq!(parent_remote_path_within("/home/meow/x", root), "/home/me");
+
+        assert_eq!(parent_remote_path_within("/srv/x", Some("/")), "/srv");
+        assert_eq!(parent_remote_path_within("C:\\Users\\me\\a", Some("C:\\Users\\me")), "C:\\Users\\me");
+        assert_eq!(parent_remote_path_within("C:\\Users\\me", Some("C:\\Users\\me")), "C:\\Users\\me");
+    }
+
+    #[test]
+    fn windows_paths_keep_their_drive_and_separator() {
+        assert_eq!(normalize_remote_path("C:/Users//me\\docs\\"), "C:\\Users\\me\\docs");
+        assert_eq!(normalize_remote_path("C:"), "C:\\");
+        assert_eq!(parent_remote_path("C:\\Users\\me"), "C:\\Users");
+        assert_eq!(parent_remote_path("C:\\Users"), "C:\\");
+        assert_eq!(parent_remote_path("C:\\"), "C:\\");
+        assert_eq!(join_remote_path("C:\\", "Users", '\\'), "C:\\Users");
+        assert_eq!(join_remote_path("/", "home", '/'), "/home");
+        assert_eq!(remote_file_name("C:\\Users\\me\\a.txt"), "a.txt");
+        assert_eq!(
+            pull_save_path("/save", "C:\\Users\\me\\a.txt", "a.txt", true),
+            "/save/C_/Users/me/a.txt"
+        );
+    }
+
+    /// Serve one canned HTTP response on a loopback port; returns the base URL.
+    fn serve_once(head: String, body: Vec<u8>) -> String {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 4096];
+            let _ = stream.read(&mut request);
+            stream.write_all(head.as_bytes()).unwrap();
+            stream.write_all(&body).unwrap();
+        });
+        base_url
+    }
+
+    #[test]
+    fn gzip_encoded_browse_is_decoded_once() {
+        use std::io::Write;
+        let json = br#"[{"name":"notes.txt","is_dir":false,"size":5,"modified":1700000000}]"#;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(json).unwrap();
+        let gzipped = encoder.finish().unwrap();
+        let head = format!(
+            "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-encoding: gzip\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+            gzipped.len()
+        );
+        let base_url = serve_once(head, gzipped);
+
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(5)))
+            .build()
+            .into();
+        let (files, _) = http_fetch_browse(&agent, &base_url, Some("/home"), None).unwrap().unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].name, "notes.txt");
+        assert_eq!(files[0].size, 5);
+    }
+
+    #[test]
+    fn not_modified_browse_keeps_the_cached_listing() {
+        let head = "HTTP/1.1 304 Not Modified\r\netag: \"abc\"\r\nconnection: close\r\n\r\n".to_string();
+        let base_url = serve_once(head, Vec::new());
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(5)))
+            .build()
+            .into();
+        assert!(http_fetch_browse(&agent, &base_url, None, Some("\"abc\"")).unwrap().is_none());
+        assert_eq!(parse_file_etag(&file_etag(1_700_000_000, 42)), Some((1_700_000_000, 42)));
+    }
+
+    #[test]
+    fn pull_cache_hits_only_while_the_saved_copy_is_untouched() {
+        let dir = std::env::temp_dir().join(format!("td-pull-cache-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let saved = dir.join("notes.txt");
+        std::fs::write(&saved, b"hello").unwrap();
+        let saved = saved.to_str().unwrap();
+        let mut cache = PullCache::default();
+        cache.record("http://desk", "/home/me/notes.txt", saved, 1_700_000_000, 5);
+
+        // Hit: same server, remote path and untouched local copy
+        assert_eq!(cache.lookup("http://desk", "/home/me/notes.txt", saved), Some((1_700_000_000, 5)));
+        assert_eq!(cache.touch("http://desk", "/home/me/notes.txt", saved).as_deref(), Some(saved));
+
+        // Miss: another file, another server, or the copy saved somewhere else
+        assert_eq!(cache.lookup("http://desk", "/home/me/other.txt", saved), None);
+        assert_eq!(cache.lookup("http://laptop", "/home/me/notes.txt", saved), None);
+        assert_eq!(cache.lookup("http://desk", "/home/me/notes.txt", "/elsewhere/notes.txt"), None);
+
+        // Out of date: the saved copy was edited on the phone
+        std::fs::write(saved, b"hello, edited").unwrap();
+        assert_eq!(cache.lookup("http://desk", "/home/me/notes.txt", saved), None);
+        assert_eq!(cache.touch("http://desk", "/home/me/notes.txt", saved), None);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn uncached_pulls_skip_the_file_info_lookup() {
+        // One response only: a /sync/file-info request first would use it up
+        let head = "HTTP/1.1 200 OK\r\netag: \"1700000000-5\"\r\ncontent-length: 5\r\nconnection: close\r\n\r\n".to_string();
+        let base_url = serve_once(head, b"hello".to_vec());
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(5)))
+            .build()
+            .into();
+        let limit = AtomicU64::new(0);
+        let event = pull_file(&agent, &base_url, "/home/me/notes.txt".to_string(), None, None, false, &limit);
+        let ClientEvent::PullComplete { data, remote_info, .. } = event else {
+            panic!("expected the pull to complete");
+        };
+        assert_eq!(data, b"hello");
+        assert_eq!(remote_info, Some((1_700_000_000, 5)));
+    }
+
+    #[test]
+    fn throttled_download_keeps_to_the_rate_limit() {
+        let body = vec![7u8; 64 * 1024];
+        let head = format!(
+            "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+            body.len()
+        );
+        let base_url = serve_once(head, body.clone());
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(5)))
+            .build()
+            .into();
+        // 64 KiB at 256 KiB/s takes a quarter of a second
+        let limit = AtomicU64::new(256 * 1024);
+        let started = Instant::now();
+        let (data, _) = http_download_file(&agent, &base_url, "x.bin", &limit).unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(200), "{:?}", started.elapsed());
+        assert_eq!(data, body);
+    }
+
+    /// Like `serve_once`, but the body goes out in `chunks` pieces `pause` apart,
+    /// the way a slow link delivers it.
+    fn serve_slowly(head: String, body: Vec<u8>, chunks: usize, pause: Duration) -> String {
+        use std::io::{Read, Write};
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 4096];
+            let _ = stream.read(&mut request);
+            stream.write_all(head.as_bytes()).unwrap();
+            for chunk in body.chunks(body.len().div_ceil(chunks)) {
+                std::thread::sleep(pause);
+                // The API agent hangs up early
+                if stream.write_all(chunk).is_err() {
+                    return;
+                }
+            }
+        });
+        base_url
+    }
+
+    #[test]
+    fn speed_limited_transfers_outlast_the_request_timeout() {
+        let body = vec![3u8; 64 * 1024];
+        let head = format!(
+            "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+            body.len()
+        );
+        // About half a second at 128 KiB/s (and as the link delivers it), past a 200 ms deadline
+        let limit = AtomicU64::new(128 * 1024);
+        let timeout = Duration::from_millis(200);
+        let pause = Duration::from_millis(60);
+
+        let api: ureq::Agent = ureq::Agent::config_builder().timeout_global(Some(timeout)).build().into();
+        let base_url = serve_slowly(head.clone(), body.clone(), 8, pause);
+        assert!(http_download_file(&api, &base_url, "x.bin", &limit).is_err());
+
+        let transfers = build_server_agent(transfer_config(timeout), None, &TlsOptions::default());
+        let base_url = serve_slowly(head, body.clone(), 8, pause);
+        let started = Instant::now();
+        let (data, _) = http_download_file(&transfers, &base_url, "x.bin", &limit).unwrap();
+        assert!(started.elapsed() > timeout, "{:?}", started.elapsed());
+        assert_eq!(data, body);
+    }
+
+    #[test]
+    fn large_text_uploads_are_gzipped() {
+        use std::io::{BufRead, BufReader, Read, Write};
+
+        let dir = std::env::temp_dir().join(format!("td-gzip-upload-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let text = "notes notes notes\n".repeat(2000);
+        let local = dir.join("notes.txt");
+        std::fs::write(&local, &text).unwrap();
+        assert!(!worth_compressing("photo.JPG", text.len()));
+        assert!(!worth_compressing("notes.txt", 100));
+
+        // Capture the request, unpack it and answer with the unpacked size like the desktop
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_url = format!("http://{}", listener.local_addr().unwrap());
+        let server = std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut headers = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                headers.push(line.trim().to_ascii_lowercase());
+            }
+            let len: usize = headers
+                .iter()
+                .find_map(|h| h.strip_prefix("content-length:"))
+                .unwrap()
+                .trim()
+                .parse()
+                .unwrap();
+            let mut body = vec![0; len];
+            reader.read_exact(&mut body).unwrap();
+            let mut unpacked = Vec::new();
+            flate2::read::GzDecoder::new(&body[..]).read_to_end(&mut unpacked).unwrap();
+            let reply = format!("{{\"path\":\"x\",\"bytes\":{}}}", unpacked.len());
+            let mut stream = stream;
+            write!(stream, "HTTP/1.1 200 OK\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}", reply.len(), reply)
+                .unwrap();
+            (headers, len, unpacked)
+        });
+
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .timeout_global(Some(Duration::from_secs(5)))
+            .build()
+            .into();
+        let limit = AtomicU64::new(0);
+        http_upload_file(&agent, &base_url, local.to_str().unwrap(), "~/notes.txt", true, &limit).unwrap();
+        let (headers, sent, unpacked) = server.join().unwrap();
+        assert!(headers.iter().any(|h| h == "content-encoding: gzip"));
+        assert!(sent < text.len() / 10);
+        assert_eq!(unpacked, text.as_bytes());
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn dropped_clients_stop_their_poll_threads() {
+        // Nothing listens here, so every poll fails fast
+        let port = std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port();
+        let url = format!("http://127.0.0.1:{}", port);
+        let mut clients: Vec<_> = (0..16).map(|_| TailscaleClient::new(&url)).collect();
+        assert!(POLL_THREADS.load(Ordering::SeqCst) >= 16);
+
+        assert!(clients[0].shutdown(Duration::from_secs(5)));
+        drop(clients);
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while POLL_THREADS.load(Ordering::SeqCst) > 0 {
+            assert!(Instant::now() < deadline, "{} poll threads still running", POLL_THREADS.load(Ordering::SeqCst));
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+}
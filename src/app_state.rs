@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::mpsc::Receiver;
 use serde::{Deserialize, Serialize};
@@ -16,6 +17,9 @@ pub struct TailscalePeer {
     pub is_self: bool,
     pub os: String,
     pub can_receive_files: bool,
+    /// How traffic to this peer flows right now: `direct`, `relay <region>`, or
+    /// `None` when tailscaled has no path yet (idle peer, or ourselves)
+    pub connection: Option<String>,
 }
 
 /// A file received via Taildrop
@@ -25,8 +29,7 @@ pub struct ReceivedFile {
     /// Filesystem path from IncomingFiles FinalPath (if available)
     pub path: Option<PathBuf>,
     pub size: u64,
-    pub from_peer: String,
-    pub received_at: std::time::Instant,
+    /// Saved with "Save As…" / "Save All"; `path` then points at the saved copy
     pub saved: bool,
 }
 
@@ -34,9 +37,29 @@ pub struct ReceivedFile {
 #[derive(Debug, Clone)]
 pub struct TransferringFile {
     pub name: String,
+    /// `DeclaredSize`; 0 while the sender hasn't declared one
     pub size: u64,
     pub transferred: u64,
     pub done: bool,
+    /// Fraction the progress bar last drew, eased toward `fraction()`
+    pub shown: f32,
+}
+
+impl TransferringFile {
+    /// Received share of the declared size, clamped to 0..=1; `None` if the size is unknown.
+    pub fn fraction(&self) -> Option<f32> {
+        (self.size > 0).then(|| (self.transferred as f64 / self.size as f64).clamp(0.0, 1.0) as f32)
+    }
+
+    /// Fold in a newer notification for the same file. A size that shows up late
+    /// is taken, but a later notification without one doesn't forget it.
+    pub fn update(&mut self, newer: TransferringFile) {
+        if newer.size > 0 {
+            self.size = newer.size;
+        }
+        self.transferred = newer.transferred;
+        self.done = newer.done;
+    }
 }
 
 /// Messages sent from the background Tailscale watcher to the UI
@@ -50,6 +73,15 @@ pub enum TailscaleEvent {
     FileTransferring(TransferringFile),
     /// Connection status changed
     ConnectionStatus(bool, String),
+    /// Outgoing sends: how many are running and how many wait for a slot
+    SendQueue { active: usize, queued: usize },
+    /// `/pull` and `/download` streams the HTTP server is serving right now
+    ServerTransfers(Vec<super::status::TransferInfo>),
+    /// Text notes posted to `/note` since the last report; `history` for the
+    /// stored ones sent at startup
+    NotesReceived { notes: Vec<super::status::Note>, history: bool },
+    /// Outcome of a `SaveReceivedFile`: where it was written, or why it wasn't
+    ReceivedFileSaved { name: String, result: Result<PathBuf, String> },
     /// Error occurred
     Error(String),
 }
@@ -67,6 +99,8 @@ pub enum TailscaleCommand {
     SaveReceivedFile { name: String, src_path: Option<PathBuf>, dest: PathBuf },
     /// Delete a received file from the Taildrop inbox
     DeleteReceivedFile(String),
+    /// Stop a transfer the HTTP server is serving
+    CancelTransfer(u64),
 }
 
 pub struct TailscaleDriveApp {
@@ -78,28 +112,52 @@ pub struct TailscaleDriveApp {
     pub connected: bool,
     pub status_message: String,
 
-    // Tailnet clients
+    // Tailnet clients, sorted online-first then by hostname when they arrive
     pub peers: Vec<TailscalePeer>,
+    /// Index into `peers` by node ID
+    peer_index: HashMap<String, usize>,
     pub selected_peer: Option<String>,
 
     // Files
     pub received_files: Vec<ReceivedFile>,
     pub transferring_files: Vec<TransferringFile>,
     pub files_to_send: Vec<PathBuf>,
+    /// Sends handed to the backend: (running, waiting for a slot)
+    pub send_queue: (usize, usize),
+    /// Files being served to other devices over `/pull` / `/download`
+    pub served_transfers: Vec<super::status::TransferInfo>,
+    /// Text notes from other devices, oldest first
+    pub notes: Vec<super::status::Note>,
+    /// Last "Save All to…" batch, kept until dismissed
+    pub save_all: Option<SaveAllBatch>,
 
     // UI state
     pub search_query: String,
     pub show_offline_peers: bool,
     pub selected_received_file: Option<usize>,
+    pub received_filter: ReceivedFilter,
 
     // File explorer state
     pub current_directory: PathBuf,
     pub path_edit_text: String,
     pub directory_contents: Vec<DirectoryEntry>,
     pub selected_directory_item: Option<usize>,
+    pub file_filter: FileFilter,
 
     // Logs
     pub show_logs: bool,
+    /// "Pair phone" window with the join QR code
+    pub show_join_code: bool,
+}
+
+/// Sort peers the way the device list shows them and index them by node ID.
+fn sort_and_index_peers(peers: &mut [TailscalePeer]) -> HashMap<String, usize> {
+    peers.sort_by(|a, b| match (a.online, b.online) {
+        (true, false) => std::cmp::Ordering::Less,
+        (false, true) => std::cmp::Ordering::Greater,
+        _ => a.hostname.to_lowercase().cmp(&b.hostname.to_lowercase()),
+    });
+    peers.iter().enumerate().map(|(i, p)| (p.id.clone(), i)).collect()
 }
 
 /// A tracked file sync between this device and a remote device.
@@ -116,6 +174,132 @@ pub struct SyncProject {
     /// DNS name of the device (e.g. "manjaro-work.taile483f.ts.net")
     #[serde(default)]
     pub device_dns: String,
+    #[serde(default)]
+    pub direction: SyncDirection,
+}
+
+/// Which side's edits a sync project carries over; one-way projects are mirrors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncDirection {
+    #[default]
+    Bidirectional,
+    DesktopToPhone,
+    PhoneToDesktop,
+}
+
+impl SyncDirection {
+    /// Desktop edits are sent to the phone.
+    pub fn pulls_to_phone(self) -> bool {
+        self != Self::PhoneToDesktop
+    }
+
+    /// Phone edits are written to the desktop.
+    pub fn pushes_from_phone(self) -> bool {
+        self != Self::DesktopToPhone
+    }
+}
+
+/// eframe storage key for the file browser's extension filter.
+pub const FILE_FILTER_KEY: &str = "file_filter";
+pub const RECEIVED_FILTER_KEY: &str = "received_filter";
+
+pub const IMAGE_EXTENSIONS: &[&str] = &[
+    "jpg", "jpeg", "png", "gif", "webp", "bmp", "heic", "heif", "tif", "tiff", "svg", "ico",
+];
+pub const CODE_EXTENSIONS: &[&str] = &[
+    "rs", "py", "js", "ts", "tsx", "jsx", "c", "cpp", "h", "hpp", "cc", "java", "kt", "swift",
+    "m", "mm", "go", "rb", "php", "sh", "bash", "zsh", "toml", "json", "yml", "yaml", "xml",
+    "html", "htm", "css", "scss", "sql",
+];
+pub const DOCUMENT_EXTENSIONS: &[&str] = &[
+    "pdf", "doc", "docx", "odt", "rtf", "txt", "md", "markdown", "xls", "xlsx", "csv", "ppt",
+    "pptx", "pages", "numbers", "key",
+];
+
+/// Lowercased extension of a filename, or empty if it has none.
+pub fn file_extension(name: &str) -> String {
+    name.rsplit_once('.')
+        .map(|(_, ext)| ext.to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Which received files the Received Files panel lists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum ReceivedFilter {
+    #[default]
+    All,
+    Unsaved,
+}
+
+impl ReceivedFilter {
+    pub fn matches(self, file: &ReceivedFile) -> bool {
+        match self {
+            ReceivedFilter::All => true,
+            ReceivedFilter::Unsaved => !file.saved,
+        }
+    }
+}
+
+/// Which files the file browser shows. Directories are always shown.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum FileFilter {
+    #[default]
+    All,
+    Images,
+    Code,
+    Documents,
+    /// Comma/space separated extensions, e.g. "rs, toml"
+    Custom(String),
+}
+
+impl FileFilter {
+    pub fn matches(&self, name: &str) -> bool {
+        let ext = file_extension(name);
+        match self {
+            FileFilter::All => true,
+            FileFilter::Images => IMAGE_EXTENSIONS.contains(&ext.as_str()),
+            FileFilter::Code => CODE_EXTENSIONS.contains(&ext.as_str()),
+            FileFilter::Documents => DOCUMENT_EXTENSIONS.contains(&ext.as_str()),
+            FileFilter::Custom(list) => {
+                let mut wanted = list
+                    .split([',', ' '])
+                    .map(|e| e.trim().trim_start_matches('.'))
+                    .filter(|e| !e.is_empty())
+                    .peekable();
+                wanted.peek().is_none() || wanted.any(|e| e.eq_ignore_ascii_case(&ext))
+            }
+        }
+    }
+}
+
+/// Received files being saved into one folder by "Save All to…"
+#[derive(Debug)]
+pub struct SaveAllBatch {
+    pub dest_dir: PathBuf,
+    /// Names still waiting on the backend
+    pub pending: Vec<String>,
+    /// Per-file outcome, in completion order
+    pub results: Vec<(String, Result<PathBuf, String>)>,
+}
+
+impl SaveAllBatch {
+    pub fn total(&self) -> usize {
+        self.pending.len() + self.results.len()
+    }
+
+    pub fn failed(&self) -> usize {
+        self.failed_names().len()
+    }
+
+    /// Files that couldn't be saved, in completion order
+    pub fn failed_names(&self) -> Vec<&str> {
+        self.results
+            .iter()
+            .filter(|(_, r)| r.is_err())
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -127,10 +311,8 @@ pub struct DirectoryEntry {
 }
 
 impl TailscaleDriveApp {
-    pub fn new(_cc: &eframe::CreationContext) -> Self {
-        let home = std::env::var("HOME")
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| PathBuf::from("/"));
+    pub fn new(cc: &eframe::CreationContext) -> Self {
+        let home = crate::status::home_dir();
 
         let mut app = Self {
             event_rx: None,
@@ -138,18 +320,32 @@ impl TailscaleDriveApp {
             connected: false,
             status_message: "Initializing...".to_string(),
             peers: Vec::new(),
+            peer_index: HashMap::new(),
             selected_peer: None,
             received_files: Vec::new(),
             transferring_files: Vec::new(),
             files_to_send: Vec::new(),
+            send_queue: (0, 0),
+            served_transfers: Vec::new(),
+            notes: Vec::new(),
+            save_all: None,
             search_query: String::new(),
             show_offline_peers: false,
             selected_received_file: None,
+            received_filter: cc
+                .storage
+                .and_then(|storage| eframe::get_value(storage, RECEIVED_FILTER_KEY))
+                .unwrap_or_default(),
             current_directory: home.clone(),
             path_edit_text: home.to_string_lossy().to_string(),
             directory_contents: Vec::new(),
             selected_directory_item: None,
+            file_filter: cc
+                .storage
+                .and_then(|storage| eframe::get_value(storage, FILE_FILTER_KEY))
+                .unwrap_or_default(),
             show_logs: false,
+            show_join_code: false,
         };
 
         app.refresh_directory();
@@ -204,6 +400,39 @@ impl TailscaleDriveApp {
         }
     }
 
+    /// Add a file to the send queue unless it's already queued (compared by
+    /// canonical path, so `./a` and `/home/me/a` count as the same file).
+    pub fn queue_file_to_send(&mut self, path: PathBuf) {
+        let canonical = |p: &PathBuf| std::fs::canonicalize(p).unwrap_or_else(|_| p.clone());
+        let key = canonical(&path);
+        if !self.files_to_send.iter().any(|queued| canonical(queued) == key) {
+            self.files_to_send.push(path);
+        }
+    }
+
+    /// Save every unsaved received file into `dir`; each is marked saved once the
+    /// backend has written it and cleared it from the inbox. Names already
+    /// taken in `dir` get a ` (n)` suffix instead of being replaced.
+    pub fn save_all_received(&mut self, dir: PathBuf) {
+        let files: Vec<ReceivedFile> = self.received_files.iter().filter(|f| !f.saved).cloned().collect();
+        self.selected_received_file = None;
+        let mut taken = Vec::new();
+        for file in &files {
+            let dest = unused_path(&dir, &file.name, &taken);
+            taken.push(dest.clone());
+            self.send_command(TailscaleCommand::SaveReceivedFile {
+                name: file.name.clone(),
+                src_path: file.path.clone(),
+                dest,
+            });
+        }
+        self.save_all = Some(SaveAllBatch {
+            dest_dir: dir,
+            pending: files.into_iter().map(|f| f.name).collect(),
+            results: Vec::new(),
+        });
+    }
+
     pub fn navigate_to(&mut self, path: PathBuf) {
         if path.is_dir() {
             self.current_directory = path;
@@ -224,12 +453,14 @@ impl TailscaleDriveApp {
         if let Some(rx) = &self.event_rx {
             while let Ok(event) = rx.try_recv() {
                 match event {
-                    TailscaleEvent::PeersUpdated(peers) => {
+                    TailscaleEvent::PeersUpdated(mut peers) => {
+                        self.peer_index = sort_and_index_peers(&mut peers);
                         self.peers = peers;
                     }
                     TailscaleEvent::FileReceived(file) => {
-                        // Check if file already exists in list
-                        if !self.received_files.iter().any(|f| f.name == file.name) {
+                        // Check if file already exists in list; a saved entry has left
+                        // the inbox, so the same name arriving again is a new file
+                        if !self.received_files.iter().any(|f| f.name == file.name && !f.saved) {
                             self.received_files.push(file);
                         }
                     }
@@ -240,7 +471,7 @@ impl TailscaleDriveApp {
                             .iter_mut()
                             .find(|f| f.name == transfer.name)
                         {
-                            *existing = transfer;
+                            existing.update(transfer);
                         } else {
                             self.transferring_files.push(transfer);
                         }
@@ -251,6 +482,52 @@ impl TailscaleDriveApp {
                         self.connected = connected;
                         self.status_message = message;
                     }
+                    TailscaleEvent::SendQueue { active, queued } => {
+                        self.send_queue = (active, queued);
+                    }
+                    TailscaleEvent::ServerTransfers(transfers) => {
+                        self.served_transfers = transfers;
+                    }
+                    TailscaleEvent::NotesReceived { notes, history } => {
+                        if !history && let Some(last) = notes.last() {
+                            self.status_message = format!("📝 Note from {}", last.from);
+                        }
+                        self.notes.extend(notes);
+                    }
+                    TailscaleEvent::ReceivedFileSaved { name, result } => {
+                        if let Ok(dest) = &result
+                            && let Some(file) = self.received_files.iter_mut().find(|f| f.name == name && !f.saved)
+                        {
+                            file.saved = true;
+                            file.path = Some(dest.clone());
+                        }
+                        if let Err(e) = &result {
+                            let err = format!("Failed to save file '{}': {}", name, e);
+                            log::error!("Error: {}", err);
+                            self.status_message = format!("Error: {}", err);
+                        }
+                        if let Some(batch) = self.save_all.as_mut()
+                            && let Some(pos) = batch.pending.iter().position(|n| *n == name)
+                        {
+                            batch.pending.remove(pos);
+                            batch.results.push((name, result));
+                            if batch.pending.is_empty() {
+                                // Replaces any per-file error above, so it names the failures itself
+                                let failed = batch.failed_names();
+                                self.status_message = if failed.is_empty() {
+                                    format!("Saved {} files to {}", batch.results.len(), batch.dest_dir.display())
+                                } else {
+                                    format!(
+                                        "Saved {} of {} files to {}; couldn't save {}",
+                                        batch.results.len() - failed.len(),
+                                        batch.results.len(),
+                                        batch.dest_dir.display(),
+                                        failed.join(", ")
+                                    )
+                                };
+                            }
+                        }
+                    }
                     TailscaleEvent::Error(err) => {
                         log::error!("Error: {}", err);
                         self.status_message = format!("Error: {}", err);
@@ -260,6 +537,10 @@ impl TailscaleDriveApp {
         }
     }
 
+    pub fn peer(&self, id: &str) -> Option<&TailscalePeer> {
+        self.peer_index.get(id).and_then(|&i| self.peers.get(i))
+    }
+
     pub fn send_command(&self, cmd: TailscaleCommand) {
         if let Some(tx) = &self.command_tx {
             let _ = tx.send(cmd);
@@ -288,4 +569,21 @@ impl TailscaleDriveApp {
             })
             .collect()
     }
-}
\ No newline at end of file
+}
+
+/// `dir/name`, or `dir/stem (n).ext` for the first `n` that's neither on disk
+/// nor in `taken`.
+fn unused_path(dir: &std::path::Path, name: &str, taken: &[PathBuf]) -> PathBuf {
+    let free = |p: &PathBuf| !p.exists() && !taken.contains(p);
+    let first = dir.join(name);
+    if free(&first) {
+        return first;
+    }
+    let path = std::path::Path::new(name);
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_else(|| name.to_string());
+    let ext = path.extension().map(|e| format!(".{}", e.to_string_lossy())).unwrap_or_default();
+    (1..)
+        .map(|n| dir.join(format!("{} ({}){}", stem, n, ext)))
+        .find(free)
+        .unwrap()
+}
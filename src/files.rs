@@ -109,6 +109,17 @@ pub async fn delete_received_file(name: &str) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Env var that turns on raw IPN bus message logging (at debug level).
+/// Off by default: the bus is chatty on busy tailnets and lines include filenames.
+pub const LOG_IPN_BUS_ENV: &str = "TAILSCALE_DRIVE_LOG_IPN_BUS";
+
+/// How often the watcher summarizes bus traffic instead of logging each message.
+const IPN_BUS_SUMMARY_INTERVAL: std::time::Duration = std::time::Duration::from_secs(60);
+
+pub fn ipn_bus_logging_enabled() -> bool {
+    std::env::var(LOG_IPN_BUS_ENV).is_ok_and(|v| !v.is_empty() && v != "0")
+}
+
 pub async fn watch_files(
     event_tx: Sender<TailscaleEvent>,
     received_state: Arc<Mutex<ReceivedState>>,
@@ -123,6 +134,10 @@ pub async fn watch_files(
     let res = client.request(req).await?;
     let mut body_stream = res.into_body();
     let mut buffer = String::new();
+    let log_raw = ipn_bus_logging_enabled();
+    // (notifications, ones carrying file activity) since `summary_since`
+    let mut counts = (0u64, 0u64);
+    let mut summary_since = std::time::Instant::now();
 
     // This loop must stay fast — NO blocking API calls here.
     // Tailscaled drops notifications if we don't read quickly enough.
@@ -139,11 +154,27 @@ pub async fn watch_files(
                     continue;
                 }
 
-                log::warn!("RAW IPN BUS MESSAGE: {line}");
+                if log_raw {
+                    log::debug!("RAW IPN BUS MESSAGE: {line}");
+                }
+                counts.0 += 1;
+                if summary_since.elapsed() >= IPN_BUS_SUMMARY_INTERVAL {
+                    log::debug!(
+                        "IPN bus: {} notifications ({} with file activity) in the last {}s",
+                        counts.0,
+                        counts.1,
+                        summary_since.elapsed().as_secs()
+                    );
+                    counts = (0, 0);
+                    summary_since = std::time::Instant::now();
+                }
 
                 if let Ok(event) =
                     serde_json::from_str::<super::tailscale::IpnBusNotification>(&line)
                 {
+                    if event.incoming_files.is_some() || event.files_waiting.is_some() {
+                        counts.1 += 1;
+                    }
                     // Handle IncomingFiles — real-time transfer progress & completion.
                     if let Some(incoming) = event.incoming_files {
                         for file in incoming {
@@ -158,6 +189,7 @@ pub async fn watch_files(
                                 {
                                     let mut state = received_state.lock().unwrap();
                                     state.last_file = Some(file.name.clone());
+                                    state.note_file(&file.name, None);
                                     if let Some(ref p) = path {
                                         state.file_paths.insert(file.name.clone(), p.clone());
                                     }
@@ -168,8 +200,6 @@ pub async fn watch_files(
                                         name: file.name.clone(),
                                         path,
                                         size: file.size as u64,
-                                        from_peer: "Unknown".to_string(),
-                                        received_at: std::time::Instant::now(),
                                         saved: false,
                                     },
                                 ));
@@ -178,9 +208,11 @@ pub async fn watch_files(
                                 let _ = event_tx.send(TailscaleEvent::FileTransferring(
                                     TransferringFile {
                                         name: file.name.clone(),
-                                        size: file.size as u64,
-                                        transferred: file.received.unwrap_or(0) as u64,
+                                        // Unknown sizes come through as 0 or -1
+                                        size: file.size.max(0) as u64,
+                                        transferred: file.received.unwrap_or(0).max(0) as u64,
                                         done: false,
+                                        shown: 0.0,
                                     },
                                 ));
                             }
@@ -191,13 +223,15 @@ pub async fn watch_files(
                     if let Some(map) = event.files_waiting {
                         for (sender_id, files) in map {
                             for file in files {
+                                received_state
+                                    .lock()
+                                    .unwrap()
+                                    .note_file(&file.name, Some(&sender_id));
                                 let _ = event_tx.send(TailscaleEvent::FileReceived(
                                     ReceivedFile {
                                         name: file.name.clone(),
                                         path: None,
                                         size: file.size as u64,
-                                        from_peer: sender_id.clone(),
-                                        received_at: std::time::Instant::now(),
                                         saved: false,
                                     },
                                 ));
@@ -0,0 +1,144 @@
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use log::LevelFilter;
+use simplelog::{ColorChoice, CombinedLogger, Config, SharedLogger, TermLogger, TerminalMode, WriteLogger};
+
+/// Set to a file path to also write logs there (rotated, see `RotatingFile`).
+pub const LOG_FILE_ENV: &str = "TAILSCALE_DRIVE_LOG_FILE";
+
+/// `error`, `warn`, `info` (default), `debug` or `trace`.
+pub const LOG_LEVEL_ENV: &str = "TAILSCALE_DRIVE_LOG_LEVEL";
+
+/// A log file is rotated once it grows past this size.
+const LOG_ROTATE_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Rotated files kept next to the live one (`<path>.1` is the newest).
+const LOG_ROTATE_KEEP: usize = 3;
+
+/// Install the global logger: the in-app log window always, plus a log file
+/// when `TAILSCALE_DRIVE_LOG_FILE` is set, otherwise the terminal.
+pub fn init() {
+    let level = std::env::var(LOG_LEVEL_ENV)
+        .ok()
+        .and_then(|v| v.trim().parse::<LevelFilter>().ok())
+        .unwrap_or(LevelFilter::Info);
+    // Raw IPN bus logging is debug-level, so raise the filter when it's asked for
+    let level = if crate::files::ipn_bus_logging_enabled() {
+        level.max(LevelFilter::Debug)
+    } else {
+        level
+    };
+
+    let egui = egui_logger::builder().max_level(level).build();
+    let mut loggers: Vec<Box<dyn SharedLogger>> = vec![Box::new(EguiShared { inner: egui, level })];
+
+    let file = std::env::var_os(LOG_FILE_ENV).map(PathBuf::from);
+    let mut file_error = None;
+    match file.as_ref().map(|path| RotatingFile::open(path.clone())) {
+        Some(Ok(file)) => loggers.push(WriteLogger::new(level, Config::default(), file)),
+        other => {
+            if let Some(Err(e)) = other {
+                file_error = Some(e);
+            }
+            loggers.push(TermLogger::new(
+                level,
+                Config::default(),
+                TerminalMode::Mixed,
+                ColorChoice::Auto,
+            ));
+        }
+    }
+    CombinedLogger::init(loggers).unwrap();
+
+    if let (Some(path), Some(e)) = (file, file_error) {
+        log::error!("Couldn't open log file {}: {}", path.display(), e);
+    }
+}
+
+/// `egui_logger` only implements `log::Log`; this lets it sit in a `CombinedLogger`.
+struct EguiShared {
+    inner: egui_logger::EguiLogger,
+    level: LevelFilter,
+}
+
+impl log::Log for EguiShared {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.inner.log(record)
+    }
+
+    fn flush(&self) {
+        self.inner.flush()
+    }
+}
+
+impl SharedLogger for EguiShared {
+    fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    fn config(&self) -> Option<&Config> {
+        None
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn log::Log> {
+        Box::new(*self)
+    }
+}
+
+/// Append-only log file that renames itself to `<path>.1` (shifting older
+/// ones up to `<path>.LOG_ROTATE_KEEP`) once it passes `LOG_ROTATE_BYTES`.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf) -> io::Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+        Ok(Self { path, file, written })
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", n));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        for n in (1..LOG_ROTATE_KEEP).rev() {
+            let from = self.rotated(n);
+            if from.exists() {
+                std::fs::rename(&from, self.rotated(n + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, self.rotated(1))?;
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.written > 0 && self.written + buf.len() as u64 > LOG_ROTATE_BYTES {
+            // Keep logging into the current file if rotation fails
+            let _ = self.rotate();
+        }
+        let n = self.file.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
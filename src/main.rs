@@ -3,6 +3,7 @@ use tokio::sync::mpsc as tokio_mpsc;
 
 mod app_state;
 mod files;
+mod logging;
 mod status;
 mod tailscale;
 mod ui;
@@ -10,10 +11,7 @@ mod ui;
 use app_state::{TailscaleCommand, TailscaleEvent};
 
 fn main() -> eframe::Result<()> {
-    egui_logger::builder()
-    .max_level(log::LevelFilter::Info)
-    .init()
-    .unwrap();
+    logging::init();
 
     // Create channels for communication between UI and background task
     let (event_tx, event_rx) = mpsc::channel::<TailscaleEvent>();
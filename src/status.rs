@@ -1,18 +1,24 @@
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use axum::{
     Json, Router,
-    body::{Body, Bytes},
-    extract::{DefaultBodyLimit, Path, Query, State},
-    http::{StatusCode, header},
+    body::Body,
+    extract::{ConnectInfo, DefaultBodyLimit, Extension, Path, Query, Request, State, rejection::StringRejection},
+    http::{HeaderMap, HeaderValue, StatusCode, header},
+    middleware::{self, Next},
     response::Response,
     routing::{delete, get, post, put},
 };
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 use serde::{Deserialize as SerdeDeserialize, Serialize};
-use tokio_util::io::ReaderStream;
+use tokio::io::{AsyncRead, AsyncReadExt, ReadBuf};
+use tokio_util::io::{ReaderStream, SyncIoBridge};
 
 // --- Shared State ---
 
@@ -26,6 +32,119 @@ pub struct SentFileInfo {
     pub sending: bool,
 }
 
+/// Sender and arrival time for a file in the Taildrop inbox.
+#[derive(Debug, Clone, Default)]
+pub struct ReceivedMeta {
+    /// Peer ID of the sender, or empty if tailscaled didn't say
+    pub from_peer: String,
+    /// Unix time the file was first seen
+    pub received_at: u64,
+}
+
+/// Records kept in `received.json`; the oldest fall off past this.
+const RECEIVED_INDEX_KEPT: usize = 1000;
+
+/// A Taildrop file this desktop received, remembered across restarts so
+/// `/download/{name}` can still serve the saved copy once the inbox is clean.
+#[derive(Debug, Clone, Serialize, SerdeDeserialize)]
+pub struct ReceivedRecord {
+    pub name: String,
+    pub size: u64,
+    /// Peer ID of the sender, or empty if tailscaled didn't say
+    #[serde(default)]
+    pub from_peer: String,
+    pub received_at: u64,
+    /// Where the desktop user saved it, if they did
+    #[serde(default)]
+    pub saved_path: Option<PathBuf>,
+}
+
+/// Persistent history of received files, oldest first.
+#[derive(Default)]
+pub struct ReceivedIndex {
+    pub records: Vec<ReceivedRecord>,
+}
+
+impl ReceivedIndex {
+    fn path(root: &std::path::Path) -> PathBuf {
+        root.join(".config").join("tailscale-drive").join("received.json")
+    }
+
+    pub fn load(root: &std::path::Path) -> Self {
+        let records = std::fs::read_to_string(Self::path(root))
+            .ok()
+            .and_then(|data| serde_json::from_str(&data).ok())
+            .unwrap_or_default();
+        Self { records }
+    }
+
+    pub fn save(&self, root: &std::path::Path) {
+        let path = Self::path(root);
+        if let Some(parent) = path.parent() {
+            let _ = std::fs::create_dir_all(parent);
+        }
+        if let Ok(data) = serde_json::to_string_pretty(&self.records) {
+            let _ = std::fs::write(&path, data);
+        }
+    }
+
+    /// Record a file seen in the inbox. Returns true if the index changed and
+    /// should be saved. A saved record only stands for the same file when
+    /// size and arrival time match too (its inbox copy couldn't be deleted);
+    /// otherwise seeing the name again means another file with that name.
+    pub fn note(&mut self, name: &str, size: u64, from_peer: &str, received_at: u64) -> bool {
+        if let Some(record) = self.records.iter_mut().rev().find(|r| r.name == name) {
+            if record.saved_path.is_some() && record.size == size && record.received_at == received_at {
+                return false;
+            }
+            if record.saved_path.is_none() {
+                let changed = record.size != size || (record.from_peer.is_empty() && !from_peer.is_empty());
+                record.size = size;
+                if record.from_peer.is_empty() {
+                    record.from_peer = from_peer.to_string();
+                }
+                return changed;
+            }
+        }
+        self.records.push(ReceivedRecord {
+            name: name.to_string(),
+            size,
+            from_peer: from_peer.to_string(),
+            received_at,
+            saved_path: None,
+        });
+        if self.records.len() > RECEIVED_INDEX_KEPT {
+            self.records.remove(0);
+        }
+        true
+    }
+
+    /// Remember where the latest file called `name` was saved.
+    pub fn note_saved(&mut self, name: &str, size: u64, path: PathBuf) {
+        if !self.records.iter().any(|r| r.name == name && r.saved_path.is_none()) {
+            self.note(name, size, "", unix_timestamp());
+        }
+        if let Some(record) = self
+            .records
+            .iter_mut()
+            .rev()
+            .find(|r| r.name == name && r.saved_path.is_none())
+        {
+            record.size = size;
+            record.saved_path = Some(path);
+        }
+    }
+
+    /// The newest saved copy of `name` that's still on disk.
+    pub fn saved_copy(&self, name: &str) -> Option<PathBuf> {
+        self.records
+            .iter()
+            .rev()
+            .filter(|r| r.name == name)
+            .find_map(|r| r.saved_path.clone().filter(|p| p.is_file()))
+    }
+}
+
 /// Tracks received files and their FinalPaths for the download endpoint.
 #[derive(Default)]
 pub struct ReceivedState {
@@ -33,8 +152,214 @@ pub struct ReceivedState {
     pub last_file: Option<String>,
     /// Maps filename → FinalPath on disk (from IncomingFiles)
     pub file_paths: HashMap<String, PathBuf>,
+    /// Maps filename → sender/arrival metadata, for `/files`
+    pub file_meta: HashMap<String, ReceivedMeta>,
+    /// Distinct Taildrop files seen since startup, for `/metrics`
+    pub total_received: u64,
+}
+
+impl ReceivedState {
+    /// Remember when (and from whom, if known) a file showed up. The first
+    /// sighting keeps its timestamp; a sender learned later fills in a blank one.
+    pub fn note_file(&mut self, name: &str, from_peer: Option<&str>) {
+        if !self.file_meta.contains_key(name) {
+            self.total_received += 1;
+        }
+        let meta = self
+            .file_meta
+            .entry(name.to_string())
+            .or_insert_with(|| ReceivedMeta {
+                from_peer: String::new(),
+                received_at: unix_timestamp(),
+            });
+        if let Some(peer) = from_peer
+            && meta.from_peer.is_empty()
+        {
+            meta.from_peer = peer.to_string();
+        }
+    }
+
+    /// Drop everything known about a file once it has left the inbox.
+    pub fn forget_file(&mut self, name: &str) {
+        self.file_paths.remove(name);
+        self.file_meta.remove(name);
+        if self.last_file.as_deref() == Some(name) {
+            self.last_file = None;
+        }
+    }
+}
+
+/// Counters for `GET /metrics`, since startup.
+#[derive(Default)]
+pub struct Metrics {
+    /// Taildrop sends that completed
+    pub taildrop_files_sent: AtomicU64,
+    /// `/pull` and `/download` streams started
+    pub http_files_sent: AtomicU64,
+    /// `/upload` and `/sync/upload` bodies written
+    pub http_files_received: AtomicU64,
+    /// Taildrop sends plus bytes streamed by `/pull` and `/download`
+    pub bytes_sent: AtomicU64,
+    /// Upload bytes written
+    pub bytes_received: AtomicU64,
+}
+
+impl Metrics {
+    fn record_upload(&self, bytes: u64) {
+        self.http_files_received.fetch_add(1, Ordering::Relaxed);
+        self.bytes_received.fetch_add(bytes, Ordering::Relaxed);
+    }
+}
+
+/// A `/pull` or `/download` stream currently being served.
+pub struct ActiveTransfer {
+    path: String,
+    client: String,
+    total_bytes: u64,
+    started: u64,
+    bytes_sent: Arc<AtomicU64>,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Snapshot of an `ActiveTransfer`, as returned by `GET /transfers`.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferInfo {
+    pub id: u64,
+    pub path: String,
+    /// Hostname of the requesting peer, or its address if it isn't in the peer list
+    pub client: String,
+    pub bytes_sent: u64,
+    pub total_bytes: u64,
+    pub started: u64,
+}
+
+/// In-flight transfers by id. Entries remove themselves when their stream is dropped.
+#[derive(Default)]
+pub struct Transfers {
+    next_id: u64,
+    active: HashMap<u64, ActiveTransfer>,
+}
+
+impl Transfers {
+    pub fn list(&self) -> Vec<TransferInfo> {
+        let mut list: Vec<TransferInfo> = self
+            .active
+            .iter()
+            .map(|(&id, t)| TransferInfo {
+                id,
+                path: t.path.clone(),
+                client: t.client.clone(),
+                bytes_sent: t.bytes_sent.load(Ordering::Relaxed),
+                total_bytes: t.total_bytes,
+                started: t.started,
+            })
+            .collect();
+        list.sort_by_key(|t| t.id);
+        list
+    }
+
+    /// Ask a transfer to stop; it fails on its next read. False if the id is unknown.
+    pub fn cancel(&self, id: u64) -> bool {
+        match self.active.get(&id) {
+            Some(t) => {
+                t.cancelled.store(true, Ordering::Relaxed);
+                true
+            }
+            None => false,
+        }
+    }
 }
 
+/// Reader under a tracked transfer: counts bytes handed to the body, errors
+/// once cancelled, and unregisters the transfer when dropped (done, failed,
+/// or the client went away).
+struct TrackedReader<R> {
+    inner: R,
+    id: u64,
+    bytes_sent: Arc<AtomicU64>,
+    cancelled: Arc<AtomicBool>,
+    transfers: Arc<Mutex<Transfers>>,
+    metrics: Arc<Metrics>,
+}
+
+impl<R: AsyncRead + Unpin> AsyncRead for TrackedReader<R> {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<std::io::Result<()>> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Poll::Ready(Err(std::io::Error::other("transfer cancelled on the desktop")));
+        }
+        let before = buf.filled().len();
+        let poll = Pin::new(&mut self.inner).poll_read(cx, buf);
+        if let Poll::Ready(Ok(())) = poll {
+            let read = (buf.filled().len() - before) as u64;
+            self.bytes_sent.fetch_add(read, Ordering::Relaxed);
+            self.metrics.bytes_sent.fetch_add(read, Ordering::Relaxed);
+        }
+        poll
+    }
+}
+
+impl<R> Drop for TrackedReader<R> {
+    fn drop(&mut self) {
+        self.transfers.lock().unwrap().active.remove(&self.id);
+    }
+}
+
+/// Register a transfer of `file` and return a body that streams it.
+fn tracked_file_body<R: AsyncRead + Unpin + Send + 'static>(
+    state: &AppState,
+    file: R,
+    path: &std::path::Path,
+    client: String,
+    total_bytes: u64,
+) -> Body {
+    state.metrics.http_files_sent.fetch_add(1, Ordering::Relaxed);
+    let bytes_sent = Arc::new(AtomicU64::new(0));
+    let cancelled = Arc::new(AtomicBool::new(false));
+    let id = {
+        let mut transfers = state.transfers.lock().unwrap();
+        transfers.next_id += 1;
+        let id = transfers.next_id;
+        transfers.active.insert(
+            id,
+            ActiveTransfer {
+                path: path.to_string_lossy().to_string(),
+                client,
+                total_bytes,
+                started: unix_timestamp(),
+                bytes_sent: bytes_sent.clone(),
+                cancelled: cancelled.clone(),
+            },
+        );
+        id
+    };
+    Body::from_stream(ReaderStream::new(TrackedReader {
+        inner: file,
+        id,
+        bytes_sent,
+        cancelled,
+        transfers: state.transfers.clone(),
+        metrics: state.metrics.clone(),
+    }))
+}
+
+/// Recursive size summary of a directory, as returned by `/dirinfo`.
+#[derive(Debug, Clone, Serialize)]
+pub struct DirInfo {
+    pub total_size: u64,
+    pub file_count: u64,
+    pub dir_count: u64,
+    /// Walk stopped early at the time or entry limit, or skipped folders past the
+    /// depth limit; totals are a lower bound
+    pub truncated: bool,
+}
+
+/// Parsed `.tsdriveignore` and the mtime of the file it was parsed from.
+pub type BrowseIgnore = (SystemTime, Arc<Gitignore>);
+
 /// Combined shared state for the HTTP server and backend.
 #[derive(Clone)]
 pub struct AppState {
@@ -42,49 +367,281 @@ pub struct AppState {
     pub received: Arc<Mutex<ReceivedState>>,
     pub peers: Arc<Mutex<Vec<crate::app_state::TailscalePeer>>>,
     pub sync_projects: Arc<Mutex<Vec<crate::app_state::SyncProject>>>,
+    /// Newest change the file watcher has seen per sync project id (Unix seconds)
+    pub sync_changes: Arc<Mutex<HashMap<String, u64>>>,
+    /// Recent pushes and acks per sync project id, oldest first (not persisted)
+    pub sync_history: Arc<Mutex<HashMap<String, std::collections::VecDeque<SyncHistoryEntry>>>>,
+    /// Recent `/dirinfo` results, keyed by directory
+    pub dir_info_cache: Arc<Mutex<HashMap<PathBuf, (Instant, DirInfo)>>>,
+    /// Parsed `.tsdriveignore` per directory, with the file's mtime when parsed
+    pub browse_ignore_cache: Arc<Mutex<HashMap<PathBuf, BrowseIgnore>>>,
+    /// `/pull` and `/download` streams in flight
+    pub transfers: Arc<Mutex<Transfers>>,
+    pub metrics: Arc<Metrics>,
+    /// Text notes received over `POST /note`
+    pub notes: Arc<Mutex<Notes>>,
+    /// Held while a note is appended to the notes file, so ids stay in file order
+    pub notes_write: Arc<tokio::sync::Mutex<()>>,
+    /// Every received file and where it was saved, for `/received`
+    pub received_index: Arc<Mutex<ReceivedIndex>>,
+    /// Home directory: the default for `/browse`, the base for `/upload`, and
+    /// where `.config/tailscale-drive` keeps sync projects and notes
+    pub root: PathBuf,
+    /// Per-client roots from `client_roots.json`; empty means every client sees `root`
+    pub client_roots: Arc<Vec<ClientRoot>>,
+    pub walk_limits: WalkLimits,
 }
 
 pub fn new_app_state() -> AppState {
-    let projects = load_sync_projects();
+    new_app_state_in(home_dir())
+}
+
+/// The user's home directory (`$HOME`, or the profile folder on Windows).
+pub fn home_dir() -> PathBuf {
+    std::env::home_dir().unwrap_or_else(std::env::temp_dir)
+}
+
+/// App state rooted at `root` instead of `$HOME`.
+pub fn new_app_state_in(root: PathBuf) -> AppState {
+    let projects = load_sync_projects(&root);
     AppState {
         last_sent: Arc::new(Mutex::new(None)),
         received: Arc::new(Mutex::new(ReceivedState::default())),
         peers: Arc::new(Mutex::new(Vec::new())),
         sync_projects: Arc::new(Mutex::new(projects)),
+        sync_changes: Arc::new(Mutex::new(HashMap::new())),
+        sync_history: Arc::new(Mutex::new(HashMap::new())),
+        dir_info_cache: Arc::new(Mutex::new(HashMap::new())),
+        browse_ignore_cache: Arc::new(Mutex::new(HashMap::new())),
+        transfers: Arc::new(Mutex::new(Transfers::default())),
+        metrics: Arc::new(Metrics::default()),
+        notes: Arc::new(Mutex::new(load_notes(&root))),
+        notes_write: Arc::new(tokio::sync::Mutex::new(())),
+        received_index: Arc::new(Mutex::new(ReceivedIndex::load(&root))),
+        client_roots: Arc::new(load_client_roots(&root)),
+        walk_limits: WalkLimits::from_env(),
+        root,
     }
 }
 
 // --- Sync project persistence ---
 
-fn sync_projects_path() -> PathBuf {
-    let home = std::env::var("HOME").unwrap_or_else(|_| "/tmp".to_string());
-    PathBuf::from(home)
-        .join(".config")
+fn sync_projects_path(root: &std::path::Path) -> PathBuf {
+    root.join(".config")
         .join("tailscale-drive")
         .join("sync_projects.json")
 }
 
-pub fn load_sync_projects() -> Vec<crate::app_state::SyncProject> {
-    let path = sync_projects_path();
+/// Desktop paths are kept as `~/...` on disk so the file survives a move to
+/// another user or machine, and expanded against `root` when loaded. Files
+/// written before that still hold absolute paths; those under `root` are
+/// rewritten in the portable form right away.
+pub fn load_sync_projects(root: &std::path::Path) -> Vec<crate::app_state::SyncProject> {
+    let path = sync_projects_path(root);
     if !path.exists() {
         return Vec::new();
     }
-    match std::fs::read_to_string(&path) {
+    let mut projects: Vec<crate::app_state::SyncProject> = match std::fs::read_to_string(&path) {
         Ok(data) => serde_json::from_str(&data).unwrap_or_default(),
         Err(_) => Vec::new(),
+    };
+    let needs_migration = projects
+        .iter()
+        .any(|p| contract_home(&p.local_path, root) != p.local_path);
+    for project in &mut projects {
+        project.local_path = expand_home(&project.local_path, root);
     }
+    if needs_migration {
+        log::info!("Storing sync project paths relative to {}", root.display());
+        save_sync_projects(root, &projects);
+    }
+    projects
 }
 
-pub fn save_sync_projects(projects: &[crate::app_state::SyncProject]) {
-    let path = sync_projects_path();
+pub fn save_sync_projects(root: &std::path::Path, projects: &[crate::app_state::SyncProject]) {
+    let path = sync_projects_path(root);
     if let Some(parent) = path.parent() {
         let _ = std::fs::create_dir_all(parent);
     }
-    if let Ok(data) = serde_json::to_string_pretty(projects) {
+    let portable: Vec<_> = projects
+        .iter()
+        .cloned()
+        .map(|mut p| {
+            p.local_path = contract_home(&p.local_path, root);
+            p
+        })
+        .collect();
+    if let Ok(data) = serde_json::to_string_pretty(&portable) {
         let _ = std::fs::write(&path, data);
     }
 }
 
+/// `~/x` or `$HOME/x` → `<root>/x`; anything else is returned as is.
+pub fn expand_home(path: &str, root: &std::path::Path) -> String {
+    let rest = match path {
+        "~" | "$HOME" => "",
+        _ => match path.strip_prefix("~/").or_else(|| path.strip_prefix("$HOME/")) {
+            Some(rest) => rest,
+            None => return path.to_string(),
+        },
+    };
+    root.join(rest)
+        .to_string_lossy()
+        .trim_end_matches(std::path::is_separator)
+        .to_string()
+}
+
+/// `<root>/x` → `~/x`; paths outside `root` are returned as is.
+pub fn contract_home(path: &str, root: &std::path::Path) -> String {
+    // A root of `/` would make every path "portable"
+    if root.parent().is_none() {
+        return path.to_string();
+    }
+    match std::path::Path::new(path).strip_prefix(root) {
+        Ok(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Ok(rest) => format!("~/{}", rest.to_string_lossy()),
+        Err(_) => path.to_string(),
+    }
+}
+
+// --- Text notes ---
+
+/// Set to a file path to keep received notes somewhere other than
+/// `~/.config/tailscale-drive/notes.jsonl`.
+pub const NOTES_FILE_ENV: &str = "TAILSCALE_DRIVE_NOTES_FILE";
+
+/// Longest note `POST /note` accepts; anything bigger should be a file.
+const NOTE_MAX_BYTES: usize = 64 * 1024;
+
+/// Notes kept in memory for `GET /notes` and the desktop UI; the file keeps all.
+const NOTES_KEPT: usize = 200;
+
+/// A text snippet sent from another device, one JSON line in the notes file.
+#[derive(Debug, Clone, Serialize, SerdeDeserialize)]
+pub struct Note {
+    pub id: u64,
+    pub text: String,
+    /// Sender's hostname, else its IP
+    pub from: String,
+    pub received_at: u64,
+}
+
+#[derive(Default)]
+pub struct Notes {
+    pub recent: Vec<Note>,
+}
+
+impl Notes {
+    fn push(&mut self, note: Note) {
+        self.recent.push(note);
+        if self.recent.len() > NOTES_KEPT {
+            self.recent.remove(0);
+        }
+    }
+
+    fn next_id(&self) -> u64 {
+        self.recent.last().map_or(1, |n| n.id + 1)
+    }
+}
+
+fn notes_path(root: &std::path::Path) -> PathBuf {
+    if let Some(path) = std::env::var_os(NOTES_FILE_ENV) {
+        return PathBuf::from(path);
+    }
+    root.join(".config")
+        .join("tailscale-drive")
+        .join("notes.jsonl")
+}
+
+fn load_notes(root: &std::path::Path) -> Notes {
+    let mut notes = Notes::default();
+    if let Ok(data) = std::fs::read_to_string(notes_path(root)) {
+        for note in data.lines().filter_map(|l| serde_json::from_str::<Note>(l).ok()) {
+            notes.push(note);
+        }
+    }
+    notes
+}
+
+fn append_note(root: &std::path::Path, note: &Note) -> std::io::Result<()> {
+    use std::io::Write;
+    let path = notes_path(root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(&path)?;
+    let line = serde_json::to_string(note).map_err(std::io::Error::other)?;
+    writeln!(file, "{}", line)
+}
+
+// --- Per-client roots ---
+
+/// One `client_roots.json` entry: which clients it covers and the folder they
+/// may see. An entry with neither `token` nor `node` covers everyone else.
+#[derive(Clone, Debug, Serialize, SerdeDeserialize)]
+pub struct ClientRoot {
+    /// Matches `Authorization: Bearer <token>`
+    #[serde(default)]
+    pub token: Option<String>,
+    /// Matches the requesting peer's hostname, MagicDNS name or tailnet IP
+    #[serde(default)]
+    pub node: Option<String>,
+    /// Nothing outside this folder is listed or served
+    pub root: PathBuf,
+    /// Where `/browse` starts without a `path`; `root` if unset
+    #[serde(default)]
+    pub default_path: Option<PathBuf>,
+}
+
+fn client_roots_path(root: &std::path::Path) -> PathBuf {
+    root.join(".config")
+        .join("tailscale-drive")
+        .join("client_roots.json")
+}
+
+/// `client_roots.json` with `~/` expanded against `root`. A missing file means
+/// no mapping; an unreadable one is logged and ignored.
+fn load_client_roots(root: &std::path::Path) -> Vec<ClientRoot> {
+    let path = client_roots_path(root);
+    let Ok(data) = std::fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<ClientRoot> = match serde_json::from_str(&data) {
+        Ok(entries) => entries,
+        Err(e) => {
+            log::error!("{}: {}; every client sees {}", path.display(), e, root.display());
+            return Vec::new();
+        }
+    };
+    let expand = |p: &std::path::Path| PathBuf::from(expand_home(&p.to_string_lossy(), root));
+    for entry in &mut entries {
+        entry.root = expand(&entry.root);
+        entry.default_path = entry.default_path.as_deref().map(expand);
+    }
+    log::info!("Loaded {} client root(s) from {}", entries.len(), path.display());
+    entries
+}
+
+/// The entry for a client: its token's first, then its node's, then the catch-all.
+pub fn match_client_root<'a>(
+    roots: &'a [ClientRoot],
+    token: Option<&str>,
+    peer: Option<&crate::app_state::TailscalePeer>,
+    ip: &str,
+) -> Option<&'a ClientRoot> {
+    let by_token = token.and_then(|t| roots.iter().find(|r| r.token.as_deref() == Some(t)));
+    let is_node = |node: &str| {
+        node == ip
+            || peer.is_some_and(|p| {
+                node.eq_ignore_ascii_case(&p.hostname)
+                    || node.eq_ignore_ascii_case(p.dns_name.trim_end_matches('.'))
+            })
+    };
+    by_token
+        .or_else(|| roots.iter().find(|r| r.token.is_none() && r.node.as_deref().is_some_and(is_node)))
+        .or_else(|| roots.iter().find(|r| r.token.is_none() && r.node.is_none()))
+}
+
 pub fn unix_timestamp() -> u64 {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
@@ -94,8 +651,12 @@ pub fn unix_timestamp() -> u64 {
 
 // --- Handlers ---
 
-/// GET /status — JSON status with last sent/received file info + device identity
-async fn status_handler(State(state): State<AppState>) -> Json<serde_json::Value> {
+/// GET /status — JSON status with last sent/received file info + device identity.
+/// `browse_root` is the folder `client_roots.json` limits the caller to, if any.
+async fn status_handler(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+) -> Json<serde_json::Value> {
     let sent = state.last_sent.lock().unwrap().clone();
     let last_received = state.received.lock().unwrap().last_file.clone();
     let server_cwd = std::env::current_dir()
@@ -112,15 +673,177 @@ async fn status_handler(State(state): State<AppState>) -> Json<serde_json::Value
             .unwrap_or_else(|| (get_system_hostname(), String::new()))
     };
 
+    let tailnet = tailnet_from_dns(&device_dns);
+
     Json(serde_json::json!({
         "last_sent_file": sent,
         "last_received_file": last_received,
         "server_cwd": server_cwd,
         "device_hostname": device_hostname,
         "device_dns": device_dns,
+        "hostname": device_hostname,
+        "os": std::env::consts::OS,
+        "arch": std::env::consts::ARCH,
+        "tailnet": tailnet,
+        "path_separator": std::path::MAIN_SEPARATOR_STR,
+        "browse_root": scope.and_then(|Extension(ClientScope(entry))| entry).map(|entry| entry.root),
+    }))
+}
+
+/// Tailnet DNS name from a MagicDNS name: `desk.tail1234.ts.net.` → `tail1234.ts.net`.
+fn tailnet_from_dns(dns_name: &str) -> Option<String> {
+    dns_name
+        .trim_end_matches('.')
+        .split_once('.')
+        .map(|(_, tailnet)| tailnet.to_string())
+        .filter(|t| !t.is_empty())
+}
+
+/// Bumped whenever an endpoint changes shape in a way older clients can't handle.
+const API_VERSION: u32 = 1;
+
+/// Optional endpoint groups this build serves; clients hide UI for anything missing.
+const FEATURES: &[&str] = &[
+    "download", "browse", "dirinfo", "pull", "preview", "upload", "peers", "sync", "syncplan",
+    "notes", "received", "pulldir", "conditional", "hash", "pullglob", "stat", "syncdirection",
+    "changes", "syncgzip", "syncstatus",
+];
+
+/// GET /version — crate version, API version and advertised features
+async fn version_handler() -> Json<serde_json::Value> {
+    Json(serde_json::json!({
+        "version": env!("CARGO_PKG_VERSION"),
+        "api_version": API_VERSION,
+        "features": FEATURES,
     }))
 }
 
+/// GET /healthz — liveness only; no locks, no filesystem, no tailscaled
+async fn healthz_handler() -> &'static str {
+    "ok"
+}
+
+/// GET /readyz — 200 once tailscaled answers on its socket, 503 otherwise
+async fn readyz_handler() -> Result<&'static str, (StatusCode, String)> {
+    if crate::tailscale::tailscaled_reachable().await {
+        Ok("ready")
+    } else {
+        Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "tailscaled is not reachable".to_string(),
+        ))
+    }
+}
+
+/// Join code for the iOS app's QR scanner, pointing at this device's tailnet name.
+/// No `token` parameter: tokens are only for `client_roots.json`, set up by hand.
+pub fn join_url(dns_name: &str) -> String {
+    let server_url = format!("http://{}:8080", dns_name.trim_end_matches('.'));
+    format!("tsdrive://connect?url={}", urlencoding::encode(&server_url))
+}
+
+/// GET /join — the join code as plain text, once the self peer's DNS name is known
+async fn join_handler(State(state): State<AppState>) -> Result<String, (StatusCode, String)> {
+    let dns = state
+        .peers
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|p| p.is_self && !p.dns_name.is_empty())
+        .map(|p| p.dns_name.clone());
+    match dns {
+        Some(dns) => Ok(join_url(&dns)),
+        None => Err((
+            StatusCode::SERVICE_UNAVAILABLE,
+            "This device's tailnet name isn't known yet".to_string(),
+        )),
+    }
+}
+
+/// GET /metrics — counters and gauges in the Prometheus text format
+async fn metrics_handler(State(state): State<AppState>) -> ([(header::HeaderName, &'static str); 1], String) {
+    let m = &state.metrics;
+    let taildrop_received = state.received.lock().unwrap().total_received;
+    let (peers_online, peers_offline) = {
+        let peers = state.peers.lock().unwrap();
+        let others = peers.iter().filter(|p| !p.is_self);
+        let online = others.clone().filter(|p| p.online).count();
+        (online, others.count() - online)
+    };
+    let (syncs_active, syncs_paused) = {
+        let projects = state.sync_projects.lock().unwrap();
+        let paused = projects.iter().filter(|p| p.paused).count();
+        (projects.len() - paused, paused)
+    };
+    let active_transfers = state.transfers.lock().unwrap().active.len();
+
+    let mut out = String::new();
+    let mut metric = |name: &str, kind: &str, help: &str, samples: &[(&str, u64)]| {
+        out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} {kind}\n"));
+        for (labels, value) in samples {
+            out.push_str(&format!("{name}{labels} {value}\n"));
+        }
+    };
+    metric(
+        "tailscale_drive_files_sent_total",
+        "counter",
+        "Files sent over Taildrop or served by /pull and /download.",
+        &[
+            ("{via=\"taildrop\"}", m.taildrop_files_sent.load(Ordering::Relaxed)),
+            ("{via=\"http\"}", m.http_files_sent.load(Ordering::Relaxed)),
+        ],
+    );
+    metric(
+        "tailscale_drive_files_received_total",
+        "counter",
+        "Files received over Taildrop or uploaded over HTTP.",
+        &[
+            ("{via=\"taildrop\"}", taildrop_received),
+            ("{via=\"http\"}", m.http_files_received.load(Ordering::Relaxed)),
+        ],
+    );
+    metric(
+        "tailscale_drive_bytes_sent_total",
+        "counter",
+        "Bytes sent over Taildrop or streamed by /pull and /download.",
+        &[("", m.bytes_sent.load(Ordering::Relaxed))],
+    );
+    metric(
+        "tailscale_drive_bytes_received_total",
+        "counter",
+        "Bytes written by HTTP uploads.",
+        &[("", m.bytes_received.load(Ordering::Relaxed))],
+    );
+    metric(
+        "tailscale_drive_sync_projects",
+        "gauge",
+        "Sync projects by state.",
+        &[
+            ("{state=\"active\"}", syncs_active as u64),
+            ("{state=\"paused\"}", syncs_paused as u64),
+        ],
+    );
+    metric(
+        "tailscale_drive_peers",
+        "gauge",
+        "Other devices on the tailnet.",
+        &[
+            ("{online=\"true\"}", peers_online as u64),
+            ("{online=\"false\"}", peers_offline as u64),
+        ],
+    );
+    metric(
+        "tailscale_drive_active_transfers",
+        "gauge",
+        "/pull and /download streams in flight.",
+        &[("", active_transfers as u64)],
+    );
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4; charset=utf-8")],
+        out,
+    )
+}
+
 /// Get the system hostname as a fallback when Tailscale self-peer isn't available yet.
 fn get_system_hostname() -> String {
     std::fs::read_to_string("/etc/hostname")
@@ -129,18 +852,44 @@ fn get_system_hostname() -> String {
         .unwrap_or_else(|_| "unknown".to_string())
 }
 
-/// GET /files — list all files waiting in the Taildrop inbox
-async fn list_files_handler() -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+/// GET /files — list all files waiting in the Taildrop inbox, newest first,
+/// with the sender's hostname and arrival time where known.
+async fn list_files_handler(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    check_whole_root(&state, &scope)?;
     let files = crate::files::list_waiting_files()
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to list files: {}", e)))?;
 
-    let result: Vec<serde_json::Value> = files
-        .iter()
-        .map(|f| {
+    let mut entries: Vec<(String, i64, String, u64)> = {
+        let mut received = state.received.lock().unwrap();
+        let peers = state.peers.lock().unwrap();
+        files
+            .iter()
+            .map(|f| {
+                received.note_file(&f.name, None);
+                let meta = received.file_meta[&f.name].clone();
+                let from = peers
+                    .iter()
+                    .find(|p| p.id == meta.from_peer)
+                    .map(|p| p.hostname.clone())
+                    .unwrap_or(meta.from_peer);
+                (f.name.clone(), f.size, from, meta.received_at)
+            })
+            .collect()
+    };
+    entries.sort_by(|a, b| b.3.cmp(&a.3).then_with(|| a.0.cmp(&b.0)));
+
+    let result: Vec<serde_json::Value> = entries
+        .into_iter()
+        .map(|(name, size, from_peer, received_at)| {
             serde_json::json!({
-                "name": f.name,
-                "size": f.size,
+                "name": name,
+                "size": size,
+                "from_peer": from_peer,
+                "received_at": received_at,
             })
         })
         .collect();
@@ -148,31 +897,114 @@ async fn list_files_handler() -> Result<Json<serde_json::Value>, (StatusCode, St
     Ok(Json(serde_json::json!({ "files": result })))
 }
 
-/// GET /download/:name — download a specific file by name.
+/// GET /received — every file this desktop has received, newest first, with
+/// where it was saved. `available` says whether `/download/{name}` can still
+/// serve it, from the inbox or the saved copy.
+async fn list_received_handler(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    check_whole_root(&state, &scope)?;
+    let records = state.received_index.lock().unwrap().records.clone();
+    let received = state.received.lock().unwrap();
+    let peers = state.peers.lock().unwrap();
+    let result: Vec<serde_json::Value> = records
+        .iter()
+        .rev()
+        .map(|r| {
+            let from = peers
+                .iter()
+                .find(|p| p.id == r.from_peer)
+                .map(|p| p.hostname.clone())
+                .unwrap_or_else(|| r.from_peer.clone());
+            let saved = r.saved_path.as_ref().filter(|p| p.is_file());
+            let in_inbox = r.saved_path.is_none() && received.file_meta.contains_key(&r.name);
+            serde_json::json!({
+                "name": r.name,
+                "size": r.size,
+                "from_peer": from,
+                "received_at": r.received_at,
+                "saved_path": r.saved_path.as_ref().map(|p| p.to_string_lossy()),
+                "available": saved.is_some() || in_inbox,
+            })
+        })
+        .collect();
+    Ok(Json(serde_json::json!({ "files": result })))
+}
+
+/// DELETE /files/{name} — remove one file from the Taildrop inbox
+async fn delete_waiting_file_handler(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+    Path(name): Path<String>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    check_whole_root(&state, &scope)?;
+    crate::files::delete_received_file(&name)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    state.received.lock().unwrap().forget_file(&name);
+    log::info!("Removed from inbox: {}", name);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// DELETE /files — clear the whole Taildrop inbox. Stops at the first failure.
+async fn clear_waiting_files_handler(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+) -> Result<Json<serde_json::Value>, (StatusCode, String)> {
+    check_whole_root(&state, &scope)?;
+    let files = crate::files::list_waiting_files()
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to list files: {}", e)))?;
+
+    for f in &files {
+        crate::files::delete_received_file(&f.name)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+        state.received.lock().unwrap().forget_file(&f.name);
+    }
+
+    log::info!("Cleared inbox ({} files)", files.len());
+    Ok(Json(serde_json::json!({ "deleted": files.len() })))
+}
+
+#[derive(SerdeDeserialize, Default)]
+struct DownloadQuery {
+    /// Ask for `Content-Disposition: inline` so previewable types render in place.
+    #[serde(default)]
+    inline: bool,
+}
+
+/// GET /download/:name?inline=<bool> — download a specific file by name.
 /// Streams from FinalPath on disk if known, otherwise buffers from the tailscaled API.
 async fn download_file_handler(
     State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+    client: Option<Extension<TransferClient>>,
     Path(name): Path<String>,
+    Query(params): Query<DownloadQuery>,
 ) -> Result<Response<Body>, (StatusCode, String)> {
-    // Check if we have a local FinalPath for this file
-    let local_path = {
+    check_whole_root(&state, &scope)?;
+    // Check if we have a local FinalPath for this file, else a copy saved
+    // after tailscaled let go of it
+    let final_path = {
         let received = state.received.lock().unwrap();
         received.file_paths.get(&name).cloned()
     };
+    let local_paths = [
+        final_path,
+        state.received_index.lock().unwrap().saved_copy(&name),
+    ];
 
     // Try streaming from disk (efficient for large files)
-    if let Some(ref path) = local_path {
+    for path in local_paths.iter().flatten() {
         if let Ok(file) = tokio::fs::File::open(path).await {
             let metadata = file.metadata().await.ok();
-            let stream = ReaderStream::new(file);
-            let body = Body::from_stream(stream);
+            let total = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+            let client = client.map(|Extension(c)| c.0).unwrap_or_default();
+            let body = tracked_file_body(&state, file, path, client, total);
 
-            let mut builder = Response::builder()
-                .header(header::CONTENT_TYPE, "application/octet-stream")
-                .header(
-                    header::CONTENT_DISPOSITION,
-                    format!("attachment; filename=\"{}\"", name),
-                );
+            let mut builder = file_response_builder(&name, params.inline);
 
             if let Some(meta) = metadata {
                 builder = builder.header(header::CONTENT_LENGTH, meta.len());
@@ -194,12 +1026,7 @@ async fn download_file_handler(
             )
         })?;
 
-    Response::builder()
-        .header(header::CONTENT_TYPE, "application/octet-stream")
-        .header(
-            header::CONTENT_DISPOSITION,
-            format!("attachment; filename=\"{}\"", name),
-        )
+    file_response_builder(&name, params.inline)
         .header(header::CONTENT_LENGTH, content.len())
         .body(Body::from(content))
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
@@ -208,7 +1035,11 @@ async fn download_file_handler(
 /// GET /download — download the most recently received file
 async fn download_last_handler(
     State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+    client: Option<Extension<TransferClient>>,
+    query: Query<DownloadQuery>,
 ) -> Result<Response<Body>, (StatusCode, String)> {
+    check_whole_root(&state, &scope)?;
     let name = {
         let received = state.received.lock().unwrap();
         received
@@ -217,7 +1048,62 @@ async fn download_last_handler(
             .ok_or((StatusCode::NOT_FOUND, "No file received yet".to_string()))?
     };
 
-    download_file_handler(State(state), Path(name)).await
+    download_file_handler(State(state), scope, client, Path(name), query).await
+}
+
+/// Response builder with `Content-Type` guessed from the filename and a matching
+/// `Content-Disposition`. `inline` is only honoured for types a client can render
+/// directly; everything else stays an attachment.
+fn file_response_builder(filename: &str, inline: bool) -> axum::http::response::Builder {
+    let content_type = mime_guess::from_path(filename).first_or_octet_stream();
+    let disposition = if inline && is_inline_mime(&content_type) {
+        "inline"
+    } else {
+        "attachment"
+    };
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, content_type.essence_str())
+        .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+        .header(header::CONTENT_DISPOSITION, content_disposition(disposition, filename))
+}
+
+/// Types that are safe and useful to display in place (images, text, JSON, PDF).
+/// Anything a browser would run script from (HTML, SVG, XML, JavaScript) isn't.
+fn is_inline_mime(mime: &mime_guess::Mime) -> bool {
+    let active = matches!(
+        mime.essence_str(),
+        "text/html"
+            | "application/xhtml+xml"
+            | "image/svg+xml"
+            | "text/javascript"
+            | "application/javascript"
+            | "text/xml"
+            | "application/xml"
+    );
+    !active
+        && (mime.type_() == mime_guess::mime::IMAGE
+            || mime.type_() == mime_guess::mime::TEXT
+            || mime.subtype() == mime_guess::mime::JSON
+            || mime.subtype() == mime_guess::mime::PDF)
+}
+
+/// `Content-Disposition` value for `filename`: an ASCII `filename` with quotes,
+/// backslashes and anything unprintable replaced, plus the exact name as
+/// `filename*` (RFC 6266).
+fn content_disposition(disposition: &str, filename: &str) -> String {
+    let fallback: String = filename
+        .chars()
+        .map(|c| if (c.is_ascii_graphic() || c == ' ') && c != '"' && c != '\\' { c } else { '_' })
+        .collect();
+    let mut encoded = String::with_capacity(filename.len());
+    for byte in filename.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => encoded.push(byte as char),
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    format!("{}; filename=\"{}\"; filename*=UTF-8''{}", disposition, fallback, encoded)
 }
 
 /// GET /peers — list all Tailscale peers on the network
@@ -248,135 +1134,1164 @@ struct BrowseQuery {
 
 #[derive(Serialize)]
 struct RemoteFileInfo {
+    /// Display name (lossy if the real name isn't valid UTF-8)
     name: String,
+    /// Opaque id of the full path; pass back as `id=` to /pull or /preview
+    path_id: String,
     is_dir: bool,
     size: i64,
     modified: u64,
 }
 
-/// GET /browse?path=<optional> — list files in a directory (defaults to $HOME).
-async fn browse_handler(
-    Query(params): Query<BrowseQuery>,
-) -> Result<Json<Vec<RemoteFileInfo>>, (StatusCode, String)> {
-    let home = std::env::var("HOME").unwrap_or_else(|_| "/".to_string());
-    let base = params.path.unwrap_or(home);
-    let base_path = std::path::PathBuf::from(&base);
+/// Encode a path's raw bytes so it survives JSON and query strings untouched.
+/// On Windows these are the WTF-8 bytes, which round-trip unpaired surrogates.
+fn encode_path_id(path: &std::path::Path) -> String {
+    use base64::Engine;
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(path.as_os_str().as_encoded_bytes())
+}
 
-    if !base_path.exists() || !base_path.is_dir() {
-        return Err((StatusCode::NOT_FOUND, "Directory not found".to_string()));
+fn decode_path_id(id: &str) -> Option<PathBuf> {
+    use base64::Engine;
+    let bytes = base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(id).ok()?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStringExt;
+        Some(PathBuf::from(std::ffi::OsString::from_vec(bytes)))
+    }
+    // Anything but Unix only gets back paths that are valid Unicode
+    #[cfg(not(unix))]
+    {
+        String::from_utf8(bytes).ok().map(PathBuf::from)
     }
+}
 
-    let mut files = Vec::new();
-    if let Ok(entries) = std::fs::read_dir(&base_path) {
-        for entry in entries.flatten() {
-            let name = entry.file_name().to_string_lossy().to_string();
-            if name.starts_with('.') {
-                continue;
-            }
-            if let Ok(metadata) = entry.metadata() {
-                let modified = metadata
-                    .modified()
-                    .ok()
-                    .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                    .map(|d| d.as_secs())
-                    .unwrap_or(0);
+/// Resolve a file from either an opaque `id` (preferred) or a plain `path`.
+/// The scope is checked first, so a 404 never describes another client's files.
+fn resolve_file_param(
+    scope: &Option<Extension<ClientScope>>,
+    path: Option<String>,
+    id: Option<String>,
+) -> Result<PathBuf, (StatusCode, String)> {
+    let file_path = match (id, path) {
+        (Some(id), _) => decode_path_id(&id)
+            .ok_or((StatusCode::BAD_REQUEST, "Invalid id parameter".to_string()))?,
+        (None, Some(path)) => PathBuf::from(path),
+        (None, None) => {
+            return Err((StatusCode::BAD_REQUEST, "Missing path parameter".to_string()));
+        }
+    };
+
+    check_scope(scope, &file_path)?;
+    if !file_path.is_file() {
+        return Err((
+            StatusCode::NOT_FOUND,
+            format!("File not found: {}", file_path.to_string_lossy()),
+        ));
+    }
+    Ok(file_path)
+}
 
-                files.push(RemoteFileInfo {
-                    name,
-                    is_dir: metadata.is_dir(),
-                    size: metadata.len() as i64,
-                    modified,
-                });
+/// Lexically normalize a path: collapse repeated `/`, drop `.` and trailing
+/// `/`, and apply `..` (never above the root). Doesn't touch the filesystem,
+/// since the iOS side of a sync lives elsewhere.
+fn normalize_lexically(path: &str) -> PathBuf {
+    use std::path::Component;
+    let mut out = PathBuf::new();
+    for component in std::path::Path::new(path).components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                out.pop();
             }
+            other => out.push(other),
         }
     }
-
-    files.sort_by(|a, b| a.name.cmp(&b.name));
-    Ok(Json(files))
+    out
 }
 
-/// GET /pull?path=<filepath> — download an arbitrary file from the server's filesystem
-async fn pull_file_handler(
+/// Optional gitignore-syntax file in a directory that hides entries from its `/browse` listing.
+const BROWSE_IGNORE_FILE: &str = ".tsdriveignore";
+
+/// Patterns from `dir/.tsdriveignore`, re-parsed only when the file's mtime changes.
+/// `None` if the directory has no ignore file.
+fn browse_ignore(state: &AppState, dir: &std::path::Path) -> Option<Arc<Gitignore>> {
+    let file = dir.join(BROWSE_IGNORE_FILE);
+    let modified = std::fs::metadata(&file).and_then(|m| m.modified()).ok();
+    let mut cache = state.browse_ignore_cache.lock().unwrap();
+    let Some(modified) = modified else {
+        cache.remove(dir);
+        return None;
+    };
+    if let Some((at, patterns)) = cache.get(dir)
+        && *at == modified
+    {
+        return Some(patterns.clone());
+    }
+
+    let mut builder = GitignoreBuilder::new(dir);
+    // Bad lines are skipped; the rest of the file still applies
+    if let Some(e) = builder.add(&file) {
+        log::warn!("{}: {}", file.display(), e);
+    }
+    let patterns = match builder.build() {
+        Ok(patterns) => Arc::new(patterns),
+        Err(e) => {
+            log::warn!("{}: {}", file.display(), e);
+            return None;
+        }
+    };
+    cache.insert(dir.to_path_buf(), (modified, patterns.clone()));
+    Some(patterns)
+}
+
+/// Where `/browse` (and `/changes`) start without a path: the client's
+/// `default_path` or root, else the home directory.
+fn browse_start<'a>(state: &'a AppState, scope: &'a Option<Extension<ClientScope>>) -> &'a std::path::Path {
+    match scope {
+        Some(Extension(ClientScope(Some(entry)))) => entry.default_path.as_ref().unwrap_or(&entry.root),
+        _ => &state.root,
+    }
+}
+
+/// GET /browse?path=<optional> — list files in a directory (defaults to $HOME).
+/// Dotfiles are always hidden; `.tsdriveignore` in the listed directory can hide more.
+/// The `ETag` hashes the listing, so a matching `If-None-Match` gets a 304.
+async fn browse_handler(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
     Query(params): Query<BrowseQuery>,
+    headers: HeaderMap,
 ) -> Result<Response<Body>, (StatusCode, String)> {
-    let path_str = params
+    let base = params
+        .path
+        .unwrap_or_else(|| browse_start(&state, &scope).to_string_lossy().into_owned());
+    // `/home//user/` and `/home/user` must list (and sort) the same directory
+    let base_path = normalize_lexically(&base);
+
+    check_scope(&scope, &base_path)?;
+    if !base_path.is_dir() {
+        return Err((StatusCode::NOT_FOUND, "Directory not found".to_string()));
+    }
+
+    let ignore = browse_ignore(&state, &base_path);
+
+    let mut files = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(&base_path) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            if let Ok(metadata) = entry.metadata() {
+                if let Some(ref ignore) = ignore
+                    && ignore.matched(entry.path(), metadata.is_dir()).is_ignore()
+                {
+                    continue;
+                }
+
+                files.push(remote_file_info(&entry.path(), name, &metadata));
+            }
+        }
+    }
+
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    let body = serde_json::to_vec(&files).map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let etag = {
+        use std::hash::{Hash, Hasher};
+        let mut hasher = std::hash::DefaultHasher::new();
+        body.hash(&mut hasher);
+        format!("\"{:016x}\"", hasher.finish())
+    };
+    if is_not_modified(&headers, &etag, None) {
+        return Ok(not_modified_response(&etag, None));
+    }
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::ETAG, etag)
+        .body(Body::from(body))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// A `/browse` entry for `path`.
+fn remote_file_info(path: &std::path::Path, name: String, metadata: &std::fs::Metadata) -> RemoteFileInfo {
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    RemoteFileInfo {
+        name,
+        path_id: encode_path_id(path),
+        is_dir: metadata.is_dir(),
+        size: metadata.len() as i64,
+        modified,
+    }
+}
+
+#[derive(SerdeDeserialize)]
+struct StatQuery {
+    path: Option<String>,
+    id: Option<String>,
+}
+
+/// GET /stat?path=<path>|id=<path_id> — one file or folder's `/browse` entry,
+/// without listing its parent. 404 if it doesn't exist.
+async fn stat_handler(
+    scope: Option<Extension<ClientScope>>,
+    Query(params): Query<StatQuery>,
+) -> Result<Json<RemoteFileInfo>, (StatusCode, String)> {
+    let path = match (params.id, params.path) {
+        (Some(id), _) => decode_path_id(&id)
+            .ok_or((StatusCode::BAD_REQUEST, "Invalid id parameter".to_string()))?,
+        (None, Some(path)) => normalize_lexically(&path),
+        (None, None) => {
+            return Err((StatusCode::BAD_REQUEST, "Missing path parameter".to_string()));
+        }
+    };
+    // Before the lookup, so a 404 doesn't tell a scoped client what exists outside its folder
+    check_scope(&scope, &path)?;
+    let metadata = std::fs::metadata(&path)
+        .map_err(|_| (StatusCode::NOT_FOUND, format!("Not found: {}", path.to_string_lossy())))?;
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| path.to_string_lossy().to_string());
+    Ok(Json(remote_file_info(&path, name, &metadata)))
+}
+
+/// `/changes` lists at most this many files, newest first.
+const CHANGES_LIMIT: usize = 500;
+
+#[derive(SerdeDeserialize)]
+struct ChangesQuery {
+    since: u64,
+    root: Option<String>,
+}
+
+/// One modified file: its `/browse` entry plus the full path.
+#[derive(Serialize)]
+struct ChangedFile {
+    path: String,
+    #[serde(flatten)]
+    info: RemoteFileInfo,
+}
+
+#[derive(Serialize)]
+struct ChangesResponse {
+    root: String,
+    since: u64,
+    /// Server time when the walk started; pass it back as `since` to catch up from here
+    now: u64,
+    /// Newest first
+    files: Vec<ChangedFile>,
+    /// More files changed than are listed, or the walk stopped at a limit
+    truncated: bool,
+}
+
+/// GET /changes?since=<unix_ts>&root=<dir> — files under `root` (where
+/// `/browse` starts by default) modified after `since`. Dotfiles are skipped
+/// like in `/browse`, and the walk is bounded like `/dirinfo`.
+async fn changes_handler(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+    Query(params): Query<ChangesQuery>,
+) -> Result<Json<ChangesResponse>, (StatusCode, String)> {
+    let root = match params.root {
+        Some(root) => normalize_lexically(&root),
+        None => browse_start(&state, &scope).to_path_buf(),
+    };
+    check_scope(&scope, &root)?;
+    if !root.is_dir() {
+        return Err((StatusCode::NOT_FOUND, "Directory not found".to_string()));
+    }
+
+    let now = unix_timestamp();
+    let (since, limits) = (params.since, state.walk_limits);
+    let walk_root = root.clone();
+    let (files, truncated) = tokio::task::spawn_blocking(move || walk_changes(&walk_root, since, &limits))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(ChangesResponse {
+        root: root.to_string_lossy().into_owned(),
+        since,
+        now,
+        files,
+        truncated,
+    }))
+}
+
+/// Files under `root` modified after `since`, newest first and at most
+/// `CHANGES_LIMIT`, and whether anything was left out.
+fn walk_changes(root: &std::path::Path, since: u64, limits: &WalkLimits) -> (Vec<ChangedFile>, bool) {
+    let started = Instant::now();
+    let mut seen = 0u64;
+    let mut truncated = false;
+    let mut files = Vec::new();
+    let mut visited = VisitedDirs::default();
+    if let Ok(metadata) = std::fs::metadata(root) {
+        visited.insert(root, &metadata);
+    }
+    let mut stack = vec![(root.to_path_buf(), 0)];
+    'walk: while let Some((dir, depth)) = stack.pop() {
+        let Ok(children) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for child in children.flatten() {
+            seen += 1;
+            if seen >= limits.max_entries || started.elapsed() >= DIR_INFO_TIME_LIMIT {
+                truncated = true;
+                break 'walk;
+            }
+            let name = child.file_name().to_string_lossy().to_string();
+            if name.starts_with('.') {
+                continue;
+            }
+            let Ok(m) = child.path().symlink_metadata() else {
+                continue;
+            };
+            if m.is_dir() {
+                if depth >= limits.max_depth {
+                    truncated = true;
+                } else if visited.insert(&child.path(), &m) {
+                    stack.push((child.path(), depth + 1));
+                }
+            } else if m.is_file() {
+                let modified = m
+                    .modified()
+                    .ok()
+                    .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+                    .map(|d| d.as_secs())
+                    .unwrap_or(0);
+                if modified > since {
+                    files.push(ChangedFile {
+                        path: child.path().to_string_lossy().into_owned(),
+                        info: remote_file_info(&child.path(), name, &m),
+                    });
+                }
+            }
+        }
+    }
+    files.sort_by_key(|f| std::cmp::Reverse(f.info.modified));
+    if files.len() > CHANGES_LIMIT {
+        files.truncate(CHANGES_LIMIT);
+        truncated = true;
+    }
+    (files, truncated)
+}
+
+/// Whether the client's copy is still current. `If-None-Match` wins when sent
+/// (`*` or a listed tag); otherwise `If-Modified-Since`, to the second.
+fn is_not_modified(headers: &HeaderMap, etag: &str, modified: Option<SystemTime>) -> bool {
+    if headers.contains_key(header::IF_NONE_MATCH) {
+        return headers
+            .get_all(header::IF_NONE_MATCH)
+            .iter()
+            .filter_map(|v| v.to_str().ok())
+            .flat_map(|v| v.split(','))
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || tag == etag);
+    }
+    let since = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| httpdate::parse_http_date(v).ok());
+    let secs = |t: SystemTime| t.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    match (modified, since) {
+        (Some(modified), Some(since)) => secs(modified) <= secs(since),
+        _ => false,
+    }
+}
+
+/// Empty 304 carrying the validators the client should keep.
+fn not_modified_response(etag: &str, modified: Option<SystemTime>) -> Response<Body> {
+    let mut builder = Response::builder()
+        .status(StatusCode::NOT_MODIFIED)
+        .header(header::ETAG, etag);
+    if let Some(modified) = modified {
+        builder = builder.header(header::LAST_MODIFIED, httpdate::fmt_http_date(modified));
+    }
+    builder.body(Body::empty()).unwrap()
+}
+
+/// `/pull` validator: the file's mtime (Unix seconds) and size.
+fn file_etag(modified: u64, size: u64) -> String {
+    format!("\"{}-{}\"", modified, size)
+}
+
+/// How long a `/dirinfo` result is reused before walking again.
+const DIR_INFO_CACHE_TTL: Duration = Duration::from_secs(30);
+/// Bounds for a single `/dirinfo` walk (the entry count is `WalkLimits::max_entries`).
+const DIR_INFO_TIME_LIMIT: Duration = Duration::from_secs(2);
+const DIR_INFO_ENTRY_LIMIT: u64 = 200_000;
+
+/// Env var overriding how many folder levels the recursive walks go down.
+pub const WALK_MAX_DEPTH_ENV: &str = "TAILSCALE_DRIVE_WALK_MAX_DEPTH";
+/// Env var overriding how many entries `/dirinfo` and sync plans look at.
+pub const WALK_MAX_ENTRIES_ENV: &str = "TAILSCALE_DRIVE_WALK_MAX_ENTRIES";
+const DEFAULT_WALK_MAX_DEPTH: u64 = 64;
+
+/// Bounds shared by `/dirinfo`, `/pull-dir`, `/pull-glob` and sync plans.
+#[derive(Debug, Clone, Copy)]
+pub struct WalkLimits {
+    /// Folder levels below the walk's root; deeper folders are left out
+    pub max_depth: usize,
+    /// Entries looked at before `/dirinfo` and sync plans stop with `truncated`
+    pub max_entries: u64,
+}
+
+impl Default for WalkLimits {
+    fn default() -> Self {
+        Self {
+            max_depth: DEFAULT_WALK_MAX_DEPTH as usize,
+            max_entries: DIR_INFO_ENTRY_LIMIT,
+        }
+    }
+}
+
+impl WalkLimits {
+    /// The defaults, overridden by `WALK_MAX_DEPTH_ENV` / `WALK_MAX_ENTRIES_ENV`.
+    pub fn from_env() -> Self {
+        Self {
+            max_depth: env_count(WALK_MAX_DEPTH_ENV, DEFAULT_WALK_MAX_DEPTH) as usize,
+            max_entries: env_count(WALK_MAX_ENTRIES_ENV, DIR_INFO_ENTRY_LIMIT),
+        }
+    }
+}
+
+/// A positive whole number from `var`, or `default` when unset or invalid.
+fn env_count(var: &str, default: u64) -> u64 {
+    match std::env::var(var) {
+        Ok(raw) => match raw.trim().parse::<u64>() {
+            Ok(n) if n > 0 => n,
+            _ => {
+                log::warn!("Ignoring {}={:?}; using {}", var, raw, default);
+                default
+            }
+        },
+        Err(_) => default,
+    }
+}
+
+/// Folders a walk has already entered, so a loop back to an ancestor (a bind
+/// mount, or a symlinked walk root) is only entered once. Keyed by device and
+/// inode on Unix and by canonical path elsewhere.
+#[derive(Default)]
+struct VisitedDirs(std::collections::HashSet<DirKey>);
+
+#[cfg(unix)]
+type DirKey = (u64, u64);
+#[cfg(not(unix))]
+type DirKey = PathBuf;
+
+impl VisitedDirs {
+    /// False if `dir` was entered before (or can't be identified).
+    fn insert(&mut self, dir: &std::path::Path, metadata: &std::fs::Metadata) -> bool {
+        dir_key(dir, metadata).is_some_and(|key| self.0.insert(key))
+    }
+}
+
+#[cfg(unix)]
+fn dir_key(_dir: &std::path::Path, metadata: &std::fs::Metadata) -> Option<DirKey> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(not(unix))]
+fn dir_key(dir: &std::path::Path, _metadata: &std::fs::Metadata) -> Option<DirKey> {
+    std::fs::canonicalize(dir).ok()
+}
+
+/// GET /dirinfo?path=<dir> — recursive total size and file count (bounded, cached).
+async fn dir_info_handler(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+    Query(params): Query<BrowseQuery>,
+) -> Result<Json<DirInfo>, (StatusCode, String)> {
+    let path = params
         .path
+        .map(PathBuf::from)
         .ok_or((StatusCode::BAD_REQUEST, "Missing path parameter".to_string()))?;
-    let file_path = std::path::PathBuf::from(&path_str);
 
-    if !file_path.exists() || !file_path.is_file() {
-        return Err((StatusCode::NOT_FOUND, format!("File not found: {}", path_str)));
+    check_scope(&scope, &path)?;
+    if !path.is_dir() {
+        return Err((StatusCode::NOT_FOUND, "Directory not found".to_string()));
     }
 
+    if let Some((at, info)) = state.dir_info_cache.lock().unwrap().get(&path)
+        && at.elapsed() < DIR_INFO_CACHE_TTL
+    {
+        return Ok(Json(info.clone()));
+    }
+
+    let walk_path = path.clone();
+    let limits = state.walk_limits;
+    let info = tokio::task::spawn_blocking(move || walk_dir_info(&walk_path, &limits))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    let mut cache = state.dir_info_cache.lock().unwrap();
+    cache.retain(|_, (at, _)| at.elapsed() < DIR_INFO_CACHE_TTL);
+    cache.insert(path, (Instant::now(), info.clone()));
+    Ok(Json(info))
+}
+
+/// Iterative walk that doesn't follow symlinks, enters each folder once and
+/// gives up at the time/entry limits. Folders past the depth limit are counted
+/// but not entered.
+fn walk_dir_info(root: &std::path::Path, limits: &WalkLimits) -> DirInfo {
+    let started = Instant::now();
+    let mut info = DirInfo {
+        total_size: 0,
+        file_count: 0,
+        dir_count: 0,
+        truncated: false,
+    };
+    let mut visited = VisitedDirs::default();
+    if let Ok(metadata) = std::fs::metadata(root) {
+        visited.insert(root, &metadata);
+    }
+    let mut stack = vec![(root.to_path_buf(), 0)];
+
+    while let Some((dir, depth)) = stack.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            if info.file_count + info.dir_count >= limits.max_entries
+                || started.elapsed() >= DIR_INFO_TIME_LIMIT
+            {
+                info.truncated = true;
+                return info;
+            }
+            let Ok(metadata) = entry.path().symlink_metadata() else {
+                continue;
+            };
+            if metadata.is_dir() {
+                info.dir_count += 1;
+                if depth >= limits.max_depth {
+                    info.truncated = true;
+                } else if visited.insert(&entry.path(), &metadata) {
+                    stack.push((entry.path(), depth + 1));
+                }
+            } else if metadata.is_file() {
+                info.file_count += 1;
+                info.total_size += metadata.len();
+            }
+        }
+    }
+    info
+}
+
+#[derive(SerdeDeserialize)]
+struct PullQuery {
+    path: Option<String>,
+    id: Option<String>,
+    #[serde(default)]
+    inline: bool,
+}
+
+/// GET /pull?path=<filepath>|id=<path_id>&inline=<bool> — download an arbitrary file from the server's filesystem.
+/// `ETag` / `Last-Modified` come from the mtime and size; a matching
+/// `If-None-Match` or `If-Modified-Since` gets a 304 without reading the file.
+async fn pull_file_handler(
+    State(state): State<AppState>,
+    client: Option<Extension<TransferClient>>,
+    scope: Option<Extension<ClientScope>>,
+    Query(params): Query<PullQuery>,
+    headers: HeaderMap,
+) -> Result<Response<Body>, (StatusCode, String)> {
+    let file_path = resolve_file_param(&scope, params.path, params.id)?;
+
     let file = tokio::fs::File::open(&file_path)
         .await
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     let metadata = file.metadata().await.ok();
-    let stream = ReaderStream::new(file);
-    let body = Body::from_stream(stream);
+    let total = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+    let modified = metadata.as_ref().and_then(|m| m.modified().ok());
+    let etag = file_etag(
+        modified
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        total,
+    );
+    if is_not_modified(&headers, &etag, modified) {
+        return Ok(not_modified_response(&etag, modified));
+    }
+    let client = client.map(|Extension(c)| c.0).unwrap_or_default();
+    let body = tracked_file_body(&state, file, &file_path, client, total);
 
     let filename = file_path
         .file_name()
         .map(|n| n.to_string_lossy().to_string())
         .unwrap_or_else(|| "file".to_string());
 
-    let mut builder = Response::builder()
-        .header(header::CONTENT_TYPE, "application/octet-stream")
-        .header(
-            header::CONTENT_DISPOSITION,
-            format!("attachment; filename=\"{}\"", filename),
-        );
+    let mut builder = file_response_builder(&filename, params.inline).header(header::ETAG, etag);
 
     if let Some(meta) = metadata {
         builder = builder.header(header::CONTENT_LENGTH, meta.len());
     }
+    if let Some(modified) = modified {
+        builder = builder.header(header::LAST_MODIFIED, httpdate::fmt_http_date(modified));
+    }
 
     builder
         .body(body)
         .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
 }
 
+/// `/pull-dir` refuses folders with more entries than this.
+const PULL_DIR_ENTRY_LIMIT: usize = 100_000;
+
+#[derive(SerdeDeserialize)]
+struct PullDirQuery {
+    path: Option<String>,
+    id: Option<String>,
+}
+
+/// One file or folder in a `/pull-dir` archive.
+struct PullDirEntry {
+    path: PathBuf,
+    /// Path inside the archive, starting with the folder's own name
+    name: PathBuf,
+    is_dir: bool,
+    size: u64,
+}
+
+/// GET /pull-dir?path=<dir>|id=<path_id> — stream a folder as a tar archive so
+/// it transfers in one connection. Dotfiles are skipped like in `/browse`, and
+/// symlinks aren't followed. `x-dir-entries` / `x-dir-bytes` give the entry
+/// count and file bytes up front for progress; `x-dir-truncated: 1` means
+/// folders past the depth limit were left out.
+async fn pull_dir_handler(
+    State(state): State<AppState>,
+    client: Option<Extension<TransferClient>>,
+    scope: Option<Extension<ClientScope>>,
+    Query(params): Query<PullDirQuery>,
+) -> Result<Response<Body>, (StatusCode, String)> {
+    let dir = pull_dir_target(&scope, params.id, params.path)?;
+    let walk_root = dir.clone();
+    let limits = state.walk_limits;
+    let (entries, truncated) = tokio::task::spawn_blocking(move || walk_pull_dir(&walk_root, true, &limits))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .ok_or((
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Folder has more than {} entries", PULL_DIR_ENTRY_LIMIT),
+        ))?;
+    tar_response(&state, client, dir, entries, truncated)
+}
+
+/// The folder named by `id` (a `path_id`) or `path`; it must be in scope and exist.
+fn pull_dir_target(
+    scope: &Option<Extension<ClientScope>>,
+    id: Option<String>,
+    path: Option<String>,
+) -> Result<PathBuf, (StatusCode, String)> {
+    let dir = match (id, path) {
+        (Some(id), _) => decode_path_id(&id)
+            .ok_or((StatusCode::BAD_REQUEST, "Invalid id parameter".to_string()))?,
+        (None, Some(path)) => normalize_lexically(&path),
+        (None, None) => {
+            return Err((StatusCode::BAD_REQUEST, "Missing path parameter".to_string()));
+        }
+    };
+    check_scope(scope, &dir)?;
+    if !dir.is_dir() {
+        return Err((StatusCode::NOT_FOUND, "Directory not found".to_string()));
+    }
+    Ok(dir)
+}
+
+/// `/pull-glob` refuses patterns matching more files than this...
+const PULL_GLOB_FILE_LIMIT: usize = 1_000;
+/// ...or more bytes in total.
+const PULL_GLOB_BYTE_LIMIT: u64 = 2 * 1024 * 1024 * 1024;
+
+#[derive(SerdeDeserialize)]
+struct PullGlobQuery {
+    dir: Option<String>,
+    id: Option<String>,
+    pattern: String,
+}
+
+/// GET /pull-glob?dir=<dir>|id=<path_id>&pattern=<glob> — the files in a folder
+/// matching a glob, as a tar archive laid out like `/pull-dir`'s. The pattern
+/// is matched against paths relative to the folder: `*.log` stays at the top
+/// level, `**/*.log` searches subfolders too. Dotfiles are skipped.
+async fn pull_glob_handler(
+    State(state): State<AppState>,
+    client: Option<Extension<TransferClient>>,
+    scope: Option<Extension<ClientScope>>,
+    Query(params): Query<PullGlobQuery>,
+) -> Result<Response<Body>, (StatusCode, String)> {
+    let dir = pull_dir_target(&scope, params.id, params.dir)?;
+    let matcher = globset::GlobBuilder::new(params.pattern.trim())
+        .literal_separator(true)
+        .build()
+        .map_err(|e| (StatusCode::BAD_REQUEST, format!("Invalid pattern: {}", e)))?
+        .compile_matcher();
+    let recursive = params.pattern.contains('/');
+    let walk_root = dir.clone();
+    let limits = state.walk_limits;
+    let (entries, truncated) =
+        tokio::task::spawn_blocking(move || walk_pull_glob(&walk_root, &matcher, recursive, &limits))
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+            .map_err(|e| (StatusCode::PAYLOAD_TOO_LARGE, e))?;
+    // Just the folder itself
+    if entries.len() <= 1 {
+        return Err((StatusCode::NOT_FOUND, format!("No files match '{}'", params.pattern)));
+    }
+    tar_response(&state, client, dir, entries, truncated)
+}
+
+/// The folder entry plus every file under `root` whose relative path matches,
+/// within `PULL_GLOB_FILE_LIMIT` / `PULL_GLOB_BYTE_LIMIT`, and whether the walk
+/// left out folders past the depth limit.
+fn walk_pull_glob(
+    root: &std::path::Path,
+    matcher: &globset::GlobMatcher,
+    recursive: bool,
+    limits: &WalkLimits,
+) -> Result<(Vec<PullDirEntry>, bool), String> {
+    let (all, truncated) = walk_pull_dir(root, recursive, limits)
+        .ok_or_else(|| format!("Folder has more than {} entries", PULL_DIR_ENTRY_LIMIT))?;
+    let mut all = all.into_iter();
+    let Some(folder) = all.next() else {
+        return Ok((Vec::new(), truncated));
+    };
+    let mut entries = Vec::new();
+    let mut bytes = 0u64;
+    for entry in all.filter(|e| !e.is_dir) {
+        let Ok(relative) = entry.name.strip_prefix(&folder.name) else {
+            continue;
+        };
+        if !matcher.is_match(relative) {
+            continue;
+        }
+        bytes += entry.size;
+        if entries.len() >= PULL_GLOB_FILE_LIMIT {
+            return Err(format!("More than {} files match", PULL_GLOB_FILE_LIMIT));
+        }
+        if bytes > PULL_GLOB_BYTE_LIMIT {
+            return Err(format!("Matching files total more than {} bytes", PULL_GLOB_BYTE_LIMIT));
+        }
+        entries.push(entry);
+    }
+    entries.insert(0, folder);
+    Ok((entries, truncated))
+}
+
+/// Stream `entries` (the folder first) as a tar named after `dir`, with the
+/// `x-dir-entries` / `x-dir-bytes` progress headers and `x-dir-truncated`.
+fn tar_response(
+    state: &AppState,
+    client: Option<Extension<TransferClient>>,
+    dir: PathBuf,
+    entries: Vec<PullDirEntry>,
+    truncated: bool,
+) -> Result<Response<Body>, (StatusCode, String)> {
+    let total: u64 = entries.iter().map(|e| e.size).sum();
+    let count = entries.len();
+
+    // tar::Builder is blocking, so it writes into a pipe the body reads from.
+    // A client that goes away closes the pipe and the writer stops.
+    let (writer, reader) = tokio::io::duplex(256 * 1024);
+    let folder = dir.clone();
+    tokio::task::spawn_blocking(move || {
+        let mut builder = tar::Builder::new(SyncIoBridge::new(writer));
+        builder.follow_symlinks(false);
+        for entry in &entries {
+            // Entries that went away or can't be opened are skipped. Once a
+            // header is written any error means the archive is broken (usually
+            // because the client is gone), so that stops it.
+            let added = if entry.is_dir {
+                if std::fs::symlink_metadata(&entry.path).is_err() {
+                    continue;
+                }
+                builder.append_dir(&entry.name, &entry.path)
+            } else {
+                let mut file = match std::fs::File::open(&entry.path) {
+                    Ok(file) => file,
+                    Err(e) => {
+                        log::info!("pull-dir {}: skipping {}: {}", folder.display(), entry.path.display(), e);
+                        continue;
+                    }
+                };
+                builder.append_file(&entry.name, &mut file)
+            };
+            if let Err(e) = added {
+                log::info!("pull-dir {} stopped: {}", folder.display(), e);
+                return;
+            }
+        }
+        if let Err(e) = builder.finish() {
+            log::info!("pull-dir {} stopped: {}", folder.display(), e);
+        }
+    });
+
+    let name = dir
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "folder".to_string());
+    let client = client.map(|Extension(c)| c.0).unwrap_or_default();
+    let body = tracked_file_body(state, reader, &dir, client, total);
+
+    Response::builder()
+        .header(header::CONTENT_TYPE, "application/x-tar")
+        .header(header::CONTENT_DISPOSITION, content_disposition("attachment", &format!("{}.tar", name)))
+        .header("x-dir-entries", count)
+        .header("x-dir-bytes", total)
+        .header("x-dir-truncated", u32::from(truncated))
+        .body(body)
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Everything under `root` (itself included) in archive order, parents first;
+/// only its direct children unless `recursive`. None past `PULL_DIR_ENTRY_LIMIT`.
+/// The flag is set when folders past the depth limit (or that loop back to one
+/// already walked) were left out.
+fn walk_pull_dir(root: &std::path::Path, recursive: bool, limits: &WalkLimits) -> Option<(Vec<PullDirEntry>, bool)> {
+    let base = root.file_name().map(PathBuf::from).unwrap_or_else(|| PathBuf::from("folder"));
+    let mut entries = vec![PullDirEntry {
+        path: root.to_path_buf(),
+        name: base.clone(),
+        is_dir: true,
+        size: 0,
+    }];
+    let mut truncated = false;
+    let mut visited = VisitedDirs::default();
+    if let Ok(metadata) = std::fs::metadata(root) {
+        visited.insert(root, &metadata);
+    }
+    let mut stack = vec![(root.to_path_buf(), base, 0)];
+    while let Some((dir, name, depth)) = stack.pop() {
+        let Ok(read) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        let mut children: Vec<_> = read.flatten().collect();
+        children.sort_by_key(|e| e.file_name());
+        for child in children {
+            if child.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            let Ok(metadata) = child.path().symlink_metadata() else {
+                continue;
+            };
+            let child_name = name.join(child.file_name());
+            if metadata.is_dir() {
+                if !recursive {
+                    continue;
+                }
+                if depth >= limits.max_depth || !visited.insert(&child.path(), &metadata) {
+                    truncated = true;
+                    continue;
+                }
+                stack.push((child.path(), child_name.clone(), depth + 1));
+            } else if !metadata.is_file() {
+                continue;
+            }
+            entries.push(PullDirEntry {
+                path: child.path(),
+                name: child_name,
+                is_dir: metadata.is_dir(),
+                size: if metadata.is_file() { metadata.len() } else { 0 },
+            });
+            if entries.len() > PULL_DIR_ENTRY_LIMIT {
+                return None;
+            }
+        }
+    }
+    Some((entries, truncated))
+}
+
+/// Default cap for `/preview` when `max_bytes` is not given.
+const PREVIEW_DEFAULT_MAX_BYTES: u64 = 256 * 1024;
+/// Longest edge of the thumbnail returned for image previews.
+const PREVIEW_IMAGE_MAX_DIM: u32 = 1024;
+
+#[derive(SerdeDeserialize)]
+struct PreviewQuery {
+    path: Option<String>,
+    id: Option<String>,
+    max_bytes: Option<u64>,
+}
+
+/// GET /preview?path=<filepath>|id=<path_id>&max_bytes=<n> — cheap preview payload for a file.
+/// Images are decoded and downsized to a PNG thumbnail; anything else returns
+/// at most `max_bytes` from the head of the file, with the full size in `X-File-Size`.
+async fn preview_handler(
+    scope: Option<Extension<ClientScope>>,
+    Query(params): Query<PreviewQuery>,
+) -> Result<Response<Body>, (StatusCode, String)> {
+    let file_path = resolve_file_param(&scope, params.path, params.id)?;
+
+    let filename = file_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "file".to_string());
+    let max_bytes = params.max_bytes.unwrap_or(PREVIEW_DEFAULT_MAX_BYTES);
+
+    let mime = mime_guess::from_path(&file_path).first_or_octet_stream();
+    if mime.type_() == mime_guess::mime::IMAGE {
+        let path = file_path.clone();
+        let thumbnail = tokio::task::spawn_blocking(move || render_thumbnail(&path))
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+        // Formats the decoder doesn't know fall through to a raw head read.
+        if let Ok(png) = thumbnail {
+            return Response::builder()
+                .header(header::CONTENT_TYPE, "image/png")
+                .header(header::X_CONTENT_TYPE_OPTIONS, "nosniff")
+                .header(header::CONTENT_DISPOSITION, content_disposition("inline", &filename))
+                .header(header::CONTENT_LENGTH, png.len())
+                .body(Body::from(png))
+                .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()));
+        }
+    }
+
+    let file = tokio::fs::File::open(&file_path)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    let file_size = file.metadata().await.map(|m| m.len()).unwrap_or(0);
+    let mut head = Vec::new();
+    file.take(max_bytes)
+        .read_to_end(&mut head)
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+
+    file_response_builder(&filename, true)
+        .header(header::CONTENT_LENGTH, head.len())
+        .header("x-file-size", file_size)
+        .body(Body::from(head))
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Decode an image and re-encode it as a PNG no larger than `PREVIEW_IMAGE_MAX_DIM`.
+/// HEIC/AVIF decode only in builds with the `heif` feature.
+fn render_thumbnail(path: &std::path::Path) -> Result<Vec<u8>, String> {
+    #[cfg(feature = "heif")]
+    {
+        static HEIF_HOOKS: std::sync::Once = std::sync::Once::new();
+        HEIF_HOOKS.call_once(|| {
+            libheif_rs::integration::image::register_all_decoding_hooks();
+        });
+    }
+    let img = image::open(path).map_err(|e| e.to_string())?;
+    let thumb = if img.width() > PREVIEW_IMAGE_MAX_DIM || img.height() > PREVIEW_IMAGE_MAX_DIM {
+        img.thumbnail(PREVIEW_IMAGE_MAX_DIM, PREVIEW_IMAGE_MAX_DIM)
+    } else {
+        img
+    };
+
+    let mut out = std::io::Cursor::new(Vec::new());
+    thumb
+        .write_to(&mut out, image::ImageFormat::Png)
+        .map_err(|e| e.to_string())?;
+    Ok(out.into_inner())
+}
+
+/// Response for both upload endpoints so the client can check nothing was truncated.
+#[derive(Serialize)]
+struct UploadResult {
+    path: String,
+    /// Size of the file on disk after the write
+    bytes: u64,
+}
+
+/// Size of the written file, read back from disk.
+fn written_size(dest: &std::path::Path) -> Result<u64, (StatusCode, String)> {
+    std::fs::metadata(dest)
+        .map(|m| m.len())
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))
+}
+
+/// Largest upload body accepted by `/upload` and `/sync/upload`.
+const UPLOAD_BYTE_LIMIT: usize = 512 * 1024 * 1024;
+
+/// Stream an upload into a temp file next to `dest` and rename it into place
+/// once the whole body has arrived. A dropped connection or failed write
+/// removes the temp file and leaves whatever was at `dest` untouched.
+/// `gzip` bodies are decompressed on the way (the size limit is on the result).
+async fn write_upload(dest: &std::path::Path, body: Body, gzip: bool) -> Result<(), (StatusCode, String)> {
+    let name = dest.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+    let temp = dest.with_file_name(format!(".{}.upload-{:x}", name, rand_id()));
+    let result = match stream_body_to(&temp, body, gzip).await {
+        Ok(()) => tokio::fs::rename(&temp, dest)
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string())),
+        Err(e) => Err(e),
+    };
+    if result.is_err() {
+        let _ = tokio::fs::remove_file(&temp).await;
+    }
+    result
+}
+
+async fn stream_body_to(path: &std::path::Path, body: Body, gzip: bool) -> Result<(), (StatusCode, String)> {
+    use http_body_util::BodyExt;
+    use std::io::Write;
+    use tokio::io::AsyncWriteExt;
+
+    let io_error = |e: std::io::Error| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string());
+    let gzip_error = |e: std::io::Error| (StatusCode::BAD_REQUEST, format!("Invalid gzip body: {}", e));
+    let too_large = || (StatusCode::PAYLOAD_TOO_LARGE, format!("Uploads are limited to {} bytes", UPLOAD_BYTE_LIMIT));
+    let mut file = tokio::fs::File::create(path).await.map_err(io_error)?;
+    let mut body = http_body_util::Limited::new(body, UPLOAD_BYTE_LIMIT);
+    // Decompressed chunks collect in its Vec until they're written out
+    let mut decoder = gzip.then(|| flate2::write::GzDecoder::new(Vec::new()));
+    let mut written = 0;
+    while let Some(frame) = body.frame().await {
+        let frame = frame.map_err(|e| {
+            if e.is::<http_body_util::LengthLimitError>() {
+                too_large()
+            } else {
+                (StatusCode::BAD_REQUEST, format!("Upload interrupted: {}", e))
+            }
+        })?;
+        let Ok(data) = frame.into_data() else {
+            continue;
+        };
+        match decoder {
+            Some(ref mut decoder) => {
+                decoder.write_all(&data).map_err(gzip_error)?;
+                written += decoder.get_ref().len();
+                if written > UPLOAD_BYTE_LIMIT {
+                    return Err(too_large());
+                }
+                file.write_all(decoder.get_ref()).await.map_err(io_error)?;
+                decoder.get_mut().clear();
+            }
+            None => file.write_all(&data).await.map_err(io_error)?,
+        }
+    }
+    if let Some(decoder) = decoder {
+        let rest = decoder.finish().map_err(gzip_error)?;
+        if written + rest.len() > UPLOAD_BYTE_LIMIT {
+            return Err(too_large());
+        }
+        file.write_all(&rest).await.map_err(io_error)?;
+    }
+    file.sync_all().await.map_err(io_error)
+}
+
+/// Whether an upload's `Content-Encoding` is gzip; anything else but identity is refused.
+fn gzip_upload(headers: &HeaderMap) -> Result<bool, (StatusCode, String)> {
+    match headers.get(header::CONTENT_ENCODING).map(|v| v.to_str().unwrap_or("").trim().to_ascii_lowercase()) {
+        None => Ok(false),
+        Some(encoding) if encoding == "identity" => Ok(false),
+        Some(encoding) if encoding == "gzip" => Ok(true),
+        Some(encoding) => Err((
+            StatusCode::UNSUPPORTED_MEDIA_TYPE,
+            format!("Unsupported Content-Encoding '{}'; send gzip or nothing", encoding),
+        )),
+    }
+}
+
 /// PUT /upload/{*path} — upload a file (raw body bytes) to the given path relative to $HOME.
 async fn upload_handler(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
     Path(file_path): Path<String>,
-    body: Bytes,
-) -> Result<StatusCode, (StatusCode, String)> {
-    let home = std::env::var("HOME").unwrap_or_else(|_| "/".to_string());
-    let dest = std::path::PathBuf::from(&home).join(&file_path);
+    body: Body,
+) -> Result<Json<UploadResult>, (StatusCode, String)> {
+    let dest = state.root.join(&file_path);
+    check_scope(&scope, &dest)?;
 
     if let Some(parent) = dest.parent() {
         std::fs::create_dir_all(parent)
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     }
 
-    std::fs::write(&dest, &body)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    write_upload(&dest, body, false).await?;
 
-    log::info!("Uploaded: {}", file_path);
-    Ok(StatusCode::OK)
+    let bytes = written_size(&dest)?;
+    state.metrics.record_upload(bytes);
+    log::info!("Uploaded: {} ({} bytes)", file_path, bytes);
+    Ok(Json(UploadResult {
+        path: dest.to_string_lossy().to_string(),
+        bytes,
+    }))
 }
 
 // --- Sync endpoints ---
 
-/// GET /sync/projects — list all sync projects
+/// GET /sync/projects — list the sync projects inside the client's root
 async fn sync_list_projects(
     State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
 ) -> Json<Vec<crate::app_state::SyncProject>> {
-    let projects = state.sync_projects.lock().unwrap();
-    Json(projects.clone())
+    let projects = state.sync_projects.lock().unwrap().clone();
+    Json(scoped_projects(&scope, &projects))
+}
+
+/// The project with `id`, cloned, if the client may see it.
+fn scoped_project(
+    state: &AppState,
+    scope: &Option<Extension<ClientScope>>,
+    id: &str,
+) -> Result<crate::app_state::SyncProject, (StatusCode, String)> {
+    let project = state
+        .sync_projects
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|p| p.id == id)
+        .cloned()
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Project '{}' not found", id)))?;
+    check_scope(scope, std::path::Path::new(&project.local_path))?;
+    Ok(project)
+}
+
+/// True when the two sync endpoints are the same location or one contains
+/// the other — syncing them would feed each change straight back in.
+fn sync_paths_overlap(a: &str, b: &str) -> bool {
+    let (a, b) = (normalize_lexically(a), normalize_lexically(b));
+    a.starts_with(&b) || b.starts_with(&a)
+}
+
+/// Reject a blank path, or a desktop/iOS path pair that would re-sync into itself.
+fn check_sync_paths(local_path: &str, remote_path: &str) -> Result<(), (StatusCode, String)> {
+    // A blank path normalizes to nothing, which every path would "contain"
+    if local_path.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Desktop path is empty".to_string()));
+    }
+    if remote_path.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "iOS path is empty".to_string()));
+    }
+    if sync_paths_overlap(local_path, remote_path) {
+        return Err((
+            StatusCode::BAD_REQUEST,
+            format!(
+                "Sync paths overlap: '{}' and '{}' are the same or nested",
+                local_path, remote_path
+            ),
+        ));
+    }
+    Ok(())
 }
 
 #[derive(SerdeDeserialize)]
 struct CreateSyncProjectRequest {
     local_path: String,
     remote_path: String,
+    #[serde(default)]
+    direction: crate::app_state::SyncDirection,
 }
 
 /// POST /sync/projects — create a new sync project (rejects duplicates)
 async fn sync_create_project(
     State(state): State<AppState>,
-    Json(body): Json<CreateSyncProjectRequest>,
+    scope: Option<Extension<ClientScope>>,
+    Json(mut body): Json<CreateSyncProjectRequest>,
 ) -> Result<Json<crate::app_state::SyncProject>, (StatusCode, String)> {
+    body.local_path = expand_home(&body.local_path, &state.root);
+    check_scope(&scope, std::path::Path::new(&body.local_path))?;
+    check_sync_paths(&body.local_path, &body.remote_path)?;
+
     let mut projects = state.sync_projects.lock().unwrap();
 
     // ── Duplicate check: reject if the same desktop file is already synced ──
@@ -405,30 +2320,260 @@ async fn sync_create_project(
         paused: false,
         device_name,
         device_dns,
+        direction: body.direction,
     };
 
     projects.push(project.clone());
-    save_sync_projects(&projects);
-    log::info!("Created sync project: {} -> {}", project.local_path, project.remote_path);
+    save_sync_projects(&state.root, &projects);
+    log::info!(
+        "Created sync project: {} -> {} ({:?})",
+        project.local_path, project.remote_path, project.direction
+    );
     Ok(Json(project))
 }
 
 /// DELETE /sync/projects/{id} — remove a sync project
 async fn sync_delete_project(
     State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
     Path(id): Path<String>,
 ) -> Result<StatusCode, (StatusCode, String)> {
+    scoped_project(&state, &scope, &id)?;
     let mut projects = state.sync_projects.lock().unwrap();
     let before = projects.len();
     projects.retain(|p| p.id != id);
     if projects.len() == before {
         return Err((StatusCode::NOT_FOUND, format!("Project '{}' not found", id)));
     }
-    save_sync_projects(&projects);
+    save_sync_projects(&state.root, &projects);
+    state.sync_changes.lock().unwrap().remove(&id);
+    state.sync_history.lock().unwrap().remove(&id);
     log::info!("Deleted sync project: {}", id);
     Ok(StatusCode::OK)
 }
 
+/// DELETE /sync/projects — remove every sync project inside the client's root
+async fn sync_clear_projects(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+) -> Json<serde_json::Value> {
+    let mut projects = state.sync_projects.lock().unwrap();
+    let (removed, kept): (Vec<_>, Vec<_>) = projects
+        .drain(..)
+        .partition(|p| check_scope(&scope, std::path::Path::new(&p.local_path)).is_ok());
+    *projects = kept;
+    let deleted = removed.len();
+    save_sync_projects(&state.root, &projects);
+    let mut changes = state.sync_changes.lock().unwrap();
+    let mut history = state.sync_history.lock().unwrap();
+    for project in &removed {
+        changes.remove(&project.id);
+        history.remove(&project.id);
+    }
+    log::info!("Cleared sync projects ({})", deleted);
+    Json(serde_json::json!({ "deleted": deleted }))
+}
+
+#[derive(SerdeDeserialize)]
+struct UpdateSyncProjectRequest {
+    local_path: Option<String>,
+    remote_path: Option<String>,
+    paused: Option<bool>,
+    direction: Option<crate::app_state::SyncDirection>,
+}
+
+/// PUT /sync/projects/{id} — change a project's paths, direction or paused flag.
+/// `last_synced` is reset only when a path actually changes, so the next
+/// check re-syncs into the new location.
+async fn sync_update_project(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+    Path(id): Path<String>,
+    Json(mut body): Json<UpdateSyncProjectRequest>,
+) -> Result<Json<crate::app_state::SyncProject>, (StatusCode, String)> {
+    scoped_project(&state, &scope, &id)?;
+    body.local_path = body.local_path.map(|p| expand_home(&p, &state.root));
+    if let Some(ref local_path) = body.local_path {
+        check_scope(&scope, std::path::Path::new(local_path))?;
+        if !std::path::Path::new(local_path).exists() {
+            return Err((
+                StatusCode::BAD_REQUEST,
+                format!("Desktop path '{}' does not exist", local_path),
+            ));
+        }
+    }
+    if body.remote_path.as_deref().is_some_and(|p| p.trim().is_empty()) {
+        return Err((StatusCode::BAD_REQUEST, "iOS path must not be empty".to_string()));
+    }
+
+    let mut projects = state.sync_projects.lock().unwrap();
+    if let Some(ref local_path) = body.local_path
+        && projects.iter().any(|p| p.id != id && &p.local_path == local_path)
+    {
+        return Err((
+            StatusCode::CONFLICT,
+            format!("A sync already exists for '{}'", local_path),
+        ));
+    }
+    let project = projects
+        .iter_mut()
+        .find(|p| p.id == id)
+        .ok_or_else(|| (StatusCode::NOT_FOUND, format!("Project '{}' not found", id)))?;
+    check_sync_paths(
+        body.local_path.as_deref().unwrap_or(&project.local_path),
+        body.remote_path.as_deref().unwrap_or(&project.remote_path),
+    )?;
+
+    let mut path_changed = false;
+    if let Some(local_path) = body.local_path {
+        path_changed |= project.local_path != local_path;
+        project.local_path = local_path;
+    }
+    if let Some(remote_path) = body.remote_path {
+        path_changed |= project.remote_path != remote_path;
+        project.remote_path = remote_path;
+    }
+    if let Some(paused) = body.paused {
+        project.paused = paused;
+    }
+    if let Some(direction) = body.direction {
+        project.direction = direction;
+    }
+    if path_changed {
+        project.last_synced = 0;
+    }
+
+    let updated = project.clone();
+    save_sync_projects(&state.root, &projects);
+    log::info!("Updated sync project {}: {} -> {}", updated.id, updated.local_path, updated.remote_path);
+    Ok(Json(updated))
+}
+
+#[derive(Serialize)]
+struct SyncPlanEntry {
+    path: String,
+    /// "add" (never synced), "modify" (changed since last sync) or "delete" (gone from the desktop)
+    action: &'static str,
+    size: u64,
+    modified: u64,
+}
+
+#[derive(Serialize)]
+struct SyncPlan {
+    id: String,
+    /// iOS-side path and last sync time, so the client can add its own pending pushes
+    remote_path: String,
+    last_synced: u64,
+    entries: Vec<SyncPlanEntry>,
+    /// Bytes the desktop would send (adds + modifies)
+    total_bytes: u64,
+    /// The directory walk hit the same limits as /dirinfo
+    truncated: bool,
+    /// Lets the client leave out its own push for desktop → phone mirrors
+    direction: crate::app_state::SyncDirection,
+    /// Paused projects plan nothing until they're resumed
+    paused: bool,
+}
+
+/// GET /sync/projects/{id}/plan — what the next sync would transfer from the
+/// desktop, without doing it
+async fn sync_plan(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+    Path(id): Path<String>,
+) -> Result<Json<SyncPlan>, (StatusCode, String)> {
+    let project = scoped_project(&state, &scope, &id)?;
+
+    let last_synced = project.last_synced;
+    let root = PathBuf::from(&project.local_path);
+    let (entries, truncated) = if project.direction.pulls_to_phone() && !project.paused {
+        let limits = state.walk_limits;
+        tokio::task::spawn_blocking(move || plan_entries(&root, last_synced, &limits))
+            .await
+            .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+    } else {
+        (Vec::new(), false)
+    };
+
+    let total_bytes = entries.iter().filter(|e| e.action != "delete").map(|e| e.size).sum();
+    Ok(Json(SyncPlan {
+        id: project.id,
+        remote_path: project.remote_path,
+        last_synced,
+        entries,
+        total_bytes,
+        truncated,
+        direction: project.direction,
+        paused: project.paused,
+    }))
+}
+
+/// Files under `root` (or `root` itself) changed after `last_synced`, walked
+/// like `/dirinfo`.
+fn plan_entries(root: &std::path::Path, last_synced: u64, limits: &WalkLimits) -> (Vec<SyncPlanEntry>, bool) {
+    let modified_secs = |m: &std::fs::Metadata| {
+        m.modified()
+            .ok()
+            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .unwrap_or(0)
+    };
+    let entry_for = |path: &std::path::Path, m: &std::fs::Metadata| {
+        let modified = modified_secs(m);
+        (modified > last_synced).then(|| SyncPlanEntry {
+            path: path.to_string_lossy().to_string(),
+            action: if last_synced == 0 { "add" } else { "modify" },
+            size: m.len(),
+            modified,
+        })
+    };
+
+    let Ok(metadata) = std::fs::metadata(root) else {
+        let gone = SyncPlanEntry {
+            path: root.to_string_lossy().to_string(),
+            action: "delete",
+            size: 0,
+            modified: 0,
+        };
+        return (vec![gone], false);
+    };
+    if !metadata.is_dir() {
+        return (entry_for(root, &metadata).into_iter().collect(), false);
+    }
+
+    let started = Instant::now();
+    let mut seen = 0u64;
+    let mut truncated = false;
+    let mut entries = Vec::new();
+    let mut visited = VisitedDirs::default();
+    visited.insert(root, &metadata);
+    let mut stack = vec![(root.to_path_buf(), 0)];
+    while let Some((dir, depth)) = stack.pop() {
+        let Ok(children) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for child in children.flatten() {
+            seen += 1;
+            if seen >= limits.max_entries || started.elapsed() >= DIR_INFO_TIME_LIMIT {
+                return (entries, true);
+            }
+            let Ok(m) = child.path().symlink_metadata() else {
+                continue;
+            };
+            if m.is_dir() {
+                if depth >= limits.max_depth {
+                    truncated = true;
+                } else if visited.insert(&child.path(), &m) {
+                    stack.push((child.path(), depth + 1));
+                }
+            } else if m.is_file() {
+                entries.extend(entry_for(&child.path(), &m));
+            }
+        }
+    }
+    (entries, truncated)
+}
+
 #[derive(Serialize)]
 struct SyncChangeResponse {
     id: String,
@@ -437,35 +2582,276 @@ struct SyncChangeResponse {
     new_modified: u64,
 }
 
-/// GET /sync/check — return projects where the desktop file has been modified since last sync
-async fn sync_check(
-    State(state): State<AppState>,
-) -> Json<Vec<SyncChangeResponse>> {
-    let projects = state.sync_projects.lock().unwrap();
-    let mut changes = Vec::new();
-    for project in projects.iter() {
-        if project.paused {
+/// When a project's desktop side last changed: the file's mtime or the newest
+/// change the watcher saw, whichever is later. None if the path is gone.
+fn sync_desktop_modified(project: &crate::app_state::SyncProject, watched: &HashMap<String, u64>) -> Option<u64> {
+    let metadata = std::fs::metadata(&project.local_path).ok()?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Some(modified.max(watched.get(&project.id).copied().unwrap_or(0)))
+}
+
+/// The desktop change the phone should pull for `project`, if any. Paused and
+/// phone → desktop projects never have one, nor do folders: the phone pulls
+/// a project as a single file.
+fn pending_sync_change(project: &crate::app_state::SyncProject, watched: &HashMap<String, u64>) -> Option<u64> {
+    if project.paused || !project.direction.pulls_to_phone() || std::path::Path::new(&project.local_path).is_dir() {
+        return None;
+    }
+    sync_desktop_modified(project, watched).filter(|&modified| modified > project.last_synced)
+}
+
+/// GET /sync/check — return projects where the desktop file has been modified since last sync.
+/// Changes the file watcher saw count too.
+async fn sync_check(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+) -> Json<Vec<SyncChangeResponse>> {
+    let projects = state.sync_projects.lock().unwrap().clone();
+    let projects = scoped_projects(&scope, &projects);
+    let watched = state.sync_changes.lock().unwrap().clone();
+    let changes = projects
+        .iter()
+        .filter_map(|project| {
+            pending_sync_change(project, &watched).map(|modified| SyncChangeResponse {
+                id: project.id.clone(),
+                remote_path: project.remote_path.clone(),
+                local_path: project.local_path.clone(),
+                new_modified: modified,
+            })
+        })
+        .collect();
+    Json(changes)
+}
+
+/// Entries kept per project in `AppState::sync_history`.
+const SYNC_HISTORY_KEPT: usize = 10;
+
+/// Something that happened to a sync project since the app started.
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncHistoryEntry {
+    /// Unix seconds
+    pub at: u64,
+    /// "pushed" (the phone uploaded `path`) or "synced" (the phone acked the project)
+    pub action: &'static str,
+    pub path: String,
+}
+
+fn record_sync_history(state: &AppState, id: &str, action: &'static str, path: String) {
+    let at = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let mut history = state.sync_history.lock().unwrap();
+    let entries = history.entry(id.to_string()).or_default();
+    if entries.len() == SYNC_HISTORY_KEPT {
+        entries.pop_front();
+    }
+    entries.push_back(SyncHistoryEntry { at, action, path });
+}
+
+#[derive(Serialize)]
+struct SyncStatusEntry {
+    #[serde(flatten)]
+    project: crate::app_state::SyncProject,
+    /// `/sync/check` would list this project
+    pending: bool,
+    /// When the desktop side last changed (see `sync_desktop_modified`); 0 if it's gone
+    desktop_modified: u64,
+    /// Newest last
+    history: Vec<SyncHistoryEntry>,
+}
+
+/// GET /sync/status — every project with its `/sync/check` state and recent
+/// history, so a dashboard needs one request instead of `/sync/projects` plus
+/// `/sync/check`.
+async fn sync_status(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+) -> Result<Json<Vec<SyncStatusEntry>>, (StatusCode, String)> {
+    // Copied out so the stats below don't hold up the watcher or uploads
+    let projects = state.sync_projects.lock().unwrap().clone();
+    let watched = state.sync_changes.lock().unwrap().clone();
+    let mut history = state.sync_history.lock().unwrap().clone();
+    let entries = tokio::task::spawn_blocking(move || {
+        scoped_projects(&scope, &projects)
+            .into_iter()
+            .map(|project| SyncStatusEntry {
+                pending: pending_sync_change(&project, &watched).is_some(),
+                desktop_modified: sync_desktop_modified(&project, &watched).unwrap_or(0),
+                history: history.remove(&project.id).map(Vec::from).unwrap_or_default(),
+                project,
+            })
+            .collect()
+    })
+    .await
+    .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    Ok(Json(entries))
+}
+
+// --- Sync file watching ---
+
+/// Bursts of events (editors often write a file several times per save) are
+/// recorded once, this long after the last one.
+const SYNC_WATCH_DEBOUNCE: Duration = Duration::from_millis(300);
+/// How often the watched paths are matched up with the sync project list.
+const SYNC_WATCH_RECONCILE: Duration = Duration::from_secs(2);
+/// Scan interval of the polling watcher used when inotify isn't available.
+const SYNC_WATCH_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+type SyncWatcher = Box<dyn notify::Watcher + Send>;
+
+/// Watch every active sync project and record changes in `sync_changes` as
+/// they happen, instead of waiting for `/sync/check` to stat them.
+async fn watch_sync_projects(state: AppState) {
+    let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Vec<PathBuf>>();
+    let Some(mut watcher) = new_sync_watcher(tx) else {
+        return;
+    };
+    let mut watched = HashMap::new();
+    let mut pending = Vec::new();
+    let mut deadline = None;
+    let mut reconcile = tokio::time::interval(SYNC_WATCH_RECONCILE);
+    loop {
+        let debounce = tokio::time::sleep_until(deadline.unwrap_or_else(tokio::time::Instant::now));
+        tokio::select! {
+            paths = rx.recv() => {
+                let Some(paths) = paths else {
+                    return;
+                };
+                pending.extend(paths);
+                deadline = Some(tokio::time::Instant::now() + SYNC_WATCH_DEBOUNCE);
+            }
+            _ = debounce, if deadline.is_some() => {
+                deadline = None;
+                let paths = std::mem::take(&mut pending);
+                mark_sync_changes(&state, paths);
+            }
+            _ = reconcile.tick() => {
+                // Adding a recursive watch walks the whole folder
+                let state = state.clone();
+                let reconciled = tokio::task::spawn_blocking(move || {
+                    reconcile_sync_watches(&state, &mut watcher, &mut watched);
+                    (watcher, watched)
+                })
+                .await;
+                let Ok(reconciled) = reconciled else {
+                    return;
+                };
+                (watcher, watched) = reconciled;
+            }
+        }
+    }
+}
+
+/// inotify (or the platform's equivalent), else a watcher that rescans every
+/// `SYNC_WATCH_POLL_INTERVAL`. `None` leaves `/sync/check`'s own mtime checks.
+fn new_sync_watcher(tx: tokio::sync::mpsc::UnboundedSender<Vec<PathBuf>>) -> Option<SyncWatcher> {
+    use notify::Watcher;
+    let handler = move |event: notify::Result<notify::Event>| match event {
+        Ok(event) if !event.kind.is_access() => {
+            let _ = tx.send(event.paths);
+        }
+        Ok(_) => {}
+        Err(e) => log::debug!("Sync watcher: {}", e),
+    };
+    // Like the walks, a symlinked folder isn't descended into
+    let config = notify::Config::default().with_follow_symlinks(false);
+    let e = match notify::RecommendedWatcher::new(handler.clone(), config) {
+        Ok(watcher) => return Some(Box::new(watcher)),
+        Err(e) => e,
+    };
+    log::warn!("Can't watch sync projects for changes ({}); scanning them instead", e);
+    let config = config.with_poll_interval(SYNC_WATCH_POLL_INTERVAL);
+    match notify::PollWatcher::new(handler, config) {
+        Ok(watcher) => Some(Box::new(watcher)),
+        Err(e) => {
+            log::warn!("Can't scan sync projects for changes either: {}", e);
+            None
+        }
+    }
+}
+
+/// A folder is watched recursively; a file through its parent folder, since
+/// editors often save by replacing the file, which drops a watch on the file itself.
+fn sync_watch_target(local_path: &str) -> (PathBuf, notify::RecursiveMode) {
+    let path = PathBuf::from(local_path);
+    if path.is_dir() {
+        return (path, notify::RecursiveMode::Recursive);
+    }
+    let parent = path.parent().map(|p| p.to_path_buf()).unwrap_or(path);
+    (parent, notify::RecursiveMode::NonRecursive)
+}
+
+/// Watch what the active projects need and drop what they no longer do. A
+/// path that can't be watched is logged once and left to `/sync/check`.
+fn reconcile_sync_watches(
+    state: &AppState,
+    watcher: &mut SyncWatcher,
+    watched: &mut HashMap<PathBuf, notify::RecursiveMode>,
+) {
+    let mut wanted = HashMap::new();
+    for project in state.sync_projects.lock().unwrap().iter().filter(|p| !p.paused) {
+        let (path, mode) = sync_watch_target(&project.local_path);
+        let entry = wanted.entry(path).or_insert(mode);
+        if mode == notify::RecursiveMode::Recursive {
+            *entry = mode;
+        }
+    }
+    watched.retain(|path, mode| {
+        if wanted.get(path) == Some(mode) {
+            return true;
+        }
+        let _ = watcher.unwatch(path);
+        false
+    });
+    for (path, mode) in wanted {
+        if watched.contains_key(&path) {
             continue;
         }
-        let path = std::path::Path::new(&project.local_path);
-        if let Ok(metadata) = std::fs::metadata(path) {
-            let modified = metadata
-                .modified()
+        if let Err(e) = watcher.watch(&path, mode) {
+            log::warn!("Not watching {} for sync changes: {}", path.display(), e);
+        }
+        watched.insert(path, mode);
+    }
+}
+
+/// Record the newest mtime among `paths` against each active project they fall
+/// under. A path that no longer exists counts as changed now.
+fn mark_sync_changes(state: &AppState, mut paths: Vec<PathBuf>) {
+    paths.sort();
+    paths.dedup();
+    let stamped: Vec<(PathBuf, u64)> = paths
+        .into_iter()
+        .map(|path| {
+            let modified = std::fs::symlink_metadata(&path)
+                .and_then(|m| m.modified())
                 .ok()
-                .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+                .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
                 .map(|d| d.as_secs())
-                .unwrap_or(0);
-            if modified > project.last_synced {
-                changes.push(SyncChangeResponse {
-                    id: project.id.clone(),
-                    remote_path: project.remote_path.clone(),
-                    local_path: project.local_path.clone(),
-                    new_modified: modified,
-                });
-            }
+                .unwrap_or_else(unix_timestamp);
+            (path, modified)
+        })
+        .collect();
+
+    let projects = state.sync_projects.lock().unwrap();
+    let mut changes = state.sync_changes.lock().unwrap();
+    for project in projects.iter().filter(|p| !p.paused) {
+        let root = std::path::Path::new(&project.local_path);
+        let newest = stamped
+            .iter()
+            .filter(|(path, _)| path.starts_with(root))
+            .map(|(_, modified)| *modified)
+            .max();
+        if let Some(newest) = newest
+            && newest > project.last_synced
+        {
+            log::debug!("Sync project {} changed on disk", project.id);
+            let seen = changes.entry(project.id.clone()).or_default();
+            *seen = (*seen).max(newest);
         }
     }
-    Json(changes)
 }
 
 #[derive(SerdeDeserialize)]
@@ -477,12 +2863,20 @@ struct SyncAckRequest {
 /// POST /sync/ack — iOS confirms it pulled a file; updates last_synced
 async fn sync_ack(
     State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
     Json(body): Json<SyncAckRequest>,
 ) -> Result<StatusCode, (StatusCode, String)> {
+    scoped_project(&state, &scope, &body.id)?;
     let mut projects = state.sync_projects.lock().unwrap();
     if let Some(project) = projects.iter_mut().find(|p| p.id == body.id) {
         project.last_synced = body.timestamp;
-        save_sync_projects(&projects);
+        let path = project.local_path.clone();
+        save_sync_projects(&state.root, &projects);
+        let mut changes = state.sync_changes.lock().unwrap();
+        if changes.get(&body.id).is_some_and(|&seen| seen <= body.timestamp) {
+            changes.remove(&body.id);
+        }
+        record_sync_history(&state, &body.id, "synced", path);
         Ok(StatusCode::OK)
     } else {
         Err((StatusCode::NOT_FOUND, format!("Project '{}' not found", body.id)))
@@ -496,18 +2890,33 @@ struct SyncUploadQuery {
 
 /// PUT /sync/upload?path=<absolute_path> — upload a file to an absolute path on the desktop
 async fn sync_upload_handler(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
     Query(params): Query<SyncUploadQuery>,
-    body: Bytes,
-) -> Result<StatusCode, (StatusCode, String)> {
-    let dest = std::path::PathBuf::from(&params.path);
+    headers: HeaderMap,
+    body: Body,
+) -> Result<Json<UploadResult>, (StatusCode, String)> {
+    // `~/…` lands in the server's home, like project paths
+    let dest = std::path::PathBuf::from(expand_home(&params.path, &state.root));
+    check_scope(&scope, &dest)?;
+    let gzip = gzip_upload(&headers)?;
+
+    // A desktop → phone project is a read-only mirror on the phone side
+    if let Some(project) = state.sync_projects.lock().unwrap().iter().find(|p| {
+        !p.direction.pushes_from_phone() && dest.starts_with(&p.local_path)
+    }) {
+        return Err((
+            StatusCode::FORBIDDEN,
+            format!("'{}' only syncs from desktop to phone", project.local_path),
+        ));
+    }
 
     if let Some(parent) = dest.parent() {
         std::fs::create_dir_all(parent)
             .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
     }
 
-    std::fs::write(&dest, &body)
-        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    write_upload(&dest, body, gzip).await?;
 
     // ── Fix permissions so non-root users can read/write the file ──
     #[cfg(unix)]
@@ -517,8 +2926,20 @@ async fn sync_upload_handler(
         let _ = std::fs::set_permissions(&dest, perms);
     }
 
-    log::info!("Sync upload: {}", params.path);
-    Ok(StatusCode::OK)
+    let bytes = written_size(&dest)?;
+    state.metrics.record_upload(bytes);
+    log::info!("Sync upload: {} ({} bytes)", params.path, bytes);
+    let project = state
+        .sync_projects
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|p| dest.starts_with(&p.local_path))
+        .map(|p| p.id.clone());
+    if let Some(id) = project {
+        record_sync_history(&state, &id, "pushed", dest.to_string_lossy().to_string());
+    }
+    Ok(Json(UploadResult { path: params.path, bytes }))
 }
 
 // --- File info endpoint (for overwrite confirmation) ---
@@ -537,10 +2958,12 @@ struct FileInfoResponse {
 
 /// GET /sync/file-info?path=<path> — check if a file exists and return its metadata
 async fn sync_file_info(
+    scope: Option<Extension<ClientScope>>,
     Query(params): Query<FileInfoQuery>,
-) -> Json<FileInfoResponse> {
+) -> Result<Json<FileInfoResponse>, (StatusCode, String)> {
     let path = std::path::Path::new(&params.path);
-    if let Ok(metadata) = std::fs::metadata(path) {
+    check_scope(&scope, path)?;
+    Ok(if let Ok(metadata) = std::fs::metadata(path) {
         let modified = metadata
             .modified()
             .ok()
@@ -558,7 +2981,51 @@ async fn sync_file_info(
             modified: 0,
             size: 0,
         })
+    })
+}
+
+#[derive(Serialize)]
+struct FileHashResponse {
+    path: String,
+    size: u64,
+    crc32: u32,
+}
+
+/// GET /sync/hash?path=<path> — CRC-32 of a file, so a pushed sync upload can
+/// be checked against the phone's copy before it's acked.
+async fn sync_hash_handler(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+    Query(params): Query<FileInfoQuery>,
+) -> Result<Json<FileHashResponse>, (StatusCode, String)> {
+    let path = std::path::PathBuf::from(expand_home(&params.path, &state.root));
+    check_scope(&scope, &path)?;
+    let (crc32, size) = tokio::task::spawn_blocking(move || file_crc32(&path))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| match e.kind() {
+            std::io::ErrorKind::NotFound => (StatusCode::NOT_FOUND, format!("{} not found", params.path)),
+            _ => (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()),
+        })?;
+    Ok(Json(FileHashResponse { path: params.path, size, crc32 }))
+}
+
+/// CRC-32 and length of a file, read in chunks.
+fn file_crc32(path: &std::path::Path) -> std::io::Result<(u32, u64)> {
+    use std::io::Read;
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0u8; 64 * 1024];
+    let mut size = 0u64;
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        size += n as u64;
     }
+    Ok((hasher.finalize(), size))
 }
 
 /// Simple random ID generator (no external crate needed)
@@ -573,27 +3040,1280 @@ fn rand_id() -> u64 {
 
 // --- Server ---
 
-pub async fn run_status_server(state: AppState) -> anyhow::Result<()> {
-    let app = Router::new()
-        .route("/status", get(status_handler))
-        .route("/files", get(list_files_handler))
+/// Response header on file transfers saying how the requesting peer is reached
+/// from this machine (`direct` or `relay <region>`). Absent when unknown.
+const TRANSFER_PATH_HEADER: &str = "x-tailscale-path";
+
+/// Tailnet IP of whoever sent the request. Behind `tailscale serve` the socket
+/// peer is loopback and the real client is the `X-Forwarded-For` hop serve
+/// appends, the last one; anything before it came from the client.
+fn requester_ip(addr: std::net::SocketAddr, headers: &HeaderMap) -> String {
+    if addr.ip().is_loopback()
+        && let Some(forwarded) = headers
+            .get("x-forwarded-for")
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.rsplit(',').next())
+    {
+        return forwarded.trim().to_string();
+    }
+    addr.ip().to_string()
+}
+
+/// Who a transfer is for, as shown in `/transfers`: peer hostname, else its IP.
+#[derive(Clone)]
+struct TransferClient(String);
+
+/// For `/pull` and `/download`: records the requester for transfer tracking and
+/// tags the response with the requester's current connection type.
+async fn tag_transfer_path(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<std::net::SocketAddr>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    let ip = requester_ip(addr, request.headers());
+    // Resolved before the transfer starts; tailscaled may switch paths mid-stream
+    let (client, connection) = {
+        let peers = state.peers.lock().unwrap();
+        match peers.iter().find(|p| p.ip_addresses.contains(&ip)) {
+            Some(p) => (p.hostname.clone(), p.connection.clone()),
+            None => (ip, None),
+        }
+    };
+    request.extensions_mut().insert(TransferClient(client));
+
+    let mut response = next.run(request).await;
+    if let Some(value) = connection.and_then(|c| HeaderValue::from_str(&c).ok()) {
+        response.headers_mut().insert(TRANSFER_PATH_HEADER, value);
+    }
+    response
+}
+
+/// The `client_roots.json` entry that covers a request. None when the file
+/// exists but no entry matches: that client may see nothing.
+#[derive(Clone)]
+struct ClientScope(Option<ClientRoot>);
+
+/// Attach the requester's `ClientScope` whenever `client_roots.json` is in use.
+async fn scope_client(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<std::net::SocketAddr>,
+    mut request: Request,
+    next: Next,
+) -> Response {
+    if !state.client_roots.is_empty() {
+        let headers = request.headers();
+        let token = headers
+            .get(header::AUTHORIZATION)
+            .and_then(|v| v.to_str().ok())
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::trim);
+        let ip = requester_ip(addr, headers);
+        let entry = {
+            let peers = state.peers.lock().unwrap();
+            let peer = peers.iter().find(|p| p.ip_addresses.contains(&ip));
+            match_client_root(&state.client_roots, token, peer, &ip).cloned()
+        };
+        request.extensions_mut().insert(ClientScope(entry));
+    }
+    next.run(request).await
+}
+
+/// 403 unless `path` is inside the client's root. Both sides are resolved
+/// first so a symlink can't lead out, and a missing root allows nothing. A path
+/// that doesn't exist yet (an upload) is judged by its nearest existing parent.
+fn check_scope(scope: &Option<Extension<ClientScope>>, path: &std::path::Path) -> Result<(), (StatusCode, String)> {
+    let Some(Extension(ClientScope(entry))) = scope else {
+        return Ok(());
+    };
+    let Some(entry) = entry else {
+        return Err((StatusCode::FORBIDDEN, "This client isn't listed in client_roots.json".to_string()));
+    };
+    let inside = match (std::fs::canonicalize(&entry.root), resolve_existing_prefix(path)) {
+        (Ok(root), Some(path)) => path.starts_with(root),
+        _ => false,
+    };
+    if inside {
+        Ok(())
+    } else {
+        Err((StatusCode::FORBIDDEN, "Outside this client's folder".to_string()))
+    }
+}
+
+/// 403 unless the client's root covers the whole server root. The inbox, notes
+/// and transfers aren't tied to a folder, so a narrower client doesn't get them.
+fn check_whole_root(state: &AppState, scope: &Option<Extension<ClientScope>>) -> Result<(), (StatusCode, String)> {
+    check_scope(scope, &state.root).map_err(|(status, message)| match scope {
+        Some(Extension(ClientScope(Some(_)))) => (status, "Only clients that see the whole home folder can use this".to_string()),
+        _ => (status, message),
+    })
+}
+
+/// `path` with its longest existing prefix resolved. None if a missing part is
+/// `..` (it would apply after the symlinks were followed) or nothing exists.
+fn resolve_existing_prefix(path: &std::path::Path) -> Option<PathBuf> {
+    let mut missing = Vec::new();
+    let mut existing = path;
+    loop {
+        if let Ok(resolved) = std::fs::canonicalize(existing) {
+            return Some(missing.iter().rev().fold(resolved, |p, part| p.join(part)));
+        }
+        match existing.components().next_back()? {
+            std::path::Component::Normal(part) => missing.push(part),
+            _ => return None,
+        }
+        existing = existing.parent()?;
+    }
+}
+
+/// Keep only the sync projects whose desktop side is inside the client's root.
+fn scoped_projects(
+    scope: &Option<Extension<ClientScope>>,
+    projects: &[crate::app_state::SyncProject],
+) -> Vec<crate::app_state::SyncProject> {
+    projects
+        .iter()
+        .filter(|p| check_scope(scope, std::path::Path::new(&p.local_path)).is_ok())
+        .cloned()
+        .collect()
+}
+
+/// Log every request with its status and duration under the `http` target.
+/// Polling traffic is debug-level; client and server errors are warnings.
+async fn log_request(request: Request, next: Next) -> Response {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let started = Instant::now();
+    let response = next.run(request).await;
+    let status = response.status();
+    let level = if status.is_client_error() || status.is_server_error() {
+        log::Level::Warn
+    } else {
+        log::Level::Debug
+    };
+    log::log!(
+        target: "http",
+        level,
+        "{} {} → {} in {} ms",
+        method,
+        path,
+        status.as_u16(),
+        started.elapsed().as_millis()
+    );
+    response
+}
+
+/// POST /note — store a text snippet from another device (body is the text)
+async fn post_note_handler(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<std::net::SocketAddr>,
+    headers: HeaderMap,
+    body: Result<String, StringRejection>,
+) -> Result<Json<Note>, (StatusCode, String)> {
+    // The route's body limit stops oversized notes before they're buffered
+    let body = body.map_err(|e| match e.status() {
+        StatusCode::PAYLOAD_TOO_LARGE => (
+            StatusCode::PAYLOAD_TOO_LARGE,
+            format!("Notes are limited to {} KB; send a file instead", NOTE_MAX_BYTES / 1024),
+        ),
+        status => (status, e.body_text()),
+    })?;
+    if body.trim().is_empty() {
+        return Err((StatusCode::BAD_REQUEST, "Note is empty".to_string()));
+    }
+    let ip = requester_ip(addr, &headers);
+    let from = state
+        .peers
+        .lock()
+        .unwrap()
+        .iter()
+        .find(|p| p.ip_addresses.contains(&ip))
+        .map(|p| p.hostname.clone())
+        .unwrap_or(ip);
+
+    let _writing = state.notes_write.lock().await;
+    let note = Note {
+        id: state.notes.lock().unwrap().next_id(),
+        text: body,
+        from,
+        received_at: unix_timestamp(),
+    };
+    let (root, line) = (state.root.clone(), note.clone());
+    tokio::task::spawn_blocking(move || append_note(&root, &line))
+        .await
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?
+        .map_err(|e| (StatusCode::INTERNAL_SERVER_ERROR, e.to_string()))?;
+    log::info!("Note from {} ({} chars)", note.from, note.text.chars().count());
+    state.notes.lock().unwrap().push(note.clone());
+    Ok(Json(note))
+}
+
+/// GET /notes — recent notes, oldest first
+async fn list_notes_handler(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+) -> Result<Json<Vec<Note>>, (StatusCode, String)> {
+    check_whole_root(&state, &scope)?;
+    Ok(Json(state.notes.lock().unwrap().recent.clone()))
+}
+
+/// GET /transfers — `/pull` and `/download` streams currently being served
+async fn list_transfers_handler(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+) -> Result<Json<Vec<TransferInfo>>, (StatusCode, String)> {
+    check_whole_root(&state, &scope)?;
+    Ok(Json(state.transfers.lock().unwrap().list()))
+}
+
+/// POST /transfers/{id}/cancel — stop a transfer; the client sees the connection drop
+async fn cancel_transfer_handler(
+    State(state): State<AppState>,
+    scope: Option<Extension<ClientScope>>,
+    Path(id): Path<u64>,
+) -> Result<StatusCode, (StatusCode, String)> {
+    check_whole_root(&state, &scope)?;
+    if state.transfers.lock().unwrap().cancel(id) {
+        Ok(StatusCode::OK)
+    } else {
+        Err((StatusCode::NOT_FOUND, format!("Transfer {} not found", id)))
+    }
+}
+
+/// Where the status API listens; `tailscale serve` exposes this port on the tailnet.
+pub const STATUS_ADDR: &str = "0.0.0.0:8080";
+
+/// Serve the status API on an already-bound listener.
+pub async fn serve_status(listener: tokio::net::TcpListener, state: AppState) -> anyhow::Result<()> {
+    tokio::spawn(watch_sync_projects(state.clone()));
+
+    let transfers = Router::new()
         .route("/download", get(download_last_handler))
         .route("/download/{name}", get(download_file_handler))
-        .route("/browse", get(browse_handler))
         .route("/pull", get(pull_file_handler))
+        .route("/pull-dir", get(pull_dir_handler))
+        .route("/pull-glob", get(pull_glob_handler))
+        .route_layer(middleware::from_fn_with_state(state.clone(), tag_transfer_path));
+
+    let app = Router::new()
+        .route("/status", get(status_handler))
+        .route("/version", get(version_handler))
+        .route("/healthz", get(healthz_handler))
+        .route("/readyz", get(readyz_handler))
+        .route("/join", get(join_handler))
+        .route("/metrics", get(metrics_handler))
+        .route("/note", post(post_note_handler).layer(DefaultBodyLimit::max(NOTE_MAX_BYTES)))
+        .route("/notes", get(list_notes_handler))
+        .route("/files", get(list_files_handler).delete(clear_waiting_files_handler))
+        .route("/files/{name}", delete(delete_waiting_file_handler))
+        .route("/received", get(list_received_handler))
+        .route("/transfers", get(list_transfers_handler))
+        .route("/transfers/{id}/cancel", post(cancel_transfer_handler))
+        .route("/browse", get(browse_handler))
+        .route("/dirinfo", get(dir_info_handler))
+        .route("/stat", get(stat_handler))
+        .route("/changes", get(changes_handler))
+        .route("/preview", get(preview_handler))
         .route("/upload/{*path}", put(upload_handler))
         .route("/peers", get(peers_handler))
-        .route("/sync/projects", get(sync_list_projects).post(sync_create_project))
-        .route("/sync/projects/{id}", delete(sync_delete_project))
+        .route(
+            "/sync/projects",
+            get(sync_list_projects).post(sync_create_project).delete(sync_clear_projects),
+        )
+        .route("/sync/projects/{id}", put(sync_update_project).delete(sync_delete_project))
+        .route("/sync/projects/{id}/plan", get(sync_plan))
         .route("/sync/check", get(sync_check))
+        .route("/sync/status", get(sync_status))
         .route("/sync/ack", post(sync_ack))
         .route("/sync/upload", put(sync_upload_handler))
         .route("/sync/file-info", get(sync_file_info))
-        .layer(DefaultBodyLimit::max(512 * 1024 * 1024)) // 512 MB limit for file uploads
+        .route("/sync/hash", get(sync_hash_handler))
+        .merge(transfers)
+        .layer(DefaultBodyLimit::max(UPLOAD_BYTE_LIMIT)) // 512 MB limit for file uploads
+        .layer(middleware::from_fn_with_state(state.clone(), scope_client))
+        .layer(middleware::from_fn(log_request))
         .with_state(state);
 
-    let listener = tokio::net::TcpListener::bind("0.0.0.0:8080").await?;
-    log::info!("Status server listening on 0.0.0.0:8080");
-    axum::serve(listener, app).await?;
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<std::net::SocketAddr>(),
+    )
+    .await?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::ffi::OsStrExt;
+
+    #[test]
+    fn normalize_lexically_cleans_browse_paths() {
+        assert_eq!(normalize_lexically("/home//user"), PathBuf::from("/home/user"));
+        assert_eq!(normalize_lexically("/home/user/"), PathBuf::from("/home/user"));
+        assert_eq!(normalize_lexically("/home/./user/docs/.."), PathBuf::from("/home/user"));
+        assert_eq!(normalize_lexically("/../.."), PathBuf::from("/"));
+        assert_eq!(normalize_lexically("//"), PathBuf::from("/"));
+    }
+
+    #[test]
+    fn sync_paths_overlap_rejects_identical_and_nested_paths() {
+        // Same location, including trivially different spellings
+        assert!(sync_paths_overlap("/home/me/notes", "/home/me/notes"));
+        assert!(sync_paths_overlap("/home/me/notes/", "/home/me/./notes"));
+        assert!(sync_paths_overlap("/home/me/notes", "/home/me/other/../notes"));
+
+        // One side nested inside the other, in either order
+        assert!(sync_paths_overlap("/home/me", "/home/me/notes/todo.md"));
+        assert!(sync_paths_overlap("/home/me/notes/todo.md", "/home/me"));
+
+        // Siblings and shared name prefixes are fine
+        assert!(!sync_paths_overlap("/home/me/notes", "/home/me/notes-old"));
+        assert!(!sync_paths_overlap("/home/me/a/todo.md", "/home/me/b/todo.md"));
+        assert!(!sync_paths_overlap(
+            "/home/me/todo.md",
+            "/var/mobile/Containers/Data/Application/X/Documents/todo.md"
+        ));
+    }
+
+    #[test]
+    fn check_sync_paths_reports_blank_paths_as_empty() {
+        let err = check_sync_paths("/home/me/notes", "  ").unwrap_err();
+        assert_eq!(err, (StatusCode::BAD_REQUEST, "iOS path is empty".to_string()));
+        let err = check_sync_paths("", "/var/mobile/notes").unwrap_err();
+        assert_eq!(err, (StatusCode::BAD_REQUEST, "Desktop path is empty".to_string()));
+        assert!(check_sync_paths("/home/me/notes", "/var/mobile/notes").is_ok());
+        assert!(check_sync_paths("/home/me/notes", "/home/me/notes/sub").is_err());
+    }
+
+    #[test]
+    fn file_responses_never_show_active_types_in_place() {
+        let disposition = |name: &str, inline: bool| {
+            let resp = file_response_builder(name, inline).body(()).unwrap();
+            assert_eq!(resp.headers()[header::X_CONTENT_TYPE_OPTIONS], "nosniff");
+            resp.headers()[header::CONTENT_DISPOSITION].to_str().unwrap().to_string()
+        };
+        assert!(disposition("a.png", true).starts_with("inline;"));
+        assert!(disposition("notes.txt", true).starts_with("inline;"));
+        assert!(disposition("a.png", false).starts_with("attachment;"));
+        for name in ["page.html", "page.xhtml", "logo.svg", "app.js", "feed.xml"] {
+            assert!(disposition(name, true).starts_with("attachment;"), "{}", name);
+        }
+
+        // Quotes can't end the parameter early, and the real name survives
+        assert_eq!(
+            disposition("say \"hi\"; é.txt", false),
+            "attachment; filename=\"say _hi_; _.txt\"; filename*=UTF-8''say%20%22hi%22%3B%20%C3%A9.txt"
+        );
+    }
+
+    #[test]
+    fn conditional_requests_prefer_etag_over_date() {
+        let modified = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let etag = file_etag(1_700_000_000, 42);
+        let mut headers = HeaderMap::new();
+        assert!(!is_not_modified(&headers, &etag, Some(modified)));
+
+        headers.insert(header::IF_MODIFIED_SINCE, httpdate::fmt_http_date(modified).parse().unwrap());
+        assert!(is_not_modified(&headers, &etag, Some(modified)));
+        let later = modified + Duration::from_secs(1);
+        assert!(!is_not_modified(&headers, &etag, Some(later)));
+
+        // A tag that doesn't match wins over a date that would
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_static("\"1-1\", W/\"2-2\""));
+        assert!(!is_not_modified(&headers, &etag, Some(modified)));
+        headers.insert(header::IF_NONE_MATCH, HeaderValue::from_str(&format!("\"x\", {}", etag)).unwrap());
+        assert!(is_not_modified(&headers, &etag, Some(later)));
+    }
+
+    #[cfg(not(feature = "heif"))]
+    #[tokio::test]
+    async fn heic_preview_without_decoder_falls_back_to_file_head() {
+        let dir = tempfile::tempdir().unwrap();
+        // `ftyp` box of an iPhone photo, followed by data the decoder never reaches
+        let mut heic = b"\0\0\0\x18ftypheic\0\0\0\0mif1heic".to_vec();
+        heic.resize(4096, 0xAB);
+        let path = dir.path().join("IMG_0001.HEIC");
+        std::fs::write(&path, &heic).unwrap();
+
+        let resp = preview_handler(None, Query(PreviewQuery {
+            path: Some(path.to_string_lossy().to_string()),
+            id: None,
+            max_bytes: Some(1024),
+        }))
+        .await
+        .unwrap();
+        assert_ne!(resp.headers()[header::CONTENT_TYPE], "image/png");
+        assert_eq!(resp.headers()["x-file-size"], "4096");
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(body.as_ref(), &heic[..1024]);
+    }
+
+    #[tokio::test]
+    async fn non_utf8_filename_round_trips_through_browse_and_pull() {
+        let dir = tempfile::tempdir().unwrap();
+        let raw_name = std::ffi::OsStr::from_bytes(b"caf\xe9.txt");
+        std::fs::write(dir.path().join(raw_name), b"hello").unwrap();
+
+        let browse = |headers: HeaderMap| {
+            browse_handler(
+                State(new_app_state_in(dir.path().to_path_buf())),
+                None,
+                Query(BrowseQuery {
+                    path: Some(dir.path().to_string_lossy().to_string()),
+                }),
+                headers,
+            )
+        };
+        let resp = browse(HeaderMap::new()).await.unwrap();
+        let etag = resp.headers()[header::ETAG].clone();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let listing: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        assert_eq!(listing.len(), 1);
+        assert_eq!(listing[0]["name"], "caf\u{fffd}.txt");
+        let path_id = listing[0]["path_id"].as_str().unwrap().to_string();
+
+        // An unchanged listing answers its own ETag with a 304
+        let mut conditional = HeaderMap::new();
+        conditional.insert(header::IF_NONE_MATCH, etag);
+        assert_eq!(browse(conditional).await.unwrap().status(), StatusCode::NOT_MODIFIED);
+
+        // Pulling by the lossy display name can't find the file...
+        let lossy = dir.path().join(listing[0]["name"].as_str().unwrap());
+        let err = pull_file_handler(
+            State(new_app_state_in(dir.path().to_path_buf())),
+            None,
+            None,
+            Query(PullQuery {
+                path: Some(lossy.to_string_lossy().to_string()),
+                id: None,
+                inline: false,
+            }),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap_err();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+
+        // ...but the opaque id resolves to the real bytes.
+        let resp = pull_file_handler(
+            State(new_app_state_in(dir.path().to_path_buf())),
+            None,
+            None,
+            Query(PullQuery {
+                path: None,
+                id: Some(path_id),
+                inline: false,
+            }),
+            HeaderMap::new(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(resp.headers().contains_key(header::LAST_MODIFIED));
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], b"hello");
+    }
+
+    #[tokio::test]
+    async fn removing_sync_projects_forgets_their_changes_and_history() {
+        let root = tempfile::tempdir().unwrap();
+        let state = new_app_state_in(root.path().to_path_buf());
+        for id in ["a", "b", "c"] {
+            state.sync_projects.lock().unwrap().push(crate::app_state::SyncProject {
+                id: id.to_string(),
+                local_path: root.path().join(id).to_string_lossy().to_string(),
+                remote_path: format!("/phone/{}", id),
+                last_synced: 0,
+                paused: false,
+                device_name: String::new(),
+                device_dns: String::new(),
+                direction: crate::app_state::SyncDirection::Bidirectional,
+            });
+            state.sync_changes.lock().unwrap().insert(id.to_string(), 1);
+            record_sync_history(&state, id, "pushed", id.to_string());
+        }
+
+        sync_delete_project(State(state.clone()), None, Path("a".to_string())).await.unwrap();
+        assert!(!state.sync_changes.lock().unwrap().contains_key("a"));
+        assert!(!state.sync_history.lock().unwrap().contains_key("a"));
+        assert!(state.sync_history.lock().unwrap().contains_key("b"));
+
+        let cleared = sync_clear_projects(State(state.clone()), None).await;
+        assert_eq!(cleared.0["deleted"], 2);
+        assert!(state.sync_changes.lock().unwrap().is_empty());
+        assert!(state.sync_history.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn watched_change_inside_synced_folder_shows_in_status_but_not_check() {
+        let root = tempfile::tempdir().unwrap();
+        let folder = root.path().join("project");
+        std::fs::create_dir_all(folder.join("sub")).unwrap();
+        let file = folder.join("sub/notes.md");
+        std::fs::write(&file, b"notes").unwrap();
+        let last_synced = unix_timestamp() + 1000;
+        let state = new_app_state_in(root.path().to_path_buf());
+        state.sync_projects.lock().unwrap().push(crate::app_state::SyncProject {
+            id: "p".to_string(),
+            local_path: folder.to_string_lossy().to_string(),
+            remote_path: "/notes".to_string(),
+            last_synced,
+            paused: false,
+            device_name: String::new(),
+            device_dns: String::new(),
+            direction: crate::app_state::SyncDirection::Bidirectional,
+        });
+        // The folder's own mtime doesn't change when a file deep inside it does
+        assert!(sync_check(State(state.clone()), None).await.0.is_empty());
+
+        let edited = last_synced + 60;
+        std::fs::File::options()
+            .write(true)
+            .open(&file)
+            .unwrap()
+            .set_modified(UNIX_EPOCH + Duration::from_secs(edited))
+            .unwrap();
+        mark_sync_changes(&state, vec![file.clone(), root.path().join("elsewhere.txt")]);
+        let status = sync_status(State(state.clone()), None).await.unwrap().0;
+        assert_eq!(status[0].desktop_modified, edited);
+        // The phone can only pull a project as one file, so a folder is never pending
+        assert!(!status[0].pending);
+        assert!(sync_check(State(state.clone()), None).await.0.is_empty());
+
+        // Acking clears the recorded change
+        sync_ack(
+            State(state.clone()),
+            None,
+            Json(SyncAckRequest { id: "p".to_string(), timestamp: edited }),
+        )
+        .await
+        .unwrap();
+        assert!(sync_status(State(state.clone()), None).await.unwrap().0[0].desktop_modified < edited);
+    }
+
+    #[test]
+    fn walks_survive_symlink_cycles_and_stop_at_the_depth_limit() {
+        let root = tempfile::tempdir().unwrap();
+        let top = root.path().join("top");
+        std::fs::create_dir_all(top.join("a/b")).unwrap();
+        std::fs::write(top.join("a/b/file.txt"), b"data").unwrap();
+        // A link back to the top from inside it, and a walk root that is itself a link
+        std::os::unix::fs::symlink(&top, top.join("a/b/loop")).unwrap();
+        let linked = root.path().join("linked");
+        std::os::unix::fs::symlink(&top, &linked).unwrap();
+        let limits = WalkLimits::default();
+
+        for walk_root in [&top, &linked] {
+            let info = walk_dir_info(walk_root, &limits);
+            assert_eq!((info.file_count, info.dir_count, info.truncated), (1, 2, false));
+            let (plan, truncated) = plan_entries(walk_root, 0, &limits);
+            assert_eq!((plan.len(), truncated), (1, false));
+            // The folder, a, a/b and file.txt
+            let (entries, truncated) = walk_pull_dir(walk_root, true, &limits).unwrap();
+            assert_eq!((entries.len(), truncated), (4, false));
+        }
+
+        // One level down is allowed: `a` is entered, `a/b` isn't
+        let shallow = WalkLimits { max_depth: 1, ..limits };
+        let info = walk_dir_info(&top, &shallow);
+        assert_eq!((info.file_count, info.dir_count, info.truncated), (0, 2, true));
+        let (plan, truncated) = plan_entries(&top, 0, &shallow);
+        assert_eq!((plan.len(), truncated), (0, true));
+        let (entries, truncated) = walk_pull_dir(&top, true, &shallow).unwrap();
+        assert_eq!((entries.len(), truncated), (2, true));
+    }
+
+    #[tokio::test]
+    async fn changes_lists_files_modified_since_newest_first() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(root.path().join("docs/deep")).unwrap();
+        std::fs::create_dir_all(root.path().join(".cache")).unwrap();
+        let set_mtime = |rel: &str, secs: u64| {
+            let path = root.path().join(rel);
+            std::fs::write(&path, rel).unwrap();
+            std::fs::File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(UNIX_EPOCH + Duration::from_secs(secs))
+                .unwrap();
+        };
+        set_mtime("old.txt", 1_000);
+        set_mtime("docs/new.md", 3_000);
+        set_mtime("docs/deep/newer.md", 4_000);
+        set_mtime(".cache/hidden.bin", 5_000);
+
+        let state = new_app_state_in(root.path().to_path_buf());
+        let changes = changes_handler(
+            State(state.clone()),
+            None,
+            Query(ChangesQuery { since: 2_000, root: None }),
+        )
+        .await
+        .unwrap()
+        .0;
+        let names: Vec<_> = changes.files.iter().map(|f| f.info.name.as_str()).collect();
+        assert_eq!(names, ["newer.md", "new.md"]);
+        assert!(changes.files[0].path.ends_with("docs/deep/newer.md"));
+        assert!(!changes.truncated);
+        assert!(changes.now >= unix_timestamp() - 5);
+
+        // A subfolder can be asked for on its own; a missing one is a 404
+        let docs = root.path().join("docs/deep").to_string_lossy().to_string();
+        let changes = changes_handler(
+            State(state.clone()),
+            None,
+            Query(ChangesQuery { since: 0, root: Some(docs) }),
+        )
+        .await
+        .unwrap()
+        .0;
+        assert_eq!(changes.files.len(), 1);
+        let missing = root.path().join("nope").to_string_lossy().to_string();
+        let err = changes_handler(State(state), None, Query(ChangesQuery { since: 0, root: Some(missing) }))
+            .await
+            .err()
+            .unwrap();
+        assert_eq!(err.0, StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn received_index_ignores_a_saved_file_left_in_the_inbox() {
+        let mut index = ReceivedIndex::default();
+        assert!(index.note("a.txt", 3, "", 100));
+        assert!(!index.note("a.txt", 3, "", 100));
+        assert!(index.note("a.txt", 3, "peer", 100));
+        index.note_saved("a.txt", 3, PathBuf::from("/tmp/a.txt"));
+
+        // Deleting it from the inbox failed, so the periodic check keeps seeing it
+        assert!(!index.note("a.txt", 3, "peer", 100));
+        assert_eq!(index.records.len(), 1);
+
+        // A later file with the same name is a new arrival
+        assert!(index.note("a.txt", 3, "peer", 200));
+        assert_eq!(index.records.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn folder_archives_skip_entries_that_went_away() {
+        let root = tempfile::tempdir().unwrap();
+        let dir = root.path().join("docs");
+        std::fs::create_dir(&dir).unwrap();
+        std::fs::write(dir.join("kept.txt"), b"kept").unwrap();
+        let state = new_app_state_in(root.path().to_path_buf());
+        let entry = |name: &str, is_dir: bool, size: u64| PullDirEntry {
+            path: root.path().join(name),
+            name: PathBuf::from(name),
+            is_dir,
+            size,
+        };
+        // As if these were removed between the walk and the archive
+        let entries = vec![
+            entry("docs", true, 0),
+            entry("docs/gone", true, 0),
+            entry("docs/gone.txt", false, 4),
+            entry("docs/kept.txt", false, 4),
+        ];
+        let resp = tar_response(&state, None, dir, entries, false).unwrap();
+        let body = axum::body::to_bytes(resp.into_body(), usize::MAX).await.unwrap();
+        let mut archive = tar::Archive::new(&body[..]);
+        let names: Vec<String> = archive
+            .entries()
+            .unwrap()
+            .map(|e| e.unwrap().path().unwrap().to_string_lossy().to_string())
+            .collect();
+        assert_eq!(names, ["docs", "docs/kept.txt"]);
+    }
+
+    #[tokio::test]
+    async fn one_way_projects_skip_the_other_direction() {
+        use crate::app_state::SyncDirection;
+        let root = tempfile::tempdir().unwrap();
+        let mirror = root.path().join("mirror.txt");
+        let inbox = root.path().join("inbox.txt");
+        std::fs::write(&mirror, b"desk").unwrap();
+        std::fs::write(&inbox, b"desk").unwrap();
+        let state = new_app_state_in(root.path().to_path_buf());
+        for (id, file, direction) in [
+            ("mirror", &mirror, SyncDirection::DesktopToPhone),
+            ("inbox", &inbox, SyncDirection::PhoneToDesktop),
+        ] {
+            state.sync_projects.lock().unwrap().push(crate::app_state::SyncProject {
+                id: id.to_string(),
+                local_path: file.to_string_lossy().to_string(),
+                remote_path: format!("/{}", id),
+                last_synced: 0,
+                paused: false,
+                device_name: String::new(),
+                device_dns: String::new(),
+                direction,
+            });
+        }
+
+        // Only the mirror's desktop changes go to the phone
+        let changes = sync_check(State(state.clone()), None).await.0;
+        assert_eq!(changes.iter().map(|c| c.id.as_str()).collect::<Vec<_>>(), ["mirror"]);
+
+        // ...and only the inbox takes uploads from it
+        let upload = |dest: PathBuf| {
+            sync_upload_handler(
+                State(state.clone()),
+                None,
+                Query(SyncUploadQuery { path: dest.to_string_lossy().to_string() }),
+                HeaderMap::new(),
+                Body::from("edit"),
+            )
+        };
+        let err = upload(mirror.clone()).await.err().unwrap();
+        assert_eq!(err.0, StatusCode::FORBIDDEN);
+        assert_eq!(std::fs::read(&mirror).unwrap(), b"desk");
+        assert_eq!(upload(inbox.clone()).await.unwrap().bytes, 4);
+        assert_eq!(std::fs::read(&inbox).unwrap(), b"edit");
+    }
+
+    #[tokio::test]
+    async fn sync_status_combines_projects_pending_changes_and_history() {
+        let root = tempfile::tempdir().unwrap();
+        let file = root.path().join("notes.txt");
+        std::fs::write(&file, "v1").unwrap();
+        let state = new_app_state_in(root.path().to_path_buf());
+        state.sync_projects.lock().unwrap().push(crate::app_state::SyncProject {
+            id: "notes".to_string(),
+            local_path: file.to_string_lossy().to_string(),
+            remote_path: "/notes.txt".to_string(),
+            last_synced: 0,
+            paused: false,
+            device_name: String::new(),
+            device_dns: String::new(),
+            direction: Default::default(),
+        });
+        let status = || async { serde_json::to_value(sync_status(State(state.clone()), None).await.unwrap().0).unwrap() };
+
+        let before = status().await;
+        assert_eq!(before[0]["id"], "notes");
+        assert_eq!(before[0]["pending"], true);
+        assert!(before[0]["desktop_modified"].as_u64().unwrap() > 0);
+        assert_eq!(before[0]["history"], serde_json::json!([]));
+
+        let pushed = sync_upload_handler(
+            State(state.clone()),
+            None,
+            Query(SyncUploadQuery { path: file.to_string_lossy().to_string() }),
+            HeaderMap::new(),
+            Body::from("v2"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(pushed.bytes, 2);
+        sync_ack(State(state.clone()), None, Json(SyncAckRequest { id: "notes".to_string(), timestamp: u64::MAX }))
+            .await
+            .unwrap();
+
+        let after = status().await;
+        assert_eq!(after[0]["pending"], false);
+        assert_eq!(after[0]["last_synced"], u64::MAX);
+        let actions: Vec<_> = after[0]["history"].as_array().unwrap().iter().map(|h| h["action"].clone()).collect();
+        assert_eq!(actions, ["pushed", "synced"]);
+    }
+
+    #[tokio::test]
+    async fn gzip_sync_uploads_are_decompressed_before_the_write() {
+        use std::io::Write;
+
+        let root = tempfile::tempdir().unwrap();
+        let state = new_app_state_in(root.path().to_path_buf());
+        let text = "a line of text that compresses well\n".repeat(1000);
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(text.as_bytes()).unwrap();
+        let gzipped = encoder.finish().unwrap();
+        assert!(gzipped.len() < text.len() / 10);
+
+        let upload = |name: &str, encoding: &'static str, body: Vec<u8>| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::CONTENT_ENCODING, HeaderValue::from_static(encoding));
+            sync_upload_handler(
+                State(state.clone()),
+                None,
+                Query(SyncUploadQuery { path: root.path().join(name).to_string_lossy().to_string() }),
+                headers,
+                Body::from(body),
+            )
+        };
+        let result = upload("notes.txt", "gzip", gzipped.clone()).await.unwrap();
+        assert_eq!(result.bytes, text.len() as u64);
+        assert_eq!(std::fs::read_to_string(root.path().join("notes.txt")).unwrap(), text);
+
+        // A truncated stream is refused and leaves nothing behind
+        let err = upload("cut.txt", "gzip", gzipped[..gzipped.len() / 2].to_vec()).await.err().unwrap();
+        assert_eq!(err.0, StatusCode::BAD_REQUEST);
+        assert!(!root.path().join("cut.txt").exists());
+
+        let err = upload("br.txt", "br", Vec::new()).await.err().unwrap();
+        assert_eq!(err.0, StatusCode::UNSUPPORTED_MEDIA_TYPE);
+    }
+
+    /// Serve the real router on an ephemeral port, rooted at `root`.
+    async fn spawn_server(root: &std::path::Path) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(serve_status(listener, new_app_state_in(root.to_path_buf())));
+        format!("http://{}", addr)
+    }
+
+    fn read_json(mut resp: ureq::http::Response<ureq::Body>) -> serde_json::Value {
+        serde_json::from_str(&resp.body_mut().read_to_string().unwrap()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn status_server_round_trip_in_temp_root() {
+        let root = tempfile::tempdir().unwrap();
+        std::fs::write(root.path().join("hello.txt"), b"hello").unwrap();
+        let base = spawn_server(root.path()).await;
+        let root = root.path().to_path_buf();
+        // ureq blocks, so drive it off the runtime thread the server runs on
+        tokio::task::spawn_blocking(move || exercise_status_server(&base, &root))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn client_roots_scope_browse_per_token() {
+        let root = tempfile::tempdir().unwrap();
+        let config = root.path().join(".config/tailscale-drive");
+        std::fs::create_dir_all(&config).unwrap();
+        std::fs::write(
+            config.join("client_roots.json"),
+            r#"[{"token": "phone", "root": "~"}, {"root": "~/shared", "default_path": "~/shared/in"}]"#,
+        )
+        .unwrap();
+        std::fs::create_dir_all(root.path().join("shared/in")).unwrap();
+        std::fs::write(root.path().join("shared/in/a.txt"), b"a").unwrap();
+        std::fs::create_dir(root.path().join("private")).unwrap();
+        std::fs::write(root.path().join("private/b.txt"), b"b").unwrap();
+        std::os::unix::fs::symlink(root.path().join("private"), root.path().join("shared/escape")).unwrap();
+        let base = spawn_server(root.path()).await;
+        let root = root.path().to_path_buf();
+
+        tokio::task::spawn_blocking(move || {
+            let agent: ureq::Agent = ureq::Agent::config_builder()
+                .http_status_as_error(false)
+                .build()
+                .into();
+            let names = |resp| -> Vec<String> {
+                read_json(resp)
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|e| e["name"].as_str().unwrap().to_string())
+                    .collect()
+            };
+            let browse = |path: Option<&std::path::Path>, token: Option<&str>| {
+                let mut req = agent.get(format!("{}/browse", base));
+                if let Some(path) = path {
+                    req = req.query("path", path.to_string_lossy());
+                }
+                if let Some(token) = token {
+                    req = req.header("Authorization", format!("Bearer {}", token));
+                }
+                req.call().unwrap()
+            };
+
+            // The catch-all starts in its default path and can't leave its root
+            assert_eq!(names(browse(None, None)), ["a.txt"]);
+            assert_eq!(browse(Some(&root.join("shared")), None).status(), 200);
+            assert_eq!(browse(Some(&root), None).status(), 403);
+            assert_eq!(browse(Some(&root.join("shared/escape")), None).status(), 403);
+            let pull = agent
+                .get(format!("{}/pull", base))
+                .query("path", root.join("private/b.txt").to_string_lossy())
+                .call()
+                .unwrap();
+            assert_eq!(pull.status(), 403);
+            let stat = agent
+                .get(format!("{}/stat", base))
+                .query("path", root.join("private/b.txt").to_string_lossy())
+                .call()
+                .unwrap();
+            assert_eq!(stat.status(), 403);
+            let stat = agent
+                .get(format!("{}/stat", base))
+                .query("path", root.join("private/nope.txt").to_string_lossy())
+                .call()
+                .unwrap();
+            assert_eq!(stat.status(), 403);
+            // Nothing outside the folder answers 404, so its layout can't be probed
+            for (route, param) in [
+                ("/browse", "path"),
+                ("/changes", "root"),
+                ("/dirinfo", "path"),
+                ("/pull", "path"),
+                ("/preview", "path"),
+                ("/pull-dir", "path"),
+                ("/pull-glob", "dir"),
+            ] {
+                let mut req = agent
+                    .get(format!("{}{}", base, route))
+                    .query(param, root.join("private/nope").to_string_lossy());
+                if route == "/changes" {
+                    req = req.query("since", "0");
+                } else if route == "/pull-glob" {
+                    req = req.query("pattern", "*");
+                }
+                assert_eq!(req.call().unwrap().status(), 403, "{}", route);
+            }
+
+            // A known token, or one nobody configured, picks its own entry
+            assert_eq!(names(browse(None, Some("phone"))), ["private", "shared"]);
+            assert_eq!(browse(Some(&root.join("private")), Some("phone")).status(), 200);
+            assert_eq!(browse(Some(&root), Some("guess")).status(), 403);
+
+            // /status tells each client where its Up button has to stop
+            let status = read_json(agent.get(format!("{}/status", base)).call().unwrap());
+            assert_eq!(status["browse_root"], root.join("shared").to_string_lossy().as_ref());
+
+            // Writes are scoped too, including to folders that don't exist yet
+            let upload = |path: &str| agent.put(format!("{}/upload/{}", base, path)).send("x").unwrap().status();
+            assert_eq!(upload("shared/new/c.txt"), 200);
+            assert_eq!(upload("private/c.txt"), 403);
+            assert_eq!(upload("shared/new/../../private/c.txt"), 403);
+            assert_eq!(upload("shared/escape/c.txt"), 403);
+            assert!(!root.join("private/c.txt").exists());
+            let sync_upload = agent
+                .put(format!("{}/sync/upload", base))
+                .query("path", "~/private/d.txt")
+                .send("x")
+                .unwrap();
+            assert_eq!(sync_upload.status(), 403);
+
+            // Each client only sees the sync projects inside its root
+            let create = |local: &str, token: &str| {
+                agent
+                    .post(format!("{}/sync/projects", base))
+                    .header("Authorization", format!("Bearer {}", token))
+                    .header("Content-Type", "application/json")
+                    .send(serde_json::json!({"local_path": local, "remote_path": "/phone/x"}).to_string())
+                    .unwrap()
+                    .status()
+            };
+            assert_eq!(create("~/private/b.txt", "phone"), 200);
+            assert_eq!(create("~/private", "guess"), 403);
+            let listed = read_json(agent.get(format!("{}/sync/projects", base)).call().unwrap());
+            assert_eq!(listed.as_array().unwrap().len(), 0);
+            let listed = read_json(
+                agent
+                    .get(format!("{}/sync/projects", base))
+                    .header("Authorization", "Bearer phone")
+                    .call()
+                    .unwrap(),
+            );
+            assert_eq!(listed.as_array().unwrap().len(), 1);
+
+            // The inbox, notes and transfers are only for clients that see the whole root
+            let status = |method: &str, path: &str, token: Option<&str>| {
+                let mut req = axum::http::Request::builder()
+                    .method(method)
+                    .uri(format!("{}{}", base, path));
+                if let Some(token) = token {
+                    req = req.header("Authorization", format!("Bearer {}", token));
+                }
+                agent.run(req.body(()).unwrap()).unwrap().status()
+            };
+            for (method, path) in [
+                ("GET", "/files"),
+                ("DELETE", "/files"),
+                ("DELETE", "/files/a.txt"),
+                ("GET", "/received"),
+                ("GET", "/download"),
+                ("GET", "/download/a.txt"),
+                ("GET", "/notes"),
+                ("GET", "/transfers"),
+                ("POST", "/transfers/1/cancel"),
+            ] {
+                assert_eq!(status(method, path, None), 403, "{} {}", method, path);
+            }
+            assert_eq!(status("GET", "/notes", Some("phone")), 200);
+            assert_eq!(status("GET", "/transfers", Some("phone")), 200);
+            assert_eq!(status("GET", "/received", Some("phone")), 200);
+            assert_eq!(status("POST", "/transfers/1/cancel", Some("phone")), 404);
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn client_roots_without_a_catch_all_deny_unlisted_clients() {
+        let root = tempfile::tempdir().unwrap();
+        let config = root.path().join(".config/tailscale-drive");
+        std::fs::create_dir_all(&config).unwrap();
+        std::fs::write(config.join("client_roots.json"), r#"[{"token": "phone", "root": "~"}]"#).unwrap();
+        let base = spawn_server(root.path()).await;
+
+        tokio::task::spawn_blocking(move || {
+            let agent: ureq::Agent = ureq::Agent::config_builder()
+                .http_status_as_error(false)
+                .build()
+                .into();
+            assert_eq!(agent.get(format!("{}/browse", base)).call().unwrap().status(), 403);
+            assert_eq!(agent.put(format!("{}/upload/a.txt", base)).send("x").unwrap().status(), 403);
+            assert_eq!(agent.get(format!("{}/notes", base)).call().unwrap().status(), 403);
+            assert_eq!(agent.get(format!("{}/download", base)).call().unwrap().status(), 403);
+            let browse = agent
+                .get(format!("{}/browse", base))
+                .header("Authorization", "Bearer phone")
+                .call()
+                .unwrap();
+            assert_eq!(browse.status(), 200);
+            // Status still answers, so the app can say what's wrong
+            assert_eq!(agent.get(format!("{}/status", base)).call().unwrap().status(), 200);
+        })
+        .await
+        .unwrap();
+    }
+
+    #[tokio::test]
+    async fn client_roots_match_the_forwarded_hop_serve_appended() {
+        let root = tempfile::tempdir().unwrap();
+        let config = root.path().join(".config/tailscale-drive");
+        std::fs::create_dir_all(&config).unwrap();
+        std::fs::write(
+            config.join("client_roots.json"),
+            r#"[{"node": "100.64.0.2", "root": "~/shared"}, {"node": "100.64.0.3", "root": "~"}]"#,
+        )
+        .unwrap();
+        std::fs::create_dir(root.path().join("shared")).unwrap();
+        let base = spawn_server(root.path()).await;
+        let root = root.path().to_path_buf();
+
+        tokio::task::spawn_blocking(move || {
+            let agent: ureq::Agent = ureq::Agent::config_builder()
+                .http_status_as_error(false)
+                .build()
+                .into();
+            let browse = |forwarded: &str| {
+                agent
+                    .get(format!("{}/browse", base))
+                    .query("path", root.to_string_lossy())
+                    .header("X-Forwarded-For", forwarded)
+                    .call()
+                    .unwrap()
+                    .status()
+            };
+            assert_eq!(browse("100.64.0.3"), 200);
+            // A hop the client wrote itself doesn't borrow another node's root
+            assert_eq!(browse("100.64.0.3, 100.64.0.2"), 403);
+            assert_eq!(browse("100.64.0.3, 100.64.0.9"), 403);
+        })
+        .await
+        .unwrap();
+    }
+
+    fn exercise_status_server(base: &str, root: &std::path::Path) {
+        let agent: ureq::Agent = ureq::Agent::config_builder()
+            .http_status_as_error(false)
+            .build()
+            .into();
+        let root_str = root.to_string_lossy().to_string();
+
+        // /browse defaults to the root, /pull fetches by the listed id
+        let listing = read_json(agent.get(format!("{}/browse", base)).call().unwrap());
+        let listing = listing.as_array().unwrap();
+        assert_eq!(listing.len(), 1);
+        assert_eq!(listing[0]["name"], "hello.txt");
+        assert_eq!(listing[0]["size"], 5);
+        let id = listing[0]["path_id"].as_str().unwrap();
+        let mut resp = agent.get(format!("{}/pull", base)).query("id", id).call().unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(resp.body_mut().read_to_string().unwrap(), "hello");
+
+        // /stat answers with the same entry without listing the folder
+        let stat = read_json(agent.get(format!("{}/stat", base)).query("id", id).call().unwrap());
+        assert_eq!(stat, listing[0]);
+        let missing = agent
+            .get(format!("{}/stat", base))
+            .query("path", root.join("gone.txt").to_string_lossy())
+            .call()
+            .unwrap();
+        assert_eq!(missing.status(), 404);
+
+        // /upload writes relative to the root, creating folders
+        let resp = agent
+            .put(format!("{}/upload/docs/new.txt", base))
+            .send("uploaded")
+            .unwrap();
+        assert_eq!(resp.status(), 200);
+        assert_eq!(read_json(resp)["bytes"], 8);
+        let uploaded = root.join("docs/new.txt");
+        assert_eq!(std::fs::read(&uploaded).unwrap(), b"uploaded");
+        let docs = read_json(
+            agent
+                .get(format!("{}/browse", base))
+                .query("path", format!("{}/docs", root_str))
+                .call()
+                .unwrap(),
+        );
+        assert_eq!(docs[0]["name"], "new.txt");
+
+        // /sync/hash checksums the written file; ~/ is the root
+        let hash = read_json(
+            agent
+                .get(format!("{}/sync/hash", base))
+                .query("path", "~/docs/new.txt")
+                .call()
+                .unwrap(),
+        );
+        assert_eq!(hash["size"], 8);
+        assert_eq!(hash["crc32"], crc32fast::hash(b"uploaded"));
+
+        // A body cut off mid-upload leaves the old file and no temp file behind
+        let leftovers = || {
+            std::fs::read_dir(root.join("docs"))
+                .unwrap()
+                .flatten()
+                .filter(|e| e.file_name().to_string_lossy().contains(".upload-"))
+                .count()
+        };
+        let wait_for = |count: usize| {
+            for _ in 0..100 {
+                if leftovers() == count {
+                    break;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(20));
+            }
+            assert_eq!(leftovers(), count);
+        };
+        {
+            use std::io::Write;
+            let mut stream = std::net::TcpStream::connect(base.trim_start_matches("http://")).unwrap();
+            write!(
+                stream,
+                "PUT /upload/docs/new.txt HTTP/1.1\r\nHost: localhost\r\nContent-Length: 100\r\n\r\npartial"
+            )
+            .unwrap();
+            // The upload is being streamed into its temp file...
+            wait_for(1);
+        }
+        // ...which goes away once the connection drops
+        wait_for(0);
+        assert_eq!(std::fs::read(&uploaded).unwrap(), b"uploaded");
+        let missing = agent.get(format!("{}/sync/hash", base)).query("path", "~/nope").call().unwrap();
+        assert_eq!(missing.status(), 404);
+
+        // /pull-glob: `*` stays in the folder, `**/` looks in subfolders
+        let glob_names = |pattern: &str| {
+            let mut resp = agent
+                .get(format!("{}/pull-glob", base))
+                .query("dir", &root_str)
+                .query("pattern", pattern)
+                .call()
+                .unwrap();
+            assert_eq!(resp.status(), 200, "{}", pattern);
+            let mut archive = tar::Archive::new(resp.body_mut().as_reader());
+            let mut names: Vec<String> = archive
+                .entries()
+                .unwrap()
+                .map(|e| e.unwrap().path().unwrap().to_string_lossy().to_string())
+                .collect();
+            names.remove(0); // the folder itself
+            names.into_iter().map(|n| n.split_once('/').unwrap().1.to_string()).collect::<Vec<_>>()
+        };
+        assert_eq!(glob_names("*.txt"), ["hello.txt"]);
+        assert_eq!(glob_names("**/*.txt"), ["hello.txt", "docs/new.txt"]);
+        let glob_status = |pattern: &str| {
+            agent
+                .get(format!("{}/pull-glob", base))
+                .query("dir", &root_str)
+                .query("pattern", pattern)
+                .call()
+                .unwrap()
+                .status()
+        };
+        assert_eq!(glob_status("*.log"), 404);
+        assert_eq!(glob_status("[oops"), 400);
+
+        // /sync/projects CRUD, persisted under the root's .config
+        let create = serde_json::json!({
+            "local_path": format!("{}/hello.txt", root_str),
+            "remote_path": "/var/mobile/Documents/hello.txt",
+        })
+        .to_string();
+        let post = |body: &str| {
+            agent
+                .post(format!("{}/sync/projects", base))
+                .header("Content-Type", "application/json")
+                .send(body)
+                .unwrap()
+        };
+        let resp = post(&create);
+        assert_eq!(resp.status(), 200);
+        let project = read_json(resp);
+        let id = project["id"].as_str().unwrap().to_string();
+        assert_eq!(post(&create).status(), 409);
+        let overlapping = serde_json::json!({ "local_path": root_str, "remote_path": root_str }).to_string();
+        assert_eq!(post(&overlapping).status(), 400);
+
+        // Stored relative to the root so the config survives a move
+        let projects_file = root.join(".config/tailscale-drive/sync_projects.json");
+        let saved = std::fs::read_to_string(&projects_file).unwrap();
+        assert!(saved.contains(&id));
+        assert!(saved.contains("\"~/hello.txt\""));
+        assert_eq!(project["local_path"], format!("{}/hello.txt", root_str));
+        let list = read_json(agent.get(format!("{}/sync/projects", base)).call().unwrap());
+        assert_eq!(list.as_array().unwrap().len(), 1);
+
+        // Nothing changed since the project was created...
+        let check = |agent: &ureq::Agent| read_json(agent.get(format!("{}/sync/check", base)).call().unwrap());
+        assert_eq!(check(&agent), serde_json::json!([]));
+
+        // ...but moving it to another file resets last_synced, so it shows up
+        let update = |body: serde_json::Value| {
+            agent
+                .put(format!("{}/sync/projects/{}", base, id))
+                .header("Content-Type", "application/json")
+                .send(&body.to_string())
+                .unwrap()
+        };
+        let moved = update(serde_json::json!({ "local_path": uploaded.to_string_lossy() }));
+        assert_eq!(moved.status(), 200);
+        assert_eq!(read_json(moved)["last_synced"], 0);
+        let changes = check(&agent);
+        assert_eq!(changes.as_array().unwrap().len(), 1);
+        assert_eq!(changes[0]["id"], id.as_str());
+        assert_eq!(changes[0]["local_path"], uploaded.to_string_lossy().as_ref());
+
+        // Paused projects aren't reported, and their plan is empty
+        let plan = || read_json(agent.get(format!("{}/sync/projects/{}/plan", base, id)).call().unwrap());
+        assert_eq!(plan()["entries"].as_array().unwrap().len(), 1);
+        assert_eq!(update(serde_json::json!({ "paused": true })).status(), 200);
+        assert_eq!(check(&agent), serde_json::json!([]));
+        assert_eq!(plan()["entries"], serde_json::json!([]));
+        assert_eq!(plan()["paused"], true);
+
+        let delete = || agent.delete(format!("{}/sync/projects/{}", base, id)).call().unwrap().status();
+        assert_eq!(delete(), 200);
+        assert_eq!(delete(), 404);
+        let list = read_json(agent.get(format!("{}/sync/projects", base)).call().unwrap());
+        assert_eq!(list, serde_json::json!([]));
+        assert!(!std::fs::read_to_string(&projects_file).unwrap().contains(&id));
+
+        // DELETE without an id drops them all
+        for name in ["a.txt", "b.txt"] {
+            let body = serde_json::json!({ "local_path": format!("{}/{}", root_str, name), "remote_path": "/r" });
+            assert_eq!(post(&body.to_string()).status(), 200);
+        }
+        let cleared = read_json(agent.delete(format!("{}/sync/projects", base)).call().unwrap());
+        assert_eq!(cleared["deleted"], 2);
+        let list = read_json(agent.get(format!("{}/sync/projects", base)).call().unwrap());
+        assert_eq!(list, serde_json::json!([]));
+
+        // Notes get ids in order; oversized ones are refused before they're read
+        let note = |text: String| agent.post(format!("{}/note", base)).send(text).unwrap();
+        assert_eq!(read_json(note("one".to_string()))["id"], 1);
+        assert_eq!(read_json(note("two".to_string()))["id"], 2);
+        let mut big = note("x".repeat(NOTE_MAX_BYTES + 1));
+        assert_eq!(big.status(), 413);
+        assert!(big.body_mut().read_to_string().unwrap().contains("send a file instead"));
+        let notes = read_json(agent.get(format!("{}/notes", base)).call().unwrap());
+        assert_eq!(notes.as_array().unwrap().len(), 2);
+    }
+}
@@ -7,6 +7,7 @@ use tokio::net::UnixStream;
 use std::collections::HashMap;
 use std::pin::Pin;
 use std::sync::mpsc::Sender;
+use std::sync::{Arc, Mutex};
 use std::task::{Context, Poll};
 use std::future::Future;
 
@@ -17,6 +18,8 @@ use serde::Deserialize;
 use super::app_state::{TailscaleCommand, TailscaleEvent, TailscalePeer};
 
 // --- Connector Logic ---
+const TAILSCALED_SOCKET: &str = "/var/run/tailscale/tailscaled.sock";
+
 #[derive(Clone)]
 pub struct UnixConnector;
 
@@ -31,7 +34,7 @@ impl tower::Service<Uri> for UnixConnector {
 
     fn call(&mut self, _req: Uri) -> Self::Future {
         Box::pin(async move {
-            let stream = UnixStream::connect("/var/run/tailscale/tailscaled.sock").await?;
+            let stream = UnixStream::connect(TAILSCALED_SOCKET).await?;
             Ok(TokioIo::new(stream))
         })
     }
@@ -70,6 +73,49 @@ pub struct PeerStatus {
     online: bool,
     #[serde(rename = "OS")]
     os: Option<String>,
+    /// Node capabilities, e.g. `https://tailscale.com/cap/file-sharing`
+    #[serde(rename = "Capabilities")]
+    capabilities: Option<Vec<String>>,
+    /// Newer tailscaled reports capabilities as a map (values unused here)
+    #[serde(rename = "CapMap")]
+    cap_map: Option<HashMap<String, serde_json::Value>>,
+    /// Endpoint of the direct UDP path, empty when traffic goes via DERP
+    #[serde(rename = "CurAddr")]
+    cur_addr: Option<String>,
+    /// DERP region code used when there's no direct path
+    #[serde(rename = "Relay")]
+    relay: Option<String>,
+}
+
+/// Capability a node needs for Taildrop.
+const CAP_FILE_SHARING: &str = "https://tailscale.com/cap/file-sharing";
+/// Peer capability granting file sends to a node owned by someone else.
+const CAP_FILE_SHARING_TARGET: &str = "https://tailscale.com/cap/file-sharing-target";
+
+impl PeerStatus {
+    /// Whether Taildrop can deliver to this node. Older tailscaled versions
+    /// don't report capabilities at all; those nodes are assumed capable.
+    fn can_receive_files(&self) -> bool {
+        if self.capabilities.is_none() && self.cap_map.is_none() {
+            return true;
+        }
+        let has = |cap: &str| {
+            self.capabilities.iter().flatten().any(|c| c == cap)
+                || self.cap_map.as_ref().is_some_and(|m| m.contains_key(cap))
+        };
+        has(CAP_FILE_SHARING) || has(CAP_FILE_SHARING_TARGET)
+    }
+
+    /// `direct` if tailscaled has a direct endpoint, else `relay <region>`.
+    fn connection(&self) -> Option<String> {
+        if self.cur_addr.as_deref().is_some_and(|a| !a.is_empty()) {
+            return Some("direct".to_string());
+        }
+        self.relay
+            .as_deref()
+            .filter(|r| self.online && !r.is_empty())
+            .map(|r| format!("relay {}", r))
+    }
 }
 
 #[derive(Debug, Deserialize)]
@@ -83,6 +129,122 @@ pub struct IpnBusNotification {
 
 // --- Background Tailscale Tasks ---
 
+/// Startup attempts at reaching tailscaled before falling back to the refresh loop.
+const INITIAL_FETCH_ATTEMPTS: u32 = 5;
+/// Delay after the first failed attempt; doubles each retry (0.5s, 1s, 2s, 4s).
+const INITIAL_FETCH_BASE_DELAY: std::time::Duration = std::time::Duration::from_millis(500);
+
+/// Env var overriding how often the Taildrop inbox is polled, in seconds.
+const FILE_CHECK_INTERVAL_ENV: &str = "TAILSCALE_DRIVE_FILE_CHECK_SECS";
+/// Env var overriding how often the peer list is refreshed, in seconds.
+const PEER_REFRESH_INTERVAL_ENV: &str = "TAILSCALE_DRIVE_PEER_REFRESH_SECS";
+/// Env var overriding how many Taildrop sends may run at once.
+const MAX_CONCURRENT_SENDS_ENV: &str = "TAILSCALE_DRIVE_MAX_CONCURRENT_SENDS";
+const DEFAULT_MAX_CONCURRENT_SENDS: usize = 2;
+const DEFAULT_FILE_CHECK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+const DEFAULT_PEER_REFRESH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(5);
+
+/// Read a whole number of seconds from `var`, falling back to `default` when
+/// unset, unparsable or zero.
+fn env_interval(var: &str, default: std::time::Duration) -> std::time::Duration {
+    match std::env::var(var) {
+        Ok(raw) => match raw.trim().parse::<u64>() {
+            Ok(secs) if secs > 0 => std::time::Duration::from_secs(secs),
+            _ => {
+                log::warn!("Ignoring {}={:?}; using {:?}", var, raw, default);
+                default
+            }
+        },
+        Err(_) => default,
+    }
+}
+
+/// Concurrent send limit from `MAX_CONCURRENT_SENDS_ENV`, default 2.
+fn max_concurrent_sends() -> usize {
+    match std::env::var(MAX_CONCURRENT_SENDS_ENV) {
+        Ok(raw) => match raw.trim().parse::<usize>() {
+            Ok(n) if n > 0 => n,
+            _ => {
+                log::warn!(
+                    "Ignoring {}={:?}; using {}",
+                    MAX_CONCURRENT_SENDS_ENV,
+                    raw,
+                    DEFAULT_MAX_CONCURRENT_SENDS
+                );
+                DEFAULT_MAX_CONCURRENT_SENDS
+            }
+        },
+        Err(_) => DEFAULT_MAX_CONCURRENT_SENDS,
+    }
+}
+
+/// Running/waiting counters for outgoing sends, reported to the UI on each change.
+#[derive(Clone)]
+struct SendQueue {
+    slots: Arc<tokio::sync::Semaphore>,
+    counts: Arc<Mutex<(usize, usize)>>,
+    event_tx: Sender<TailscaleEvent>,
+}
+
+impl SendQueue {
+    fn new(limit: usize, event_tx: Sender<TailscaleEvent>) -> Self {
+        Self {
+            slots: Arc::new(tokio::sync::Semaphore::new(limit)),
+            counts: Arc::new(Mutex::new((0, 0))),
+            event_tx,
+        }
+    }
+
+    /// Apply `f` to (active, queued) and tell the UI.
+    fn update(&self, f: impl FnOnce(&mut (usize, usize))) {
+        let (active, queued) = {
+            let mut counts = self.counts.lock().unwrap();
+            f(&mut counts);
+            *counts
+        };
+        let _ = self.event_tx.send(TailscaleEvent::SendQueue { active, queued });
+    }
+
+    /// Count a new send as queued for as long as the returned guard lives.
+    fn enqueue(&self) -> QueuedSend {
+        self.update(|(_, queued)| *queued += 1);
+        QueuedSend {
+            queue: self.clone(),
+            active: false,
+        }
+    }
+}
+
+/// One send's place in the `SendQueue` counters: queued, then active after
+/// `start`. Dropping it takes the send back out, even on an early return or panic.
+struct QueuedSend {
+    queue: SendQueue,
+    active: bool,
+}
+
+impl QueuedSend {
+    fn start(&mut self) {
+        self.active = true;
+        self.queue.update(|(active, queued)| {
+            *queued -= 1;
+            *active += 1;
+        });
+    }
+}
+
+impl Drop for QueuedSend {
+    fn drop(&mut self) {
+        let was_active = self.active;
+        self.queue.update(|(active, queued)| {
+            if was_active {
+                *active -= 1;
+            } else {
+                *queued -= 1;
+            }
+        });
+    }
+}
+
 pub async fn run_tailscale_backend(
     event_tx: Sender<TailscaleEvent>,
     mut command_rx: tokio_mpsc::UnboundedReceiver<TailscaleCommand>,
@@ -96,56 +258,68 @@ pub async fn run_tailscale_backend(
         "Connecting to Tailscale...".to_string(),
     ));
 
-    // Fetch initial peer list
-    match fetch_status(&client).await {
-        Ok(peers) => {
-            // Update shared state for HTTP server
-            {
-                let mut shared_peers = app_state.peers.lock().unwrap();
-                *shared_peers = peers.clone();
-            }
-            let _ = event_tx.send(TailscaleEvent::PeersUpdated(peers));
-            let _ = event_tx.send(TailscaleEvent::ConnectionStatus(
-                true,
-                "Connected to Tailscale".to_string(),
-            ));
-        }
-        Err(e) => {
-            let _ = event_tx.send(TailscaleEvent::Error(format!(
-                "Failed to connect: {}",
-                e
-            )));
-        }
-    }
-
-    // Auto-configure `tailscale serve` to expose port 8080 on the tailnet
-    tokio::spawn(async {
-        match tokio::process::Command::new("tailscale")
-            .args(["serve", "--bg", "8080"])
-            .output()
-            .await
-        {
-            Ok(output) => {
-                let stdout = String::from_utf8_lossy(&output.stdout);
-                if output.status.success() {
-                    log::info!("Tailscale serve configured: {stdout}");
-                } else {
-                    let stderr = String::from_utf8_lossy(&output.stderr);
-                    log::warn!("Tailscale serve setup: {}", stderr.trim());
+    // Fetch initial peer list, retrying with backoff while tailscaled comes up (e.g. right after boot)
+    let mut socket_check = Ok(());
+    let mut delay = INITIAL_FETCH_BASE_DELAY;
+    for attempt in 1..=INITIAL_FETCH_ATTEMPTS {
+        match fetch_status(&client).await {
+            Ok(peers) => {
+                // Update shared state for HTTP server
+                {
+                    let mut shared_peers = app_state.peers.lock().unwrap();
+                    *shared_peers = peers.clone();
                 }
+                let _ = event_tx.send(TailscaleEvent::PeersUpdated(peers));
+                let _ = event_tx.send(TailscaleEvent::ConnectionStatus(
+                    true,
+                    "Connected to Tailscale".to_string(),
+                ));
+                break;
+            }
+            Err(e) if attempt < INITIAL_FETCH_ATTEMPTS => {
+                log::warn!("Initial status fetch failed (attempt {}): {}", attempt, e);
+                let _ = event_tx.send(TailscaleEvent::ConnectionStatus(
+                    false,
+                    format!(
+                        "Waiting for tailscaled (attempt {}/{})...",
+                        attempt + 1,
+                        INITIAL_FETCH_ATTEMPTS
+                    ),
+                ));
+                tokio::time::sleep(delay).await;
+                delay *= 2;
+            }
+            Err(e) => {
+                socket_check = Err(e.to_string());
+                let _ = event_tx.send(TailscaleEvent::Error(format!(
+                    "Failed to connect: {}",
+                    e
+                )));
             }
-            Err(e) => log::error!("Failed to run 'tailscale serve': {}", e),
         }
-    });
+    }
 
-    // Spawn status HTTP server (0.0.0.0:8080)
+    // Spawn status HTTP server (0.0.0.0:8080), binding first so the self-check can report it
+    let listener = tokio::net::TcpListener::bind(super::status::STATUS_ADDR).await;
+    let port_check = listener.as_ref().map(|_| ()).map_err(|e| e.to_string());
     let state_for_server = app_state.clone();
     let status_handle = tokio::spawn(async move {
-        if let Err(e) = super::status::run_status_server(state_for_server).await {
+        let Ok(listener) = listener else {
+            return;
+        };
+        log::info!("Status server listening on {}", super::status::STATUS_ADDR);
+        if let Err(e) = super::status::serve_status(listener, state_for_server).await {
             log::error!("Status server error: {:?}", e);
         }
     });
 
+    // Auto-configure `tailscale serve` to expose port 8080 on the tailnet, then
+    // sum up startup
+    tokio::spawn(async move {
+        let serve_check = configure_tailscale_serve().await;
+        log_self_check(&socket_check, &serve_check, &port_check);
+    });
+
     // Spawn IPN bus watcher (must stay lean — no blocking calls in the read loop)
     let event_tx_watcher = event_tx.clone();
     let received_for_watcher = app_state.received.clone();
@@ -155,60 +329,133 @@ pub async fn run_tailscale_backend(
         }
     });
 
-    // Spawn periodic check for waiting files via the API.
-    // Catches files received before the app started.
-    let event_tx_files = event_tx.clone();
+    // Spawn one periodic task for both the waiting-files check (catches files
+    // received before the app started) and the peer refresh, so tailscaled
+    // sees a single wake-up per tick instead of two independent timers.
+    let event_tx_periodic = event_tx.clone();
     let received_for_checker = app_state.received.clone();
-    let files_check_handle = tokio::spawn(async move {
-        let files_client = Client::builder(hyper_util::rt::TokioExecutor::new())
-            .build(UnixConnector);
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(5));
+    let index_for_checker = app_state.received_index.clone();
+    let root_for_checker = app_state.root.clone();
+    let client_clone = client.clone();
+    let peers_shared = app_state.peers.clone();
+    let file_check_every = env_interval(FILE_CHECK_INTERVAL_ENV, DEFAULT_FILE_CHECK_INTERVAL);
+    let peer_refresh_every = env_interval(PEER_REFRESH_INTERVAL_ENV, DEFAULT_PEER_REFRESH_INTERVAL);
+    log::info!(
+        "Periodic checks: files every {:?}, peers every {:?}",
+        file_check_every,
+        peer_refresh_every
+    );
+    let periodic_handle = tokio::spawn(async move {
+        let tick = file_check_every.min(peer_refresh_every);
+        let mut interval = tokio::time::interval(tick);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+        let mut last_file_check: Option<std::time::Instant> = None;
+        let mut last_peer_refresh: Option<std::time::Instant> = None;
         loop {
             interval.tick().await;
-            if let Ok(waiting) = super::files::fetch_waiting_files(&files_client).await {
-                for wf in waiting {
-                    // Update the received state so the download server knows about these files
-                    {
-                        let mut state = received_for_checker.lock().unwrap();
-                        if state.last_file.is_none() {
-                            state.last_file = Some(wf.name.clone());
-                        }
+
+            if last_file_check.is_none_or(|t| t.elapsed() >= file_check_every) {
+                last_file_check = Some(std::time::Instant::now());
+                if let Ok(waiting) = super::files::fetch_waiting_files(&client_clone).await {
+                    let mut index_changed = false;
+                    for wf in waiting {
+                        // Update the received state so the download server knows about these files
+                        let meta = {
+                            let mut state = received_for_checker.lock().unwrap();
+                            state.note_file(&wf.name, None);
+                            if state.last_file.is_none() {
+                                state.last_file = Some(wf.name.clone());
+                            }
+                            state.file_meta[&wf.name].clone()
+                        };
+                        index_changed |= index_for_checker.lock().unwrap().note(
+                            &wf.name,
+                            wf.size as u64,
+                            &meta.from_peer,
+                            meta.received_at,
+                        );
+
+                        let _ = event_tx_periodic.send(TailscaleEvent::FileReceived(
+                            super::app_state::ReceivedFile {
+                                name: wf.name.clone(),
+                                path: None,
+                                size: wf.size as u64,
+                                saved: false,
+                            },
+                        ));
+                    }
+                    if index_changed {
+                        index_for_checker.lock().unwrap().save(&root_for_checker);
                     }
+                }
+            }
 
-                    let _ = event_tx_files.send(TailscaleEvent::FileReceived(
-                        super::app_state::ReceivedFile {
-                            name: wf.name.clone(),
-                            path: None,
-                            size: wf.size as u64,
-                            from_peer: "Unknown".to_string(),
-                            received_at: std::time::Instant::now(),
-                            saved: false,
-                        },
-                    ));
+            if last_peer_refresh.is_none_or(|t| t.elapsed() >= peer_refresh_every) {
+                last_peer_refresh = Some(std::time::Instant::now());
+                if let Ok(peers) = fetch_status(&client_clone).await {
+                    // Update shared state for HTTP server
+                    {
+                        let mut shared = peers_shared.lock().unwrap();
+                        *shared = peers.clone();
+                    }
+                    let _ = event_tx_periodic.send(TailscaleEvent::PeersUpdated(peers));
                 }
             }
         }
     });
 
-    // Spawn periodic peer refresh
-    let event_tx_status = event_tx.clone();
-    let client_clone = client.clone();
-    let peers_shared = app_state.peers.clone();
-    let refresh_handle = tokio::spawn(async move {
-        let mut interval = tokio::time::interval(tokio::time::Duration::from_secs(5));
+    // Forward notes posted to the HTTP server (history first, then new ones)
+    let event_tx_notes = event_tx.clone();
+    let notes = app_state.notes.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+        let mut last_id = None;
         loop {
             interval.tick().await;
-            if let Ok(peers) = fetch_status(&client_clone).await {
-                // Update shared state for HTTP server
-                {
-                    let mut shared = peers_shared.lock().unwrap();
-                    *shared = peers.clone();
-                }
-                let _ = event_tx_status.send(TailscaleEvent::PeersUpdated(peers));
+            let new: Vec<_> = notes
+                .lock()
+                .unwrap()
+                .recent
+                .iter()
+                .filter(|n| last_id.is_none_or(|id| n.id > id))
+                .cloned()
+                .collect();
+            let history = last_id.is_none();
+            if new.is_empty() && !history {
+                continue;
+            }
+            last_id = Some(new.last().map_or(0, |n| n.id));
+            if event_tx_notes
+                .send(TailscaleEvent::NotesReceived { notes: new, history })
+                .is_err()
+            {
+                break;
+            }
+        }
+    });
+
+    // Report transfers the HTTP server is serving; quiet once the list stays empty
+    let event_tx_transfers = event_tx.clone();
+    let transfers = app_state.transfers.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(std::time::Duration::from_secs(1));
+        let mut was_empty = true;
+        loop {
+            interval.tick().await;
+            let list = transfers.lock().unwrap().list();
+            if list.is_empty() && was_empty {
+                continue;
+            }
+            was_empty = list.is_empty();
+            if event_tx_transfers.send(TailscaleEvent::ServerTransfers(list)).is_err() {
+                break;
             }
         }
     });
 
+    // Sends beyond the limit wait for a slot instead of all hitting tailscaled at once
+    let send_queue = SendQueue::new(max_concurrent_sends(), event_tx.clone());
+
     // Handle commands from UI
     while let Some(cmd) = command_rx.recv().await {
         match cmd {
@@ -216,7 +463,15 @@ pub async fn run_tailscale_backend(
                 let client = client.clone();
                 let event_tx = event_tx.clone();
                 let last_sent = app_state.last_sent.clone();
+                let metrics = app_state.metrics.clone();
+                let slots = send_queue.slots.clone();
+                let mut queued = send_queue.enqueue();
                 tokio::spawn(async move {
+                    let Ok(_permit) = slots.acquire().await else {
+                        return;
+                    };
+                    queued.start();
+
                     let file_name = file_path
                         .file_name()
                         .and_then(|n| n.to_str())
@@ -256,12 +511,20 @@ pub async fn run_tailscale_backend(
                         });
                     }
 
-                    if let Err(e) = result {
-                        let _ = event_tx.send(TailscaleEvent::Error(format!(
-                            "Failed to send file: {}",
-                            e
-                        )));
+                    match result {
+                        Ok(_) => {
+                            use std::sync::atomic::Ordering;
+                            metrics.taildrop_files_sent.fetch_add(1, Ordering::Relaxed);
+                            metrics.bytes_sent.fetch_add(file_size, Ordering::Relaxed);
+                        }
+                        Err(e) => {
+                            let _ = event_tx.send(TailscaleEvent::Error(format!(
+                                "Failed to send file: {}",
+                                e
+                            )));
+                        }
                     }
+                    drop(queued);
                 });
             }
             TailscaleCommand::RefreshPeers => {
@@ -275,6 +538,8 @@ pub async fn run_tailscale_backend(
             }
             TailscaleCommand::SaveReceivedFile { name, src_path, dest } => {
                 let event_tx = event_tx.clone();
+                let index = app_state.received_index.clone();
+                let root = app_state.root.clone();
                 tokio::spawn(async move {
                     let save_result = if let Some(src) = &src_path {
                         // Fast path: copy directly from FinalPath on disk
@@ -290,22 +555,32 @@ pub async fn run_tailscale_backend(
                         }
                     };
 
-                    match save_result {
+                    let result = match save_result {
                         Ok(()) => {
                             log::info!("Saved '{}' to {:?}", name, dest);
+                            // Keep serving it from the saved copy once the inbox forgets it
+                            let size = tokio::fs::metadata(&dest).await.map(|m| m.len()).unwrap_or(0);
+                            {
+                                let mut index = index.lock().unwrap();
+                                index.note_saved(&name, size, dest.clone());
+                                index.save(&root);
+                            }
                             // Clean up from the Taildrop inbox
                             if let Err(e) = super::files::delete_received_file(&name).await {
                                 log::warn!("Failed to clean up '{}' from inbox: {}", name, e);
                             }
+                            Ok(dest)
                         }
-                        Err(e) => {
-                            let _ = event_tx.send(TailscaleEvent::Error(format!(
-                                "Failed to save file '{}': {}", name, e
-                            )));
-                        }
-                    }
+                        Err(e) => Err(e.to_string()),
+                    };
+                    let _ = event_tx.send(TailscaleEvent::ReceivedFileSaved { name, result });
                 });
             }
+            TailscaleCommand::CancelTransfer(id) => {
+                if !app_state.transfers.lock().unwrap().cancel(id) {
+                    log::warn!("Transfer {} already finished", id);
+                }
+            }
             TailscaleCommand::DeleteReceivedFile(name) => {
                 let event_tx = event_tx.clone();
                 tokio::spawn(async move {
@@ -320,12 +595,64 @@ pub async fn run_tailscale_backend(
     }
 
     watcher_handle.abort();
-    files_check_handle.abort();
-    refresh_handle.abort();
+    periodic_handle.abort();
     status_handle.abort();
     Ok(())
 }
 
+async fn configure_tailscale_serve() -> Result<(), String> {
+    let output = tokio::process::Command::new("tailscale")
+        .args(["serve", "--bg", "8080"])
+        .output()
+        .await
+        .map_err(|e| format!("couldn't run 'tailscale': {}", e))?;
+    if output.status.success() {
+        log::info!("Tailscale serve configured: {}", String::from_utf8_lossy(&output.stdout));
+        Ok(())
+    } else {
+        Err(String::from_utf8_lossy(&output.stderr).trim().to_string())
+    }
+}
+
+/// Log one line per startup step, with what to try for the ones that failed.
+fn log_self_check(
+    socket: &Result<(), String>,
+    serve: &Result<(), String>,
+    port: &Result<(), String>,
+) {
+    let socket_hint = format!(
+        "is tailscaled running (`sudo systemctl start tailscaled`)? The app talks to it at {}",
+        TAILSCALED_SOCKET
+    );
+    let steps = [
+        ("tailscaled socket", socket, socket_hint.as_str()),
+        (
+            "tailscale serve",
+            serve,
+            "run `tailscale serve --bg 8080` yourself; without root it may need `sudo tailscale set --operator=$USER`",
+        ),
+        ("port 8080", port, "another copy of the app (or something else) is already using it"),
+    ];
+    for (step, result, hint) in steps {
+        match result {
+            Ok(()) => log::info!("Self-check: {} OK", step),
+            Err(e) => log::warn!("Self-check: {} failed: {} — {}", step, e, hint),
+        }
+    }
+}
+
+/// Cheap check that tailscaled is accepting connections on its socket.
+pub async fn tailscaled_reachable() -> bool {
+    matches!(
+        tokio::time::timeout(
+            std::time::Duration::from_secs(2),
+            UnixStream::connect(TAILSCALED_SOCKET),
+        )
+        .await,
+        Ok(Ok(_))
+    )
+}
+
 pub async fn fetch_status(
     client: &Client<UnixConnector, Empty<Bytes>>,
 ) -> anyhow::Result<Vec<TailscalePeer>> {
@@ -337,11 +664,16 @@ pub async fn fetch_status(
     let res = client.request(req).await?;
     let body = res.into_body().collect().await?.to_bytes();
     let status: TailscaleStatus = serde_json::from_slice(&body)?;
+    Ok(peers_from_status(status))
+}
 
+/// Flatten a `/localapi/v0/status` response into the peer list (self included).
+fn peers_from_status(status: TailscaleStatus) -> Vec<TailscalePeer> {
     let mut peers = Vec::new();
 
     // Add self
     if let Some(self_node) = status.self_node {
+        let can_receive_files = self_node.can_receive_files();
         peers.push(TailscalePeer {
             id: self_node.id,
             hostname: self_node.hostname,
@@ -350,13 +682,16 @@ pub async fn fetch_status(
             online: true,
             is_self: true,
             os: self_node.os.unwrap_or_default(),
-            can_receive_files: true,
+            can_receive_files,
+            connection: None,
         });
     }
 
     // Add other peers
     if let Some(peer_map) = status.peers {
         for (_, peer) in peer_map {
+            let can_receive_files = peer.can_receive_files();
+            let connection = peer.connection();
             peers.push(TailscalePeer {
                 id: peer.id,
                 hostname: peer.hostname,
@@ -365,13 +700,87 @@ pub async fn fetch_status(
                 online: peer.online,
                 is_self: false,
                 os: peer.os.unwrap_or_default(),
-                can_receive_files: true,
+                can_receive_files,
+                connection,
             });
         }
     }
 
     // Sort: online first, then alphabetically
-    let peers_with_os = peers.iter().filter(|p| !p.os.is_empty()).cloned().collect::<Vec<_>>();
+    peers.into_iter().filter(|p| !p.os.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status_with_peer(peer_caps: &str) -> TailscaleStatus {
+        let json = format!(
+            r#"{{
+                "BackendState": "Running",
+                "Self": {{
+                    "ID": "self", "HostName": "desk", "DNSName": "desk.ts.net.",
+                    "Online": true, "OS": "linux",
+                    "CapMap": {{ "{CAP_FILE_SHARING}": null }}
+                }},
+                "Peer": {{
+                    "key": {{
+                        "ID": "p1", "HostName": "tv", "DNSName": "tv.ts.net.",
+                        "Online": true, "OS": "linux"{peer_caps}
+                    }}
+                }}
+            }}"#
+        );
+        serde_json::from_str(&json).unwrap()
+    }
+
+    fn peer_can_receive(peer_caps: &str) -> bool {
+        peers_from_status(status_with_peer(peer_caps))
+            .into_iter()
+            .find(|p| p.id == "p1")
+            .unwrap()
+            .can_receive_files
+    }
 
-    Ok(peers_with_os)
+    #[test]
+    fn peer_without_file_sharing_capability_cannot_receive() {
+        assert!(!peer_can_receive(
+            r#", "CapMap": { "https://tailscale.com/cap/ssh": null }"#
+        ));
+        assert!(!peer_can_receive(r#", "Capabilities": []"#));
+    }
+
+    #[test]
+    fn peer_with_file_sharing_capability_can_receive() {
+        assert!(peer_can_receive(&format!(r#", "CapMap": {{ "{CAP_FILE_SHARING}": null }}"#)));
+        assert!(peer_can_receive(&format!(r#", "Capabilities": ["{CAP_FILE_SHARING_TARGET}"]"#)));
+    }
+
+    #[test]
+    fn peer_from_older_tailscaled_is_assumed_capable() {
+        assert!(peer_can_receive(""));
+    }
+
+    #[test]
+    fn send_queue_counts_drop_with_the_send() {
+        let (event_tx, events) = std::sync::mpsc::channel();
+        let queue = SendQueue::new(1, event_tx);
+        let mut started = queue.enqueue();
+        let waiting = queue.enqueue();
+        started.start();
+        assert_eq!(*queue.counts.lock().unwrap(), (1, 1));
+
+        // A send that never got a slot, and one that panicked mid-send
+        drop(waiting);
+        let panicked = std::panic::catch_unwind(std::panic::AssertUnwindSafe(move || {
+            let _started = started;
+            panic!("send failed");
+        }));
+        assert!(panicked.is_err());
+        assert_eq!(*queue.counts.lock().unwrap(), (0, 0));
+        assert!(matches!(
+            events.try_iter().last(),
+            Some(TailscaleEvent::SendQueue { active: 0, queued: 0 })
+        ));
+    }
 }
@@ -1,11 +1,18 @@
 use eframe::egui::{self, Color32, RichText, Sense, StrokeKind, Vec2};
 use egui::{Layout, TextEdit, Widget};
-use std::cmp::Ordering;
 use std::path::PathBuf;
 
-use super::app_state::TailscaleCommand;
+use super::app_state::{
+    CODE_EXTENSIONS, DOCUMENT_EXTENSIONS, FILE_FILTER_KEY, FileFilter, IMAGE_EXTENSIONS,
+    RECEIVED_FILTER_KEY, ReceivedFilter, TailscaleCommand, file_extension,
+};
 
 impl eframe::App for super::app_state::TailscaleDriveApp {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, FILE_FILTER_KEY, &self.file_filter);
+        eframe::set_value(storage, RECEIVED_FILTER_KEY, &self.received_filter);
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Process events from background task
         self.process_events();
@@ -17,9 +24,7 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
         ctx.input(|i| {
             for file in &i.raw.dropped_files {
                 if let Some(path) = &file.path {
-                    if !self.files_to_send.contains(path) {
-                        self.files_to_send.push(path.clone());
-                    }
+                    self.queue_file_to_send(path.clone());
                 }
             }
         });
@@ -30,6 +35,32 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
             egui_logger::logger_ui().show(ui)
         );
 
+        let join_url = self
+            .peers
+            .iter()
+            .find(|p| p.is_self && !p.dns_name.is_empty())
+            .map(|p| super::status::join_url(&p.dns_name));
+        egui::Window::new("Pair phone")
+            .open(&mut self.show_join_code)
+            .resizable(false)
+            .show(ctx, |ui| match join_url {
+                Some(ref url) => {
+                    ui.label("Scan with the iOS app, or paste the code into its server URL field.");
+                    ui.add_space(6.0);
+                    draw_qr_code(ui, url);
+                    ui.add_space(6.0);
+                    ui.horizontal(|ui| {
+                        ui.monospace(url);
+                        if ui.small_button("📋").on_hover_text("Copy").clicked() {
+                            ui.ctx().copy_text(url.clone());
+                        }
+                    });
+                }
+                None => {
+                    ui.label("Waiting for this device's tailnet name…");
+                }
+            });
+
         // Top bar
         egui::TopBottomPanel::top("top_panel").show(ctx, |ui| {
             ui.horizontal(|ui| {
@@ -52,6 +83,10 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                         self.send_command(TailscaleCommand::RefreshPeers);
                     }
                     ui.separator();
+                    if ui.button("📱 Pair phone").clicked() {
+                        self.show_join_code = !self.show_join_code;
+                    }
+                    ui.separator();
                     let show_hide_logs = if self.show_logs { "Hide Logs" } else { "Show Logs" };
                     if ui.button(show_hide_logs).clicked() {
                         self.show_logs = !self.show_logs;
@@ -80,18 +115,6 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
 
                 // Device list
                 egui::ScrollArea::vertical().auto_shrink([false, true]).max_height(ui.available_height()/1.5).show(ui, |ui| {
-                    self.peers.sort_by(|a, b | {
-                        match (a.online, b.online) {
-                            (true, false) => Ordering::Less,
-                            (false, true) => Ordering::Greater,
-                            _ => {
-                                a.hostname
-                                    .to_lowercase()
-                                    .cmp(&b.hostname.to_lowercase())
-                            }
-                        }
-                    });
-
                     let peer_data: Vec<_> = self
                         .peers
                         .iter()
@@ -110,7 +133,7 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                             true
                         })
                         .map(|p| (p.id.clone(), p.hostname.clone(), p.dns_name.clone(), 
-                                  p.ip_addresses.clone(), p.online, p.os.clone()))
+                                  p.ip_addresses.clone(), p.online, p.os.clone(), p.can_receive_files))
                         .collect();
 
                     if peer_data.is_empty() {
@@ -118,7 +141,7 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                     } else {
                         let mut new_selection = None;
 
-                        for (id, hostname, dns_name, ips, _online, os) in &peer_data {
+                        for (id, hostname, dns_name, ips, _online, os, can_receive) in &peer_data {
                             // ui.horizontal(|ui| {
 
                             //     ui.with_layout(Layout::right_to_left(egui::Align::Max), |ui| {
@@ -136,19 +159,19 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                                 "ios" => "🍎",
                                 _ => "🖳",
                             };
-                            let response = ui.selectable_label(
-                                is_selected,
-                                format!("{logo} {hostname}"),
-                            );
+                            let label = if *can_receive {
+                                RichText::new(format!("{logo} {hostname}"))
+                            } else {
+                                RichText::new(format!("{logo} {hostname}")).weak()
+                            };
+                            let response = ui.selectable_label(is_selected, label);
 
                             if response.clicked() {
                                 new_selection = Some(id.clone());
                             }
 
-                            if response.secondary_clicked() {
-                                if ips.len() >= 1 {
-                                    ctx.copy_text(ips[0].clone());
-                                }
+                            if response.secondary_clicked() && !ips.is_empty() {
+                                ctx.copy_text(ips[0].clone());
                             }
 
                             // Show IP on hover
@@ -157,6 +180,12 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                                 for ip in ips {
                                     ui.label(format!("IP: {}", ip));
                                 }
+                                if !can_receive {
+                                    ui.label(
+                                        RichText::new("Can't receive Taildrop files (no file-sharing capability for this node)")
+                                            .weak(),
+                                    );
+                                }
                             });
                         }
                         if let Some(sel) = new_selection {
@@ -166,30 +195,111 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                 });
 
                 ui.separator();
-                ui.heading("Received Files");
+                ui.horizontal(|ui| {
+                    ui.heading("Received Files");
+                    if self.received_files.iter().any(|f| !f.saved)
+                        && ui
+                            .button("📥 Save All to…")
+                            .on_hover_text("Save every received file into one folder and clear them from the inbox")
+                            .clicked()
+                        && let Some(dir) = rfd::FileDialog::new().pick_folder()
+                    {
+                        self.save_all_received(dir);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let unsaved = self.received_files.iter().filter(|f| !f.saved).count();
+                    ui.selectable_value(&mut self.received_filter, ReceivedFilter::All, "All");
+                    ui.selectable_value(
+                        &mut self.received_filter,
+                        ReceivedFilter::Unsaved,
+                        format!("Unsaved ({})", unsaved),
+                    );
+                });
                 ui.separator();
 
+                // Progress, then per-file results, of the last "Save All"
+                let mut dismiss_save_all = false;
+                if let Some(batch) = &self.save_all {
+                    let done = batch.results.len();
+                    let total = batch.total();
+                    if !batch.pending.is_empty() {
+                        ui.add(
+                            egui::ProgressBar::new(done as f32 / total.max(1) as f32)
+                                .text(format!("Saving {}/{} to {}", done, total, batch.dest_dir.display()))
+                                .desired_width(220.0),
+                        );
+                    } else {
+                        let failed = batch.failed();
+                        let title = if failed == 0 {
+                            format!("✔ Saved {} files", done)
+                        } else {
+                            format!("Saved {} of {} files, {} failed", done - failed, done, failed)
+                        };
+                        ui.horizontal(|ui| {
+                            egui::CollapsingHeader::new(title)
+                                .id_salt("save_all_results")
+                                .default_open(failed > 0)
+                                .show(ui, |ui| {
+                                    ui.label(RichText::new(batch.dest_dir.display().to_string()).weak().small());
+                                    for (name, result) in &batch.results {
+                                        match result {
+                                            Ok(path) => {
+                                                let saved_as = path.file_name().map(|n| n.to_string_lossy());
+                                                let label = match saved_as {
+                                                    Some(n) if n != name.as_str() => format!("✔ {} → {}", name, n),
+                                                    _ => format!("✔ {}", name),
+                                                };
+                                                ui.label(label);
+                                            }
+                                            Err(e) => {
+                                                ui.colored_label(Color32::from_rgb(231, 76, 60), format!("🗙 {}", name))
+                                                    .on_hover_text(e);
+                                            }
+                                        }
+                                    }
+                                });
+                            if ui.small_button("🗙").on_hover_text("Dismiss").clicked() {
+                                dismiss_save_all = true;
+                            }
+                        });
+                    }
+                    ui.separator();
+                }
+                if dismiss_save_all {
+                    self.save_all = None;
+                }
+
                 // Active transfers / incoming files
                 if !self.transferring_files.is_empty() {
                     ui.label(RichText::new("Incoming").strong());
                     let mut transfer_to_clear = None;
-                    for (tidx, transfer) in self.transferring_files.iter().enumerate() {
+                    for (tidx, transfer) in self.transferring_files.iter_mut().enumerate() {
                         ui.horizontal(|ui| {
-                            let progress = if transfer.size > 0 {
-                                transfer.transferred as f32 / transfer.size as f32
-                            } else {
-                                0.0
-                            };
                             let stuck = transfer.transferred == 0 && transfer.size > 0;
-                            ui.add(
-                                egui::ProgressBar::new(progress)
-                                    .text(if stuck {
+                            let bar = match transfer.fraction() {
+                                Some(target) => {
+                                    // Notifications arrive in bursts; ease toward the latest value
+                                    if target < transfer.shown || (target - transfer.shown).abs() < 0.002 {
+                                        transfer.shown = target;
+                                    } else {
+                                        transfer.shown += (target - transfer.shown) * 0.25;
+                                        ctx.request_repaint();
+                                    }
+                                    egui::ProgressBar::new(transfer.shown).text(if stuck {
                                         format!("{} (stuck)", transfer.name)
                                     } else {
                                         transfer.name.clone()
                                     })
-                                    .desired_width(180.0),
-                            );
+                                }
+                                // No declared size: show what has arrived on an animated bar
+                                None => egui::ProgressBar::new(0.0).animate(true).text(format!(
+                                    "{} ({})",
+                                    transfer.name,
+                                    format_size(transfer.transferred)
+                                )),
+                            };
+                            ui.add(bar.desired_width(180.0));
                             if ui.small_button("🗙").on_hover_text("Clear from inbox").clicked() {
                                 transfer_to_clear = Some(tidx);
                             }
@@ -203,21 +313,59 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                     ui.separator();
                 }
 
+                // Text notes from other devices, newest first
+                if !self.notes.is_empty() {
+                    egui::CollapsingHeader::new(format!("📝 Notes ({})", self.notes.len()))
+                        .default_open(true)
+                        .show(ui, |ui| {
+                            egui::ScrollArea::vertical()
+                                .id_salt("notes_scroll")
+                                .max_height(160.0)
+                                .show(ui, |ui| {
+                                    for note in self.notes.iter().rev() {
+                                        ui.horizontal(|ui| {
+                                            ui.label(
+                                                RichText::new(format!(
+                                                    "{} · {}",
+                                                    note.from,
+                                                    format_note_time(note.received_at)
+                                                ))
+                                                .weak()
+                                                .small(),
+                                            );
+                                            if ui.small_button("📋").on_hover_text("Copy").clicked() {
+                                                ui.ctx().copy_text(note.text.clone());
+                                            }
+                                        });
+                                        ui.label(&note.text);
+                                        ui.add_space(4.0);
+                                    }
+                                });
+                        });
+                    ui.separator();
+                }
+
                 // Received files list
                 egui::ScrollArea::vertical().auto_shrink([true, false]).show(ui, |ui| {
+                    let filter = self.received_filter;
                     if self.received_files.is_empty() {
                         ui.label(RichText::new("No files received yet").weak());
+                    } else if !self.received_files.iter().any(|f| filter.matches(f)) {
+                        ui.label(RichText::new("Everything has been saved").weak());
                     } else {
                         let mut file_to_save = None;
                         let mut file_to_delete = None;
 
                         for (idx, file) in self.received_files.iter().enumerate() {
+                            if !filter.matches(file) {
+                                continue;
+                            }
                             let is_selected = self.selected_received_file == Some(idx);
                             
                             
                             ui.group(|ui| {
                                 ui.horizontal(|ui| {
-                                    if ui.selectable_label(is_selected, RichText::new(format!("📄 {}", &file.name)).strong()).clicked() {
+                                    if ui.selectable_label(is_selected, RichText::new(format!("{} {}", file_icon(&file.name, false).0, &file.name)).strong()).clicked() {
                                         self.selected_received_file = Some(idx);
                                     }
                                     ui.vertical(|ui| {
@@ -225,6 +373,11 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                                             RichText::new(format_size(file.size)).weak().small(),
                                         );
                                     });
+                                    if file.saved {
+                                        let saved_to = file.path.as_ref().map(|p| p.display().to_string());
+                                        ui.colored_label(Color32::from_rgb(46, 204, 113), "✓")
+                                            .on_hover_text(format!("Saved to {}", saved_to.unwrap_or_default()));
+                                    }
                                 });
 
                                 if is_selected {
@@ -232,7 +385,9 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                                         if ui.button("💾 Save As...").clicked() {
                                             file_to_save = Some(idx);
                                         }
-                                        if ui.button("🗑 Delete").clicked() {
+                                        // A saved file is already out of the inbox; only forget it here
+                                        let delete = if file.saved { "🗙 Remove" } else { "🗑 Delete" };
+                                        if ui.button(delete).clicked() {
                                             file_to_delete = Some(idx);
                                         }
                                     });
@@ -241,28 +396,27 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                         }
 
                         // Handle save
-                        if let Some(idx) = file_to_save {
-                            if let Some(file) = self.received_files.get(idx) {
-                                if let Some(dest) = rfd::FileDialog::new()
-                                    .set_file_name(&file.name)
-                                    .save_file()
-                                {
-                                    self.send_command(TailscaleCommand::SaveReceivedFile {
-                                        name: file.name.clone(),
-                                        src_path: file.path.clone(),
-                                        dest,
-                                    });
-                                    // Remove from list so the name slot is freed.
-                                    // The background task handles the actual save + inbox cleanup.
-                                    self.received_files.remove(idx);
-                                    self.selected_received_file = None;
-                                }
-                            }
+                        if let Some(idx) = file_to_save
+                            && let Some(file) = self.received_files.get(idx)
+                            && let Some(dest) = rfd::FileDialog::new()
+                                .set_file_name(&file.name)
+                                .save_file()
+                        {
+                            // The background task handles the actual save + inbox
+                            // cleanup; the entry is marked saved when it reports back.
+                            self.send_command(TailscaleCommand::SaveReceivedFile {
+                                name: file.name.clone(),
+                                src_path: file.path.clone(),
+                                dest,
+                            });
+                            self.selected_received_file = None;
                         }
 
                         // Handle delete
                         if let Some(idx) = file_to_delete {
-                            if let Some(file) = self.received_files.get(idx) {
+                            if let Some(file) = self.received_files.get(idx)
+                                && !file.saved
+                            {
                                 self.send_command(TailscaleCommand::DeleteReceivedFile(
                                     file.name.clone(),
                                 ));
@@ -326,13 +480,74 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
 
             ui.add_space(8.0);
 
+            // Sends already handed to the backend
+            let (active, queued) = self.send_queue;
+            if active + queued > 0 {
+                let text = if queued > 0 {
+                    format!("📤 Sending {} · {} queued", active, queued)
+                } else {
+                    format!("📤 Sending {}", active)
+                };
+                ui.label(RichText::new(text).weak());
+                ui.add_space(4.0);
+            }
+
+            // Files other devices are pulling from us right now
+            if !self.served_transfers.is_empty() {
+                let mut cancel = None;
+                ui.group(|ui| {
+                    ui.label(RichText::new("Serving:").strong());
+                    for transfer in &self.served_transfers {
+                        let name = transfer.path.rsplit('/').next().unwrap_or(&transfer.path);
+                        ui.horizontal(|ui| {
+                            ui.label(format!("📥 {} → {}", name, transfer.client));
+                            let progress = if transfer.total_bytes > 0 {
+                                transfer.bytes_sent as f32 / transfer.total_bytes as f32
+                            } else {
+                                0.0
+                            };
+                            ui.add(
+                                egui::ProgressBar::new(progress)
+                                    .desired_width(160.0)
+                                    .text(format!(
+                                        "{} / {}",
+                                        format_size(transfer.bytes_sent),
+                                        format_size(transfer.total_bytes)
+                                    )),
+                            );
+                            if ui.small_button("🗙 Stop").on_hover_text(&transfer.path).clicked() {
+                                cancel = Some(transfer.id);
+                            }
+                        });
+                    }
+                });
+                if let Some(id) = cancel {
+                    self.send_command(TailscaleCommand::CancelTransfer(id));
+                }
+                ui.add_space(4.0);
+            }
+
             // Files queued for sending
             if !self.files_to_send.is_empty() {
                 ui.group(|ui| {
-                    ui.label(RichText::new("Files to send:").strong());
+                    ui.horizontal(|ui| {
+                        ui.label(RichText::new("Files to send:").strong());
+                        ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.small_button("Clear All").clicked() {
+                                self.files_to_send.clear();
+                            }
+                        });
+                    });
                     let mut to_remove = None;
+                    // (from, to) when a row is dragged by its handle and dropped on another row
+                    let mut to_move = None;
                     for (idx, path) in self.files_to_send.iter().enumerate() {
-                        ui.horizontal(|ui| {
+                        let row = ui.horizontal(|ui| {
+                            ui.dnd_drag_source(egui::Id::new(("send_queue", path)), idx, |ui| {
+                                ui.label(RichText::new("☰").weak());
+                            })
+                            .response
+                            .on_hover_text("Drag to reorder");
                             ui.label(format!(
                                 "📰 {}",
                                 path.file_name()
@@ -342,28 +557,54 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                             if ui.small_button("🗙").clicked() {
                                 to_remove = Some(idx);
                             }
-                        });
+                        })
+                        .response;
+
+                        if row.dnd_hover_payload::<usize>().is_some() {
+                            let rect = row.rect;
+                            ui.painter().hline(
+                                rect.x_range(),
+                                rect.top(),
+                                ui.visuals().selection.stroke,
+                            );
+                        }
+                        if let Some(from) = row.dnd_release_payload::<usize>() {
+                            to_move = Some((*from, idx));
+                        }
                     }
                     if let Some(idx) = to_remove {
                         self.files_to_send.remove(idx);
+                    } else if let Some((from, to)) = to_move
+                        && from != to
+                    {
+                        let path = self.files_to_send.remove(from);
+                        self.files_to_send.insert(to, path);
                     }
 
                     ui.separator();
 
                     // Send button
                     let mut should_send = false;
-                    let peer_hostname = self.selected_peer.as_ref().and_then(|pid| {
-                        self.peers.iter().find(|p| &p.id == pid).map(|p| p.hostname.clone())
-                    });
+                    let selected = self
+                        .selected_peer
+                        .as_ref()
+                        .and_then(|pid| self.peer(pid));
+                    let peer_hostname = selected.map(|p| p.hostname.clone());
+                    let peer_can_receive = selected.is_none_or(|p| p.can_receive_files);
 
                     ui.horizontal(|ui| {
-                        let can_send =
-                            self.selected_peer.is_some() && !self.files_to_send.is_empty();
+                        let can_send = self.selected_peer.is_some()
+                            && !self.files_to_send.is_empty()
+                            && peer_can_receive;
 
-                        if ui
+                        let response = ui
                             .add_enabled(can_send, egui::Button::new("💌 Send to Device"))
-                            .clicked()
-                        {
+                            .on_disabled_hover_text(if peer_can_receive {
+                                "Select a device and add files first"
+                            } else {
+                                "This device doesn't have Taildrop enabled (no file-sharing capability)"
+                            });
+                        if response.clicked() {
                             should_send = true;
                         }
 
@@ -374,16 +615,16 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                         }
                     });
 
-                    if should_send {
-                        if let Some(peer_id) = self.selected_peer.clone() {
-                            let files: Vec<_> = self.files_to_send.drain(..).collect();
-                            if let Some(tx) = &self.command_tx {
-                                for file_path in files {
-                                    let _ = tx.send(TailscaleCommand::SendFile {
-                                        peer_id: peer_id.clone(),
-                                        file_path,
-                                    });
-                                }
+                    if should_send
+                        && let Some(peer_id) = self.selected_peer.clone()
+                    {
+                        let files: Vec<_> = self.files_to_send.drain(..).collect();
+                        if let Some(tx) = &self.command_tx {
+                            for file_path in files {
+                                let _ = tx.send(TailscaleCommand::SendFile {
+                                    peer_id: peer_id.clone(),
+                                    file_path,
+                                });
                             }
                         }
                     }
@@ -402,9 +643,7 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                     self.navigate_up();
                 }
                 if ui.button("🏠").on_hover_text("Home").clicked() {
-                    if let Ok(home) = std::env::var("HOME") {
-                        self.navigate_to(PathBuf::from(home));
-                    }
+                    self.navigate_to(crate::status::home_dir());
                 }
                 if ui.button("⟳").on_hover_text("Refresh").clicked() {
                     self.refresh_directory();
@@ -427,7 +666,7 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
 
             // Quick-access folder buttons
             ui.horizontal(|ui| {
-                let home = std::env::var("HOME").unwrap_or_else(|_| "/".to_string());
+                let home = crate::status::home_dir();
                 let shortcuts = [
                     ("📄 Documents", "Documents"),
                     ("📥 Downloads", "Downloads"),
@@ -436,7 +675,7 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                     ("🎬 Videos", "Videos"),
                 ];
                 for (label, folder) in shortcuts {
-                    let path = PathBuf::from(&home).join(folder);
+                    let path = home.join(folder);
                     let enabled = path.is_dir();
                     if ui.add_enabled(enabled, egui::Button::new(
                         RichText::new(label).small()
@@ -446,6 +685,40 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                 }
             });
 
+            // Extension filter (directories always stay visible)
+            ui.horizontal(|ui| {
+                ui.label(RichText::new("Show:").small());
+                let presets = [
+                    ("All", FileFilter::All),
+                    ("🖼 Images", FileFilter::Images),
+                    ("💻 Code", FileFilter::Code),
+                    ("📃 Documents", FileFilter::Documents),
+                ];
+                for (label, filter) in presets {
+                    let selected = self.file_filter == filter;
+                    if ui.selectable_label(selected, RichText::new(label).small()).clicked() {
+                        self.file_filter = filter;
+                    }
+                }
+                ui.separator();
+                let mut custom = match &self.file_filter {
+                    FileFilter::Custom(list) => list.clone(),
+                    _ => String::new(),
+                };
+                let response = ui.add(
+                    egui::TextEdit::singleline(&mut custom)
+                        .hint_text("e.g. rs, toml")
+                        .desired_width(120.0),
+                );
+                if response.changed() {
+                    self.file_filter = if custom.trim().is_empty() {
+                        FileFilter::All
+                    } else {
+                        FileFilter::Custom(custom)
+                    };
+                }
+            });
+
             ui.separator();
 
             // Directory contents
@@ -454,22 +727,26 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                 let mut add_to_send = None;
 
                 for (idx, entry) in self.directory_contents.iter().enumerate() {
+                    if !entry.is_dir && !self.file_filter.matches(&entry.name) {
+                        continue;
+                    }
                     let is_selected = self.selected_directory_item == Some(idx);
-                    let icon = if entry.is_dir { "📂" } else { "📰" };
-
-                    let response = ui.selectable_label(
-                        is_selected,
-                        format!(
-                            "{} {}{}",
-                            icon,
-                            entry.name,
-                            if entry.is_dir {
-                                "/".to_string()
-                            } else {
-                                format!(" ({})", format_size(entry.size))
-                            }
-                        ),
-                    );
+                    let (icon, color) = file_icon(&entry.name, entry.is_dir);
+
+                    let mut label = RichText::new(format!(
+                        "{} {}{}",
+                        icon,
+                        entry.name,
+                        if entry.is_dir {
+                            "/".to_string()
+                        } else {
+                            format!(" ({})", format_size(entry.size))
+                        }
+                    ));
+                    if let Some(color) = color {
+                        label = label.color(color);
+                    }
+                    let response = ui.selectable_label(is_selected, label);
 
                     if response.clicked() {
                         self.selected_directory_item = Some(idx);
@@ -485,17 +762,13 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
 
                     // Context menu
                     response.context_menu(|ui| {
-                        if !entry.is_dir {
-                            if ui.button("Add to send queue").clicked() {
-                                add_to_send = Some(entry.path.clone());
-                                ui.close();
-                            }
+                        if !entry.is_dir && ui.button("Add to send queue").clicked() {
+                            add_to_send = Some(entry.path.clone());
+                            ui.close();
                         }
-                        if entry.is_dir {
-                            if ui.button("Open folder").clicked() {
-                                nav_to = Some(entry.path.clone());
-                                ui.close();
-                            }
+                        if entry.is_dir && ui.button("Open folder").clicked() {
+                            nav_to = Some(entry.path.clone());
+                            ui.close();
                         }
                     });
                 }
@@ -505,15 +778,78 @@ impl eframe::App for super::app_state::TailscaleDriveApp {
                 }
 
                 if let Some(path) = add_to_send {
-                    if !self.files_to_send.contains(&path) {
-                        self.files_to_send.push(path);
-                    }
+                    self.queue_file_to_send(path);
                 }
             });
         });
     }
 }
 
+/// Icon and accent color for a directory entry, picked from the file extension.
+/// Draw `data` as a QR code, black modules on white with a quiet zone.
+fn draw_qr_code(ui: &mut egui::Ui, data: &str) {
+    let Ok(code) = qrcode::QrCode::new(data.as_bytes()) else {
+        ui.colored_label(Color32::from_rgb(231, 76, 60), "Join code too long for a QR code");
+        return;
+    };
+    let width = code.width();
+    let colors = code.to_colors();
+    let module = 5.0;
+    let quiet = 4.0 * module;
+    let side = width as f32 * module + 2.0 * quiet;
+    let (rect, _) = ui.allocate_exact_size(Vec2::splat(side), Sense::hover());
+    let painter = ui.painter_at(rect);
+    painter.rect_filled(rect, 0.0, Color32::WHITE);
+    for (i, color) in colors.iter().enumerate() {
+        if *color == qrcode::Color::Dark {
+            let min = rect.min
+                + Vec2::new(
+                    quiet + (i % width) as f32 * module,
+                    quiet + (i / width) as f32 * module,
+                );
+            painter.rect_filled(
+                egui::Rect::from_min_size(min, Vec2::splat(module)),
+                0.0,
+                Color32::BLACK,
+            );
+        }
+    }
+}
+
+/// How long ago a unix timestamp was, e.g. "5 min ago".
+fn format_note_time(ts: u64) -> String {
+    let diff = super::status::unix_timestamp().saturating_sub(ts);
+    if diff < 60 {
+        "just now".to_string()
+    } else if diff < 3600 {
+        format!("{} min ago", diff / 60)
+    } else if diff < 86_400 {
+        format!("{} hr ago", diff / 3600)
+    } else {
+        format!("{} days ago", diff / 86_400)
+    }
+}
+
+fn file_icon(name: &str, is_dir: bool) -> (&'static str, Option<Color32>) {
+    if is_dir {
+        return ("📂", None);
+    }
+    match file_extension(name).as_str() {
+        ext if IMAGE_EXTENSIONS.contains(&ext) => ("🖼", Some(Color32::from_rgb(155, 89, 182))),
+        "zip" | "tar" | "gz" | "tgz" | "bz2" | "xz" | "zst" | "7z" | "rar" | "dmg" | "iso" => {
+            ("📦", Some(Color32::from_rgb(230, 126, 34)))
+        }
+        "mp3" | "wav" | "flac" | "aac" | "m4a" | "ogg" | "opus" | "aiff" => {
+            ("🎵", Some(Color32::from_rgb(26, 188, 156)))
+        }
+        "mp4" | "mov" | "m4v" | "mkv" | "avi" | "webm" => {
+            ("🎬", Some(Color32::from_rgb(231, 76, 60)))
+        }
+        ext if DOCUMENT_EXTENSIONS.contains(&ext) => ("📃", Some(Color32::from_rgb(241, 196, 15))),
+        ext if CODE_EXTENSIONS.contains(&ext) => ("💻", Some(Color32::from_rgb(52, 152, 219))),
+        _ => ("📰", None),
+    }
+}
 
 fn format_size(bytes: u64) -> String {
     const KB: u64 = 1024;